@@ -0,0 +1,62 @@
+//! Count the allocations made by the scanner on quoted scalars.
+//!
+//! This runs on the `quoted_strings.yaml` file generated by `gen_large_yaml`, in which most of the
+//! scanning happens in multi-line double-quoted scalars. The scanner is expected to reuse its
+//! buffers for whitespace and line breaks, so that a scalar costs little more than the allocations
+//! of its own contents.
+//!
+//! ```sh
+//! $> cargo bench -p saphyr-bench --bench quoted_scalars -- [path/to/quoted_strings.yaml]
+//! ```
+
+#![allow(clippy::cast_precision_loss)]
+
+#[path = "../../parser/tools/alloc_stats.rs"]
+mod alloc_stats;
+
+use saphyr_parser::{tokenize, TScalarStyle, TokenType};
+
+/// Where `gen_large_yaml` writes the file, relative to the directory it is run from.
+const DEFAULT_INPUT: &str = "bench_yaml/quoted_strings.yaml";
+
+fn main() {
+    // `cargo bench` passes `--bench` to the binary.
+    let path = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+    let input = match (
+        std::fs::read_to_string(path.as_deref().unwrap_or(DEFAULT_INPUT)),
+        path,
+    ) {
+        (Ok(input), _) => input,
+        (Err(_), None) => {
+            println!(
+                "SKIPPED: cannot read `{DEFAULT_INPUT}`. Generate it with `cargo gen_large_yaml`."
+            );
+            return;
+        }
+        (Err(err), Some(path)) => {
+            eprintln!("Cannot read `{path}`: {err}");
+            std::process::exit(1);
+        }
+    };
+
+    let mut scalars = 0usize;
+    let mut bytes = 0usize;
+    alloc_stats::reset();
+    let begin = std::time::Instant::now();
+    for token in tokenize(&input) {
+        if let Ok((TokenType::Scalar(TScalarStyle::DoubleQuoted, contents), _)) = token {
+            scalars += 1;
+            bytes += contents.len();
+        }
+    }
+    let elapsed = begin.elapsed();
+    let stats = alloc_stats::get();
+
+    println!("Scanned {scalars} double-quoted scalars ({bytes} bytes) in {elapsed:?}");
+    println!(
+        "{} allocations ({:.2} per scalar), peaking at {} bytes",
+        stats.allocations,
+        stats.allocations as f64 / scalars.max(1) as f64,
+        stats.peak_bytes
+    );
+}
@@ -135,6 +135,12 @@ struct BenchYamlOutput {
     iterations: u64,
     /// Parsing times for each run.
     times: Vec<u64>,
+    /// Number of allocations during a parse, if the parser reports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    allocations: Option<u64>,
+    /// Peak number of bytes allocated during a parse, if the parser reports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    peak_bytes: Option<u64>,
 }
 
 /// Save a CSV file with all averages from `run_bench`.
@@ -168,7 +174,7 @@ fn list_input_files(config: &Config) -> Result<Vec<String>, Error> {
         .filter(|path| {
             Path::new(path)
                 .extension()
-                .map_or(false, |ext| ext.eq_ignore_ascii_case("yaml"))
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml"))
         })
         .collect())
 }
@@ -34,6 +34,10 @@ fn main() -> std::io::Result<()> {
     println!("Generating strings_array.yaml");
     let mut out = BufWriter::new(File::create(output_path.join("strings_array.yaml")).unwrap());
     generator.gen_strings_array(&mut out, 1_300_000, 1_300_001, 10, 40)?;
+
+    println!("Generating quoted_strings.yaml");
+    let mut out = BufWriter::new(File::create(output_path.join("quoted_strings.yaml")).unwrap());
+    generator.gen_quoted_strings_array(&mut out, 600_000, 600_001)?;
     Ok(())
 }
 
@@ -83,6 +87,28 @@ impl Generator {
         })
     }
 
+    /// Generate an array of multi-line double-quoted lipsum texts.
+    ///
+    /// Line breaks in quoted scalars are folded, which puts weight on the handling of whitespace
+    /// and line breaks in flow scalar scanning.
+    fn gen_quoted_strings_array<W: std::io::Write>(
+        &mut self,
+        writer: &mut W,
+        items_lo: usize,
+        items_hi: usize,
+    ) -> std::io::Result<()> {
+        self.gen_array(writer, items_lo, items_hi, |gen, writer| {
+            write!(writer, "\"")?;
+            let indent = gen.indent();
+            let text: Vec<String> = gen::text(&mut gen.rng, 1, 4, 2, 6, 8, 15, 80 - indent)
+                .into_iter()
+                .map(|line| line.replace(['"', '\\'], ""))
+                .collect();
+            gen.write_lines(writer, &text)?;
+            write!(writer, "\"")
+        })
+    }
+
     /// Generate a YAML object/mapping containing a record.
     ///
     /// Fields are description, hash, version, home, repository and pdf.
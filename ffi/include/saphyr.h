@@ -0,0 +1,113 @@
+/*
+ * C bindings to saphyr, a YAML 1.2 library written in Rust.
+ *
+ * See the documentation of the `saphyr-ffi` crate for the details of each function.
+ */
+
+#ifndef SAPHYR_H
+#define SAPHYR_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SAPHYR_OK 0
+#define SAPHYR_ERROR_PARSE 1
+#define SAPHYR_STOPPED 2
+#define SAPHYR_ERROR_INVALID_ARGUMENT 3
+#define SAPHYR_ERROR_EMIT 4
+#define SAPHYR_ERROR_PANIC 5
+
+/* A UTF-8 string which is not NUL-terminated. A null `ptr` denotes a missing string. */
+typedef struct SaphyrStr {
+    const char *ptr;
+    size_t len;
+} SaphyrStr;
+
+/* A location in a YAML stream. `index` and `col` count characters; `line` starts at 1. */
+typedef struct SaphyrMarker {
+    size_t index;
+    size_t line;
+    size_t col;
+} SaphyrMarker;
+
+typedef enum SaphyrEventKind {
+    SAPHYR_STREAM_START = 0,
+    SAPHYR_STREAM_END = 1,
+    SAPHYR_DOCUMENT_START = 2,
+    SAPHYR_DOCUMENT_END = 3,
+    SAPHYR_ALIAS = 4,
+    SAPHYR_SCALAR = 5,
+    SAPHYR_SEQUENCE_START = 6,
+    SAPHYR_SEQUENCE_END = 7,
+    SAPHYR_MAPPING_START = 8,
+    SAPHYR_MAPPING_END = 9,
+} SaphyrEventKind;
+
+typedef enum SaphyrScalarStyle {
+    SAPHYR_PLAIN = 0,
+    SAPHYR_SINGLE_QUOTED = 1,
+    SAPHYR_DOUBLE_QUOTED = 2,
+    SAPHYR_LITERAL = 3,
+    SAPHYR_FOLDED = 4,
+} SaphyrScalarStyle;
+
+/* An event of a YAML stream. Fields which do not apply to `kind` are zeroed. */
+typedef struct SaphyrEvent {
+    /* A `SaphyrEventKind`. */
+    int kind;
+    /* Document start and end: whether `---` or `...` was written. */
+    bool explicit_;
+    /* Scalar: its contents and style. */
+    SaphyrStr value;
+    /* A `SaphyrScalarStyle`. */
+    int style;
+    /* Anchor ID of the node (0 if none), or the anchor an alias refers to. */
+    size_t anchor_id;
+    /* Name of the anchor of the node, if any. */
+    SaphyrStr anchor;
+    /* Tag of the node, its handle resolved to its prefix, if any. */
+    SaphyrStr tag_handle;
+    SaphyrStr tag_suffix;
+    SaphyrMarker start;
+    SaphyrMarker end;
+} SaphyrEvent;
+
+/* A parse error. `message` is NUL-terminated and must be freed with `saphyr_string_free`. */
+typedef struct SaphyrError {
+    SaphyrMarker start;
+    SaphyrMarker end;
+    char *message;
+} SaphyrError;
+
+/* Called with each event. The strings of the event are only valid during the call. Returning a
+ * non-zero value stops the parsing. */
+typedef int (*SaphyrEventCallback)(const SaphyrEvent *event, void *user_data);
+
+/* Parse `len` bytes of UTF-8 YAML and call `callback` with each event. */
+int saphyr_parse(const char *input, size_t len, SaphyrEventCallback callback, void *user_data,
+                 SaphyrError *error);
+
+typedef struct SaphyrEmitter SaphyrEmitter;
+
+/* Create an emitter building documents from events. */
+SaphyrEmitter *saphyr_emitter_new(void);
+/* Feed an event to the emitter. */
+int saphyr_emitter_event(SaphyrEmitter *emitter, const SaphyrEvent *event);
+/* Write the documents as a NUL-terminated string, to be freed with `saphyr_string_free`, and free
+ * the emitter. */
+int saphyr_emitter_finish(SaphyrEmitter *emitter, char **output, size_t *output_len);
+/* Free the emitter without writing its documents. */
+void saphyr_emitter_free(SaphyrEmitter *emitter);
+
+/* Free a string returned by this library. */
+void saphyr_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SAPHYR_H */
@@ -0,0 +1,663 @@
+//! C bindings to `saphyr`.
+//!
+//! This crate exposes the parser and the emitter of `saphyr` through `extern "C"` functions so
+//! that projects written in other languages can use them, e.g. in place of libyaml. The matching
+//! C declarations are in `include/saphyr.h`.
+//!
+//! - [`saphyr_parse`] parses a YAML stream and calls a callback with each of its events.
+//! - [`saphyr_emitter_new`], [`saphyr_emitter_event`] and [`saphyr_emitter_finish`] build YAML
+//!   documents from events and write them as text.
+//!
+//! No function unwinds into its caller: should the library panic, functions return
+//! [`SAPHYR_ERROR_PANIC`] (or null for [`saphyr_emitter_new`]).
+//!
+//! Strings given to and returned by these functions are UTF-8 and are not NUL-terminated unless
+//! stated otherwise, since YAML scalars may contain NUL characters. Strings allocated by this crate
+//! must be freed with [`saphyr_string_free`].
+
+#![warn(missing_docs, clippy::pedantic)]
+
+use std::ffi::{c_char, c_int, c_void, CString};
+
+use saphyr::{Yaml, YamlEmitter, YamlLoader};
+use saphyr_parser::{
+    BlockScalarHeader, Event, Marker, Parser, ScanError, Span, SpannedEventReceiver, TScalarStyle,
+    Tag,
+};
+
+/// The function succeeded.
+pub const SAPHYR_OK: c_int = 0;
+/// The input is not valid YAML. The error is described by a [`SaphyrError`].
+pub const SAPHYR_ERROR_PARSE: c_int = 1;
+/// The callback given to [`saphyr_parse`] returned a non-zero value.
+pub const SAPHYR_STOPPED: c_int = 2;
+/// An argument is invalid: a pointer is null, a string is not UTF-8 or an event is out of place.
+pub const SAPHYR_ERROR_INVALID_ARGUMENT: c_int = 3;
+/// Writing the documents failed.
+pub const SAPHYR_ERROR_EMIT: c_int = 4;
+/// The library panicked, which is a bug. An emitter given to the failing function must only be
+/// freed.
+pub const SAPHYR_ERROR_PANIC: c_int = 5;
+
+/// A string which is not NUL-terminated.
+///
+/// A null `ptr` denotes the absence of a string, which is different from an empty string.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct SaphyrStr {
+    /// The bytes of the string, in UTF-8.
+    pub ptr: *const c_char,
+    /// The number of bytes of the string.
+    pub len: usize,
+}
+
+impl SaphyrStr {
+    /// A missing string.
+    const NULL: Self = Self {
+        ptr: std::ptr::null(),
+        len: 0,
+    };
+
+    /// Borrow `s`.
+    fn new(s: &str) -> Self {
+        Self {
+            ptr: s.as_ptr().cast(),
+            len: s.len(),
+        }
+    }
+
+    /// Return the string, or `None` if it is missing.
+    ///
+    /// # Errors
+    /// Returns an error if the string is not valid UTF-8.
+    ///
+    /// # Safety
+    /// If `ptr` is not null, it must point to `len` readable bytes, valid for `'a`.
+    unsafe fn to_str<'a>(self) -> Result<Option<&'a str>, std::str::Utf8Error> {
+        if self.ptr.is_null() {
+            return Ok(None);
+        }
+        // SAFETY: Upheld by the caller.
+        let bytes = unsafe { std::slice::from_raw_parts(self.ptr.cast::<u8>(), self.len) };
+        std::str::from_utf8(bytes).map(Some)
+    }
+}
+
+/// A location in a YAML stream.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SaphyrMarker {
+    /// The index of the location, in characters.
+    pub index: usize,
+    /// The line of the location, starting at 1.
+    pub line: usize,
+    /// The column of the location, in characters, starting at 0.
+    pub col: usize,
+}
+
+impl From<Marker> for SaphyrMarker {
+    fn from(marker: Marker) -> Self {
+        Self {
+            index: marker.index(),
+            line: marker.line(),
+            col: marker.col(),
+        }
+    }
+}
+
+impl From<SaphyrMarker> for Marker {
+    fn from(marker: SaphyrMarker) -> Self {
+        Marker::new(marker.index, marker.line, marker.col)
+    }
+}
+
+/// The kind of a [`SaphyrEvent`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SaphyrEventKind {
+    /// The start of the stream.
+    StreamStart = 0,
+    /// The end of the stream.
+    StreamEnd = 1,
+    /// The start of a document.
+    DocumentStart = 2,
+    /// The end of a document.
+    DocumentEnd = 3,
+    /// An alias to an anchored node.
+    Alias = 4,
+    /// A scalar.
+    Scalar = 5,
+    /// The start of a sequence.
+    SequenceStart = 6,
+    /// The end of a sequence.
+    SequenceEnd = 7,
+    /// The start of a mapping.
+    MappingStart = 8,
+    /// The end of a mapping.
+    MappingEnd = 9,
+}
+
+impl TryFrom<c_int> for SaphyrEventKind {
+    /// The invalid value.
+    type Error = c_int;
+
+    fn try_from(kind: c_int) -> Result<Self, c_int> {
+        Ok(match kind {
+            0 => Self::StreamStart,
+            1 => Self::StreamEnd,
+            2 => Self::DocumentStart,
+            3 => Self::DocumentEnd,
+            4 => Self::Alias,
+            5 => Self::Scalar,
+            6 => Self::SequenceStart,
+            7 => Self::SequenceEnd,
+            8 => Self::MappingStart,
+            9 => Self::MappingEnd,
+            _ => return Err(kind),
+        })
+    }
+}
+
+/// The style in which a scalar was written.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SaphyrScalarStyle {
+    /// An unquoted scalar.
+    Plain = 0,
+    /// A scalar between single quotes.
+    SingleQuoted = 1,
+    /// A scalar between double quotes.
+    DoubleQuoted = 2,
+    /// A literal block scalar (`|`).
+    Literal = 3,
+    /// A folded block scalar (`>`).
+    Folded = 4,
+}
+
+impl TryFrom<c_int> for SaphyrScalarStyle {
+    /// The invalid value.
+    type Error = c_int;
+
+    fn try_from(style: c_int) -> Result<Self, c_int> {
+        Ok(match style {
+            0 => Self::Plain,
+            1 => Self::SingleQuoted,
+            2 => Self::DoubleQuoted,
+            3 => Self::Literal,
+            4 => Self::Folded,
+            _ => return Err(style),
+        })
+    }
+}
+
+/// An event of a YAML stream.
+///
+/// Fields which do not apply to the kind of the event are zeroed (or null). The kind and the style
+/// are plain integers rather than enums, since C callers may store any value in them.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct SaphyrEvent {
+    /// The kind of the event, one of [`SaphyrEventKind`].
+    pub kind: c_int,
+    /// For `DocumentStart` and `DocumentEnd`, whether the marker (`---` or `...`) was written.
+    pub explicit: bool,
+    /// For `Scalar`, the contents of the scalar.
+    pub value: SaphyrStr,
+    /// For `Scalar`, its style, one of [`SaphyrScalarStyle`].
+    pub style: c_int,
+    /// For `Scalar`, `SequenceStart` and `MappingStart`, the ID of the anchor of the node, or 0 if
+    /// it has none. For `Alias`, the ID of the anchor it refers to.
+    pub anchor_id: usize,
+    /// The name of the anchor of the node, if any. Ignored by [`saphyr_emitter_event`].
+    pub anchor: SaphyrStr,
+    /// The handle of the tag of the node, resolved to its prefix, if the node has a tag.
+    pub tag_handle: SaphyrStr,
+    /// The suffix of the tag of the node, if the node has a tag.
+    pub tag_suffix: SaphyrStr,
+    /// The start of the event in the stream.
+    pub start: SaphyrMarker,
+    /// The end of the event in the stream.
+    pub end: SaphyrMarker,
+}
+
+impl SaphyrEvent {
+    /// Borrow the event `ev`, found at `span`, with the given anchor name.
+    fn new(ev: &Event, span: Span, anchor: Option<&str>) -> Self {
+        let mut event = Self {
+            kind: SaphyrEventKind::StreamStart as c_int,
+            explicit: false,
+            value: SaphyrStr::NULL,
+            style: SaphyrScalarStyle::Plain as c_int,
+            anchor_id: 0,
+            anchor: anchor.map_or(SaphyrStr::NULL, SaphyrStr::new),
+            tag_handle: SaphyrStr::NULL,
+            tag_suffix: SaphyrStr::NULL,
+            start: span.start.into(),
+            end: span.end.into(),
+        };
+        let tag = match ev {
+            Event::Nothing | Event::StreamStart => None,
+            Event::StreamEnd => {
+                event.kind = SaphyrEventKind::StreamEnd as c_int;
+                None
+            }
+            Event::DocumentStart(explicit) => {
+                event.kind = SaphyrEventKind::DocumentStart as c_int;
+                event.explicit = *explicit;
+                None
+            }
+            Event::DocumentEnd(explicit) => {
+                event.kind = SaphyrEventKind::DocumentEnd as c_int;
+                event.explicit = *explicit;
+                None
+            }
+            Event::Alias(id) => {
+                event.kind = SaphyrEventKind::Alias as c_int;
+                event.anchor_id = *id;
+                None
+            }
+            Event::Scalar(value, style, id, tag) => {
+                event.kind = SaphyrEventKind::Scalar as c_int;
+                event.value = SaphyrStr::new(value);
+                event.style = match style {
+                    TScalarStyle::Plain => SaphyrScalarStyle::Plain,
+                    TScalarStyle::SingleQuoted => SaphyrScalarStyle::SingleQuoted,
+                    TScalarStyle::DoubleQuoted => SaphyrScalarStyle::DoubleQuoted,
+                    TScalarStyle::Literal(_) => SaphyrScalarStyle::Literal,
+                    TScalarStyle::Folded(_) => SaphyrScalarStyle::Folded,
+                } as c_int;
+                event.anchor_id = *id;
+                tag.as_ref()
+            }
+            Event::SequenceStart(id, tag) => {
+                event.kind = SaphyrEventKind::SequenceStart as c_int;
+                event.anchor_id = *id;
+                tag.as_ref()
+            }
+            Event::SequenceEnd => {
+                event.kind = SaphyrEventKind::SequenceEnd as c_int;
+                None
+            }
+            Event::MappingStart(id, tag) => {
+                event.kind = SaphyrEventKind::MappingStart as c_int;
+                event.anchor_id = *id;
+                tag.as_ref()
+            }
+            Event::MappingEnd => {
+                event.kind = SaphyrEventKind::MappingEnd as c_int;
+                None
+            }
+        };
+        if let Some(tag) = tag {
+            event.tag_handle = SaphyrStr::new(&tag.handle);
+            event.tag_suffix = SaphyrStr::new(&tag.suffix);
+        }
+        event
+    }
+
+    /// Convert the event into an [`Event`] and its [`Span`].
+    ///
+    /// Returns `None` if the kind or the style of the event is unknown or if a string of the event
+    /// is not valid UTF-8.
+    ///
+    /// # Safety
+    /// The strings of the event must be valid, as described in [`SaphyrStr::to_str`].
+    unsafe fn read(&self) -> Option<(Event, Span)> {
+        // SAFETY: Upheld by the caller.
+        let (handle, suffix) = unsafe { (self.tag_handle.to_str(), self.tag_suffix.to_str()) };
+        let tag = match (handle.ok()?, suffix.ok()?) {
+            (Some(handle), suffix) => Some(Tag {
+                handle: handle.to_owned(),
+                suffix: suffix.unwrap_or_default().to_owned(),
+                original_handle: String::new(),
+            }),
+            (None, _) => None,
+        };
+        let ev = match SaphyrEventKind::try_from(self.kind).ok()? {
+            SaphyrEventKind::StreamStart => Event::StreamStart,
+            SaphyrEventKind::StreamEnd => Event::StreamEnd,
+            SaphyrEventKind::DocumentStart => Event::DocumentStart(self.explicit),
+            SaphyrEventKind::DocumentEnd => Event::DocumentEnd(self.explicit),
+            SaphyrEventKind::Alias => Event::Alias(self.anchor_id),
+            SaphyrEventKind::Scalar => {
+                // SAFETY: Upheld by the caller.
+                let value = unsafe { self.value.to_str() }.ok()?.unwrap_or_default();
+                let style = match SaphyrScalarStyle::try_from(self.style).ok()? {
+                    SaphyrScalarStyle::Plain => TScalarStyle::Plain,
+                    SaphyrScalarStyle::SingleQuoted => TScalarStyle::SingleQuoted,
+                    SaphyrScalarStyle::DoubleQuoted => TScalarStyle::DoubleQuoted,
+                    SaphyrScalarStyle::Literal => {
+                        TScalarStyle::Literal(BlockScalarHeader::default())
+                    }
+                    SaphyrScalarStyle::Folded => TScalarStyle::Folded(BlockScalarHeader::default()),
+                };
+                Event::Scalar(value.to_owned(), style, self.anchor_id, tag)
+            }
+            SaphyrEventKind::SequenceStart => Event::SequenceStart(self.anchor_id, tag),
+            SaphyrEventKind::SequenceEnd => Event::SequenceEnd,
+            SaphyrEventKind::MappingStart => Event::MappingStart(self.anchor_id, tag),
+            SaphyrEventKind::MappingEnd => Event::MappingEnd,
+        };
+        Some((ev, Span::new(self.start.into(), self.end.into())))
+    }
+}
+
+/// An error returned by [`saphyr_parse`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct SaphyrError {
+    /// The start of the error in the stream.
+    pub start: SaphyrMarker,
+    /// The end of the error in the stream.
+    pub end: SaphyrMarker,
+    /// A NUL-terminated description of the error, to be freed with [`saphyr_string_free`].
+    pub message: *mut c_char,
+}
+
+impl SaphyrError {
+    /// Describe `err`.
+    fn new(err: &ScanError) -> Self {
+        Self {
+            start: err.span().start.into(),
+            end: err.span().end.into(),
+            message: into_c_string(err.to_string()),
+        }
+    }
+}
+
+/// The callback given to [`saphyr_parse`].
+///
+/// It is given the event and the `user_data` given to [`saphyr_parse`]. The strings of the event
+/// are only valid during the call. Returning a non-zero value stops the parsing.
+pub type SaphyrEventCallback =
+    Option<unsafe extern "C" fn(event: *const SaphyrEvent, user_data: *mut c_void) -> c_int>;
+
+/// Parse a YAML stream and call `callback` with each of its events, in order.
+///
+/// Returns:
+///   - [`SAPHYR_OK`] once all events have been given to `callback`;
+///   - [`SAPHYR_STOPPED`] if `callback` returned a non-zero value, in which case it is not called
+///     again;
+///   - [`SAPHYR_ERROR_PARSE`] if the input is not valid YAML, after calling `callback` with the
+///     events preceding the error. If `error` is not null, it is filled with the error, whose
+///     message must then be freed with [`saphyr_string_free`];
+///   - [`SAPHYR_ERROR_INVALID_ARGUMENT`] if `input` or `callback` is null or if the input is not
+///     UTF-8.
+///
+/// # Safety
+/// `input` must point to `len` readable bytes. `error` must be null or point to a writable
+/// [`SaphyrError`]. `callback` must be safe to call with `user_data`.
+#[no_mangle]
+pub unsafe extern "C" fn saphyr_parse(
+    input: *const c_char,
+    len: usize,
+    callback: SaphyrEventCallback,
+    user_data: *mut c_void,
+    error: *mut SaphyrError,
+) -> c_int {
+    catch_panic(SAPHYR_ERROR_PANIC, || {
+        let Some(callback) = callback else {
+            return SAPHYR_ERROR_INVALID_ARGUMENT;
+        };
+        // SAFETY: Upheld by the caller.
+        let Ok(Some(input)) = (unsafe { SaphyrStr { ptr: input, len }.to_str() }) else {
+            return SAPHYR_ERROR_INVALID_ARGUMENT;
+        };
+
+        let mut parser = Parser::new_from_str(input);
+        while let Some(result) = parser.next_event() {
+            let (ev, span) = match result {
+                Ok(event) => event,
+                Err(err) => {
+                    if !error.is_null() {
+                        // SAFETY: Upheld by the caller.
+                        unsafe { error.write(SaphyrError::new(&err)) };
+                    }
+                    return SAPHYR_ERROR_PARSE;
+                }
+            };
+            let anchor = match ev {
+                Event::Scalar(_, _, id, _)
+                | Event::SequenceStart(id, _)
+                | Event::MappingStart(id, _) => parser.anchor_name(id),
+                _ => None,
+            };
+            let event = SaphyrEvent::new(&ev, span, anchor);
+            // SAFETY: The caller guarantees that the callback is safe to call with `user_data`.
+            // The strings of the event outlive the call.
+            if unsafe { callback(&event, user_data) } != 0 {
+                return SAPHYR_STOPPED;
+            }
+        }
+        SAPHYR_OK
+    })
+}
+
+/// An emitter building YAML documents from events.
+///
+/// Created with [`saphyr_emitter_new`], fed with [`saphyr_emitter_event`] and consumed by
+/// [`saphyr_emitter_finish`] or [`saphyr_emitter_free`].
+pub struct SaphyrEmitter {
+    /// The loader building the documents.
+    loader: YamlLoader<Yaml>,
+    /// Whether a document was started and not ended.
+    in_document: bool,
+    /// Whether the current document has a root node.
+    has_root: bool,
+    /// The collections being built, along with the number of nodes they hold.
+    collections: Vec<(SaphyrEventKind, usize)>,
+}
+
+impl SaphyrEmitter {
+    /// Feed `ev` to the loader, if it is valid at this point of the stream.
+    fn push(&mut self, ev: Event, span: Span) -> bool {
+        let valid = match ev {
+            Event::Nothing | Event::StreamStart | Event::StreamEnd => !self.in_document,
+            Event::DocumentStart(_) => !std::mem::replace(&mut self.in_document, true),
+            Event::DocumentEnd(_) => {
+                let valid = self.in_document && self.collections.is_empty();
+                if valid {
+                    self.in_document = false;
+                    self.has_root = false;
+                }
+                valid
+            }
+            Event::Alias(_) | Event::Scalar(..) => self.push_node(),
+            Event::SequenceStart(..) => {
+                let valid = self.push_node();
+                if valid {
+                    self.collections.push((SaphyrEventKind::SequenceStart, 0));
+                }
+                valid
+            }
+            Event::MappingStart(..) => {
+                let valid = self.push_node();
+                if valid {
+                    self.collections.push((SaphyrEventKind::MappingStart, 0));
+                }
+                valid
+            }
+            Event::SequenceEnd => {
+                self.collections.last().map(|(kind, _)| kind)
+                    == Some(&SaphyrEventKind::SequenceStart)
+                    && self.collections.pop().is_some()
+            }
+            Event::MappingEnd => {
+                let even = matches!(
+                    self.collections.last(),
+                    Some((SaphyrEventKind::MappingStart, nodes)) if nodes % 2 == 0
+                );
+                even && self.collections.pop().is_some()
+            }
+        };
+        if valid {
+            self.loader.on_event(ev, span);
+        }
+        valid
+    }
+
+    /// Account for a new node, returning whether one is valid at this point of the stream.
+    fn push_node(&mut self) -> bool {
+        if !self.in_document {
+            return false;
+        }
+        match self.collections.last_mut() {
+            Some((_, nodes)) => {
+                *nodes += 1;
+                true
+            }
+            None => !std::mem::replace(&mut self.has_root, true),
+        }
+    }
+}
+
+/// Create an emitter, to be consumed by [`saphyr_emitter_finish`] or [`saphyr_emitter_free`].
+///
+/// Returns null if the library panicked.
+#[no_mangle]
+pub extern "C" fn saphyr_emitter_new() -> *mut SaphyrEmitter {
+    catch_panic(std::ptr::null_mut(), || {
+        Box::into_raw(Box::new(SaphyrEmitter {
+            loader: YamlLoader::default(),
+            in_document: false,
+            has_root: false,
+            collections: vec![],
+        }))
+    })
+}
+
+/// Feed an event to `emitter`.
+///
+/// Events must form a valid stream, as given by [`saphyr_parse`]: nodes within documents,
+/// collections properly closed and mappings with as many keys as values. `StreamStart` and
+/// `StreamEnd` events are optional. Aliases are replaced by the node they refer to, and spans and
+/// anchor names are ignored.
+///
+/// Tags and scalar styles are not written back. They are only used to resolve scalars as when
+/// loading a document: a quoted `"1"` or a `!!str 1` is a string and `1` is an integer. Each value
+/// is then written in the style the emitter picks for it (e.g. the string `"1"` is quoted), and
+/// other tags are dropped.
+///
+/// Returns [`SAPHYR_OK`], or [`SAPHYR_ERROR_INVALID_ARGUMENT`] if a pointer is null, a string of
+/// the event is not UTF-8 or the event is out of place, in which case it is ignored.
+///
+/// # Safety
+/// `emitter` must be null or have been returned by [`saphyr_emitter_new`] and not consumed yet.
+/// `event` must be null or point to a valid [`SaphyrEvent`] whose strings point to readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn saphyr_emitter_event(
+    emitter: *mut SaphyrEmitter,
+    event: *const SaphyrEvent,
+) -> c_int {
+    catch_panic(SAPHYR_ERROR_PANIC, || {
+        // SAFETY: Upheld by the caller.
+        let (Some(emitter), Some(event)) = (unsafe { emitter.as_mut() }, unsafe { event.as_ref() })
+        else {
+            return SAPHYR_ERROR_INVALID_ARGUMENT;
+        };
+        // SAFETY: Upheld by the caller.
+        match unsafe { event.read() } {
+            Some((ev, span)) => {
+                if emitter.push(ev, span) {
+                    SAPHYR_OK
+                } else {
+                    SAPHYR_ERROR_INVALID_ARGUMENT
+                }
+            }
+            None => SAPHYR_ERROR_INVALID_ARGUMENT,
+        }
+    })
+}
+
+/// Write the documents built by `emitter` and free it.
+///
+/// Documents are written as [`saphyr::YamlEmitter`] writes them, each starting with `---`. On
+/// success, `*output` is set to a NUL-terminated string, to be freed with [`saphyr_string_free`],
+/// and `*output_len` (if not null) to its length, without the NUL.
+///
+/// Returns [`SAPHYR_OK`], [`SAPHYR_ERROR_EMIT`] if writing failed, or
+/// [`SAPHYR_ERROR_INVALID_ARGUMENT`] if `emitter` or `output` is null or a document was not
+/// ended. `emitter` is freed in all cases.
+///
+/// # Safety
+/// `emitter` must be null or have been returned by [`saphyr_emitter_new`] and not consumed yet.
+/// `output` must be null or point to a writable pointer, and `output_len` must be null or point to
+/// a writable `size_t`.
+#[no_mangle]
+pub unsafe extern "C" fn saphyr_emitter_finish(
+    emitter: *mut SaphyrEmitter,
+    output: *mut *mut c_char,
+    output_len: *mut usize,
+) -> c_int {
+    catch_panic(SAPHYR_ERROR_PANIC, || {
+        if emitter.is_null() {
+            return SAPHYR_ERROR_INVALID_ARGUMENT;
+        }
+        // SAFETY: Upheld by the caller.
+        let emitter = unsafe { Box::from_raw(emitter) };
+        if output.is_null() || emitter.in_document {
+            return SAPHYR_ERROR_INVALID_ARGUMENT;
+        }
+
+        let mut out = String::new();
+        for doc in emitter.loader.into_documents() {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            if YamlEmitter::new(&mut out).dump(&doc).is_err() {
+                return SAPHYR_ERROR_EMIT;
+            }
+        }
+        if !output_len.is_null() {
+            // SAFETY: Upheld by the caller.
+            unsafe { output_len.write(out.len()) };
+        }
+        // SAFETY: Upheld by the caller.
+        unsafe { output.write(into_c_string(out)) };
+        SAPHYR_OK
+    })
+}
+
+/// Free an emitter without writing its documents.
+///
+/// # Safety
+/// `emitter` must be null or have been returned by [`saphyr_emitter_new`] and not consumed yet.
+#[no_mangle]
+pub unsafe extern "C" fn saphyr_emitter_free(emitter: *mut SaphyrEmitter) {
+    catch_panic((), || {
+        if !emitter.is_null() {
+            // SAFETY: Upheld by the caller.
+            drop(unsafe { Box::from_raw(emitter) });
+        }
+    });
+}
+
+/// Free a string returned by this library.
+///
+/// # Safety
+/// `s` must be null or a string returned by this library which has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn saphyr_string_free(s: *mut c_char) {
+    catch_panic((), || {
+        if !s.is_null() {
+            // SAFETY: Upheld by the caller.
+            drop(unsafe { CString::from_raw(s) });
+        }
+    });
+}
+
+/// Run `f`, returning `on_panic` if it panics instead of unwinding into C code.
+fn catch_panic<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).unwrap_or(on_panic)
+}
+
+/// Turn `s` into a NUL-terminated string owned by the caller.
+///
+/// NUL characters cannot appear in the strings this crate returns: the emitter escapes them and
+/// error messages do not contain any. Should one appear, the string is truncated before it.
+fn into_c_string(mut s: String) -> *mut c_char {
+    if let Some(nul) = s.find('\0') {
+        s.truncate(nul);
+    }
+    CString::new(s).unwrap_or_default().into_raw()
+}
@@ -0,0 +1,280 @@
+use std::ffi::{c_int, c_void, CStr};
+
+use saphyr_ffi::{
+    saphyr_emitter_event, saphyr_emitter_finish, saphyr_emitter_free, saphyr_emitter_new,
+    saphyr_parse, saphyr_string_free, SaphyrError, SaphyrEvent, SaphyrEventKind, SaphyrMarker,
+    SaphyrScalarStyle, SaphyrStr, SAPHYR_ERROR_INVALID_ARGUMENT, SAPHYR_ERROR_PARSE, SAPHYR_OK,
+    SAPHYR_STOPPED,
+};
+
+/// An event with its strings copied.
+#[derive(Debug, PartialEq)]
+struct Owned {
+    kind: SaphyrEventKind,
+    value: Option<String>,
+    anchor: Option<String>,
+    tag: Option<String>,
+    start: SaphyrMarker,
+}
+
+fn read(s: SaphyrStr) -> Option<String> {
+    (!s.ptr.is_null()).then(|| {
+        let bytes = unsafe { std::slice::from_raw_parts(s.ptr.cast::<u8>(), s.len) };
+        String::from_utf8(bytes.to_vec()).unwrap()
+    })
+}
+
+/// The `user_data` of [`collect`].
+#[derive(Default)]
+struct Collector {
+    /// The events received so far.
+    events: Vec<Owned>,
+    /// The number of events after which to stop, if any.
+    stop_after: Option<usize>,
+}
+
+/// Store the events in the [`Collector`] pointed to by `user_data`, stopping once it has
+/// received as many as it asks for.
+unsafe extern "C" fn collect(event: *const SaphyrEvent, user_data: *mut c_void) -> c_int {
+    let collector = unsafe { &mut *user_data.cast::<Collector>() };
+    let event = unsafe { &*event };
+    collector.events.push(Owned {
+        kind: SaphyrEventKind::try_from(event.kind).unwrap(),
+        value: read(event.value),
+        anchor: read(event.anchor),
+        tag: read(event.tag_handle).map(|handle| handle + &read(event.tag_suffix).unwrap()),
+        start: event.start,
+    });
+    c_int::from(collector.stop_after == Some(collector.events.len()))
+}
+
+fn parse(input: &str, collector: &mut Collector) -> (c_int, Option<(SaphyrMarker, String)>) {
+    let mut error = SaphyrError {
+        start: SaphyrMarker::default(),
+        end: SaphyrMarker::default(),
+        message: std::ptr::null_mut(),
+    };
+    let status = unsafe {
+        saphyr_parse(
+            input.as_ptr().cast(),
+            input.len(),
+            Some(collect),
+            (collector as *mut Collector).cast(),
+            &mut error,
+        )
+    };
+    let error = (!error.message.is_null()).then(|| {
+        let message = unsafe { CStr::from_ptr(error.message) }
+            .to_str()
+            .unwrap()
+            .to_owned();
+        unsafe { saphyr_string_free(error.message) };
+        (error.start, message)
+    });
+    (status, error)
+}
+
+#[test]
+fn test_parse() {
+    let mut collector = Collector::default();
+    assert_eq!(
+        parse("a: &x !!str b\nc: *x", &mut collector),
+        (SAPHYR_OK, None)
+    );
+    let events = collector.events;
+    let kinds: Vec<_> = events.iter().map(|event| event.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            SaphyrEventKind::StreamStart,
+            SaphyrEventKind::DocumentStart,
+            SaphyrEventKind::MappingStart,
+            SaphyrEventKind::Scalar,
+            SaphyrEventKind::Scalar,
+            SaphyrEventKind::Scalar,
+            SaphyrEventKind::Alias,
+            SaphyrEventKind::MappingEnd,
+            SaphyrEventKind::DocumentEnd,
+            SaphyrEventKind::StreamEnd,
+        ]
+    );
+    assert_eq!(
+        events[4],
+        Owned {
+            kind: SaphyrEventKind::Scalar,
+            value: Some("b".to_owned()),
+            anchor: Some("x".to_owned()),
+            tag: Some("tag:yaml.org,2002:str".to_owned()),
+            start: SaphyrMarker {
+                index: 12,
+                line: 1,
+                col: 12
+            },
+        }
+    );
+    assert_eq!(events[3].anchor, None);
+    assert_eq!(events[3].tag, None);
+}
+
+#[test]
+fn test_parse_stop_and_errors() {
+    let mut collector = Collector {
+        stop_after: Some(3),
+        ..Collector::default()
+    };
+    assert_eq!(parse("[a, b]", &mut collector), (SAPHYR_STOPPED, None));
+    assert_eq!(collector.events.len(), 3);
+
+    // Nothing is parsed after the callback asks to stop, so later errors are not reported.
+    let mut collector = Collector {
+        stop_after: Some(3),
+        ..Collector::default()
+    };
+    assert_eq!(
+        parse(
+            "[a, b]
+]",
+            &mut collector
+        ),
+        (SAPHYR_STOPPED, None)
+    );
+    assert_eq!(collector.events.len(), 3);
+
+    let mut collector = Collector::default();
+    let (status, error) = parse("a: [b\nc", &mut collector);
+    assert_eq!(status, SAPHYR_ERROR_PARSE);
+    let expected = saphyr_parser::Parser::new_from_str("a: [b\nc")
+        .find_map(Result::err)
+        .unwrap();
+    let (start, message) = error.unwrap();
+    assert_eq!(start.index, expected.marker().index());
+    assert_eq!(start.line, expected.marker().line());
+    assert_eq!(message, expected.to_string());
+    assert_eq!(
+        collector.events.last().unwrap().value.as_deref(),
+        Some("b c")
+    );
+
+    let invalid = [b'a', 0xff];
+    let status = unsafe {
+        saphyr_parse(
+            invalid.as_ptr().cast(),
+            invalid.len(),
+            Some(collect),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    assert_eq!(status, SAPHYR_ERROR_INVALID_ARGUMENT);
+}
+
+/// Build an event of the given kind, with a value for scalars.
+fn event(kind: SaphyrEventKind, value: &str) -> SaphyrEvent {
+    let null = SaphyrStr {
+        ptr: std::ptr::null(),
+        len: 0,
+    };
+    SaphyrEvent {
+        kind: kind as c_int,
+        explicit: false,
+        value: SaphyrStr {
+            ptr: value.as_ptr().cast(),
+            len: value.len(),
+        },
+        style: SaphyrScalarStyle::Plain as c_int,
+        anchor_id: 0,
+        anchor: null,
+        tag_handle: null,
+        tag_suffix: null,
+        start: SaphyrMarker::default(),
+        end: SaphyrMarker::default(),
+    }
+}
+
+#[test]
+fn test_emit() {
+    use SaphyrEventKind::{DocumentEnd, DocumentStart, MappingEnd, MappingStart, Scalar};
+
+    let emitter = saphyr_emitter_new();
+    // The style resolves the scalar as a string, which is then quoted as the emitter sees fit.
+    let mut quoted = event(Scalar, "1");
+    quoted.style = SaphyrScalarStyle::SingleQuoted as c_int;
+    let stream = [
+        event(DocumentStart, ""),
+        event(MappingStart, ""),
+        event(Scalar, "a"),
+        event(Scalar, "1"),
+        event(Scalar, "b"),
+        event(Scalar, "x\0y"),
+        event(MappingEnd, ""),
+        event(DocumentEnd, ""),
+        event(DocumentStart, ""),
+        event(Scalar, "c"),
+        event(DocumentEnd, ""),
+        event(DocumentStart, ""),
+        quoted,
+        event(DocumentEnd, ""),
+    ];
+    for ev in &stream {
+        assert_eq!(unsafe { saphyr_emitter_event(emitter, ev) }, SAPHYR_OK);
+    }
+    let mut output = std::ptr::null_mut();
+    let mut len = 0;
+    assert_eq!(
+        unsafe { saphyr_emitter_finish(emitter, &mut output, &mut len) },
+        SAPHYR_OK
+    );
+    let text = unsafe { CStr::from_ptr(output) }
+        .to_str()
+        .unwrap()
+        .to_owned();
+    unsafe { saphyr_string_free(output) };
+    assert_eq!(text, "---\na: 1\nb: \"x\\u0000y\"\n---\nc\n---\n\"1\"");
+    assert_eq!(len, text.len());
+}
+
+#[test]
+fn test_emit_rejects_invalid_streams() {
+    use SaphyrEventKind::{
+        DocumentEnd, DocumentStart, MappingEnd, MappingStart, Scalar, SequenceEnd,
+    };
+
+    let emitter = saphyr_emitter_new();
+    let feed = |kind, value| unsafe { saphyr_emitter_event(emitter, &event(kind, value)) };
+    assert_eq!(feed(Scalar, "outside"), SAPHYR_ERROR_INVALID_ARGUMENT);
+    assert_eq!(feed(DocumentStart, ""), SAPHYR_OK);
+    assert_eq!(feed(MappingStart, ""), SAPHYR_OK);
+    assert_eq!(feed(Scalar, "key"), SAPHYR_OK);
+    assert_eq!(feed(SequenceEnd, ""), SAPHYR_ERROR_INVALID_ARGUMENT);
+    assert_eq!(feed(MappingEnd, ""), SAPHYR_ERROR_INVALID_ARGUMENT);
+    assert_eq!(feed(DocumentEnd, ""), SAPHYR_ERROR_INVALID_ARGUMENT);
+    assert_eq!(feed(Scalar, "value"), SAPHYR_OK);
+    assert_eq!(feed(MappingEnd, ""), SAPHYR_OK);
+    assert_eq!(feed(Scalar, "second root"), SAPHYR_ERROR_INVALID_ARGUMENT);
+
+    // Unknown kinds and styles are rejected.
+    let unknown = |kind, style| {
+        let mut ev = event(Scalar, "x");
+        ev.kind = kind;
+        ev.style = style;
+        unsafe { saphyr_emitter_event(emitter, &ev) }
+    };
+    assert_eq!(unknown(10, 0), SAPHYR_ERROR_INVALID_ARGUMENT);
+    assert_eq!(unknown(-1, 0), SAPHYR_ERROR_INVALID_ARGUMENT);
+    assert_eq!(unknown(Scalar as c_int, 5), SAPHYR_ERROR_INVALID_ARGUMENT);
+    assert_eq!(SaphyrEventKind::try_from(10), Err(10));
+    assert_eq!(
+        SaphyrScalarStyle::try_from(4),
+        Ok(SaphyrScalarStyle::Folded)
+    );
+
+    let mut output = std::ptr::null_mut();
+    // The document was not ended.
+    assert_eq!(
+        unsafe { saphyr_emitter_finish(emitter, &mut output, std::ptr::null_mut()) },
+        SAPHYR_ERROR_INVALID_ARGUMENT
+    );
+    assert!(output.is_null());
+
+    unsafe { saphyr_emitter_free(saphyr_emitter_new()) };
+}
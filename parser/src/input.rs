@@ -1,13 +1,18 @@
 //! Utilities to create a source of input to the parser.
 //!
 //! [`Input`] must be implemented for the parser to fetch input. Make sure your needs aren't
-//! covered by the [`BufferedInput`].
+//! covered by the [`BufferedInput`] or the [`ChunkedInput`].
+
+use alloc::string::String;
 
 pub(crate) mod buffered;
+pub(crate) mod chunked;
 pub(crate) mod str;
 
 #[allow(clippy::module_name_repetitions)]
 pub use buffered::BufferedInput;
+#[allow(clippy::module_name_repetitions)]
+pub use chunked::ChunkedInput;
 
 pub use crate::char_traits::{
     is_alpha, is_blank, is_blank_or_breakz, is_break, is_breakz, is_digit, is_flow, is_z,
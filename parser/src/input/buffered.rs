@@ -13,7 +13,7 @@ use arraydeque::ArrayDeque;
 /// This constant must be set to at least 8. When scanning indent in scalars, the lookahead is done
 /// in a single call if and only if the indent is `BUFFER_LEN - 2` or less. If the indent is higher
 /// than that, the code will fall back to a loop of lookaheads.
-const BUFFER_LEN: usize = 16;
+pub(crate) const BUFFER_LEN: usize = 16;
 
 /// A wrapper around an [`Iterator`] of [`char`]s with a buffer.
 ///
@@ -0,0 +1,146 @@
+use crate::char_traits::is_breakz;
+use crate::input::{buffered::BUFFER_LEN, Input};
+
+use arraydeque::ArrayDeque;
+
+/// A parser input that uses an [`Iterator`] of string chunks as source.
+///
+/// Chunks may be anything that dereferences to a `str` (e.g.: `&str` or `String`) and can be
+/// split at any character boundary, including in the middle of a line or of a token. This is
+/// useful when the input arrives in pieces, such as lines from a pipe or frames of an HTTP body.
+///
+/// Compared to using a [`BufferedInput`] over an iterator of `char`s, characters are copied from
+/// the current chunk into the lookahead buffer in bulk rather than pulled one at a time through
+/// the iterator.
+///
+/// [`BufferedInput`]: crate::BufferedInput
+#[allow(clippy::module_name_repetitions)]
+pub struct ChunkedInput<I, S>
+where
+    I: Iterator<Item = S>,
+    S: AsRef<str>,
+{
+    /// The chunks that are yet to be read.
+    chunks: I,
+    /// The chunk we are currently reading from, if any.
+    chunk: Option<S>,
+    /// The byte offset in `chunk` of the next character to read.
+    offset: usize,
+    /// Buffer for the next characters to consume.
+    buffer: ArrayDeque<char, BUFFER_LEN>,
+}
+
+impl<I, S> ChunkedInput<I, S>
+where
+    I: Iterator<Item = S>,
+    S: AsRef<str>,
+{
+    /// Create a new [`ChunkedInput`] reading from the given chunks.
+    pub fn new(chunks: I) -> Self {
+        Self {
+            chunks,
+            chunk: None,
+            offset: 0,
+            buffer: ArrayDeque::default(),
+        }
+    }
+
+    /// Return the unread part of the current chunk, fetching the next non-empty chunk if needed.
+    ///
+    /// Returns `None` once all chunks have been read.
+    fn remaining(&mut self) -> Option<&str> {
+        while self
+            .chunk
+            .as_ref()
+            .map_or(true, |chunk| self.offset >= chunk.as_ref().len())
+        {
+            self.chunk = Some(self.chunks.next()?);
+            self.offset = 0;
+        }
+        self.chunk
+            .as_ref()
+            .map(|chunk| &chunk.as_ref()[self.offset..])
+    }
+
+    /// Read the next character from the chunks, bypassing the buffer.
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.remaining()?.chars().next()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+}
+
+impl<I, S> Input for ChunkedInput<I, S>
+where
+    I: Iterator<Item = S>,
+    S: AsRef<str>,
+{
+    #[inline]
+    fn lookahead(&mut self, count: usize) {
+        while self.buffer.len() < count {
+            let missing = count - self.buffer.len();
+            let Some(remaining) = self.remaining() else {
+                // Pad with `\0`s once the input is exhausted.
+                for _ in 0..missing {
+                    self.buffer.push_back('\0').unwrap();
+                }
+                return;
+            };
+            // Copy as many characters as we can from the current chunk at once.
+            let mut consumed = 0;
+            let mut chars = ArrayDeque::<char, BUFFER_LEN>::new();
+            for c in remaining.chars().take(missing) {
+                consumed += c.len_utf8();
+                chars.push_back(c).unwrap();
+            }
+            self.offset += consumed;
+            self.buffer.extend_back(chars);
+        }
+    }
+
+    #[inline]
+    fn buflen(&self) -> usize {
+        self.buffer.len()
+    }
+
+    #[inline]
+    fn bufmaxlen(&self) -> usize {
+        BUFFER_LEN
+    }
+
+    #[inline]
+    fn raw_read_ch(&mut self) -> char {
+        self.next_char().unwrap_or('\0')
+    }
+
+    #[inline]
+    fn raw_read_non_breakz_ch(&mut self) -> Option<char> {
+        let c = self.next_char()?;
+        if is_breakz(c) {
+            self.buffer.push_back(c).unwrap();
+            None
+        } else {
+            Some(c)
+        }
+    }
+
+    #[inline]
+    fn skip(&mut self) {
+        self.buffer.pop_front();
+    }
+
+    #[inline]
+    fn skip_n(&mut self, count: usize) {
+        self.buffer.drain(0..count);
+    }
+
+    #[inline]
+    fn peek(&self) -> char {
+        self.buffer[0]
+    }
+
+    #[inline]
+    fn peek_nth(&self, n: usize) -> char {
+        self.buffer[n]
+    }
+}
@@ -1,3 +1,5 @@
+use alloc::string::String;
+
 use crate::{
     char_traits::{
         is_alpha, is_blank, is_blank_or_breakz, is_break, is_breakz, is_digit, is_flow, is_z,
@@ -15,6 +17,11 @@ pub struct StrInput<'a> {
     /// We must however keep track of how many characters the parser asked us to look ahead for so
     /// that we can return the correct value in [`Self::buflen`].
     lookahead: usize,
+    /// Whether the input contains only ASCII characters.
+    ///
+    /// Characters are then single bytes, which can be indexed directly instead of decoding UTF-8.
+    /// The input is only ever shortened from the front, so this stays true once set.
+    ascii: bool,
 }
 
 impl<'a> StrInput<'a> {
@@ -24,6 +31,42 @@ impl<'a> StrInput<'a> {
         Self {
             buffer: input,
             lookahead: 0,
+            ascii: input.is_ascii(),
+        }
+    }
+
+    /// Split the first character of the input and return it along with the rest of the input.
+    #[inline]
+    fn split_first(&self) -> Option<(char, &'a str)> {
+        if self.ascii {
+            let c = *self.buffer.as_bytes().first()?;
+            Some((c as char, &self.buffer[1..]))
+        } else {
+            split_first_char(self.buffer)
+        }
+    }
+
+    /// Return the number of characters before the first breakz in `s` and the rest of `s` from
+    /// that breakz.
+    #[inline]
+    fn split_at_breakz(&self, s: &'a str) -> (usize, &'a str) {
+        if self.ascii {
+            let n = s
+                .bytes()
+                .position(|b| is_breakz(b as char))
+                .unwrap_or(s.len());
+            (n, &s[n..])
+        } else {
+            let mut rest = s;
+            let mut count = 0;
+            while let Some((c, sub_str)) = split_first_char(rest) {
+                if is_breakz(c) {
+                    break;
+                }
+                rest = sub_str;
+                count += 1;
+            }
+            (count, rest)
         }
     }
 }
@@ -53,9 +96,8 @@ impl Input for StrInput<'_> {
 
     #[inline]
     fn raw_read_ch(&mut self) -> char {
-        let mut chars = self.buffer.chars();
-        if let Some(c) = chars.next() {
-            self.buffer = chars.as_str();
+        if let Some((c, sub_str)) = self.split_first() {
+            self.buffer = sub_str;
             c
         } else {
             '\0'
@@ -64,7 +106,7 @@ impl Input for StrInput<'_> {
 
     #[inline]
     fn raw_read_non_breakz_ch(&mut self) -> Option<char> {
-        if let Some((c, sub_str)) = split_first_char(self.buffer) {
+        if let Some((c, sub_str)) = self.split_first() {
             if is_breakz(c) {
                 None
             } else {
@@ -78,14 +120,17 @@ impl Input for StrInput<'_> {
 
     #[inline]
     fn skip(&mut self) {
-        let mut chars = self.buffer.chars();
-        if chars.next().is_some() {
-            self.buffer = chars.as_str();
+        if let Some((_, sub_str)) = self.split_first() {
+            self.buffer = sub_str;
         }
     }
 
     #[inline]
     fn skip_n(&mut self, count: usize) {
+        if self.ascii {
+            self.buffer = &self.buffer[count.min(self.buffer.len())..];
+            return;
+        }
         let mut chars = self.buffer.chars();
         for _ in 0..count {
             if chars.next().is_none() {
@@ -97,11 +142,14 @@ impl Input for StrInput<'_> {
 
     #[inline]
     fn peek(&self) -> char {
-        self.buffer.chars().next().unwrap_or('\0')
+        self.split_first().map_or('\0', |(c, _)| c)
     }
 
     #[inline]
     fn peek_nth(&self, n: usize) -> char {
+        if self.ascii {
+            return self.buffer.as_bytes().get(n).map_or('\0', |&b| b as char);
+        }
         let mut chars = self.buffer.chars();
         for _ in 0..n {
             if chars.next().is_none() {
@@ -224,13 +272,9 @@ impl Input for StrInput<'_> {
             }
 
             // Skip remaining characters until we hit a breakz.
-            while let Some((c, sub_str)) = split_first_char(new_str) {
-                if is_breakz(c) {
-                    break;
-                }
-                new_str = sub_str;
-                chars_consumed += 1;
-            }
+            let (n_chars, rest) = self.split_at_breakz(new_str);
+            new_str = rest;
+            chars_consumed += n_chars;
         }
 
         self.buffer = new_str;
@@ -313,20 +357,8 @@ impl Input for StrInput<'_> {
     }
 
     fn skip_while_non_breakz(&mut self) -> usize {
-        let mut new_str = self.buffer;
-        let mut count = 0;
-
-        // Skip over all non-breaks.
-        while let Some((c, sub_str)) = split_first_char(new_str) {
-            if is_breakz(c) {
-                break;
-            }
-            new_str = sub_str;
-            count += 1;
-        }
-
-        self.buffer = new_str;
-
+        let (count, rest) = self.split_at_breakz(self.buffer);
+        self.buffer = rest;
         count
     }
 
@@ -442,4 +474,21 @@ mod test {
         assert!(input.next_is_document_end());
         assert!(input.next_is_document_indicator());
     }
+
+    #[test]
+    pub fn ascii_and_non_ascii_agree() {
+        for s in ["key: value # comment\nnext", "kéy: välue # cömment\nnext"] {
+            let mut input = StrInput::new(s);
+            assert_eq!(input.peek(), 'k');
+            assert_eq!(input.peek_nth(3), ':');
+            input.skip_n(5);
+            assert_eq!(input.peek(), 'v');
+            assert_eq!(input.skip_while_non_breakz(), 15);
+            assert_eq!(input.raw_read_ch(), '\n');
+            assert_eq!(input.raw_read_non_breakz_ch(), Some('n'));
+            input.skip_n(10);
+            assert_eq!(input.peek(), '\0');
+            assert_eq!(input.peek_nth(2), '\0');
+        }
+    }
 }
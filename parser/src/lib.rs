@@ -23,22 +23,94 @@
 //! # Features
 //! **Note:** With all features disabled, this crate's MSRV is `1.65.0`.
 //!
+//! #### `std` (_enabled by default_)
+//! Implements [`std::error::Error`] for the error types. Without it, the crate is `no_std` and
+//! only requires `alloc`, so that it can be used on targets such as `wasm32v1-none`. All the
+//! other features require it.
+//!
 //! #### `debug_prints`
 //! Enables the `debug` module and usage of debug prints in the scanner and the parser. Do not
 //! enable if you are consuming the crate rather than working on it as this can significantly
 //! decrease performance.
 //!
 //! The MSRV for this feature is `1.70.0`.
+//!
+//! #### `tracing`
+//! Emits diagnostics through the [`tracing`](https://docs.rs/tracing) crate. Unlike
+//! `debug_prints`, this is meant to be usable by consumers of the crate: nothing is printed unless
+//! a subscriber is installed, and events can be filtered by target and level.
+//!
+//! - [`Parser::load`] runs within a `DEBUG` span named `load`.
+//! - Each token produced by the scanner and each state the parser goes through is emitted as a
+//!   `TRACE` event, as well as each event returned by the parser.
+//! - Scanning errors are emitted as `DEBUG` events.
+//!
+//! #### `diagnostics`
+//! Implements [`miette::Diagnostic`](https://docs.rs/miette/latest/miette/trait.Diagnostic.html)
+//! for [`ScanError`] and, as warnings, for [`Lint`]. The error is labelled with its
+//! [span](ScanError::span) and, if any, the start of the construct it happened in, so that
+//! attaching the source to it is enough to get a caret-annotated report:
+//!
+//! ```ignore
+//! let report = miette::Report::new(error.with_source(source)).with_source_code(source.to_owned());
+//! eprintln!("{report:?}");
+//! ```
+//!
+//! Labels are byte offsets in the source, which [`ScanError::with_source`] and
+//! [`Lint::with_source`] compute from the [`Marker::index`]es, which count characters. Without
+//! it, labels are placed at the indices themselves and are misplaced in sources containing
+//! non-ASCII characters before the error.
+//!
+//! The MSRV for this feature is `1.70.0`.
+//!
+//! #### `serde`
+//! Implements [`serde::Serialize`](https://docs.rs/serde/latest/serde/trait.Serialize.html) for
+//! [`Event`], [`Span`] and the types they hold, so that event streams can be dumped in a
+//! machine-readable format (e.g. JSON) for golden tests or comparisons with other parsers. See
+//! [`Event`] for the layout of events; other types are serialized as their fields.
+//!
+//! #### `alloc_stats`
+//! Only affects the benchmarking tools (`run_bench` and `time_parse`). Replaces their global
+//! allocator with one that counts allocations, so that they can report the number of allocations
+//! and the peak memory usage of a parse alongside timings. Timings collected with this feature are
+//! not comparable with timings collected without it.
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs, clippy::pedantic)]
 
+extern crate alloc;
+
 mod char_traits;
 #[macro_use]
 mod debug;
 pub mod input;
 mod parser;
+mod record;
 mod scanner;
+mod split;
+
+pub use crate::input::{str::StrInput, BufferedInput, ChunkedInput, Input};
+pub use crate::parser::{
+    Event, EventContext, EventReceiver, ParseTagError, Parser, ParserStats, SpannedEventReceiver,
+    Tag,
+};
+pub use crate::record::RecordedEvents;
+pub use crate::scanner::{
+    tokenize, BlockScalarHeader, Chomping, Lint, LintKind, Marker, ScanError, ScanErrorKind, Span,
+    TEncoding, TScalarStyle, TokenType, Tokens,
+};
+pub use crate::split::{split_documents, DocumentSlice};
 
-pub use crate::input::{str::StrInput, BufferedInput, Input};
-pub use crate::parser::{Event, EventReceiver, Parser, SpannedEventReceiver, Tag};
-pub use crate::scanner::{Marker, ScanError, Span, TScalarStyle};
+// Events and errors are plain data and can be sent to and shared with other threads. Fail to
+// compile if that ever changes.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Event>();
+    assert_send_sync::<Tag>();
+    assert_send_sync::<ParserStats>();
+    assert_send_sync::<RecordedEvents>();
+    assert_send_sync::<Marker>();
+    assert_send_sync::<Span>();
+    assert_send_sync::<ScanError>();
+    assert_send_sync::<Lint>();
+};
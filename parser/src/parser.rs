@@ -6,11 +6,18 @@
 
 use crate::{
     input::{str::StrInput, Input},
-    scanner::{ScanError, Scanner, Span, TScalarStyle, Token, TokenType},
-    BufferedInput, Marker,
+    scanner::{Lint, ScanError, ScanErrorKind, Scanner, Span, TScalarStyle, Token, TokenType},
+    BufferedInput, ChunkedInput, Marker,
 };
 
-use std::collections::HashMap;
+use alloc::{
+    borrow::ToOwned,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
 
 #[derive(Clone, Copy, PartialEq, Debug, Eq)]
 enum State {
@@ -58,8 +65,14 @@ pub enum Event {
     /// When the boolean is `false`, it is an implicit document start
     /// (without `---`).
     DocumentStart(bool),
-    /// The YAML end document directive (`...`).
-    DocumentEnd,
+    /// The end of a YAML document.
+    ///
+    /// When the boolean is `true`, it is an explicit document end
+    /// directive (`...`).
+    ///
+    /// When the boolean is `false`, it is an implicit document end
+    /// (without `...`).
+    DocumentEnd(bool),
     /// A YAML Alias.
     Alias(
         /// The anchor ID the alias refers to.
@@ -68,6 +81,9 @@ pub enum Event {
     /// Value, style, `anchor_id`, tag
     Scalar(String, TScalarStyle, usize, Option<Tag>),
     /// The start of a YAML sequence (array).
+    ///
+    /// The span of the event is that of the opening `[` of a flow sequence. It is empty for a
+    /// block sequence.
     SequenceStart(
         /// The anchor ID of the start of the sequence.
         usize,
@@ -75,8 +91,14 @@ pub enum Event {
         Option<Tag>,
     ),
     /// The end of a YAML sequence (array).
+    ///
+    /// The span of the event is that of the closing `]` of a flow sequence. It is empty for a block
+    /// sequence.
     SequenceEnd,
     /// The start of a YAML mapping (object, hash).
+    ///
+    /// The span of the event is that of the opening `{` of a flow mapping. It is empty for a block
+    /// mapping and for a single pair mapping in a flow sequence (`[a: b]`).
     MappingStart(
         /// The anchor ID of the start of the mapping.
         usize,
@@ -84,16 +106,201 @@ pub enum Event {
         Option<Tag>,
     ),
     /// The end of a YAML mapping (object, hash).
+    ///
+    /// The span of the event is that of the closing `}` of a flow mapping. It is empty for a block
+    /// mapping and for a single pair mapping in a flow sequence.
     MappingEnd,
 }
 
 /// A YAML tag.
+///
+/// Tags on events are always resolved: `handle` holds the prefix the handle expands to given the
+/// `%TAG` directives in effect for the document. The handle as it was written is kept in
+/// `original_handle` so that the tag can be emitted back in its shorthand form.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Clone, PartialEq, Debug, Eq)]
 pub struct Tag {
-    /// Handle of the tag (`!` included).
+    /// Handle of the tag (`!` included), resolved to its prefix.
     pub handle: String,
     /// The suffix of the tag.
     pub suffix: String,
+    /// Handle of the tag as it was written in the document (`!!`, `!e!`, ...), before resolution.
+    ///
+    /// Empty for verbatim tags (see [`Tag::is_verbatim`]) and the non-specific `!` tag.
+    pub original_handle: String,
+}
+
+/// The prefix the `!!` handle resolves to, unless overridden by a `%TAG` directive.
+const CORE_SCHEMA_PREFIX: &str = "tag:yaml.org,2002:";
+
+impl Tag {
+    /// Create a tag from the YAML core schema (e.g.: `Tag::core("int")` for `!!int`).
+    #[must_use]
+    pub fn core(suffix: impl Into<String>) -> Self {
+        Self {
+            handle: CORE_SCHEMA_PREFIX.to_owned(),
+            suffix: suffix.into(),
+            original_handle: "!!".to_owned(),
+        }
+    }
+
+    /// Create a tag from a prefix and a suffix.
+    ///
+    /// ```
+    /// # use saphyr_parser::Tag;
+    /// let tag = Tag::custom("tag:example.com,2000:", "point");
+    /// assert_eq!(tag.resolved(), "tag:example.com,2000:point");
+    /// assert_eq!(tag.to_string(), "!<tag:example.com,2000:point>");
+    /// ```
+    #[must_use]
+    pub fn custom(prefix: impl Into<String>, suffix: impl Into<String>) -> Self {
+        Self {
+            handle: prefix.into(),
+            suffix: suffix.into(),
+            original_handle: String::new(),
+        }
+    }
+
+    /// Return whether the tag belongs to the YAML core schema (`tag:yaml.org,2002:`).
+    #[must_use]
+    pub fn is_core_schema(&self) -> bool {
+        self.handle == CORE_SCHEMA_PREFIX
+    }
+
+    /// Return whether the suffix of the tag is `suffix`.
+    ///
+    /// This does not check the handle. Combine with [`Tag::is_core_schema`] to match core types:
+    ///
+    /// ```
+    /// # use saphyr_parser::Tag;
+    /// let tag: Tag = "!!str".parse().unwrap();
+    /// assert!(tag.is_core_schema() && tag.suffix_is("str"));
+    /// ```
+    #[must_use]
+    pub fn suffix_is(&self, suffix: &str) -> bool {
+        self.suffix == suffix
+    }
+
+    /// Return whether the tag must be written in its verbatim form (`!<...>`).
+    ///
+    /// This is the case for tags that were written verbatim in the document and for tags that
+    /// have no handle to be written with, such as those created with [`Tag::custom`].
+    ///
+    /// ```
+    /// # use saphyr_parser::Tag;
+    /// let tag: Tag = "!<tag:example.com,2000:foo>".parse().unwrap();
+    /// assert!(tag.is_verbatim());
+    /// assert_eq!(tag.to_string(), "!<tag:example.com,2000:foo>");
+    /// ```
+    #[must_use]
+    pub fn is_verbatim(&self) -> bool {
+        self.original_handle.is_empty() && !self.is_non_specific()
+    }
+
+    /// Create a tag from the content of a verbatim tag (`!<...>`).
+    ///
+    /// Verbatim tags from the core schema are split so that [`Tag::is_core_schema`] and
+    /// [`Tag::suffix_is`] behave the same as with their shorthand form.
+    fn verbatim(uri: String) -> Self {
+        match uri.strip_prefix(CORE_SCHEMA_PREFIX) {
+            Some(suffix) if !suffix.is_empty() => Tag::custom(CORE_SCHEMA_PREFIX, suffix),
+            _ => Tag::custom(String::new(), uri),
+        }
+    }
+
+    /// Return whether the tag is the non-specific `!` tag.
+    fn is_non_specific(&self) -> bool {
+        self.original_handle.is_empty() && self.handle.is_empty() && self.suffix == "!"
+    }
+
+    /// Return the full, resolved tag (e.g.: `tag:yaml.org,2002:str`).
+    #[must_use]
+    pub fn resolved(&self) -> String {
+        format!("{}{}", self.handle, self.suffix)
+    }
+
+    /// Return the tag as it was written in the document (e.g.: `!!str`).
+    ///
+    /// Verbatim tags have no shorthand form; this returns their content without the `!<...>`
+    /// delimiters. Use the [`Display`](std::fmt::Display) implementation to format any tag.
+    #[must_use]
+    pub fn shorthand(&self) -> String {
+        format!("{}{}", self.original_handle, self.suffix)
+    }
+}
+
+impl fmt::Display for Tag {
+    /// Format the tag as it would appear in a YAML document.
+    ///
+    /// Tags that were written with a handle are formatted in their shorthand form. Verbatim tags
+    /// are formatted in their verbatim form (`!<...>`).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_verbatim() {
+            write!(f, "!<{}{}>", self.handle, self.suffix)
+        } else {
+            write!(f, "{}{}", self.original_handle, self.suffix)
+        }
+    }
+}
+
+impl core::str::FromStr for Tag {
+    type Err = ParseTagError;
+
+    /// Parse a tag as it would appear in a YAML document, outside of any `%TAG` directive.
+    ///
+    /// Accepted forms are `!!suffix`, `!suffix`, `!` and `!<verbatim>`. Named handles (`!e!foo`)
+    /// cannot be resolved without a directive and are rejected.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(suffix) = s.strip_prefix("!!") {
+            if suffix.is_empty() {
+                return Err(ParseTagError("missing suffix after `!!`"));
+            }
+            Ok(Tag::core(suffix))
+        } else if let Some(verbatim) = s.strip_prefix("!<") {
+            match verbatim.strip_suffix('>') {
+                Some(uri) if !uri.is_empty() => Ok(Tag::verbatim(uri.to_owned())),
+                _ => Err(ParseTagError("invalid verbatim tag")),
+            }
+        } else if s == "!" {
+            Ok(Tag {
+                handle: String::new(),
+                suffix: "!".to_owned(),
+                original_handle: String::new(),
+            })
+        } else if let Some(suffix) = s.strip_prefix('!') {
+            if suffix.contains('!') {
+                return Err(ParseTagError("named tag handles require a %TAG directive"));
+            }
+            Ok(Tag {
+                handle: "!".to_owned(),
+                suffix: suffix.to_owned(),
+                original_handle: "!".to_owned(),
+            })
+        } else {
+            Err(ParseTagError("a tag must start with `!`"))
+        }
+    }
+}
+
+/// The error returned when parsing a [`Tag`] from a string fails.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ParseTagError(&'static str);
+
+impl fmt::Display for ParseTagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseTagError {}
+
+/// Return the empty span right after the `:` of the [`TokenType::Value`] token spanning `span`.
+///
+/// This is where the empty scalar of a value which is missing after its `:` is.
+fn after_value_indicator(span: Span) -> Span {
+    let mark = span.start;
+    Span::empty(Marker::new(mark.index() + 1, mark.line(), mark.col() + 1))
 }
 
 impl Event {
@@ -109,6 +316,67 @@ impl Event {
     }
 }
 
+/// Events are serialized as variants with named fields, so that the output does not depend on the
+/// order of the fields of tuple variants:
+///   - `DocumentStart` and `DocumentEnd` have an `explicit` field;
+///   - `Alias` has an `anchor_id` field;
+///   - `Scalar` has `value`, `style`, `anchor_id` and `tag` fields;
+///   - `SequenceStart` and `MappingStart` have `anchor_id` and `tag` fields;
+///   - other events are unit variants.
+///
+/// This requires the `serde` feature.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Event {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStructVariant;
+
+        let (index, name) = match self {
+            Event::Nothing => (0, "Nothing"),
+            Event::StreamStart => (1, "StreamStart"),
+            Event::StreamEnd => (2, "StreamEnd"),
+            Event::DocumentStart(_) => (3, "DocumentStart"),
+            Event::DocumentEnd(_) => (4, "DocumentEnd"),
+            Event::Alias(_) => (5, "Alias"),
+            Event::Scalar(..) => (6, "Scalar"),
+            Event::SequenceStart(..) => (7, "SequenceStart"),
+            Event::SequenceEnd => (8, "SequenceEnd"),
+            Event::MappingStart(..) => (9, "MappingStart"),
+            Event::MappingEnd => (10, "MappingEnd"),
+        };
+        match self {
+            Event::Nothing
+            | Event::StreamStart
+            | Event::StreamEnd
+            | Event::SequenceEnd
+            | Event::MappingEnd => serializer.serialize_unit_variant("Event", index, name),
+            Event::DocumentStart(explicit) | Event::DocumentEnd(explicit) => {
+                let mut variant = serializer.serialize_struct_variant("Event", index, name, 1)?;
+                variant.serialize_field("explicit", explicit)?;
+                variant.end()
+            }
+            Event::Alias(anchor_id) => {
+                let mut variant = serializer.serialize_struct_variant("Event", index, name, 1)?;
+                variant.serialize_field("anchor_id", anchor_id)?;
+                variant.end()
+            }
+            Event::Scalar(value, style, anchor_id, tag) => {
+                let mut variant = serializer.serialize_struct_variant("Event", index, name, 4)?;
+                variant.serialize_field("value", value)?;
+                variant.serialize_field("style", style)?;
+                variant.serialize_field("anchor_id", anchor_id)?;
+                variant.serialize_field("tag", tag)?;
+                variant.end()
+            }
+            Event::SequenceStart(anchor_id, tag) | Event::MappingStart(anchor_id, tag) => {
+                let mut variant = serializer.serialize_struct_variant("Event", index, name, 2)?;
+                variant.serialize_field("anchor_id", anchor_id)?;
+                variant.serialize_field("tag", tag)?;
+                variant.end()
+            }
+        }
+    }
+}
+
 /// A YAML parser.
 #[derive(Debug)]
 pub struct Parser<T: Input> {
@@ -126,16 +394,29 @@ pub struct Parser<T: Input> {
     /// The next YAML event to emit.
     current: Option<(Event, Span)>,
     /// Anchors that have been encountered in the YAML document.
-    anchors: HashMap<String, usize>,
+    anchors: BTreeMap<String, usize>,
     /// Next ID available for an anchor.
     ///
     /// Every anchor is given a unique ID. We use an incrementing ID and this is both the ID to
     /// return for the next anchor and the count of anchor IDs emitted.
     anchor_id_count: usize,
+    /// The ID, name and span of the last anchor that was registered.
+    ///
+    /// The anchor belongs to the node of the event that is emitted right after it is registered.
+    /// This is used to report anchor names to [`SpannedEventReceiver::on_anchor`].
+    last_anchor: Option<(usize, String, Span)>,
+    /// The spans of the header and of the content of the last block scalar that was emitted.
+    ///
+    /// This is used to report headers to [`SpannedEventReceiver::on_block_scalar_header`].
+    last_block_scalar: Option<(Span, Span)>,
     /// The tag directives (`%TAG`) the parser has encountered.
     ///
     /// Key is the handle, and value is the prefix.
-    tags: HashMap<String, String>,
+    tags: BTreeMap<String, String>,
+    /// The `%YAML` directive of the current document, if any.
+    version_directive: Option<(u32, u32)>,
+    /// The `%TAG` directives declared by the current document, in order.
+    tag_directives: Vec<(String, String)>,
     /// Whether we have emitted [`Event::StreamEnd`].
     ///
     /// Emitted means that it has been returned from [`Self::next_token`]. If it is stored in
@@ -143,6 +424,85 @@ pub struct Parser<T: Input> {
     stream_end_emitted: bool,
     /// Make tags global across all documents.
     keep_tags: bool,
+    /// The positions of the opening brackets of the flow collections we are in.
+    ///
+    /// These are used as the context of errors happening within a flow collection.
+    flow_starts: Vec<Marker>,
+    /// Statistics about the events emitted so far, if they are collected.
+    stats: Option<ParserStats>,
+    /// The rules the documents of the stream must follow.
+    document_rules: DocumentRules,
+    /// The number of documents started so far.
+    documents: usize,
+}
+
+/// The rules the documents parsed by a [`Parser`] must follow.
+///
+/// See [`Parser::require_explicit_document_start`] and [`Parser::max_documents`].
+#[derive(Clone, Copy, Debug, Default)]
+struct DocumentRules {
+    /// Whether documents must start with `---`.
+    require_start: bool,
+    /// The maximum number of documents in the stream, if limited.
+    max_documents: Option<usize>,
+}
+
+/// Statistics about the events a [`Parser`] emitted.
+///
+/// These are collected when [`Parser::collect_stats`] is set and retrieved with
+/// [`Parser::stats`]. They are updated as events are emitted, so they can be checked while
+/// parsing, e.g. to reject a document by its shape before building a tree out of it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParserStats {
+    /// The number of documents started.
+    pub documents: usize,
+    /// The number of sequences started.
+    pub sequences: usize,
+    /// The number of mappings started.
+    pub mappings: usize,
+    /// The number of scalars, keys included.
+    pub scalars: usize,
+    /// The number of aliases.
+    pub aliases: usize,
+    /// The highest number of collections that were nested in one another.
+    pub max_depth: usize,
+    /// The total length of the contents of the scalars, in bytes.
+    ///
+    /// This is the length of the contents after escape sequences have been resolved and lines
+    /// folded, not the length of the scalars in the input.
+    pub scalar_bytes: usize,
+    /// The number of collections we are currently in.
+    depth: usize,
+}
+
+impl ParserStats {
+    /// Account for the given event.
+    fn record(&mut self, ev: &Event) {
+        match ev {
+            Event::DocumentStart(_) => self.documents += 1,
+            Event::SequenceStart(..) => {
+                self.sequences += 1;
+                self.enter_collection();
+            }
+            Event::MappingStart(..) => {
+                self.mappings += 1;
+                self.enter_collection();
+            }
+            Event::SequenceEnd | Event::MappingEnd => self.depth -= 1,
+            Event::Scalar(value, ..) => {
+                self.scalars += 1;
+                self.scalar_bytes += value.len();
+            }
+            Event::Alias(_) => self.aliases += 1,
+            Event::Nothing | Event::StreamStart | Event::StreamEnd | Event::DocumentEnd(_) => {}
+        }
+    }
+
+    /// Account for a collection being started.
+    fn enter_collection(&mut self) {
+        self.depth += 1;
+        self.max_depth = self.max_depth.max(self.depth);
+    }
 }
 
 /// Trait to be implemented in order to use the low-level parsing API.
@@ -225,6 +585,95 @@ pub trait EventReceiver {
 pub trait SpannedEventReceiver {
     /// Handler called for each event that occurs.
     fn on_event(&mut self, ev: Event, span: Span);
+
+    /// Handler called with the name of an anchor, right before the event of the anchored node.
+    ///
+    /// `id` is the anchor ID carried by the event and `span` is the span of the anchor property
+    /// (`&name`). This is only called by [`Parser::load`]. The default implementation does nothing.
+    fn on_anchor(&mut self, _id: usize, _name: &str, _span: Span) {}
+
+    /// Handler called with the span of the header of a block scalar, right before its event.
+    ///
+    /// The header is the `|` or `>` indicator along with its chomping and indentation indicators,
+    /// if any. `span` is the span of the event, which is that of the content of the scalar: it
+    /// starts at the first character of the first non-empty line and ends with the last one,
+    /// leaving out the indentation and line breaks around them. It is empty, right after the
+    /// header, if the scalar has no such line. This is only called by [`Parser::load`]. The
+    /// default implementation does nothing.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser, Span, SpannedEventReceiver};
+    /// struct Headers(Vec<(usize, usize)>);
+    ///
+    /// impl SpannedEventReceiver for Headers {
+    ///     fn on_event(&mut self, _: Event, _: Span) {}
+    ///
+    ///     fn on_block_scalar_header(&mut self, header: Span, span: Span) {
+    ///         self.0.push((header.start.index(), span.start.index()));
+    ///     }
+    /// }
+    ///
+    /// let mut headers = Headers(vec![]);
+    /// Parser::new_from_str("a: |-\n  b\n").load(&mut headers, false).unwrap();
+    /// assert_eq!(headers.0, [(3, 8)]);
+    /// ```
+    fn on_block_scalar_header(&mut self, _header: Span, _span: Span) {}
+
+    /// Handler called for each event that occurs, along with where it occurs in the document.
+    ///
+    /// [`Parser::load`] calls this rather than [`SpannedEventReceiver::on_event`]. Implement it
+    /// instead of mirroring the event stream to know the depth of an event or whether it is in a
+    /// flow collection. The default implementation calls [`SpannedEventReceiver::on_event`].
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, EventContext, Parser, Span, SpannedEventReceiver};
+    /// struct Depths(Vec<(usize, bool)>);
+    ///
+    /// impl SpannedEventReceiver for Depths {
+    ///     fn on_event(&mut self, _: Event, _: Span) {}
+    ///
+    ///     fn on_event_with_context(&mut self, ev: Event, _: Span, context: EventContext) {
+    ///         if let Event::Scalar(..) = ev {
+    ///             self.0.push((context.depth, context.in_flow));
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut depths = Depths(vec![]);
+    /// Parser::new_from_str("a: [b]").load(&mut depths, false).unwrap();
+    /// assert_eq!(depths.0, [(1, false), (2, true)]);
+    /// ```
+    fn on_event_with_context(&mut self, ev: Event, span: Span, _context: EventContext) {
+        self.on_event(ev, span);
+    }
+}
+
+/// Where an event occurs in the structure of a document.
+///
+/// This is given to [`SpannedEventReceiver::on_event_with_context`] by [`Parser::load`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EventContext {
+    /// The number of collections the event is nested in.
+    ///
+    /// The events starting and ending a collection are at the depth of the collection itself,
+    /// which is one less than the depth of its items. The events of the root node of a document,
+    /// as well as stream and document events, are at depth 0.
+    pub depth: usize,
+    /// Whether the event is in a flow collection.
+    ///
+    /// This is `true` for the items of a flow collection, as well as for the events starting and
+    /// ending it, since the brackets are part of the flow context.
+    pub in_flow: bool,
+}
+
+impl EventContext {
+    /// Return the context of the items of a collection in context `self`.
+    fn nested(self) -> Self {
+        EventContext {
+            depth: self.depth + 1,
+            ..self
+        }
+    }
 }
 
 impl<R: EventReceiver> SpannedEventReceiver for R {
@@ -257,6 +706,21 @@ where
     }
 }
 
+impl<I, S> Parser<ChunkedInput<I, S>>
+where
+    I: Iterator<Item = S>,
+    S: AsRef<str>,
+{
+    /// Create a new instance of a parser from an iterator of string chunks.
+    ///
+    /// See [`ChunkedInput`] for details.
+    #[must_use]
+    pub fn new_from_chunks(chunks: I) -> Self {
+        debug_print!("\x1B[;31m>>>>>>>>>> New parser from chunks\x1B[;0m");
+        Parser::new(ChunkedInput::new(chunks))
+    }
+}
+
 impl<T: Input> Parser<T> {
     /// Create a new instance of a parser from the given input of characters.
     pub fn new(src: T) -> Parser<T> {
@@ -267,12 +731,20 @@ impl<T: Input> Parser<T> {
             token: None,
             current: None,
 
-            anchors: HashMap::new(),
+            anchors: BTreeMap::new(),
             // valid anchor_id starts from 1
             anchor_id_count: 1,
-            tags: HashMap::new(),
+            last_anchor: None,
+            last_block_scalar: None,
+            tags: BTreeMap::new(),
+            version_directive: None,
+            tag_directives: Vec::new(),
             stream_end_emitted: false,
             keep_tags: false,
+            flow_starts: Vec::new(),
+            stats: None,
+            document_rules: DocumentRules::default(),
+            documents: 0,
         }
     }
 
@@ -304,6 +776,216 @@ impl<T: Input> Parser<T> {
         self
     }
 
+    /// Set whether tags are kept across documents.
+    ///
+    /// This is the same setting as [`Parser::keep_tags`], but it can be changed while parsing. It
+    /// is checked at the end of each document, which allows the consumer to decide, document per
+    /// document, whether the `%TAG` directives in effect should carry over to the next one.
+    ///
+    /// When tags are kept, directives of the next document are added to the kept ones, overriding
+    /// handles that are declared again.
+    pub fn set_keep_tags(&mut self, value: bool) {
+        self.keep_tags = value;
+    }
+
+    /// Limit the number of characters allowed on a single line.
+    ///
+    /// Parsing fails with [`ScanErrorKind::LengthLimitExceeded`] as soon as a line exceeds the
+    /// limit, without reading the rest of it. This protects services from inputs made of a huge
+    /// single line, which would otherwise be read in full before anything else is checked (e.g.
+    /// into a scalar). Line breaks are not counted. By default, lines are not limited.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Parser, ScanErrorKind};
+    /// let input = format!("key: {}", "a".repeat(1000));
+    /// let mut parser = Parser::new_from_str(&input).max_line_length(100);
+    /// let error = parser.find_map(Result::err).unwrap();
+    /// assert_eq!(error.kind(), ScanErrorKind::LengthLimitExceeded);
+    /// ```
+    #[must_use]
+    pub fn max_line_length(mut self, limit: usize) -> Self {
+        self.scanner.set_max_line_length(Some(limit));
+        self
+    }
+
+    /// Limit the number of characters allowed in a single comment, including its `#`.
+    ///
+    /// Parsing fails with [`ScanErrorKind::LengthLimitExceeded`] as soon as a comment exceeds the
+    /// limit, without reading the rest of it. By default, comments are not limited, other than by
+    /// [`Parser::max_line_length`].
+    #[must_use]
+    pub fn max_comment_length(mut self, limit: usize) -> Self {
+        self.scanner.set_max_comment_length(Some(limit));
+        self
+    }
+
+    /// Whether documents must start with an explicit document start marker (`---`).
+    ///
+    /// When set, parsing fails with [`ScanErrorKind::DocumentNotAllowed`] at the first token of
+    /// a bare document, that is a document with neither `---` nor directives. By default, bare
+    /// documents are allowed.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Parser, ScanErrorKind};
+    /// let mut parser = Parser::new_from_str("---\na: b\n").require_explicit_document_start(true);
+    /// assert!(parser.all(|event| event.is_ok()));
+    /// let mut parser =
+    ///     Parser::new_from_str("# config\na: b\n").require_explicit_document_start(true);
+    /// let error = parser.find_map(Result::err).unwrap();
+    /// assert_eq!(error.kind(), ScanErrorKind::DocumentNotAllowed);
+    /// assert_eq!(error.marker().line(), 2);
+    /// ```
+    #[must_use]
+    pub fn require_explicit_document_start(mut self, value: bool) -> Self {
+        self.document_rules.require_start = value;
+        self
+    }
+
+    /// Whether the stream may contain at most one document.
+    ///
+    /// When set, parsing fails with [`ScanErrorKind::DocumentNotAllowed`] at the start of the
+    /// second document, before any of its content is parsed. A stream without documents is
+    /// accepted. By default, the number of documents is not limited. This is the same as
+    /// [`Parser::max_documents`] with a limit of 1. Unsetting it only removes a limit of 1.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Parser, ScanErrorKind};
+    /// let mut parser = Parser::new_from_str("a: b\n---\nc: d\n").forbid_multiple_documents(true);
+    /// let error = parser.find_map(Result::err).unwrap();
+    /// assert_eq!(error.kind(), ScanErrorKind::DocumentNotAllowed);
+    /// assert_eq!(error.marker().line(), 2);
+    /// ```
+    #[must_use]
+    pub fn forbid_multiple_documents(mut self, value: bool) -> Self {
+        if value {
+            self.document_rules.max_documents = Some(1);
+        } else if self.document_rules.max_documents == Some(1) {
+            self.document_rules.max_documents = None;
+        }
+        self
+    }
+
+    /// Limit the number of documents in the stream.
+    ///
+    /// Parsing fails with [`ScanErrorKind::DocumentNotAllowed`] at the start of the document
+    /// which exceeds the limit, before any of its content is parsed. This protects services from
+    /// streams of many small documents, each of which would otherwise be loaded. By default, the
+    /// number of documents is not limited.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Parser, ScanErrorKind};
+    /// let input = "--- a\n".repeat(1000);
+    /// let mut parser = Parser::new_from_str(&input).max_documents(100);
+    /// let error = parser.find_map(Result::err).unwrap();
+    /// assert_eq!(error.kind(), ScanErrorKind::DocumentNotAllowed);
+    /// assert_eq!(error.marker().line(), 101);
+    /// ```
+    #[must_use]
+    pub fn max_documents(mut self, limit: usize) -> Self {
+        self.document_rules.max_documents = Some(limit);
+        self
+    }
+
+    /// Whether to collect statistics about the emitted events.
+    ///
+    /// When set, [`Parser::stats`] returns the number of documents, collections, scalars and
+    /// aliases emitted so far, along with the maximum nesting depth and the total size of the
+    /// scalars. By default, statistics are not collected.
+    ///
+    /// ```
+    /// # use saphyr_parser::Parser;
+    /// let mut parser = Parser::new_from_str("a: [b, {c: d}]\n---\ne").collect_stats(true);
+    /// for event in parser.by_ref() {
+    ///     event.unwrap();
+    /// }
+    /// let stats = parser.stats().unwrap();
+    /// assert_eq!(stats.documents, 2);
+    /// assert_eq!(stats.max_depth, 3);
+    /// assert_eq!(stats.scalar_bytes, 5);
+    /// ```
+    #[must_use]
+    pub fn collect_stats(mut self, value: bool) -> Self {
+        self.stats = value.then(ParserStats::default);
+        self
+    }
+
+    /// Return the statistics about the events emitted so far.
+    ///
+    /// Returns `None` unless [`Parser::collect_stats`] is set.
+    #[must_use]
+    pub fn stats(&self) -> Option<&ParserStats> {
+        self.stats.as_ref()
+    }
+
+    /// Return the lints found so far, in the order they were found.
+    ///
+    /// Lints are valid YAML constructs which other implementations may reject (see
+    /// [`LintKind`](crate::LintKind)).
+    /// The scanner reads ahead of the events, so lints may be found before the events around
+    /// them are emitted.
+    ///
+    /// ```
+    /// # use saphyr_parser::{LintKind, Parser};
+    /// let mut parser = Parser::new_from_str("{a:\tb}");
+    /// for event in parser.by_ref() {
+    ///     event.unwrap();
+    /// }
+    /// let lints = parser.lints();
+    /// assert_eq!(lints.len(), 1);
+    /// assert_eq!(lints[0].kind(), LintKind::TabAfterFlowIndicator);
+    /// assert_eq!(lints[0].span().start.index(), 3);
+    /// ```
+    #[must_use]
+    pub fn lints(&self) -> &[Lint] {
+        self.scanner.lints()
+    }
+
+    /// Return the `%YAML` directive of the current document as `(major, minor)`, if any.
+    ///
+    /// The current document is the one whose [`Event::DocumentStart`] was last emitted. Directives
+    /// are still available after its [`Event::DocumentEnd`] was emitted, until the next document
+    /// starts.
+    #[must_use]
+    pub fn version_directive(&self) -> Option<(u32, u32)> {
+        self.version_directive
+    }
+
+    /// Return the `%TAG` directives of the current document as `(handle, prefix)` pairs.
+    ///
+    /// Only the directives that appear right before the current document are returned, even if
+    /// [`Parser::keep_tags`] is set. See [`Parser::version_directive`] for which document is the
+    /// current one.
+    #[must_use]
+    pub fn tag_directives(&self) -> &[(String, String)] {
+        &self.tag_directives
+    }
+
+    /// Return the name of the anchor with ID `id`, if it is the last anchor that was registered.
+    ///
+    /// The anchor of a node is registered right before the event of the node is emitted. This
+    /// gives the anchor names of events read with [`Parser::next_event`], which [`Parser::load`]
+    /// reports to [`SpannedEventReceiver::on_anchor`] instead.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Event, Parser};
+    /// let mut parser = Parser::new_from_str("&a [x]");
+    /// let id = parser
+    ///     .find_map(|ev| match ev.unwrap().0 {
+    ///         Event::SequenceStart(id, _) => Some(id),
+    ///         _ => None,
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(parser.anchor_name(id), Some("a"));
+    /// assert_eq!(parser.anchor_name(0), None);
+    /// ```
+    #[must_use]
+    pub fn anchor_name(&self, id: usize) -> Option<&str> {
+        match &self.last_anchor {
+            Some((anchor_id, name, _)) if *anchor_id == id => Some(name),
+            _ => None,
+        }
+    }
+
     /// Try to load the next event and return it, but do not consuming it from `self`.
     ///
     /// Any subsequent call to [`Parser::peek`] will return the same value, until a call to
@@ -372,7 +1054,11 @@ impl<T: Input> Parser<T> {
         let token = self.scanner.next();
         match token {
             None => match self.scanner.get_error() {
-                None => Err(ScanError::new_str(self.scanner.mark(), "unexpected eof")),
+                None => Err(ScanError::new_with_kind(
+                    self.scanner.mark(),
+                    ScanErrorKind::UnexpectedEof,
+                    "unexpected eof",
+                )),
                 Some(e) => Err(e),
             },
             Some(tok) => Ok(tok),
@@ -398,12 +1084,30 @@ impl<T: Input> Parser<T> {
         self.states.push(state);
     }
 
+    /// Create an error about the flow collection we are in, found at the token spanning `span`.
+    fn flow_error(&self, span: Span, info: &str) -> ScanError {
+        let error =
+            ScanError::new_with_kind(span.start, ScanErrorKind::UnclosedFlow, info).with_span(span);
+        match self.flow_starts.last() {
+            Some(start) => error.with_context(*start),
+            None => error,
+        }
+    }
+
     fn parse(&mut self) -> ParseResult {
         if self.state == State::End {
             return Ok((Event::StreamEnd, Span::empty(self.scanner.mark())));
         }
-        let (ev, mark) = self.state_machine()?;
-        Ok((ev, mark))
+        let result = self.state_machine();
+        if let (Some(stats), Ok((ev, _))) = (&mut self.stats, &result) {
+            stats.record(ev);
+        }
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok((ev, span)) => tracing::trace!(event = ?ev, span = ?span, "parsed event"),
+            Err(e) => tracing::debug!(error = %e, "scan error"),
+        }
+        result
     }
 
     /// Load the YAML from the stream in `self`, pushing events into `recv`.
@@ -423,26 +1127,35 @@ impl<T: Input> Parser<T> {
         recv: &mut R,
         multi: bool,
     ) -> Result<(), ScanError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("load", multi).entered();
+
         if !self.scanner.stream_started() {
             let (ev, span) = self.next_event_impl()?;
             if ev != Event::StreamStart {
-                return Err(ScanError::new_str(
+                return Err(ScanError::new_with_kind(
                     span.start,
+                    ScanErrorKind::UnexpectedToken,
                     "did not find expected <stream-start>",
-                ));
+                )
+                .with_span(span));
             }
-            recv.on_event(ev, span);
+            recv.on_event_with_context(ev, span, EventContext::default());
         }
 
         if self.scanner.stream_ended() {
             // XXX has parsed?
-            recv.on_event(Event::StreamEnd, Span::empty(self.scanner.mark()));
+            recv.on_event_with_context(
+                Event::StreamEnd,
+                Span::empty(self.scanner.mark()),
+                EventContext::default(),
+            );
             return Ok(());
         }
         loop {
             let (ev, span) = self.next_event_impl()?;
             if ev == Event::StreamEnd {
-                recv.on_event(ev, span);
+                recv.on_event_with_context(ev, span, EventContext::default());
                 return Ok(());
             }
             // clear anchors before a new document
@@ -462,20 +1175,22 @@ impl<T: Input> Parser<T> {
         recv: &mut R,
     ) -> Result<(), ScanError> {
         if !matches!(first_ev, Event::DocumentStart(_)) {
-            return Err(ScanError::new_str(
+            return Err(ScanError::new_with_kind(
                 span.start,
+                ScanErrorKind::UnexpectedToken,
                 "did not find expected <document-start>",
-            ));
+            )
+            .with_span(span));
         }
-        recv.on_event(first_ev, span);
+        recv.on_event_with_context(first_ev, span, EventContext::default());
 
         let (ev, span) = self.next_event_impl()?;
-        self.load_node(ev, span, recv)?;
+        self.load_node(ev, span, EventContext::default(), recv)?;
 
         // DOCUMENT-END is expected.
         let (ev, mark) = self.next_event_impl()?;
-        assert_eq!(ev, Event::DocumentEnd);
-        recv.on_event(ev, mark);
+        assert!(matches!(ev, Event::DocumentEnd(_)));
+        recv.on_event_with_context(ev, mark, EventContext::default());
 
         Ok(())
     }
@@ -484,58 +1199,91 @@ impl<T: Input> Parser<T> {
         &mut self,
         first_ev: Event,
         span: Span,
+        context: EventContext,
         recv: &mut R,
     ) -> Result<(), ScanError> {
+        if let Event::Scalar(_, _, anchor_id, _)
+        | Event::SequenceStart(anchor_id, _)
+        | Event::MappingStart(anchor_id, _) = first_ev
+        {
+            if let Some((id, name, anchor_span)) = &self.last_anchor {
+                if *id == anchor_id {
+                    recv.on_anchor(anchor_id, name, *anchor_span);
+                }
+            }
+        }
+        if let Event::Scalar(_, TScalarStyle::Literal(_) | TScalarStyle::Folded(_), ..) = first_ev {
+            if let Some((header, content)) = self.last_block_scalar {
+                if content == span {
+                    recv.on_block_scalar_header(header, span);
+                }
+            }
+        }
         match first_ev {
             Event::Alias(..) | Event::Scalar(..) => {
-                recv.on_event(first_ev, span);
+                recv.on_event_with_context(first_ev, span, context);
                 Ok(())
             }
             Event::SequenceStart(..) => {
-                recv.on_event(first_ev, span);
-                self.load_sequence(recv)
+                let context = EventContext {
+                    in_flow: context.in_flow || self.state == State::FlowSequenceFirstEntry,
+                    ..context
+                };
+                recv.on_event_with_context(first_ev, span, context);
+                self.load_sequence(context, recv)
             }
             Event::MappingStart(..) => {
-                recv.on_event(first_ev, span);
-                self.load_mapping(recv)
+                let context = EventContext {
+                    in_flow: context.in_flow || self.state == State::FlowMappingFirstKey,
+                    ..context
+                };
+                recv.on_event_with_context(first_ev, span, context);
+                self.load_mapping(context, recv)
             }
             _ => {
-                println!("UNREACHABLE EVENT: {first_ev:?}");
-                unreachable!();
+                unreachable!("unreachable event: {first_ev:?}");
             }
         }
     }
 
-    fn load_mapping<R: SpannedEventReceiver>(&mut self, recv: &mut R) -> Result<(), ScanError> {
+    fn load_mapping<R: SpannedEventReceiver>(
+        &mut self,
+        context: EventContext,
+        recv: &mut R,
+    ) -> Result<(), ScanError> {
         let (mut key_ev, mut key_mark) = self.next_event_impl()?;
         while key_ev != Event::MappingEnd {
             // key
-            self.load_node(key_ev, key_mark, recv)?;
+            self.load_node(key_ev, key_mark, context.nested(), recv)?;
 
             // value
             let (ev, mark) = self.next_event_impl()?;
-            self.load_node(ev, mark, recv)?;
+            self.load_node(ev, mark, context.nested(), recv)?;
 
             // next event
             let (ev, mark) = self.next_event_impl()?;
             key_ev = ev;
             key_mark = mark;
         }
-        recv.on_event(key_ev, key_mark);
+        recv.on_event_with_context(key_ev, key_mark, context);
         Ok(())
     }
 
-    fn load_sequence<R: SpannedEventReceiver>(&mut self, recv: &mut R) -> Result<(), ScanError> {
+    fn load_sequence<R: SpannedEventReceiver>(
+        &mut self,
+        context: EventContext,
+        recv: &mut R,
+    ) -> Result<(), ScanError> {
         let (mut ev, mut mark) = self.next_event_impl()?;
         while ev != Event::SequenceEnd {
-            self.load_node(ev, mark, recv)?;
+            self.load_node(ev, mark, context.nested(), recv)?;
 
             // next event
             let (next_ev, next_mark) = self.next_event_impl()?;
             ev = next_ev;
             mark = next_mark;
         }
-        recv.on_event(ev, mark);
+        recv.on_event_with_context(ev, mark, context);
         Ok(())
     }
 
@@ -543,6 +1291,8 @@ impl<T: Input> Parser<T> {
         // let next_tok = self.peek_token().cloned()?;
         // println!("cur_state {:?}, next tok: {:?}", self.state, next_tok);
         debug_print!("\n\x1B[;33mParser state: {:?} \x1B[;0m", self.state);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(state = ?self.state, "parser state");
 
         match self.state {
             State::StreamStart => self.stream_start(),
@@ -588,10 +1338,12 @@ impl<T: Input> Parser<T> {
                 self.skip();
                 Ok((Event::StreamStart, span))
             }
-            Token(span, _) => Err(ScanError::new_str(
+            Token(span, _) => Err(ScanError::new_with_kind(
                 span.start,
+                ScanErrorKind::UnexpectedToken,
                 "did not find expected <stream-start>",
-            )),
+            )
+            .with_span(span)),
         }
     }
 
@@ -600,12 +1352,34 @@ impl<T: Input> Parser<T> {
             self.skip();
         }
 
+        let limit = self
+            .document_rules
+            .max_documents
+            .filter(|&limit| self.documents >= limit);
         match *self.peek_token()? {
             Token(span, TokenType::StreamEnd) => {
                 self.state = State::End;
                 self.skip();
-                Ok((Event::StreamEnd, span))
+                return Ok((Event::StreamEnd, span));
             }
+            Token(span, _) if limit.is_some() => {
+                let info = match limit {
+                    Some(1) => "found a second document where only one is allowed".to_owned(),
+                    _ => format!("found more documents than the limit of {}", self.documents),
+                };
+                return Err(ScanError::new_with_kind(
+                    span.start,
+                    ScanErrorKind::DocumentNotAllowed,
+                    info,
+                )
+                .with_span(span));
+            }
+            _ => {}
+        }
+        self.documents += 1;
+
+        let bare_allowed = !self.document_rules.require_start;
+        match *self.peek_token()? {
             Token(
                 _,
                 TokenType::VersionDirective(..)
@@ -615,6 +1389,12 @@ impl<T: Input> Parser<T> {
                 // explicit document
                 self.explicit_document_start()
             }
+            Token(span, _) if implicit && !bare_allowed => Err(ScanError::new_with_kind(
+                span.start,
+                ScanErrorKind::DocumentNotAllowed,
+                "did not find expected <document start>",
+            )
+            .with_span(span)),
             Token(span, _) if implicit => {
                 self.parser_process_directives()?;
                 self.push_state(State::DocumentEnd);
@@ -629,35 +1409,48 @@ impl<T: Input> Parser<T> {
     }
 
     fn parser_process_directives(&mut self) -> Result<(), ScanError> {
-        let mut version_directive_received = false;
+        let mut version_directive = None;
+        let mut tag_directives: Vec<(String, String)> = Vec::new();
         loop {
-            let mut tags = HashMap::new();
             match self.peek_token()? {
-                Token(span, TokenType::VersionDirective(_, _)) => {
+                Token(span, TokenType::VersionDirective(major, minor)) => {
                     // XXX parsing with warning according to spec
                     //if major != 1 || minor > 2 {
                     //    return Err(ScanError::new_str(tok.0,
                     //        "found incompatible YAML document"));
                     //}
-                    if version_directive_received {
-                        return Err(ScanError::new_str(
+                    if version_directive.is_some() {
+                        return Err(ScanError::new_with_kind(
                             span.start,
+                            ScanErrorKind::InvalidDirective,
                             "duplicate version directive",
-                        ));
+                        )
+                        .with_span(*span));
                     }
-                    version_directive_received = true;
+                    version_directive = Some((*major, *minor));
                 }
                 Token(mark, TokenType::TagDirective(handle, prefix)) => {
-                    if tags.contains_key(handle) {
-                        return Err(ScanError::new_str(mark.start, "the TAG directive must only be given at most once per handle in the same document"));
+                    // Tags kept from previous documents may be redeclared.
+                    if tag_directives.iter().any(|(h, _)| h == handle) {
+                        let info = "the TAG directive must only be given at most once per handle \
+                                    in the same document";
+                        return Err(ScanError::new_with_kind(
+                            mark.start,
+                            ScanErrorKind::InvalidDirective,
+                            info,
+                        )
+                        .with_span(*mark));
                     }
-                    tags.insert(handle.to_string(), prefix.to_string());
+                    let (handle, prefix) = (handle.clone(), prefix.clone());
+                    self.tags.insert(handle.clone(), prefix.clone());
+                    tag_directives.push((handle, prefix));
                 }
                 _ => break,
             }
-            self.tags = tags;
             self.skip();
         }
+        self.version_directive = version_directive;
+        self.tag_directives = tag_directives;
         Ok(())
     }
 
@@ -670,10 +1463,12 @@ impl<T: Input> Parser<T> {
                 self.skip();
                 Ok((Event::DocumentStart(true), mark))
             }
-            Token(span, _) => Err(ScanError::new_str(
+            Token(span, _) => Err(ScanError::new_with_kind(
                 span.start,
+                ScanErrorKind::UnexpectedToken,
                 "did not find expected <document start>",
-            )),
+            )
+            .with_span(span)),
         }
     }
 
@@ -715,18 +1510,20 @@ impl<T: Input> Parser<T> {
             if let Token(span, TokenType::VersionDirective(..) | TokenType::TagDirective(..)) =
                 *self.peek_token()?
             {
-                return Err(ScanError::new_str(
+                return Err(ScanError::new_with_kind(
                     span.start,
+                    ScanErrorKind::InvalidDirective,
                     "missing explicit document end marker before directive",
-                ));
+                )
+                .with_span(span));
             }
             self.state = State::DocumentStart;
         }
 
-        Ok((Event::DocumentEnd, span))
+        Ok((Event::DocumentEnd(explicit_end), span))
     }
 
-    fn register_anchor(&mut self, name: String, _: &Span) -> usize {
+    fn register_anchor(&mut self, name: String, span: &Span) -> usize {
         // anchors can be overridden/reused
         // if self.anchors.contains_key(name) {
         //     return Err(ScanError::new_str(*mark,
@@ -734,7 +1531,8 @@ impl<T: Input> Parser<T> {
         // }
         let new_id = self.anchor_id_count;
         self.anchor_id_count += 1;
-        self.anchors.insert(name, new_id);
+        self.anchors.insert(name.clone(), new_id);
+        self.last_anchor = Some((new_id, name, *span));
         new_id
     }
 
@@ -747,10 +1545,12 @@ impl<T: Input> Parser<T> {
                 if let Token(span, TokenType::Alias(name)) = self.fetch_token() {
                     match self.anchors.get(&name) {
                         None => {
-                            return Err(ScanError::new_str(
+                            return Err(ScanError::new_with_kind(
                                 span.start,
+                                ScanErrorKind::UnknownAnchor,
                                 "while parsing node, found unknown anchor",
-                            ))
+                            )
+                            .with_span(span))
                         }
                         Some(id) => return Ok((Event::Alias(*id), span)),
                     }
@@ -795,6 +1595,10 @@ impl<T: Input> Parser<T> {
             Token(_, TokenType::Scalar(..)) => {
                 self.pop_state();
                 if let Token(mark, TokenType::Scalar(style, v)) = self.fetch_token() {
+                    self.last_block_scalar = self
+                        .scanner
+                        .block_scalar_header()
+                        .map(|header| (header, mark));
                     Ok((Event::Scalar(v, style, anchor_id, tag), mark))
                 } else {
                     unreachable!()
@@ -821,10 +1625,12 @@ impl<T: Input> Parser<T> {
                 self.pop_state();
                 Ok((Event::empty_scalar_with_anchor(anchor_id, tag), mark))
             }
-            Token(span, _) => Err(ScanError::new_str(
+            Token(span, _) => Err(ScanError::new_with_kind(
                 span.start,
+                ScanErrorKind::UnexpectedToken,
                 "while parsing a node, did not find expected node content",
-            )),
+            )
+            .with_span(span)),
         }
     }
 
@@ -859,23 +1665,25 @@ impl<T: Input> Parser<T> {
                 self.skip();
                 Ok((Event::MappingEnd, mark))
             }
-            Token(span, _) => Err(ScanError::new_str(
+            Token(span, _) => Err(ScanError::new_with_kind(
                 span.start,
+                ScanErrorKind::UnexpectedToken,
                 "while parsing a block mapping, did not find expected key",
-            )),
+            )
+            .with_span(span)),
         }
     }
 
     fn block_mapping_value(&mut self) -> ParseResult {
         match *self.peek_token()? {
-            Token(_, TokenType::Value) => {
+            Token(value, TokenType::Value) => {
                 self.skip();
-                if let Token(mark, TokenType::Key | TokenType::Value | TokenType::BlockEnd) =
+                if let Token(_, TokenType::Key | TokenType::Value | TokenType::BlockEnd) =
                     *self.peek_token()?
                 {
                     self.state = State::BlockMappingKey;
                     // empty scalar
-                    Ok((Event::empty_scalar(), mark))
+                    Ok((Event::empty_scalar(), after_value_indicator(value)))
                 } else {
                     self.push_state(State::BlockMappingKey);
                     self.parse_node(true, true)
@@ -891,7 +1699,8 @@ impl<T: Input> Parser<T> {
 
     fn flow_mapping_key(&mut self, first: bool) -> ParseResult {
         if first {
-            let _ = self.peek_token()?;
+            let Token(span, _) = *self.peek_token()?;
+            self.flow_starts.push(span.start);
             self.skip();
         }
         let span: Span = {
@@ -901,8 +1710,8 @@ impl<T: Input> Parser<T> {
                     if !first {
                         match *self.peek_token()? {
                             Token(_, TokenType::FlowEntry) => self.skip(),
-                            Token(span, _) => return Err(ScanError::new_str(
-                                span.start,
+                            Token(span, _) => return Err(self.flow_error(
+                                span,
                                 "while parsing a flow mapping, did not find expected ',' or '}'",
                             )),
                         }
@@ -939,6 +1748,7 @@ impl<T: Input> Parser<T> {
         };
 
         self.pop_state();
+        self.flow_starts.pop();
         self.skip();
         Ok((Event::MappingEnd, span))
     }
@@ -960,7 +1770,7 @@ impl<T: Input> Parser<T> {
                             return self.parse_node(false, false);
                         }
                     }
-                    span
+                    after_value_indicator(span)
                 }
                 Token(marker, _) => marker,
             }
@@ -973,13 +1783,14 @@ impl<T: Input> Parser<T> {
     fn flow_sequence_entry(&mut self, first: bool) -> ParseResult {
         // skip FlowMappingStart
         if first {
-            let _ = self.peek_token()?;
-            //self.marks.push(tok.0);
+            let Token(span, _) = *self.peek_token()?;
+            self.flow_starts.push(span.start);
             self.skip();
         }
         match *self.peek_token()? {
             Token(mark, TokenType::FlowSequenceEnd) => {
                 self.pop_state();
+                self.flow_starts.pop();
                 self.skip();
                 return Ok((Event::SequenceEnd, mark));
             }
@@ -987,16 +1798,16 @@ impl<T: Input> Parser<T> {
                 self.skip();
             }
             Token(span, _) if !first => {
-                return Err(ScanError::new_str(
-                    span.start,
-                    "while parsing a flow sequence, expected ',' or ']'",
-                ));
+                return Err(
+                    self.flow_error(span, "while parsing a flow sequence, expected ',' or ']'")
+                );
             }
             _ => { /* next */ }
         }
         match *self.peek_token()? {
             Token(mark, TokenType::FlowSequenceEnd) => {
                 self.pop_state();
+                self.flow_starts.pop();
                 self.skip();
                 Ok((Event::SequenceEnd, mark))
             }
@@ -1059,10 +1870,12 @@ impl<T: Input> Parser<T> {
                     self.parse_node(true, false)
                 }
             }
-            Token(span, _) => Err(ScanError::new_str(
+            Token(span, _) => Err(ScanError::new_with_kind(
                 span.start,
+                ScanErrorKind::UnexpectedToken,
                 "while parsing a block collection, did not find expected '-' indicator",
-            )),
+            )
+            .with_span(span)),
         }
     }
 
@@ -1081,13 +1894,13 @@ impl<T: Input> Parser<T> {
 
     fn flow_sequence_entry_mapping_value(&mut self) -> ParseResult {
         match *self.peek_token()? {
-            Token(_, TokenType::Value) => {
+            Token(value, TokenType::Value) => {
                 self.skip();
                 self.state = State::FlowSequenceEntryMappingValue;
                 let Token(span, ref tok) = *self.peek_token()?;
                 if matches!(tok, TokenType::FlowEntry | TokenType::FlowSequenceEnd) {
                     self.state = State::FlowSequenceEntryMappingEnd(span.end);
-                    Ok((Event::empty_scalar(), span))
+                    Ok((Event::empty_scalar(), after_value_indicator(value)))
                 } else {
                     self.push_state(State::FlowSequenceEntryMappingEnd(span.end));
                     self.parse_node(false, false)
@@ -1115,28 +1928,35 @@ impl<T: Input> Parser<T> {
                 handle: self
                     .tags
                     .get("!!")
-                    .map_or_else(|| "tag:yaml.org,2002:".to_string(), ToString::to_string),
+                    .map_or_else(|| CORE_SCHEMA_PREFIX.to_string(), ToString::to_string),
                 suffix,
+                original_handle: handle.to_string(),
             })
         } else if handle.is_empty() && suffix == "!" {
             // "!" introduces a local tag. Local tags may have their prefix overridden.
             match self.tags.get("") {
                 Some(prefix) => Ok(Tag {
-                    handle: prefix.to_string(),
+                    handle: prefix.clone(),
                     suffix,
+                    original_handle: String::new(),
                 }),
                 None => Ok(Tag {
                     handle: String::new(),
                     suffix,
+                    original_handle: String::new(),
                 }),
             }
+        } else if handle.is_empty() {
+            // Verbatim tags are not subject to tag directives.
+            Ok(Tag::verbatim(suffix))
         } else {
             // Lookup handle in our tag directives.
             let prefix = self.tags.get(handle);
             if let Some(prefix) = prefix {
                 Ok(Tag {
-                    handle: prefix.to_string(),
+                    handle: prefix.clone(),
                     suffix,
+                    original_handle: handle.to_string(),
                 })
             } else {
                 // Otherwise, it may be a local handle. With a local handle, the handle is set to
@@ -1144,11 +1964,17 @@ impl<T: Input> Parser<T> {
                 // If the handle is of the form "!foo!", this cannot be a local handle and we need
                 // to error.
                 if handle.len() >= 2 && handle.starts_with('!') && handle.ends_with('!') {
-                    Err(ScanError::new_str(span.start, "the handle wasn't declared"))
+                    Err(ScanError::new_with_kind(
+                        span.start,
+                        ScanErrorKind::InvalidTag,
+                        "the handle wasn't declared",
+                    )
+                    .with_span(span))
                 } else {
                     Ok(Tag {
                         handle: handle.to_string(),
                         suffix,
+                        original_handle: handle.to_string(),
                     })
                 }
             }
@@ -1219,4 +2045,45 @@ baz: "qux"
         }
         panic!("Test failed, did not encounter error")
     }
+
+    #[test]
+    fn test_keep_tags_per_document() {
+        let text = "
+%TAG !t! tag:test,2024:
+--- !t!1
+a: b
+...
+%TAG !u! tag:other,2024:
+--- !t!2
+c: !u!3 d
+...
+--- !u!4
+e: f
+";
+        let mut parser = Parser::new_from_str(text);
+        let mut tags = vec![];
+        while let Some(x) = parser.next() {
+            match x.unwrap().0 {
+                Event::MappingStart(_, Some(tag)) | Event::Scalar(_, _, _, Some(tag)) => {
+                    tags.push((tag.resolved(), tag.shorthand()));
+                }
+                // Keep the tags of the first document only.
+                Event::DocumentStart(_) => parser.set_keep_tags(tags.is_empty()),
+                _ => {}
+            }
+            if tags.len() == 3 {
+                break;
+            }
+        }
+        assert_eq!(
+            tags,
+            [
+                ("tag:test,2024:1".to_owned(), "!t!1".to_owned()),
+                ("tag:test,2024:2".to_owned(), "!t!2".to_owned()),
+                ("tag:other,2024:3".to_owned(), "!u!3".to_owned()),
+            ]
+        );
+        // `!u!` was declared in the second document, whose tags were not kept.
+        assert!(parser.any(|x| x.is_err()));
+    }
 }
@@ -0,0 +1,121 @@
+//! Recording of event streams, to replay them later.
+
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+
+use crate::{Event, EventContext, Input, Parser, ScanError, Span, SpannedEventReceiver};
+
+/// An event stream recorded from a [`Parser`], which can be replayed into receivers.
+///
+/// [`RecordedEvents`] is itself a [`SpannedEventReceiver`] which records all it receives, along
+/// with the context of events, the names of anchors and the headers of block scalars. Replaying
+/// the recording into another receiver calls it exactly as [`Parser::load`] would have. This
+/// allows caching parsed documents without loading them, and loading them any number of times
+/// later on.
+///
+/// ```
+/// # use saphyr_parser::{Event, EventReceiver, Parser, RecordedEvents};
+/// struct Counter(usize);
+///
+/// impl EventReceiver for Counter {
+///     fn on_event(&mut self, _: Event) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// let recorded = RecordedEvents::record(&mut Parser::new_from_str("[a, b]"), true).unwrap();
+/// let mut counter = Counter(0);
+/// recorded.replay(&mut counter);
+/// recorded.replay(&mut counter);
+/// assert_eq!(counter.0, 16);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecordedEvents {
+    /// The calls received, in order.
+    calls: Vec<RecordedCall>,
+}
+
+/// A call received by a [`RecordedEvents`].
+#[derive(Clone, Debug, PartialEq)]
+enum RecordedCall {
+    /// A call to [`SpannedEventReceiver::on_event_with_context`].
+    Event(Event, Span, EventContext),
+    /// A call to [`SpannedEventReceiver::on_anchor`].
+    Anchor(usize, String, Span),
+    /// A call to [`SpannedEventReceiver::on_block_scalar_header`].
+    BlockScalarHeader(Span, Span),
+}
+
+impl RecordedEvents {
+    /// Create an empty recording.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the events of `parser`, as loaded by [`Parser::load`] with the given `multi`.
+    ///
+    /// # Errors
+    /// Returns `ScanError` when parsing fails. The events emitted before the error are lost.
+    pub fn record<T: Input>(parser: &mut Parser<T>, multi: bool) -> Result<Self, ScanError> {
+        let mut recorded = Self::new();
+        parser.load(&mut recorded, multi)?;
+        Ok(recorded)
+    }
+
+    /// Call `recv` with the recorded events, in the order they were received.
+    ///
+    /// Events are cloned, so that the recording can be replayed again.
+    pub fn replay<R: SpannedEventReceiver>(&self, recv: &mut R) {
+        for call in &self.calls {
+            match call {
+                RecordedCall::Event(ev, span, context) => {
+                    recv.on_event_with_context(ev.clone(), *span, *context);
+                }
+                RecordedCall::Anchor(id, name, span) => recv.on_anchor(*id, name, *span),
+                RecordedCall::BlockScalarHeader(header, span) => {
+                    recv.on_block_scalar_header(*header, *span);
+                }
+            }
+        }
+    }
+
+    /// Iterate over the recorded events along with their span.
+    pub fn events(&self) -> impl Iterator<Item = (&Event, Span)> {
+        self.calls.iter().filter_map(|call| match call {
+            RecordedCall::Event(ev, span, _) => Some((ev, *span)),
+            RecordedCall::Anchor(..) | RecordedCall::BlockScalarHeader(..) => None,
+        })
+    }
+
+    /// Return the number of recorded events.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.events().count()
+    }
+
+    /// Return whether no event was recorded.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.events().next().is_none()
+    }
+}
+
+impl SpannedEventReceiver for RecordedEvents {
+    fn on_event(&mut self, ev: Event, span: Span) {
+        self.on_event_with_context(ev, span, EventContext::default());
+    }
+
+    fn on_anchor(&mut self, id: usize, name: &str, span: Span) {
+        self.calls
+            .push(RecordedCall::Anchor(id, name.to_owned(), span));
+    }
+
+    fn on_block_scalar_header(&mut self, header: Span, span: Span) {
+        self.calls
+            .push(RecordedCall::BlockScalarHeader(header, span));
+    }
+
+    fn on_event_with_context(&mut self, ev: Event, span: Span, context: EventContext) {
+        self.calls.push(RecordedCall::Event(ev, span, context));
+    }
+}
@@ -9,14 +9,19 @@
 #![allow(clippy::cast_possible_wrap)]
 #![allow(clippy::cast_sign_loss)]
 
-use std::{char, collections::VecDeque, error::Error, fmt};
+use alloc::{
+    borrow::ToOwned, boxed::Box, collections::VecDeque, format, string::String, vec, vec::Vec,
+};
+use core::{char, fmt, ops::Range};
+#[cfg(feature = "std")]
+use std::error::Error;
 
 use crate::{
     char_traits::{
         as_hex, is_anchor_char, is_blank_or_breakz, is_break, is_breakz, is_flow, is_hex,
         is_tag_char, is_uri_char,
     },
-    input::{Input, SkipTabs},
+    input::{str::StrInput, Input, SkipTabs},
 };
 
 /// The encoding of the input. Currently, only UTF-8 is supported.
@@ -27,6 +32,7 @@ pub enum TEncoding {
 }
 
 /// The style as which the scalar was written in the YAML document.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Clone, Copy, PartialEq, Debug, Eq)]
 pub enum TScalarStyle {
     /// A YAML plain scalar.
@@ -37,12 +43,46 @@ pub enum TScalarStyle {
     DoubleQuoted,
 
     /// A YAML literal block (`|` block).
-    Literal,
+    Literal(BlockScalarHeader),
     /// A YAML folded block (`>` block).
-    Folded,
+    Folded(BlockScalarHeader),
+}
+
+/// The indicators written after the `|` or `>` of a block scalar.
+///
+/// See YAML spec 8.1.1.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Default)]
+pub struct BlockScalarHeader {
+    /// The chomping indicator (`-` or `+`), [`Chomping::Clip`] if there is none.
+    pub chomping: Chomping,
+    /// The indentation indicator (`1` to `9`), if any.
+    pub indent: Option<u8>,
+}
+
+impl fmt::Display for BlockScalarHeader {
+    /// Write the indicators as they would be in a YAML document, the indentation indicator first.
+    ///
+    /// ```
+    /// # use saphyr_parser::{BlockScalarHeader, Chomping};
+    /// let header = BlockScalarHeader { chomping: Chomping::Keep, indent: Some(2) };
+    /// assert_eq!(header.to_string(), "2+");
+    /// assert_eq!(BlockScalarHeader::default().to_string(), "");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(indent) = self.indent {
+            write!(f, "{indent}")?;
+        }
+        match self.chomping {
+            Chomping::Strip => write!(f, "-"),
+            Chomping::Clip => Ok(()),
+            Chomping::Keep => write!(f, "+"),
+        }
+    }
 }
 
 /// A location in a yaml document.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Clone, Copy, PartialEq, Debug, Eq, Default)]
 pub struct Marker {
     /// The index (in chars) in the input string.
@@ -60,7 +100,7 @@ impl Marker {
         Marker { index, line, col }
     }
 
-    /// Return the index (in bytes) of the marker in the source.
+    /// Return the index (in chars) of the marker in the source.
     #[must_use]
     pub fn index(&self) -> usize {
         self.index
@@ -80,6 +120,7 @@ impl Marker {
 }
 
 /// A range of locations in a Yaml document.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[derive(Clone, Copy, PartialEq, Debug, Eq, Default)]
 pub struct Span {
     /// The start (inclusive) of the range.
@@ -108,6 +149,92 @@ impl Span {
             end: mark,
         }
     }
+
+    /// Return the range of bytes the span covers in `input`, the source it was found in.
+    ///
+    /// The markers of the span count characters, which this converts to byte offsets. Returns
+    /// `None` if the span does not lie within `input`.
+    ///
+    /// ```
+    /// # use saphyr_parser::{Marker, Span};
+    /// let span = Span::new(Marker::new(2, 1, 2), Marker::new(7, 1, 7));
+    /// assert_eq!(&"é tiède"[span.byte_range("é tiède").unwrap()], "tiède");
+    /// ```
+    #[must_use]
+    pub fn byte_range(&self, input: &str) -> Option<Range<usize>> {
+        /// Return the byte offset of the character at `index` in `s`, or of the end of `s`.
+        fn byte_offset(s: &str, index: usize) -> Option<usize> {
+            s.char_indices()
+                .map(|(offset, _)| offset)
+                .chain([s.len()])
+                .nth(index)
+        }
+
+        let start = byte_offset(input, self.start.index)?;
+        let len = self.end.index.checked_sub(self.start.index)?;
+        let end = start + byte_offset(&input[start..], len)?;
+        Some(start..end)
+    }
+}
+
+/// The category of a [`ScanError`].
+///
+/// This allows matching on the kind of error that happened without relying on the error message,
+/// which is meant for humans and may change between versions.
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Hash)]
+#[non_exhaustive]
+pub enum ScanErrorKind {
+    /// The input ended in the middle of a construct.
+    UnexpectedEof,
+    /// A character was found where it is not allowed.
+    UnexpectedCharacter,
+    /// A valid token was found, but not one the parser expected at this point.
+    UnexpectedToken,
+    /// A line or a construct is not properly indented.
+    BadIndentation,
+    /// A tab was used where only spaces are allowed for indentation.
+    TabInIndent,
+    /// A flow sequence or mapping is missing its separator or closing bracket.
+    UnclosedFlow,
+    /// An indicator (`-`, `?`, `:`) is used where it is not allowed.
+    MisplacedIndicator,
+    /// An indicator or a comment is not followed or preceded by the required whitespace.
+    MissingWhitespace,
+    /// An implicit (simple) key is invalid, e.g. spans multiple lines or is missing its `:`.
+    InvalidSimpleKey,
+    /// A `%YAML` or `%TAG` directive is malformed, duplicated or misplaced.
+    InvalidDirective,
+    /// A tag is malformed or uses an undeclared handle.
+    InvalidTag,
+    /// An anchor or alias name is malformed.
+    InvalidAnchor,
+    /// An alias refers to an anchor that was not defined.
+    UnknownAnchor,
+    /// A quoted scalar contains an invalid escape sequence.
+    InvalidEscape,
+    /// A block scalar header is malformed.
+    InvalidBlockScalar,
+    /// The input is nested too deeply.
+    RecursionLimitExceeded,
+    /// A line or a comment is longer than allowed by [`Parser::max_line_length`] or
+    /// [`Parser::max_comment_length`].
+    ///
+    /// [`Parser::max_line_length`]: crate::Parser::max_line_length
+    /// [`Parser::max_comment_length`]: crate::Parser::max_comment_length
+    LengthLimitExceeded,
+    /// A document is not allowed by [`Parser::require_explicit_document_start`],
+    /// [`Parser::forbid_multiple_documents`] or [`Parser::max_documents`].
+    ///
+    /// [`Parser::require_explicit_document_start`]: crate::Parser::require_explicit_document_start
+    /// [`Parser::forbid_multiple_documents`]: crate::Parser::forbid_multiple_documents
+    /// [`Parser::max_documents`]: crate::Parser::max_documents
+    DocumentNotAllowed,
+    /// Aliases expand to more nodes than a loader allows, e.g. to prevent "billion laughs"
+    /// documents from loading into huge trees.
+    AliasLimitExceeded,
+    /// Any other error, including errors created with [`ScanError::new`] and
+    /// [`ScanError::new_str`].
+    Other,
 }
 
 /// An error that occurred while scanning.
@@ -115,32 +242,118 @@ impl Span {
 pub struct ScanError {
     /// The position at which the error happened in the source.
     mark: Marker,
+    /// The end of the span of the offending construct, which starts at `mark`.
+    end: Marker,
+    /// The position of the start of the construct within which the error happened, if any.
+    context: Option<Marker>,
+    /// The category of the error.
+    kind: ScanErrorKind,
     /// Human-readable details about the error.
     info: String,
+    /// The byte offsets of the span and of the context of the error in its source, if it was
+    /// given one with [`ScanError::with_source`].
+    ///
+    /// They are boxed to keep errors small.
+    byte_offsets: Option<Box<(Range<usize>, Option<usize>)>>,
 }
 
 impl ScanError {
     /// Create a new error from a location and an error string.
+    ///
+    /// The kind of the error is [`ScanErrorKind::Other`].
     #[must_use]
     pub fn new(loc: Marker, info: String) -> ScanError {
-        ScanError { mark: loc, info }
+        ScanError::new_with_kind(loc, ScanErrorKind::Other, info)
     }
 
     /// Convenience alias for string slices.
     #[must_use]
     pub fn new_str(loc: Marker, info: &str) -> ScanError {
+        ScanError::new(loc, info.to_owned())
+    }
+
+    /// Create a new error from a location, a kind and an error string.
+    #[must_use]
+    pub fn new_with_kind(loc: Marker, kind: ScanErrorKind, info: impl Into<String>) -> ScanError {
         ScanError {
             mark: loc,
-            info: info.to_owned(),
+            end: loc,
+            context: None,
+            kind,
+            info: info.into(),
+            byte_offsets: None,
         }
     }
 
+    /// Set the span of the offending construct.
+    ///
+    /// By default, the span of an error is empty and located at its marker. The marker of the error
+    /// is moved to the start of `span`.
+    #[must_use]
+    pub fn with_span(mut self, span: Span) -> ScanError {
+        self.mark = span.start;
+        self.end = span.end;
+        self.byte_offsets = None;
+        self
+    }
+
+    /// Set the position of the start of the construct within which the error happened.
+    ///
+    /// For instance, an error about a missing `]` has the position of the matching `[` as its
+    /// context.
+    #[must_use]
+    pub fn with_context(mut self, mark: Marker) -> ScanError {
+        self.context = Some(mark);
+        self.byte_offsets = None;
+        self
+    }
+
+    /// Locate the error in `source`, the input it was found in.
+    ///
+    /// Markers count characters, while the labels of the `miette` diagnostic of the error (see
+    /// the `diagnostics` feature) are byte offsets in the source. Without a source, the labels
+    /// are placed at the indices of the markers, which is wrong after non-ASCII characters. The
+    /// error is left as is if it does not lie within `source`.
+    #[must_use]
+    pub fn with_source(mut self, source: &str) -> ScanError {
+        let context = self
+            .context
+            .and_then(|context| Span::empty(context).byte_range(source))
+            .map(|range| range.start);
+        self.byte_offsets = self
+            .span()
+            .byte_range(source)
+            .map(|span| Box::new((span, context)));
+        self
+    }
+
     /// Return the marker pointing to the error in the source.
     #[must_use]
     pub fn marker(&self) -> &Marker {
         &self.mark
     }
 
+    /// Return the span of the offending construct.
+    #[must_use]
+    pub fn span(&self) -> Span {
+        Span::new(self.mark, self.end)
+    }
+
+    /// Return the position of the start of the construct within which the error happened, if
+    /// any.
+    ///
+    /// This is the structured counterpart of the "while scanning X" part of some error messages.
+    #[must_use]
+    pub fn context_marker(&self) -> Option<&Marker> {
+        self.context.as_ref()
+    }
+
+    /// Return the category of the error.
+    #[must_use]
+    pub fn kind(&self) -> ScanErrorKind {
+        self.kind
+    }
+
     /// Return the information string describing the error that happened.
     #[must_use]
     pub fn info(&self) -> &str {
@@ -148,6 +361,7 @@ impl ScanError {
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for ScanError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         None
@@ -167,6 +381,141 @@ impl fmt::Display for ScanError {
     }
 }
 
+#[cfg(feature = "diagnostics")]
+impl miette::Diagnostic for ScanError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new("saphyr_parser::scan_error"))
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let (span, context) = match self.byte_offsets.as_deref() {
+            Some((span, context)) => (span.clone(), *context),
+            None => (
+                self.mark.index..self.end.index,
+                self.context.map(|context| context.index),
+            ),
+        };
+        let context = context
+            .filter(|&context| context != span.start)
+            .map(|context| miette::LabeledSpan::at_offset(context, "started here"));
+        let span = miette::LabeledSpan::new(None, span.start, span.len());
+        Some(Box::new(core::iter::once(span).chain(context)))
+    }
+}
+
+/// The kind of a [`Lint`].
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum LintKind {
+    /// A `?` or `:` indicator inside a flow collection is separated from what follows it by tabs,
+    /// as in `{a:\tb}`.
+    ///
+    /// The YAML specification allows tabs there, but other implementations may only accept spaces.
+    TabAfterFlowIndicator,
+}
+
+/// A construct which is valid YAML but may not be accepted by other implementations, or is likely a
+/// mistake.
+///
+/// The scanner accepts these constructs and records a lint for each (see [`Parser::lints`]).
+///
+/// [`Parser::lints`]: crate::Parser::lints
+#[derive(Clone, Copy, PartialEq, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Lint {
+    /// What was found.
+    kind: LintKind,
+    /// The span of the offending characters.
+    span: Span,
+    /// The byte range of `span` in its source, if it was given one with [`Lint::with_source`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    byte_range: Option<(usize, usize)>,
+}
+
+impl Lint {
+    /// Create a lint of the given kind, about the characters in `span`.
+    #[must_use]
+    pub fn new(kind: LintKind, span: Span) -> Lint {
+        Lint {
+            kind,
+            span,
+            byte_range: None,
+        }
+    }
+
+    /// Locate the lint in `source`, the input it was found in.
+    ///
+    /// This places the label of its `miette` diagnostic at the right byte offsets. See
+    /// [`ScanError::with_source`].
+    #[must_use]
+    pub fn with_source(mut self, source: &str) -> Lint {
+        self.byte_range = self
+            .span
+            .byte_range(source)
+            .map(|range| (range.start, range.end));
+        self
+    }
+
+    /// Return the kind of the lint.
+    #[must_use]
+    pub fn kind(&self) -> LintKind {
+        self.kind
+    }
+
+    /// Return the span of the offending characters.
+    #[must_use]
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Return a human-readable description of the lint.
+    #[must_use]
+    pub fn message(&self) -> &'static str {
+        match self.kind {
+            LintKind::TabAfterFlowIndicator => "tabs separate a flow indicator from its content",
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{} at index {} line {} column {}",
+            self.message(),
+            self.span.start.index,
+            self.span.start.line,
+            self.span.start.col + 1,
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for Lint {}
+
+#[cfg(feature = "diagnostics")]
+impl miette::Diagnostic for Lint {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new("saphyr_parser::lint"))
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(miette::Severity::Warning)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let (start, end) = self
+            .byte_range
+            .unwrap_or((self.span.start.index, self.span.end.index));
+        Some(Box::new(core::iter::once(miette::LabeledSpan::new(
+            None,
+            start,
+            end - start,
+        ))))
+    }
+}
+
 /// The contents of a scanner token.
 #[derive(Clone, PartialEq, Debug, Eq)]
 pub enum TokenType {
@@ -453,6 +802,16 @@ pub struct Scanner<T> {
     /// [`Possible`]: ImplicitMappingState::Possible
     /// [`Inside`]: ImplicitMappingState::Inside
     implicit_flow_mapping_states: Vec<ImplicitMappingState>,
+    /// The maximum number of characters allowed on a single line, if any.
+    max_line_length: Option<usize>,
+    /// The maximum number of characters allowed in a single comment, if any.
+    max_comment_length: Option<usize>,
+    /// The lints found so far.
+    lints: Vec<Lint>,
+    /// The spans of the headers of the block scalars whose token is in [`Self::tokens`], in order.
+    block_scalar_headers: VecDeque<Span>,
+    /// The span of the header of the last token returned, if it is a block scalar.
+    last_block_scalar_header: Option<Span>,
     buf_leading_break: String,
     buf_trailing_breaks: String,
     buf_whitespaces: String,
@@ -471,6 +830,8 @@ impl<T: Input> Iterator for Scanner<T> {
                     tok.1,
                     tok.0
                 );
+                #[cfg(feature = "tracing")]
+                tracing::trace!(token = ?tok.1, span = ?tok.0, "scanned token");
                 Some(tok)
             }
             Ok(tok) => tok,
@@ -507,6 +868,11 @@ impl<T: Input> Scanner<T> {
             leading_whitespace: true,
             flow_mapping_started: false,
             implicit_flow_mapping_states: vec![],
+            max_line_length: None,
+            max_comment_length: None,
+            lints: Vec::new(),
+            block_scalar_headers: VecDeque::new(),
+            last_block_scalar_header: None,
 
             buf_leading_break: String::new(),
             buf_trailing_breaks: String::new(),
@@ -514,6 +880,20 @@ impl<T: Input> Scanner<T> {
         }
     }
 
+    /// Set the maximum number of characters allowed on a single line, if any.
+    ///
+    /// See [`crate::Parser::max_line_length`].
+    pub(crate) fn set_max_line_length(&mut self, limit: Option<usize>) {
+        self.max_line_length = limit;
+    }
+
+    /// Set the maximum number of characters allowed in a single comment, if any.
+    ///
+    /// See [`crate::Parser::max_comment_length`].
+    pub(crate) fn set_max_comment_length(&mut self, limit: Option<usize>) {
+        self.max_comment_length = limit;
+    }
+
     /// Get a copy of the last error that was encountered, if any.
     ///
     /// This does not clear the error state and further calls to [`Self::get_error`] will return (a
@@ -523,6 +903,23 @@ impl<T: Input> Scanner<T> {
         self.error.clone()
     }
 
+    /// Return the lints found so far, in the order they were found.
+    ///
+    /// See [`crate::Parser::lints`].
+    #[inline]
+    pub fn lints(&self) -> &[Lint] {
+        &self.lints
+    }
+
+    /// Return the span of the header of the last token returned, if it is a block scalar.
+    ///
+    /// The header is the `|` or `>` indicator along with the chomping and indentation indicators
+    /// following it. The span of the token itself is that of the content of the scalar.
+    #[inline]
+    pub fn block_scalar_header(&self) -> Option<Span> {
+        self.last_block_scalar_header
+    }
+
     /// Consume the next character. It is assumed the next character is a blank.
     #[inline]
     fn skip_blank(&mut self) {
@@ -594,6 +991,15 @@ impl<T: Input> Scanner<T> {
         self.mark
     }
 
+    /// Create an error about the construct starting at `start_mark`.
+    ///
+    /// The error spans from the start of the construct to the current position.
+    fn construct_error(&self, start_mark: Marker, kind: ScanErrorKind, info: &str) -> ScanError {
+        ScanError::new_with_kind(start_mark, kind, info)
+            .with_span(Span::new(start_mark, self.mark))
+            .with_context(start_mark)
+    }
+
     // Read and consume a line break (either `\r`, `\n` or `\r\n`).
     //
     // A `\n` is pushed into `s`.
@@ -676,8 +1082,9 @@ impl<T: Input> Scanner<T> {
                 self.fetch_document_indicator(TokenType::DocumentEnd)?;
                 self.skip_ws_to_eol(SkipTabs::Yes)?;
                 if !self.input.next_is_breakz() {
-                    return Err(ScanError::new_str(
+                    return Err(ScanError::new_with_kind(
                         self.mark,
+                        ScanErrorKind::UnexpectedCharacter,
                         "invalid content after document end marker",
                     ));
                 }
@@ -686,7 +1093,11 @@ impl<T: Input> Scanner<T> {
         }
 
         if (self.mark.col as isize) < self.indent {
-            return Err(ScanError::new_str(self.mark, "invalid indentation"));
+            return Err(ScanError::new_with_kind(
+                self.mark,
+                ScanErrorKind::BadIndentation,
+                "invalid indentation",
+            ));
         }
 
         let c = self.input.peek();
@@ -721,8 +1132,9 @@ impl<T: Input> Scanner<T> {
             ':' | '?' if !is_blank_or_breakz(nc) && self.flow_level == 0 => {
                 self.fetch_plain_scalar()
             }
-            '%' | '@' | '`' => Err(ScanError::new(
+            '%' | '@' | '`' => Err(ScanError::new_with_kind(
                 self.mark,
+                ScanErrorKind::UnexpectedCharacter,
                 format!("unexpected character: `{c}'"),
             )),
             _ => self.fetch_plain_scalar(),
@@ -741,13 +1153,20 @@ impl<T: Input> Scanner<T> {
             self.fetch_more_tokens()?;
         }
         let Some(t) = self.tokens.pop_front() else {
-            return Err(ScanError::new_str(
+            return Err(ScanError::new_with_kind(
                 self.mark,
+                ScanErrorKind::UnexpectedCharacter,
                 "did not find expected next token",
             ));
         };
         self.token_available = false;
         self.tokens_parsed += 1;
+        self.last_block_scalar_header = match t.1 {
+            TokenType::Scalar(TScalarStyle::Literal(_) | TScalarStyle::Folded(_), _) => {
+                self.block_scalar_headers.pop_front()
+            }
+            _ => None,
+        };
 
         if let TokenType::StreamEnd = t.1 {
             self.stream_end_produced = true;
@@ -780,6 +1199,7 @@ impl<T: Input> Scanner<T> {
                 break;
             }
             self.fetch_next_token()?;
+            self.check_line_length()?;
         }
         self.token_available = true;
 
@@ -801,7 +1221,11 @@ impl<T: Input> Scanner<T> {
                     && (sk.mark.line < self.mark.line || sk.mark.index + 1024 < self.mark.index)
             {
                 if sk.required {
-                    return Err(ScanError::new_str(self.mark, "simple key expect ':'"));
+                    return Err(ScanError::new_with_kind(
+                        self.mark,
+                        ScanErrorKind::InvalidSimpleKey,
+                        "simple key expect ':'",
+                    ));
                 }
                 sk.possible = false;
             }
@@ -831,13 +1255,17 @@ impl<T: Input> Scanner<T> {
                     self.skip_ws_to_eol(SkipTabs::Yes)?;
                     // If we have content on that line with a tab, return an error.
                     if !self.input.next_is_breakz() {
-                        return Err(ScanError::new_str(
+                        return Err(ScanError::new_with_kind(
                             self.mark,
+                            ScanErrorKind::TabInIndent,
                             "tabs disallowed within this context (block indentation)",
                         ));
                     }
                 }
-                '\t' | ' ' => self.skip_blank(),
+                '\t' | ' ' => {
+                    self.skip_blank();
+                    self.check_line_length()?;
+                }
                 '\n' | '\r' => {
                     self.input.lookahead(2);
                     self.skip_linebreak();
@@ -845,11 +1273,7 @@ impl<T: Input> Scanner<T> {
                         self.allow_simple_key();
                     }
                 }
-                '#' => {
-                    let comment_length = self.input.skip_while_non_breakz();
-                    self.mark.index += comment_length;
-                    self.mark.col += comment_length;
-                }
+                '#' => self.skip_comment()?,
                 _ => break,
             }
         }
@@ -877,27 +1301,74 @@ impl<T: Input> Scanner<T> {
                     }
                     need_whitespace = false;
                 }
-                '#' => {
-                    let comment_length = self.input.skip_while_non_breakz();
-                    self.mark.index += comment_length;
-                    self.mark.col += comment_length;
-                }
+                '#' => self.skip_comment()?,
                 _ => break,
             }
         }
 
         if need_whitespace {
-            Err(ScanError::new_str(self.mark(), "expected whitespace"))
+            Err(ScanError::new_with_kind(
+                self.mark(),
+                ScanErrorKind::MissingWhitespace,
+                "expected whitespace",
+            ))
         } else {
             Ok(())
         }
     }
 
+    /// Skip over a comment, up to the end of the line.
+    ///
+    /// # Errors
+    /// This function returns an error if the comment or the line is too long.
+    fn skip_comment(&mut self) -> ScanResult {
+        let start_mark = self.mark;
+        let comment_length = match self.max_comment_length {
+            None => self.input.skip_while_non_breakz(),
+            Some(max) => {
+                // Do not read more of the comment than needed to know it is too long.
+                let mut comment_length = 0;
+                while comment_length <= max && !is_breakz(self.input.look_ch()) {
+                    self.input.skip();
+                    comment_length += 1;
+                }
+                if comment_length > max {
+                    return Err(ScanError::new_with_kind(
+                        start_mark,
+                        ScanErrorKind::LengthLimitExceeded,
+                        format!("comment is longer than the maximum of {max} characters"),
+                    ));
+                }
+                comment_length
+            }
+        };
+        self.mark.index += comment_length;
+        self.mark.col += comment_length;
+        self.check_line_length()
+    }
+
+    /// Check that the current line is not longer than allowed.
+    ///
+    /// # Errors
+    /// This function returns an error if the current column is past the maximum line length.
+    fn check_line_length(&self) -> ScanResult {
+        match self.max_line_length {
+            Some(max) if self.mark.col > max => Err(ScanError::new_with_kind(
+                self.mark,
+                ScanErrorKind::LengthLimitExceeded,
+                format!("line is longer than the maximum of {max} characters"),
+            )),
+            _ => Ok(()),
+        }
+    }
+
     fn skip_ws_to_eol(&mut self, skip_tabs: SkipTabs) -> Result<SkipTabs, ScanError> {
         let (n_bytes, result) = self.input.skip_ws_to_eol(skip_tabs);
         self.mark.col += n_bytes;
         self.mark.index += n_bytes;
-        result.map_err(|msg| ScanError::new_str(self.mark, msg))
+        result.map_err(|msg| {
+            ScanError::new_with_kind(self.mark, ScanErrorKind::MissingWhitespace, msg)
+        })
     }
 
     fn fetch_stream_start(&mut self) {
@@ -923,7 +1394,11 @@ impl<T: Input> Scanner<T> {
         // had. If one was required, however, that was an error and we must propagate it.
         for sk in &mut self.simple_keys {
             if sk.required && sk.possible {
-                return Err(ScanError::new_str(self.mark, "simple key expected"));
+                return Err(ScanError::new_with_kind(
+                    self.mark,
+                    ScanErrorKind::InvalidSimpleKey,
+                    "simple key expected",
+                ));
             }
             sk.possible = false;
         }
@@ -980,8 +1455,9 @@ impl<T: Input> Scanner<T> {
             self.skip_linebreak();
             Ok(tok)
         } else {
-            Err(ScanError::new_str(
+            Err(self.construct_error(
                 start_mark,
+                ScanErrorKind::InvalidDirective,
                 "while scanning a directive, did not find expected comment or line break",
             ))
         }
@@ -995,8 +1471,9 @@ impl<T: Input> Scanner<T> {
         let major = self.scan_version_directive_number(mark)?;
 
         if self.input.peek() != '.' {
-            return Err(ScanError::new_str(
+            return Err(ScanError::new_with_kind(
                 *mark,
+                ScanErrorKind::InvalidDirective,
                 "while scanning a YAML directive, did not find expected digit or '.' character",
             ));
         }
@@ -1019,15 +1496,17 @@ impl<T: Input> Scanner<T> {
         self.mark.col += n_chars;
 
         if string.is_empty() {
-            return Err(ScanError::new_str(
+            return Err(self.construct_error(
                 start_mark,
+                ScanErrorKind::InvalidDirective,
                 "while scanning a directive, could not find expected directive name",
             ));
         }
 
         if !is_blank_or_breakz(self.input.peek()) {
-            return Err(ScanError::new_str(
+            return Err(self.construct_error(
                 start_mark,
+                ScanErrorKind::InvalidDirective,
                 "while scanning a directive, found unexpected non-alphabetical character",
             ));
         }
@@ -1040,8 +1519,9 @@ impl<T: Input> Scanner<T> {
         let mut length = 0usize;
         while let Some(digit) = self.input.look_ch().to_digit(10) {
             if length + 1 > 9 {
-                return Err(ScanError::new_str(
+                return Err(ScanError::new_with_kind(
                     *mark,
+                    ScanErrorKind::InvalidDirective,
                     "while scanning a YAML directive, found extremely long version number",
                 ));
             }
@@ -1051,8 +1531,9 @@ impl<T: Input> Scanner<T> {
         }
 
         if length == 0 {
-            return Err(ScanError::new_str(
+            return Err(ScanError::new_with_kind(
                 *mark,
+                ScanErrorKind::InvalidDirective,
                 "while scanning a YAML directive, did not find expected version number",
             ));
         }
@@ -1081,8 +1562,9 @@ impl<T: Input> Scanner<T> {
                 TokenType::TagDirective(handle, prefix),
             ))
         } else {
-            Err(ScanError::new_str(
+            Err(ScanError::new_with_kind(
                 *mark,
+                ScanErrorKind::InvalidDirective,
                 "while scanning TAG, did not find expected whitespace or line break",
             ))
         }
@@ -1137,8 +1619,9 @@ impl<T: Input> Scanner<T> {
                 TokenType::Tag(handle, suffix),
             ))
         } else {
-            Err(ScanError::new_str(
+            Err(self.construct_error(
                 start_mark,
+                ScanErrorKind::InvalidTag,
                 "while scanning a tag, did not find expected whitespace or line break",
             ))
         }
@@ -1147,8 +1630,9 @@ impl<T: Input> Scanner<T> {
     fn scan_tag_handle(&mut self, directive: bool, mark: &Marker) -> Result<String, ScanError> {
         let mut string = String::new();
         if self.input.look_ch() != '!' {
-            return Err(ScanError::new_str(
+            return Err(ScanError::new_with_kind(
                 *mark,
+                ScanErrorKind::InvalidTag,
                 "while scanning a tag, did not find expected '!'",
             ));
         }
@@ -1168,8 +1652,9 @@ impl<T: Input> Scanner<T> {
             // It's either the '!' tag or not really a tag handle.  If it's a %TAG
             // directive, it's an error.  If it's a tag token, it must be a part of
             // URI.
-            return Err(ScanError::new_str(
+            return Err(ScanError::new_with_kind(
                 *mark,
+                ScanErrorKind::InvalidDirective,
                 "while parsing a tag directive, did not find expected '!'",
             ));
         }
@@ -1190,8 +1675,9 @@ impl<T: Input> Scanner<T> {
             self.skip_non_blank();
         } else if !is_tag_char(self.input.peek()) {
             // Otherwise, check if the first global tag character is valid.
-            return Err(ScanError::new_str(
+            return Err(self.construct_error(
                 *start_mark,
+                ScanErrorKind::InvalidTag,
                 "invalid global tag character",
             ));
         } else if self.input.peek() == '%' {
@@ -1234,8 +1720,9 @@ impl<T: Input> Scanner<T> {
         }
 
         if self.input.peek() != '>' {
-            return Err(ScanError::new_str(
+            return Err(self.construct_error(
                 *start_mark,
+                ScanErrorKind::InvalidTag,
                 "while scanning a verbatim tag, did not find the expected '>'",
             ));
         }
@@ -1273,8 +1760,9 @@ impl<T: Input> Scanner<T> {
         }
 
         if length == 0 {
-            return Err(ScanError::new_str(
+            return Err(ScanError::new_with_kind(
                 *mark,
+                ScanErrorKind::InvalidTag,
                 "while parsing a tag, did not find expected tag URI",
             ));
         }
@@ -1292,8 +1780,9 @@ impl<T: Input> Scanner<T> {
             let nc = self.input.peek_nth(2);
 
             if !(self.input.peek() == '%' && is_hex(c) && is_hex(nc)) {
-                return Err(ScanError::new_str(
+                return Err(ScanError::new_with_kind(
                     *mark,
+                    ScanErrorKind::InvalidTag,
                     "while parsing a tag, found an invalid escape sequence",
                 ));
             }
@@ -1306,8 +1795,9 @@ impl<T: Input> Scanner<T> {
                     _ if byte & 0xF0 == 0xE0 => 3,
                     _ if byte & 0xF8 == 0xF0 => 4,
                     _ => {
-                        return Err(ScanError::new_str(
+                        return Err(ScanError::new_with_kind(
                             *mark,
+                            ScanErrorKind::InvalidTag,
                             "while parsing a tag, found an incorrect leading UTF-8 byte",
                         ));
                     }
@@ -1315,8 +1805,9 @@ impl<T: Input> Scanner<T> {
                 code = byte;
             } else {
                 if byte & 0xc0 != 0x80 {
-                    return Err(ScanError::new_str(
+                    return Err(ScanError::new_with_kind(
                         *mark,
+                        ScanErrorKind::InvalidTag,
                         "while parsing a tag, found an incorrect trailing UTF-8 byte",
                     ));
                 }
@@ -1333,8 +1824,9 @@ impl<T: Input> Scanner<T> {
 
         match char::from_u32(code) {
             Some(ch) => Ok(ch),
-            None => Err(ScanError::new_str(
+            None => Err(ScanError::new_with_kind(
                 *mark,
+                ScanErrorKind::InvalidTag,
                 "while parsing a tag, found an invalid UTF-8 codepoint",
             )),
         }
@@ -1362,7 +1854,9 @@ impl<T: Input> Scanner<T> {
         }
 
         if string.is_empty() {
-            return Err(ScanError::new_str(start_mark, "while scanning an anchor or alias, did not find expected alphabetic or numeric character"));
+            let info = "while scanning an anchor or alias, did not find expected alphabetic or \
+                        numeric character";
+            return Err(self.construct_error(start_mark, ScanErrorKind::InvalidAnchor, info));
         }
 
         let tok = if alias {
@@ -1384,6 +1878,7 @@ impl<T: Input> Scanner<T> {
 
         let start_mark = self.mark;
         self.skip_non_blank();
+        let span = Span::new(start_mark, self.mark);
 
         if tok == TokenType::FlowMappingStart {
             self.flow_mapping_started = true;
@@ -1394,8 +1889,7 @@ impl<T: Input> Scanner<T> {
 
         self.skip_ws_to_eol(SkipTabs::Yes)?;
 
-        self.tokens
-            .push_back(Token(Span::new(start_mark, self.mark), tok));
+        self.tokens.push_back(Token(span, tok));
         Ok(())
     }
 
@@ -1413,6 +1907,7 @@ impl<T: Input> Scanner<T> {
 
         let start_mark = self.mark;
         self.skip_non_blank();
+        let span = Span::new(start_mark, self.mark);
         self.skip_ws_to_eol(SkipTabs::Yes)?;
 
         // A flow collection within a flow mapping can be a key. In that case, the value may be
@@ -1424,8 +1919,7 @@ impl<T: Input> Scanner<T> {
             self.adjacent_value_allowed_at = self.mark.index;
         }
 
-        self.tokens
-            .push_back(Token(Span::new(start_mark, self.mark), tok));
+        self.tokens.push_back(Token(span, tok));
         Ok(())
     }
 
@@ -1449,10 +1943,13 @@ impl<T: Input> Scanner<T> {
 
     fn increase_flow_level(&mut self) -> ScanResult {
         self.simple_keys.push(SimpleKey::new(Marker::new(0, 0, 0)));
-        self.flow_level = self
-            .flow_level
-            .checked_add(1)
-            .ok_or_else(|| ScanError::new_str(self.mark, "recursion limit exceeded"))?;
+        self.flow_level = self.flow_level.checked_add(1).ok_or_else(|| {
+            ScanError::new_with_kind(
+                self.mark,
+                ScanErrorKind::RecursionLimitExceeded,
+                "recursion limit exceeded",
+            )
+        })?;
         Ok(())
     }
 
@@ -1471,15 +1968,17 @@ impl<T: Input> Scanner<T> {
     fn fetch_block_entry(&mut self) -> ScanResult {
         if self.flow_level > 0 {
             // - * only allowed in block
-            return Err(ScanError::new_str(
+            return Err(ScanError::new_with_kind(
                 self.mark,
+                ScanErrorKind::MisplacedIndicator,
                 r#""-" is only valid inside a block"#,
             ));
         }
         // Check if we are allowed to start a new entry.
         if !self.simple_key_allowed {
-            return Err(ScanError::new_str(
+            return Err(ScanError::new_with_kind(
                 self.mark,
+                ScanErrorKind::MisplacedIndicator,
                 "block sequence entries are not allowed in this context",
             ));
         }
@@ -1487,8 +1986,9 @@ impl<T: Input> Scanner<T> {
         // ???, fixes test G9HC.
         if let Some(Token(span, TokenType::Anchor(..) | TokenType::Tag(..))) = self.tokens.back() {
             if self.mark.col == 0 && span.start.col == 0 && self.indent > -1 {
-                return Err(ScanError::new_str(
+                return Err(ScanError::new_with_kind(
                     span.start,
+                    ScanErrorKind::BadIndentation,
                     "invalid indentation for anchor",
                 ));
             }
@@ -1504,8 +2004,9 @@ impl<T: Input> Scanner<T> {
         self.input.lookahead(2);
         if found_tabs && self.input.next_char_is('-') && is_blank_or_breakz(self.input.peek_nth(1))
         {
-            return Err(ScanError::new_str(
+            return Err(ScanError::new_with_kind(
                 self.mark,
+                ScanErrorKind::MissingWhitespace,
                 "'-' must be followed by a valid YAML whitespace",
             ));
         }
@@ -1555,11 +2056,6 @@ impl<T: Input> Scanner<T> {
         let mut indent: usize = 0;
         let mut trailing_blank: bool;
         let mut leading_blank: bool = false;
-        let style = if literal {
-            TScalarStyle::Literal
-        } else {
-            TScalarStyle::Folded
-        };
 
         let mut string = String::new();
         let mut leading_break = String::new();
@@ -1580,8 +2076,9 @@ impl<T: Input> Scanner<T> {
             self.input.lookahead(1);
             if self.input.next_is_digit() {
                 if self.input.peek() == '0' {
-                    return Err(ScanError::new_str(
+                    return Err(self.construct_error(
                         start_mark,
+                        ScanErrorKind::InvalidBlockScalar,
                         "while scanning a block scalar, found an indentation indicator equal to 0",
                     ));
                 }
@@ -1590,8 +2087,9 @@ impl<T: Input> Scanner<T> {
             }
         } else if self.input.next_is_digit() {
             if self.input.peek() == '0' {
-                return Err(ScanError::new_str(
+                return Err(self.construct_error(
                     start_mark,
+                    ScanErrorKind::InvalidBlockScalar,
                     "while scanning a block scalar, found an indentation indicator equal to 0",
                 ));
             }
@@ -1609,13 +2107,25 @@ impl<T: Input> Scanner<T> {
             }
         }
 
+        let header_span = Span::new(start_mark, self.mark);
+        let header = BlockScalarHeader {
+            chomping,
+            indent: u8::try_from(increment).ok().filter(|&indent| indent > 0),
+        };
+        let style = if literal {
+            TScalarStyle::Literal(header)
+        } else {
+            TScalarStyle::Folded(header)
+        };
+
         self.skip_ws_to_eol(SkipTabs::Yes)?;
 
         // Check if we are at the end of the line.
         self.input.lookahead(1);
         if !self.input.next_is_breakz() {
-            return Err(ScanError::new_str(
+            return Err(self.construct_error(
                 start_mark,
+                ScanErrorKind::InvalidBlockScalar,
                 "while scanning a block scalar, did not find expected comment or line break",
             ));
         }
@@ -1626,8 +2136,9 @@ impl<T: Input> Scanner<T> {
         }
 
         if self.input.look_ch() == '\t' {
-            return Err(ScanError::new_str(
+            return Err(self.construct_error(
                 start_mark,
+                ScanErrorKind::TabInIndent,
                 "a block scalar content cannot start with a tab",
             ));
         }
@@ -1666,21 +2177,26 @@ impl<T: Input> Scanner<T> {
                 // Otherwise, the newline after chomping is ignored.
                 Chomping::Keep => trailing_breaks,
             };
+            self.block_scalar_headers.push_back(header_span);
             return Ok(Token(
-                Span::new(start_mark, self.mark),
+                Span::empty(header_span.end),
                 TokenType::Scalar(style, contents),
             ));
         }
 
         if self.mark.col < indent && (self.mark.col as isize) > self.indent {
-            return Err(ScanError::new_str(
+            return Err(ScanError::new_with_kind(
                 self.mark,
+                ScanErrorKind::BadIndentation,
                 "wrongly indented line in block scalar",
             ));
         }
 
         let mut line_buffer = String::with_capacity(100);
-        let start_mark = self.mark;
+        let content_start = self.mark;
+        // The content spans from the first character of the first non-empty line to the end of
+        // the last one, leaving out the header and the empty lines and indentation around them.
+        let mut content_span = Span::empty(header_span.end);
         while self.mark.col == indent && !self.input.next_is_z() {
             if indent == 0 {
                 self.input.lookahead(4);
@@ -1706,7 +2222,8 @@ impl<T: Input> Scanner<T> {
 
             leading_blank = self.input.next_is_blank();
 
-            self.scan_block_scalar_content_line(&mut string, &mut line_buffer);
+            self.scan_block_scalar_content_line(&mut string, &mut line_buffer)?;
+            content_span = Span::new(content_start, self.mark);
 
             // break on EOF
             self.input.lookahead(2);
@@ -1735,10 +2252,8 @@ impl<T: Input> Scanner<T> {
             string.push_str(&trailing_breaks);
         }
 
-        Ok(Token(
-            Span::new(start_mark, self.mark),
-            TokenType::Scalar(style, string),
-        ))
+        self.block_scalar_headers.push_back(header_span);
+        Ok(Token(content_span, TokenType::Scalar(style, string)))
     }
 
     /// Retrieve the contents of the line, parsing it as a block scalar.
@@ -1750,7 +2265,14 @@ impl<T: Input> Scanner<T> {
     ///
     /// This function assumed the first character to read is the first content character in the
     /// line. This function does not consume the line break character(s) after the line.
-    fn scan_block_scalar_content_line(&mut self, string: &mut String, line_buffer: &mut String) {
+    ///
+    /// # Errors
+    /// This function returns an error if the line is too long.
+    fn scan_block_scalar_content_line(
+        &mut self,
+        string: &mut String,
+        line_buffer: &mut String,
+    ) -> ScanResult {
         // Start by evaluating characters in the buffer.
         while !self.input.buf_is_empty() && !self.input.next_is_breakz() {
             string.push(self.input.peek());
@@ -1761,6 +2283,7 @@ impl<T: Input> Scanner<T> {
             // This allows us to call a slightly less expensive function.
             self.skip_blank();
         }
+        self.check_line_length()?;
 
         // All characters that were in the buffer were consumed. We need to check if more
         // follow.
@@ -1770,21 +2293,34 @@ impl<T: Input> Scanner<T> {
             // characters are appended here as their real size (1B for ascii, or up to 4 bytes for
             // UTF-8). We can then use the internal `line_buffer` `Vec` to push data into `string`
             // (using `String::push_str`).
+            let mut n_chars = 0;
             while let Some(c) = self.input.raw_read_non_breakz_ch() {
                 line_buffer.push(c);
+                n_chars += 1;
+                // Stop reading as soon as the line is too long.
+                if self
+                    .max_line_length
+                    .is_some_and(|max| self.mark.col + n_chars > max)
+                {
+                    break;
+                }
             }
 
             // We need to manually update our position; we haven't called a `skip` function.
-            let n_chars = line_buffer.chars().count();
             self.mark.col += n_chars;
             self.mark.index += n_chars;
+            if let Err(e) = self.check_line_length() {
+                line_buffer.clear();
+                return Err(e);
+            }
 
             // We can now append our bytes to our `string`.
-            string.reserve(line_buffer.as_bytes().len());
+            string.reserve(line_buffer.len());
             string.push_str(line_buffer);
             // This clears the _contents_ without touching the _capacity_.
             line_buffer.clear();
         }
+        Ok(())
     }
 
     /// Skip the block scalar indentation and empty lines.
@@ -1887,9 +2423,9 @@ impl<T: Input> Scanner<T> {
         let start_mark = self.mark;
 
         let mut string = String::new();
-        let mut leading_break = String::new();
-        let mut trailing_breaks = String::new();
-        let mut whitespaces = String::new();
+        self.buf_whitespaces.clear();
+        self.buf_leading_break.clear();
+        self.buf_trailing_breaks.clear();
         let mut leading_blanks;
 
         /* Eat the left quote. */
@@ -1900,22 +2436,25 @@ impl<T: Input> Scanner<T> {
             self.input.lookahead(4);
 
             if self.mark.col == 0 && self.input.next_is_document_indicator() {
-                return Err(ScanError::new_str(
+                return Err(self.construct_error(
                     start_mark,
+                    ScanErrorKind::UnexpectedCharacter,
                     "while scanning a quoted scalar, found unexpected document indicator",
                 ));
             }
 
             if self.input.next_is_z() {
-                return Err(ScanError::new_str(
+                return Err(self.construct_error(
                     start_mark,
+                    ScanErrorKind::UnexpectedEof,
                     "while scanning a quoted scalar, found unexpected end of stream",
                 ));
             }
 
             if (self.mark.col as isize) < self.indent {
-                return Err(ScanError::new_str(
+                return Err(self.construct_error(
                     start_mark,
+                    ScanErrorKind::BadIndentation,
                     "invalid indentation in quoted scalar",
                 ));
             }
@@ -1940,24 +2479,28 @@ impl<T: Input> Scanner<T> {
                     // Consume a space or a tab character.
                     if leading_blanks {
                         if self.input.peek() == '\t' && (self.mark.col as isize) < self.indent {
-                            return Err(ScanError::new_str(
+                            return Err(ScanError::new_with_kind(
                                 self.mark,
+                                ScanErrorKind::TabInIndent,
                                 "tab cannot be used as indentation",
                             ));
                         }
                         self.skip_blank();
                     } else {
-                        whitespaces.push(self.input.peek());
+                        self.buf_whitespaces.push(self.input.peek());
                         self.skip_blank();
                     }
+                    self.check_line_length()?;
                 } else {
                     self.input.lookahead(2);
                     // Check if it is a first line break.
                     if leading_blanks {
-                        self.read_break(&mut trailing_breaks);
+                        self.skip_break();
+                        self.buf_trailing_breaks.push('\n');
                     } else {
-                        whitespaces.clear();
-                        self.read_break(&mut leading_break);
+                        self.buf_whitespaces.clear();
+                        self.skip_break();
+                        self.buf_leading_break.push('\n');
                         leading_blanks = true;
                     }
                 }
@@ -1966,28 +2509,29 @@ impl<T: Input> Scanner<T> {
 
             // Join the whitespaces or fold line breaks.
             if leading_blanks {
-                if leading_break.is_empty() {
-                    string.push_str(&leading_break);
-                    string.push_str(&trailing_breaks);
-                    trailing_breaks.clear();
-                    leading_break.clear();
+                if self.buf_leading_break.is_empty() {
+                    string.push_str(&self.buf_leading_break);
+                    string.push_str(&self.buf_trailing_breaks);
+                    self.buf_trailing_breaks.clear();
+                    self.buf_leading_break.clear();
                 } else {
-                    if trailing_breaks.is_empty() {
+                    if self.buf_trailing_breaks.is_empty() {
                         string.push(' ');
                     } else {
-                        string.push_str(&trailing_breaks);
-                        trailing_breaks.clear();
+                        string.push_str(&self.buf_trailing_breaks);
+                        self.buf_trailing_breaks.clear();
                     }
-                    leading_break.clear();
+                    self.buf_leading_break.clear();
                 }
             } else {
-                string.push_str(&whitespaces);
-                whitespaces.clear();
+                string.push_str(&self.buf_whitespaces);
+                self.buf_whitespaces.clear();
             }
         } // loop
 
         // Eat the right quote.
         self.skip_non_blank();
+        let end_mark = self.mark;
         // Ensure there is no invalid trailing content.
         self.skip_ws_to_eol(SkipTabs::Yes)?;
         match self.input.peek() {
@@ -2001,8 +2545,9 @@ impl<T: Input> Scanner<T> {
             // Inside a flow context, this is allowed.
             ':' if self.flow_level > 0 => {}
             _ => {
-                return Err(ScanError::new_str(
+                return Err(ScanError::new_with_kind(
                     self.mark,
+                    ScanErrorKind::UnexpectedCharacter,
                     "invalid trailing content after double-quoted scalar",
                 ));
             }
@@ -2014,7 +2559,7 @@ impl<T: Input> Scanner<T> {
             TScalarStyle::DoubleQuoted
         };
         Ok(Token(
-            Span::new(start_mark, self.mark),
+            Span::new(start_mark, end_mark),
             TokenType::Scalar(style, string),
         ))
     }
@@ -2062,6 +2607,7 @@ impl<T: Input> Scanner<T> {
                     self.skip_non_blank();
                 }
             }
+            self.check_line_length()?;
             self.input.lookahead(2);
         }
         Ok(())
@@ -2106,8 +2652,9 @@ impl<T: Input> Scanner<T> {
             'u' => code_length = 4,
             'U' => code_length = 8,
             _ => {
-                return Err(ScanError::new_str(
+                return Err(self.construct_error(
                     *start_mark,
+                    ScanErrorKind::InvalidEscape,
                     "while parsing a quoted scalar, found unknown escape character",
                 ))
             }
@@ -2121,8 +2668,9 @@ impl<T: Input> Scanner<T> {
             for i in 0..code_length {
                 let c = self.input.peek_nth(i);
                 if !is_hex(c) {
-                    return Err(ScanError::new_str(
+                    return Err(self.construct_error(
                         *start_mark,
+                        ScanErrorKind::InvalidEscape,
                         "while parsing a quoted scalar, did not find expected hexadecimal number",
                     ));
                 }
@@ -2130,8 +2678,9 @@ impl<T: Input> Scanner<T> {
             }
 
             let Some(ch) = char::from_u32(value) else {
-                return Err(ScanError::new_str(
+                return Err(self.construct_error(
                     *start_mark,
+                    ScanErrorKind::InvalidEscape,
                     "while parsing a quoted scalar, found invalid Unicode character escape code",
                 ));
             };
@@ -2163,8 +2712,9 @@ impl<T: Input> Scanner<T> {
         let start_mark = self.mark;
 
         if self.flow_level > 0 && (start_mark.col as isize) < indent {
-            return Err(ScanError::new_str(
+            return Err(self.construct_error(
                 start_mark,
+                ScanErrorKind::BadIndentation,
                 "invalid indentation in flow construct",
             ));
         }
@@ -2184,8 +2734,9 @@ impl<T: Input> Scanner<T> {
             }
 
             if self.flow_level > 0 && self.input.peek() == '-' && is_flow(self.input.peek_nth(1)) {
-                return Err(ScanError::new_str(
+                return Err(ScanError::new_with_kind(
                     self.mark,
+                    ScanErrorKind::UnexpectedCharacter,
                     "plain scalar cannot start with '-' followed by ,[]{}",
                 ));
             }
@@ -2236,6 +2787,7 @@ impl<T: Input> Scanner<T> {
                         string.push(self.input.peek());
                         self.skip_non_blank();
                     }
+                    self.check_line_length()?;
                 }
                 end_mark = self.mark;
             }
@@ -2260,14 +2812,16 @@ impl<T: Input> Scanner<T> {
                         // empty. Skip to the end of the line.
                         self.skip_ws_to_eol(SkipTabs::Yes)?;
                         if !self.input.next_is_breakz() {
-                            return Err(ScanError::new_str(
+                            return Err(self.construct_error(
                                 start_mark,
+                                ScanErrorKind::TabInIndent,
                                 "while scanning a plain scalar, found a tab",
                             ));
                         }
                     } else {
                         self.skip_blank();
                     }
+                    self.check_line_length()?;
                 } else {
                     // Check if it is a first line break
                     if self.leading_whitespace {
@@ -2297,8 +2851,9 @@ impl<T: Input> Scanner<T> {
             // `fetch_plain_scalar` must absolutely consume at least one byte. Otherwise,
             // `fetch_next_token` will never stop calling it. An empty plain scalar may happen with
             // erroneous inputs such as "{...".
-            Err(ScanError::new_str(
+            Err(self.construct_error(
                 start_mark,
+                ScanErrorKind::UnexpectedEof,
                 "unexpected end of plain scalar",
             ))
         } else {
@@ -2314,8 +2869,9 @@ impl<T: Input> Scanner<T> {
         if self.flow_level == 0 {
             // Check if we are allowed to start a new key (not necessarily simple).
             if !self.simple_key_allowed {
-                return Err(ScanError::new_str(
+                return Err(ScanError::new_with_kind(
                     self.mark,
+                    ScanErrorKind::MisplacedIndicator,
                     "mapping keys are not allowed in this context",
                 ));
             }
@@ -2339,18 +2895,42 @@ impl<T: Input> Scanner<T> {
         }
 
         self.skip_non_blank();
-        self.skip_yaml_whitespace()?;
-        if self.input.peek() == '\t' {
-            return Err(ScanError::new_str(
-                self.mark(),
-                "tabs disallowed in this context",
-            ));
+        if self.flow_level > 0 {
+            self.skip_flow_indicator_blanks()?;
+            if self.input.next_is_break() {
+                self.skip_yaml_whitespace()?;
+            }
+        } else {
+            self.skip_yaml_whitespace()?;
+            if self.input.peek() == '\t' {
+                return Err(ScanError::new_with_kind(
+                    self.mark(),
+                    ScanErrorKind::TabInIndent,
+                    "tabs disallowed in this context",
+                ));
+            }
         }
         self.tokens
             .push_back(Token(Span::new(start_mark, self.mark), TokenType::Key));
         Ok(())
     }
 
+    /// Skip the blanks following a `?` or `:` indicator inside a flow collection.
+    ///
+    /// Block collections cannot start inside a flow collection, so tabs are allowed there as
+    /// spaces are. If tabs separate the indicator from content on the same line, record a
+    /// [`LintKind::TabAfterFlowIndicator`] lint.
+    fn skip_flow_indicator_blanks(&mut self) -> ScanResult {
+        let start_mark = self.mark;
+        if self.skip_ws_to_eol(SkipTabs::Yes)?.found_tabs() && !self.input.next_is_breakz() {
+            self.lints.push(Lint::new(
+                LintKind::TabAfterFlowIndicator,
+                Span::new(start_mark, self.mark),
+            ));
+        }
+        Ok(())
+    }
+
     /// Fetch a value in a mapping inside of a flow collection.
     ///
     /// This must not be called if [`self.flow_level`] is 0. This ensures the rules surrounding
@@ -2373,8 +2953,9 @@ impl<T: Input> Scanner<T> {
         // flow character), but the ']' is not the value. The value is an invisible empty
         // space which is represented as null ('~').
         if self.mark.index != self.adjacent_value_allowed_at && (nc == '[' || nc == '{') {
-            return Err(ScanError::new_str(
+            return Err(ScanError::new_with_kind(
                 self.mark,
+                ScanErrorKind::MisplacedIndicator,
                 "':' may not precede any of `[{` in flow mapping",
             ));
         }
@@ -2394,12 +2975,15 @@ impl<T: Input> Scanner<T> {
 
         // Skip over ':'.
         self.skip_non_blank();
-        if self.input.look_ch() == '\t'
+        if self.flow_level > 0 && self.input.look_ch() == '\t' {
+            self.skip_flow_indicator_blanks()?;
+        } else if self.input.look_ch() == '\t'
             && !self.skip_ws_to_eol(SkipTabs::Yes)?.has_valid_yaml_ws()
             && (self.input.peek() == '-' || self.input.next_is_alpha())
         {
-            return Err(ScanError::new_str(
+            return Err(ScanError::new_with_kind(
                 self.mark,
+                ScanErrorKind::MissingWhitespace,
                 "':' must be followed by a valid YAML whitespace",
             ));
         }
@@ -2410,8 +2994,9 @@ impl<T: Input> Scanner<T> {
             self.insert_token(sk.token_number - self.tokens_parsed, tok);
             if is_implicit_flow_mapping {
                 if sk.mark.line < start_mark.line {
-                    return Err(ScanError::new_str(
+                    return Err(ScanError::new_with_kind(
                         start_mark,
+                        ScanErrorKind::MisplacedIndicator,
                         "illegal placement of ':' indicator",
                     ));
                 }
@@ -2440,8 +3025,9 @@ impl<T: Input> Scanner<T> {
             // The ':' indicator follows a complex key.
             if self.flow_level == 0 {
                 if !self.simple_key_allowed {
-                    return Err(ScanError::new_str(
+                    return Err(ScanError::new_with_kind(
                         start_mark,
+                        ScanErrorKind::MisplacedIndicator,
                         "mapping values are not allowed in this context",
                     ));
                 }
@@ -2528,7 +3114,7 @@ impl<T: Input> Scanner<T> {
     /// An indentation is not added if we are inside a flow level or if the last indent is already
     /// a non-block indent.
     fn roll_one_col_indent(&mut self) {
-        if self.flow_level == 0 && self.indents.last().map_or(false, |x| x.needs_block_end) {
+        if self.flow_level == 0 && self.indents.last().is_some_and(|x| x.needs_block_end) {
             self.indents.push(Indent {
                 indent: self.indent,
                 needs_block_end: false,
@@ -2567,7 +3153,11 @@ impl<T: Input> Scanner<T> {
     fn remove_simple_key(&mut self) -> ScanResult {
         let last = self.simple_keys.last_mut().unwrap();
         if last.possible && last.required {
-            return Err(ScanError::new_str(self.mark, "simple key expected"));
+            return Err(ScanError::new_with_kind(
+                self.mark,
+                ScanErrorKind::InvalidSimpleKey,
+                "simple key expected",
+            ));
         }
 
         last.possible = false;
@@ -2596,14 +3186,147 @@ impl<T: Input> Scanner<T> {
     }
 }
 
+/// Iterate lazily over the tokens of a YAML string.
+///
+/// This is a lower-level API than the [`Parser`], intended for tools like syntax highlighters,
+/// which need the class and position of each token rather than YAML events. Tokens are yielded
+/// along with their span in `input`.
+///
+/// Unlike the [`Parser`], the iterator does not stop at the first error. When the scanner fails,
+/// the error is yielded and scanning resumes at the start of the next line, as if it were the
+/// start of a new stream. Tokens after an error may therefore not reflect the structure of the
+/// document (e.g.: an indented line is scanned as if it were at the top-level), but every line
+/// that can be tokenized is. Tokens from a resumed scan never overlap previously yielded tokens.
+///
+/// A [`TokenType::StreamStart`] is only yielded at the very beginning and a
+/// [`TokenType::StreamEnd`] only at the very end of the input.
+///
+/// ```
+/// use saphyr_parser::{tokenize, TokenType};
+///
+/// let classes: Vec<_> = tokenize("a: [1, 2]")
+///     .filter_map(Result::ok)
+///     .map(|(token, span)| (token, span.start.col()))
+///     .collect();
+/// assert!(matches!(classes[4], (TokenType::Value, 1)));
+/// assert!(matches!(classes[5], (TokenType::FlowSequenceStart, 3)));
+/// ```
+///
+/// [`Parser`]: crate::Parser
+#[must_use]
+pub fn tokenize(input: &str) -> Tokens<'_> {
+    Tokens {
+        input,
+        scanner: Scanner::new(StrInput::new(input)),
+        base: Marker::new(0, 1, 0),
+        base_offset: 0,
+        last_end: Marker::new(0, 1, 0),
+        done: false,
+    }
+}
+
+/// An iterator over the tokens of a YAML string, created by [`tokenize`].
+pub struct Tokens<'input> {
+    /// The whole input string.
+    input: &'input str,
+    /// The scanner for the part of the input we are currently tokenizing.
+    scanner: Scanner<StrInput<'input>>,
+    /// The position in `input` at which `scanner` started.
+    base: Marker,
+    /// The byte offset in `input` at which `scanner` started.
+    base_offset: usize,
+    /// The end of the last token that was yielded.
+    last_end: Marker,
+    /// Whether we reached the end of the input.
+    done: bool,
+}
+
+impl Tokens<'_> {
+    /// Convert a marker relative to the current scanner to one relative to the whole input.
+    fn rebase(&self, mark: Marker) -> Marker {
+        let col = if mark.line == 1 {
+            self.base.col + mark.col
+        } else {
+            mark.col
+        };
+        Marker::new(
+            self.base.index + mark.index,
+            self.base.line + mark.line - 1,
+            col,
+        )
+    }
+
+    /// Restart scanning at the line following `mark`.
+    ///
+    /// Returns `false` if there is no such line.
+    fn resume_after(&mut self, mark: Marker) -> bool {
+        let rest = &self.input[self.base_offset..];
+        // Byte offset in `rest` of the character `mark` points to.
+        let Some((start, _)) = rest.char_indices().nth(mark.index - self.base.index) else {
+            return false;
+        };
+        let Some(newline) = rest[start..].find('\n') else {
+            return false;
+        };
+        let skipped_chars = rest[start..start + newline].chars().count();
+        self.base = Marker::new(mark.index + skipped_chars + 1, mark.line + 1, 0);
+        self.base_offset += start + newline + 1;
+        self.scanner = Scanner::new(StrInput::new(&self.input[self.base_offset..]));
+        true
+    }
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Result<(TokenType, Span), ScanError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if let Some(Token(span, token)) = self.scanner.next() {
+                // Only the first scanner produces the start of the stream.
+                if self.base_offset != 0 && matches!(token, TokenType::StreamStart(_)) {
+                    continue;
+                }
+                let span = Span::new(self.rebase(span.start), self.rebase(span.end));
+                // Tokens may be emitted out of order (e.g.: keys); keep the furthest end.
+                if span.end.index > self.last_end.index {
+                    self.last_end = span.end;
+                }
+                return Some(Ok((token, span)));
+            }
+
+            let Some(mut error) = self.scanner.get_error() else {
+                self.done = true;
+                return None;
+            };
+            error.mark = self.rebase(error.mark);
+            error.end = self.rebase(error.end);
+            error.context = error.context.map(|mark| self.rebase(mark));
+            // Resume after whichever of the error or the last token comes last, so that tokens
+            // never overlap.
+            let resume_mark = if error.mark.index > self.last_end.index {
+                error.mark
+            } else {
+                self.last_end
+            };
+            if !self.resume_after(resume_mark) {
+                self.done = true;
+            }
+            return Some(Err(error));
+        }
+        None
+    }
+}
+
 /// Chomping, how final line breaks and trailing empty lines are interpreted.
 ///
 /// See YAML spec 8.1.1.2.
-#[derive(PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Default)]
 pub enum Chomping {
     /// The final line break and any trailing empty lines are excluded.
     Strip,
     /// The final line break is preserved, but trailing empty lines are excluded.
+    #[default]
     Clip,
     /// The final line break and trailing empty lines are included.
     Keep,
@@ -0,0 +1,184 @@
+//! Splitting of a YAML stream into its documents, without parsing them.
+
+use alloc::{vec, vec::Vec};
+use core::ops::Range;
+
+use crate::{char_traits::is_blank, Marker, Span};
+
+/// The text of a document of a YAML stream, as found by [`split_documents`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocumentSlice<'a> {
+    /// The text of the document, which can be given to a parser on its own.
+    pub text: &'a str,
+    /// The range of bytes of the document in the stream.
+    pub byte_range: Range<usize>,
+    /// The span of the document in the stream.
+    pub span: Span,
+}
+
+/// Split a YAML stream into the text of each of its documents.
+///
+/// Documents are delimited by their markers: a document starts at a `---` line or after a `...`
+/// line, and its directives (`%YAML`, `%TAG`) belong to it. Markers are only looked for at the
+/// start of lines; the contents of the documents are not scanned. This is enough to tell them
+/// apart from the contents of scalars and comments, since a line starting with a marker cannot be
+/// part of either (it ends block scalars and is an error within quoted scalars). This makes
+/// splitting much cheaper than parsing, for instance to dispatch documents to workers or to skip
+/// some of them.
+///
+/// Comments and empty lines between documents are part of the document that follows them, or of
+/// the last document if none does. Documents therefore cover the whole stream, unless it has no
+/// document at all, in which case nothing is returned.
+///
+/// Errors are not detected: each document may still fail to parse. Parsing a document on its own
+/// yields the same events as it would within the stream, save for `%TAG` directives kept with
+/// [`Parser::keep_tags`], which are only part of the document they precede.
+///
+/// ```
+/// use saphyr_parser::split_documents;
+///
+/// let input = "a: 1\n---\nb: |\n  ---\n...\n# c\n%YAML 1.2\n---\nd\n";
+/// let docs: Vec<_> = split_documents(input).into_iter().map(|doc| doc.text).collect();
+/// assert_eq!(docs, ["a: 1\n", "---\nb: |\n  ---\n...\n", "# c\n%YAML 1.2\n---\nd\n"]);
+/// ```
+///
+/// [`Parser::keep_tags`]: crate::Parser::keep_tags
+#[must_use]
+pub fn split_documents(input: &str) -> Vec<DocumentSlice<'_>> {
+    let mut documents = vec![];
+    // The start of the document being read and whether it has a marker or contents. Until then,
+    // it is only made of comments, empty lines and directives.
+    let mut start = Position::default();
+    let mut has_document = false;
+    // Whether the document being read follows a `...` (or is the first one), in which case `%`
+    // starts directives.
+    let mut after_end = true;
+    let mut position = Position::default();
+
+    for line in Lines(input) {
+        let end = position.after(line);
+        let bytes = line.strip_prefix('\u{feff}').unwrap_or(line).as_bytes();
+        if is_marker(bytes, b'-') {
+            if has_document {
+                documents.push(position.slice_from(&start, input));
+                start = position;
+            }
+            has_document = true;
+            after_end = false;
+        } else if is_marker(bytes, b'.') {
+            if has_document {
+                documents.push(end.slice_from(&start, input));
+                start = end;
+                has_document = false;
+            }
+            after_end = true;
+        } else if bytes.first() == Some(&b'%') && after_end && !has_document {
+            // A directive of the next document.
+        } else if !is_blank_or_comment(line) {
+            has_document = true;
+            after_end = false;
+        }
+        position = end;
+    }
+
+    if has_document {
+        documents.push(position.slice_from(&start, input));
+    } else if let Some(last) = documents.last_mut() {
+        // Trailing comments and empty lines.
+        *last = position.slice_from(&Position::of(last), input);
+    }
+    documents
+}
+
+/// Return whether `line` starts with a document marker made of `c` (`---` or `...`).
+fn is_marker(line: &[u8], c: u8) -> bool {
+    line.len() >= 3
+        && line[..3].iter().all(|&b| b == c)
+        && line
+            .get(3)
+            .map_or(true, |&b| is_blank(b as char) || b == b'\r' || b == b'\n')
+}
+
+/// Return whether `line` only has whitespace and comments.
+fn is_blank_or_comment(line: &str) -> bool {
+    let line = line.trim_start_matches([' ', '\t', '\r', '\n', '\u{feff}']);
+    line.is_empty() || line.starts_with('#')
+}
+
+/// A position in the stream.
+#[derive(Clone, Copy, Debug, Default)]
+struct Position {
+    /// The index of the byte at the position.
+    byte: usize,
+    /// The index of the character at the position.
+    index: usize,
+    /// The line of the position, starting at 0.
+    line: usize,
+    /// The column of the position.
+    col: usize,
+}
+
+impl Position {
+    /// Return the position of the start of `document`.
+    fn of(document: &DocumentSlice) -> Self {
+        let start = document.span.start;
+        Self {
+            byte: document.byte_range.start,
+            index: start.index(),
+            line: start.line() - 1,
+            col: start.col(),
+        }
+    }
+
+    /// Return the position after `line`, which starts at `self`.
+    fn after(&self, line: &str) -> Self {
+        let chars = line.chars().count();
+        let (line_number, col) = if line.ends_with(['\r', '\n']) {
+            (self.line + 1, 0)
+        } else {
+            (self.line, self.col + chars)
+        };
+        Self {
+            byte: self.byte + line.len(),
+            index: self.index + chars,
+            line: line_number,
+            col,
+        }
+    }
+
+    /// Return the [`Marker`] at this position.
+    fn marker(&self) -> Marker {
+        Marker::new(self.index, self.line + 1, self.col)
+    }
+
+    /// Return the document from `start` to `self`.
+    fn slice_from<'a>(&self, start: &Self, input: &'a str) -> DocumentSlice<'a> {
+        DocumentSlice {
+            text: &input[start.byte..self.byte],
+            byte_range: start.byte..self.byte,
+            span: Span::new(start.marker(), self.marker()),
+        }
+    }
+}
+
+/// An iterator over the lines of a string, line breaks (`\n`, `\r\n` or `\r`) included.
+struct Lines<'a>(&'a str);
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let bytes = self.0.as_bytes();
+        let len = match bytes.iter().position(|&b| b == b'\r' || b == b'\n') {
+            Some(i) if bytes[i] == b'\r' && bytes.get(i + 1) == Some(&b'\n') => i + 2,
+            Some(i) => i + 1,
+            None => bytes.len(),
+        };
+        let (line, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(line)
+    }
+}
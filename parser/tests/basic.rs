@@ -1,7 +1,13 @@
 #![allow(clippy::bool_assert_comparison)]
 #![allow(clippy::float_cmp)]
 
-use saphyr_parser::{Event, Parser, ScanError, TScalarStyle};
+mod common;
+
+use common::slice;
+use saphyr_parser::{
+    split_documents, BlockScalarHeader, Chomping, Event, EventContext, LintKind, Parser,
+    RecordedEvents, ScanError, ScanErrorKind, Span, SpannedEventReceiver, TScalarStyle,
+};
 
 /// Run the parser through the string.
 ///
@@ -12,6 +18,8 @@ fn run_parser(input: &str) -> Result<Vec<Event>, ScanError> {
     let mut str_error = None;
     let mut iter_events = vec![];
     let mut iter_error = None;
+    let mut chunk_events = vec![];
+    let mut chunk_error = None;
 
     for x in Parser::new_from_str(input) {
         match x {
@@ -31,6 +39,15 @@ fn run_parser(input: &str) -> Result<Vec<Event>, ScanError> {
             }
         }
     }
+    for x in Parser::new_from_chunks(chunks(input, 3)) {
+        match x {
+            Ok(event) => chunk_events.push(event),
+            Err(e) => {
+                chunk_error = Some(e);
+                break;
+            }
+        }
+    }
 
     // eprintln!("str_events");
     // for x in &str_events {
@@ -43,6 +60,8 @@ fn run_parser(input: &str) -> Result<Vec<Event>, ScanError> {
 
     assert_eq!(str_events, iter_events);
     assert_eq!(str_error, iter_error);
+    assert_eq!(str_events, chunk_events);
+    assert_eq!(str_error, chunk_error);
 
     if let Some(err) = str_error {
         Err(err)
@@ -51,6 +70,13 @@ fn run_parser(input: &str) -> Result<Vec<Event>, ScanError> {
     }
 }
 
+/// Split `input` into chunks of `size` characters.
+fn chunks(input: &str, size: usize) -> std::vec::IntoIter<String> {
+    let chars: Vec<char> = input.chars().collect();
+    let chunks: Vec<String> = chars.chunks(size).map(|c| c.iter().collect()).collect();
+    chunks.into_iter()
+}
+
 #[test]
 fn test_fail() {
     let s = "
@@ -60,6 +86,7 @@ key: [1, 2]]
 key1:a2
 ";
     let Err(error) = run_parser(s) else { panic!() };
+    assert_eq!(error.kind(), ScanErrorKind::MisplacedIndicator);
     assert_eq!(
         error.info(),
         "mapping values are not allowed in this context"
@@ -83,7 +110,7 @@ fn test_empty_doc() {
             Event::StreamStart,
             Event::DocumentStart(true),
             Event::Scalar("~".to_string(), TScalarStyle::Plain, 0, None),
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
@@ -100,7 +127,7 @@ fn test_utf() {
             Event::Scalar("a".to_string(), TScalarStyle::Plain, 0, None),
             Event::Scalar("你好".to_string(), TScalarStyle::Plain, 0, None),
             Event::MappingEnd,
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
@@ -124,7 +151,7 @@ a: b # This is another comment
             Event::Scalar("a".to_string(), TScalarStyle::Plain, 0, None),
             Event::Scalar("b".to_string(), TScalarStyle::Plain, 0, None),
             Event::MappingEnd,
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
@@ -148,7 +175,7 @@ fn test_quoting() {
             Event::Scalar("squote".to_string(), TScalarStyle::SingleQuoted, 0, None),
             Event::Scalar("dquote".to_string(), TScalarStyle::DoubleQuoted, 0, None),
             Event::SequenceEnd,
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
@@ -169,13 +196,13 @@ a scalar
             Event::StreamStart,
             Event::DocumentStart(false),
             Event::Scalar("a scalar".to_string(), TScalarStyle::Plain, 0, None),
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::DocumentStart(true),
             Event::Scalar("a scalar".to_string(), TScalarStyle::Plain, 0, None),
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::DocumentStart(true),
             Event::Scalar("a scalar".to_string(), TScalarStyle::Plain, 0, None),
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
@@ -190,7 +217,7 @@ fn test_github_27() {
             Event::StreamStart,
             Event::DocumentStart(false),
             Event::Scalar(String::new(), TScalarStyle::Plain, 1, None),
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
@@ -232,7 +259,7 @@ foobar";
             Event::StreamStart,
             Event::DocumentStart(true),
             Event::Scalar("foobar".to_string(), TScalarStyle::Plain, 0, None),
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
@@ -257,9 +284,17 @@ a: |-
             Event::DocumentStart(false),
             Event::MappingStart(0, None),
             Event::Scalar("a".to_string(), TScalarStyle::Plain, 0, None),
-            Event::Scalar("a\n    b".to_string(), TScalarStyle::Literal, 0, None),
+            Event::Scalar(
+                "a\n    b".to_string(),
+                TScalarStyle::Literal(BlockScalarHeader {
+                    chomping: Chomping::Strip,
+                    indent: None,
+                }),
+                0,
+                None,
+            ),
             Event::MappingEnd,
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
@@ -274,7 +309,7 @@ fn test_bad_docstart() {
             Event::StreamStart,
             Event::DocumentStart(false),
             Event::Scalar("----".to_string(), TScalarStyle::Plain, 0, None),
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
@@ -285,7 +320,7 @@ fn test_bad_docstart() {
             Event::StreamStart,
             Event::DocumentStart(true),
             Event::Scalar("~".to_string(), TScalarStyle::Plain, 0, None),
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
@@ -296,12 +331,72 @@ fn test_bad_docstart() {
             Event::StreamStart,
             Event::DocumentStart(false),
             Event::Scalar("----".to_string(), TScalarStyle::Plain, 0, None),
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
+            Event::StreamEnd,
+        ]
+    );
+}
+
+#[test]
+fn test_explicit_document_end() {
+    assert_eq!(
+        run_parser("a\n...\n--- b\n").unwrap(),
+        [
+            Event::StreamStart,
+            Event::DocumentStart(false),
+            Event::Scalar("a".to_string(), TScalarStyle::Plain, 0, None),
+            Event::DocumentEnd(true),
+            Event::DocumentStart(true),
+            Event::Scalar("b".to_string(), TScalarStyle::Plain, 0, None),
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
 }
 
+#[test]
+fn test_event_context() {
+    struct Contexts(Vec<(Event, usize, bool)>);
+
+    impl SpannedEventReceiver for Contexts {
+        fn on_event(&mut self, _: Event, _: Span) {
+            unreachable!("`Parser::load` calls `on_event_with_context`");
+        }
+
+        fn on_event_with_context(&mut self, ev: Event, _: Span, context: EventContext) {
+            self.0.push((ev, context.depth, context.in_flow));
+        }
+    }
+
+    let scalar = |s: &str| Event::Scalar(s.to_string(), TScalarStyle::Plain, 0, None);
+    let mut contexts = Contexts(vec![]);
+    Parser::new_from_str("a:\n  - [b, {c: d}]\n  - e")
+        .load(&mut contexts, true)
+        .unwrap();
+    assert_eq!(
+        contexts.0,
+        [
+            (Event::StreamStart, 0, false),
+            (Event::DocumentStart(false), 0, false),
+            (Event::MappingStart(0, None), 0, false),
+            (scalar("a"), 1, false),
+            (Event::SequenceStart(0, None), 1, false),
+            (Event::SequenceStart(0, None), 2, true),
+            (scalar("b"), 3, true),
+            (Event::MappingStart(0, None), 3, true),
+            (scalar("c"), 4, true),
+            (scalar("d"), 4, true),
+            (Event::MappingEnd, 3, true),
+            (Event::SequenceEnd, 2, true),
+            (scalar("e"), 2, false),
+            (Event::SequenceEnd, 1, false),
+            (Event::MappingEnd, 0, false),
+            (Event::DocumentEnd(false), 0, false),
+            (Event::StreamEnd, 0, false),
+        ]
+    );
+}
+
 #[test]
 fn test_indentation_equality() {
     let four_spaces = run_parser(
@@ -363,3 +458,389 @@ fn test_recursion_depth_check_arrays() {
     let s = "[".repeat(10_000) + &"]".repeat(10_000);
     assert!(run_parser(&s).is_err());
 }
+
+#[test]
+fn test_error_kinds() {
+    let cases = [
+        ("\"abc", ScanErrorKind::UnexpectedEof),
+        ("[1, 2", ScanErrorKind::UnclosedFlow),
+        ("a:\n\t- b", ScanErrorKind::TabInIndent),
+        ("a: \"\\q\"", ScanErrorKind::InvalidEscape),
+        ("*anchor", ScanErrorKind::UnknownAnchor),
+        ("!foo!bar baz", ScanErrorKind::InvalidTag),
+        ("%YAML 1.2\n%YAML 1.2\n---", ScanErrorKind::InvalidDirective),
+        ("a: |0\n  b", ScanErrorKind::InvalidBlockScalar),
+        ("a: b\n c: d", ScanErrorKind::MisplacedIndicator),
+        (&"[".repeat(10_000), ScanErrorKind::RecursionLimitExceeded),
+    ];
+    for (input, kind) in cases {
+        let Err(error) = run_parser(input) else {
+            panic!("{input:?} should fail to parse")
+        };
+        assert_eq!(error.kind(), kind, "{input:?}: {error}");
+    }
+}
+
+#[test]
+fn test_length_limits() {
+    let parse = |input: &str| {
+        let from_str = Parser::new_from_str(input)
+            .max_line_length(20)
+            .max_comment_length(10)
+            .collect::<Result<Vec<_>, _>>();
+        let from_iter = Parser::new_from_iter(input.chars())
+            .max_line_length(20)
+            .max_comment_length(10)
+            .collect::<Result<Vec<_>, _>>();
+        // Inputs read more or less characters at once, so errors may not be at the same place.
+        assert_eq!(
+            from_str.as_ref().map_err(ScanError::kind),
+            from_iter.as_ref().map_err(ScanError::kind),
+            "{input:?}"
+        );
+        from_str.map(|_| ())
+    };
+    let long = "a".repeat(1000);
+    let spaces = " ".repeat(1000);
+
+    for input in [
+        format!("key: {long}"),
+        format!("key: \"{long}\""),
+        format!("key: '{long}'"),
+        format!("key: a{spaces}b"),
+        format!("key: \"a{spaces}b\""),
+        format!("key: |\n  {long}\n"),
+        format!("key: >\n  {long}"),
+        format!("[{}]", "1,".repeat(100)),
+        format!("a: b{spaces}"),
+        format!("a: b # {long}"),
+        format!("# {long}"),
+    ] {
+        let Err(error) = parse(&input) else {
+            panic!("{input:?} should fail to parse")
+        };
+        assert_eq!(
+            error.kind(),
+            ScanErrorKind::LengthLimitExceeded,
+            "{input:?}"
+        );
+        assert_eq!(error.marker().line(), input.lines().count(), "{input:?}");
+    }
+
+    // Lines and comments within the limits.
+    parse("key: aaaaaaaaaaaaaaa\n# 34567890\nb: |\n  aaaaaaaaaaaaaaaaaa\n").unwrap();
+    // Line breaks are not counted.
+    parse(&"a: b\n".repeat(100)).unwrap();
+}
+
+#[test]
+fn test_document_restrictions() {
+    let parse = |input: &str, require_start: bool, single: bool| {
+        Parser::new_from_str(input)
+            .require_explicit_document_start(require_start)
+            .forbid_multiple_documents(single)
+            .find_map(Result::err)
+            .map(|error| {
+                assert_eq!(error.kind(), ScanErrorKind::DocumentNotAllowed, "{input:?}");
+                (error.marker().line(), error.marker().col())
+            })
+    };
+
+    assert_eq!(parse("a: b", true, false), Some((1, 0)));
+    assert_eq!(parse("--- a\n...\nb\n", true, false), Some((3, 0)));
+    assert_eq!(parse("%YAML 1.2\n--- a\n--- b\n", true, false), None);
+    assert_eq!(parse("# only comments\n", true, false), None);
+
+    assert_eq!(parse("a: b\n# c\n--- d\n", false, true), Some((3, 0)));
+    assert_eq!(parse("--- a\n...\nb\n", false, true), Some((3, 0)));
+    assert_eq!(parse("--- a\n...\n# end\n", false, true), None);
+    assert_eq!(parse("", false, true), None);
+}
+
+#[test]
+fn test_max_documents() {
+    let first_error = |input: &str, limit: usize| {
+        Parser::new_from_str(input)
+            .max_documents(limit)
+            .find_map(Result::err)
+            .map(|error| {
+                assert_eq!(error.kind(), ScanErrorKind::DocumentNotAllowed, "{input:?}");
+                error.marker().line()
+            })
+    };
+
+    assert_eq!(first_error("--- a\n--- b\n--- c\n", 3), None);
+    assert_eq!(first_error("--- a\n--- b\n--- c\n--- d\n", 3), Some(4));
+    assert_eq!(first_error("a\n...\nb\n", 1), Some(3));
+    assert_eq!(first_error("# only comments\n", 0), None);
+    assert_eq!(first_error("a", 0), Some(1));
+
+    // The last option set wins.
+    let parser = Parser::new_from_str("--- a\n--- b\n")
+        .forbid_multiple_documents(true)
+        .max_documents(2);
+    assert!(parser.into_iter().all(|event| event.is_ok()));
+    // Unsetting `forbid_multiple_documents` keeps a different limit.
+    let mut parser = Parser::new_from_str("--- a\n--- b\n--- c\n")
+        .max_documents(2)
+        .forbid_multiple_documents(false);
+    assert!(parser.any(|event| event.is_err()));
+    let parser = Parser::new_from_str("--- a\n--- b\n")
+        .forbid_multiple_documents(true)
+        .forbid_multiple_documents(false);
+    assert!(parser.into_iter().all(|event| event.is_ok()));
+}
+
+#[test]
+fn test_error_kind_of_user_errors() {
+    let error = ScanError::new_str(saphyr_parser::Marker::new(0, 1, 0), "custom");
+    assert_eq!(error.kind(), ScanErrorKind::Other);
+}
+
+#[test]
+fn test_error_span_and_context() {
+    // Errors within a flow collection have the opening bracket as context.
+    let Err(error) = run_parser("a: [1, 2") else {
+        panic!()
+    };
+    let context = error.context_marker().unwrap();
+    assert_eq!((context.line(), context.col()), (1, 3));
+    assert_eq!(error.span().start, *error.marker());
+
+    // Errors within a scalar span from the start of the scalar.
+    let Err(error) = run_parser("a: \"abc") else {
+        panic!()
+    };
+    assert_eq!(error.context_marker(), Some(&error.span().start));
+    assert_eq!(error.span().start.index(), 3);
+    assert_eq!(error.span().end.index(), 7);
+
+    // Other errors have an empty span and no context.
+    let Err(error) = run_parser("a: b\n c: d") else {
+        panic!()
+    };
+    assert_eq!(error.context_marker(), None);
+    assert_eq!(error.span(), saphyr_parser::Span::empty(*error.marker()));
+}
+
+#[test]
+fn test_chunked_input_split_anywhere() {
+    let input = "---\nkey: [\"välue\", '🎉']\n# comment\nlist:\n  - |\n    literal\n  - ~\n...\n";
+    let expected: Vec<_> = Parser::new_from_str(input).map(Result::unwrap).collect();
+    let chars: Vec<_> = input.char_indices().map(|(i, _)| i).collect();
+    for &split in &chars {
+        let (a, b) = input.split_at(split);
+        // Also include empty chunks, which must be skipped.
+        let events: Vec<_> = Parser::new_from_chunks([a, "", b].into_iter())
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(events, expected, "split at byte {split}");
+    }
+}
+
+#[test]
+fn test_stats() {
+    let input = "---\na: &x [1, \"x\\ty\"]\nb: {c: *x, d: [[]]}\n---\nfoo\n";
+    let mut parser = Parser::new_from_str(input).collect_stats(true);
+    assert_eq!(parser.stats().map(|stats| stats.documents), Some(0));
+    while let Some(event) = parser.next_event() {
+        event.unwrap();
+    }
+    let stats = parser.stats().unwrap();
+    assert_eq!(stats.documents, 2);
+    assert_eq!(stats.sequences, 3);
+    assert_eq!(stats.mappings, 2);
+    assert_eq!(stats.scalars, 7);
+    assert_eq!(stats.aliases, 1);
+    assert_eq!(stats.max_depth, 4);
+    // `a`, `1`, `x\ty`, `b`, `c`, `d` and `foo`.
+    assert_eq!(stats.scalar_bytes, 11);
+
+    // Statistics are not collected by default.
+    let mut parser = Parser::new_from_str(input);
+    parser.by_ref().for_each(drop);
+    assert!(parser.stats().is_none());
+}
+
+#[test]
+fn test_split_documents() {
+    let inputs = [
+        "",
+        "# only a comment\n",
+        "a: 1",
+        "a: 1\n---\nb: 2\n",
+        "# c\n---\na\n...\n# d\n\n...\n---\nb\n# e\n",
+        "%YAML 1.2\n%TAG !e! tag:example.com,2000:\n--- !e!x\na\n...\n%YAML 1.2\n---\nb\n",
+        "a: |\n  ---\n  ...\n---\nb: \"c\n  d\"\n---\r\nc # ---\r\n...\r\nd\n",
+        "---\n---\n--- x\n...\n",
+        "\u{feff}--- é\n---\nè",
+        "a: ---\n----: b\n...x: c\n",
+    ];
+    for input in inputs {
+        let documents = split_documents(input);
+        // The documents cover the input.
+        let texts: String = documents.iter().map(|doc| doc.text).collect();
+        if !documents.is_empty() {
+            assert_eq!(texts, input);
+        }
+        for doc in &documents {
+            assert_eq!(&input[doc.byte_range.clone()], doc.text);
+            assert_eq!(slice(input, doc.span), doc.text);
+        }
+        // Parsing each document gives the same events as parsing the whole stream.
+        let stream_events = run_parser(input).unwrap();
+        let mut events = vec![Event::StreamStart];
+        for doc in &documents {
+            let doc_events = run_parser(doc.text).unwrap();
+            assert_eq!(
+                doc_events
+                    .iter()
+                    .filter(|ev| matches!(ev, Event::DocumentStart(_)))
+                    .count(),
+                1,
+                "{:?}",
+                doc.text
+            );
+            events.extend(doc_events[1..doc_events.len() - 1].iter().cloned());
+        }
+        events.push(Event::StreamEnd);
+        assert_eq!(events, stream_events, "{input:?}");
+    }
+
+    let documents = split_documents("a\n---\nb\n...\n# c");
+    assert_eq!(documents.len(), 2);
+    assert_eq!(documents[1].text, "---\nb\n...\n# c");
+    assert_eq!(documents[1].byte_range, 2..15);
+    assert_eq!(
+        (documents[1].span.start.line(), documents[1].span.end.line()),
+        (2, 5)
+    );
+    assert_eq!(documents[1].span.end.col(), 3);
+}
+
+#[test]
+fn test_recorded_events() {
+    /// Receiver logging all calls it receives.
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    impl SpannedEventReceiver for Log {
+        fn on_event(&mut self, _: Event, _: Span) {
+            unreachable!()
+        }
+
+        fn on_anchor(&mut self, id: usize, name: &str, span: Span) {
+            self.0
+                .push(format!("anchor {id} {name} {}", span.start.index()));
+        }
+
+        fn on_event_with_context(&mut self, ev: Event, span: Span, context: EventContext) {
+            self.0
+                .push(format!("{ev:?} {} {context:?}", span.start.index()));
+        }
+    }
+
+    let input = "a: &x [b, {c: d}]\ne: *x\n---\nf\n";
+    let mut direct = Log::default();
+    Parser::new_from_str(input).load(&mut direct, true).unwrap();
+    // Anchors are located at their `&name`.
+    assert!(
+        direct.0.contains(&"anchor 1 x 3".to_owned()),
+        "{:?}",
+        direct.0
+    );
+
+    let recorded = RecordedEvents::record(&mut Parser::new_from_str(input), true).unwrap();
+    assert_eq!(recorded.len(), 19);
+    assert!(!recorded.is_empty());
+    assert_eq!(
+        recorded
+            .events()
+            .map(|(ev, _)| ev.clone())
+            .collect::<Vec<_>>(),
+        run_parser(input).unwrap()
+    );
+    for _ in 0..2 {
+        let mut replayed = Log::default();
+        recorded.replay(&mut replayed);
+        assert_eq!(replayed.0, direct.0);
+    }
+
+    let recorded = RecordedEvents::record(&mut Parser::new_from_str(input), false).unwrap();
+    assert_eq!(recorded.len(), 15);
+    assert!(RecordedEvents::record(&mut Parser::new_from_str("[a"), true).is_err());
+    assert!(RecordedEvents::new().is_empty());
+}
+
+#[test]
+fn test_block_scalar_header() {
+    let styles: Vec<_> = run_parser("- |\n a\n- >-\n a\n- |+2\n   a\n- >3-\n    a\n")
+        .unwrap()
+        .into_iter()
+        .filter_map(|ev| match ev {
+            Event::Scalar(_, style, ..) => Some(style),
+            _ => None,
+        })
+        .collect();
+    let header = |chomping, indent| BlockScalarHeader { chomping, indent };
+    assert_eq!(
+        styles,
+        [
+            TScalarStyle::Literal(header(Chomping::Clip, None)),
+            TScalarStyle::Folded(header(Chomping::Strip, None)),
+            TScalarStyle::Literal(header(Chomping::Keep, Some(2))),
+            TScalarStyle::Folded(header(Chomping::Strip, Some(3))),
+        ]
+    );
+    let headers: Vec<_> = styles
+        .iter()
+        .map(|style| match style {
+            TScalarStyle::Literal(header) | TScalarStyle::Folded(header) => header.to_string(),
+            _ => unreachable!(),
+        })
+        .collect();
+    assert_eq!(headers, ["", "-", "2+", "3-"]);
+}
+
+#[test]
+fn test_tabs_after_flow_indicators() {
+    let lints = |input: &str| {
+        let mut parser = Parser::new_from_str(input);
+        for event in parser.by_ref() {
+            event.unwrap();
+        }
+        parser
+            .lints()
+            .iter()
+            .map(|lint| {
+                (
+                    lint.kind(),
+                    lint.span().start.index(),
+                    lint.span().end.index(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        run_parser("{a:\tb, [c]:\t-d}").unwrap(),
+        run_parser("{a: b, [c]: -d}").unwrap()
+    );
+    assert_eq!(
+        run_parser("[?\ta, ? \tb: c]").unwrap(),
+        run_parser("[? a, ? b: c]").unwrap()
+    );
+    assert_eq!(
+        lints("{a:\tb, ? \tc: d}"),
+        [
+            (LintKind::TabAfterFlowIndicator, 3, 4),
+            (LintKind::TabAfterFlowIndicator, 8, 10)
+        ]
+    );
+    assert_eq!(lints("{a: b, ? c:\t\n  d}"), []);
+
+    // Block collections may not start after a tab, so the scanner still rejects these outside of
+    // flow collections.
+    assert!(run_parser("a:\tb").is_err());
+    assert!(run_parser("?\tkey:").is_err());
+}
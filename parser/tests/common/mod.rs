@@ -0,0 +1,14 @@
+//! Helpers shared by the integration tests.
+
+use saphyr_parser::Span;
+
+/// Return the part of `input` covered by `span`.
+///
+/// Markers count characters, not bytes, so this cannot index `input` directly.
+pub fn slice(input: &str, span: Span) -> String {
+    input
+        .chars()
+        .skip(span.start.index())
+        .take(span.end.index() - span.start.index())
+        .collect()
+}
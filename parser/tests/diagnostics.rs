@@ -0,0 +1,61 @@
+#![cfg(feature = "diagnostics")]
+
+use miette::{Diagnostic, GraphicalReportHandler, GraphicalTheme, Report};
+use saphyr_parser::{Parser, ScanError};
+
+fn first_error(source: &str) -> ScanError {
+    Parser::new_from_str(source)
+        .find_map(Result::err)
+        .expect("the source should fail to parse")
+}
+
+#[test]
+fn test_error_is_labelled_at_marker() {
+    let err = first_error("a: [1, 2\nb: c");
+    let labels: Vec<_> = err.labels().unwrap().collect();
+    assert_eq!(labels.len(), 1);
+    assert_eq!(labels[0].offset(), err.marker().index());
+    assert!(err.code().is_some());
+}
+
+#[test]
+fn test_report_renders_source() {
+    let source = "key: value\nkey: [unterminated\n";
+    let err = first_error(source);
+    let report = Report::new(err).with_source_code(source.to_owned());
+    let mut out = String::new();
+    GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+        .render_report(&mut out, report.as_ref())
+        .unwrap();
+    assert!(out.contains("saphyr_parser::scan_error"));
+    assert!(out.contains("key: [unterminated"));
+}
+
+#[test]
+fn test_context_is_labelled() {
+    let err = first_error("a: [1, 2");
+    let offsets: Vec<_> = err.labels().unwrap().map(|l| l.offset()).collect();
+    assert_eq!(offsets, [err.span().start.index(), 3]);
+}
+
+#[test]
+fn test_labels_are_byte_offsets_with_source() {
+    let source = "é: [1, 2";
+    let err = first_error(source).with_source(source);
+    let offsets: Vec<_> = err.labels().unwrap().map(|l| l.offset()).collect();
+    assert_eq!(offsets, [err.span().byte_range(source).unwrap().start, 4]);
+    assert_eq!(&source[4..5], "[");
+}
+
+#[test]
+fn test_lint_is_a_warning() {
+    let source = "é: {a:\tb}";
+    let mut parser = Parser::new_from_str(source);
+    assert!(parser.by_ref().all(|event| event.is_ok()));
+    let lint = parser.lints()[0].with_source(source);
+    assert_eq!(lint.severity(), Some(miette::Severity::Warning));
+    let label = lint.labels().unwrap().next().unwrap();
+    let range = lint.span().byte_range(source).unwrap();
+    assert_eq!((label.offset(), label.len()), (range.start, range.len()));
+    assert_eq!(range.start, 7);
+}
@@ -1,4 +1,4 @@
-use saphyr_parser::{Event, Parser, ScanError, TScalarStyle};
+use saphyr_parser::{BlockScalarHeader, Chomping, Event, Parser, ScanError, TScalarStyle};
 
 /// Run the parser through the string.
 ///
@@ -86,7 +86,7 @@ fn test_issue1() {
         Event::SequenceEnd,
         Event::MappingEnd,
         Event::SequenceEnd,
-        Event::DocumentEnd,
+        Event::DocumentEnd(false),
         Event::StreamEnd,
     ];
     assert_eq!(run_parser(reference).unwrap(), expected);
@@ -112,7 +112,7 @@ fn test_issue1() {
             Event::SequenceEnd,
             Event::Scalar("baz".to_string(), TScalarStyle::Plain, 0, None),
             Event::MappingEnd,
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
@@ -129,7 +129,7 @@ fn test_issue1() {
             Event::Scalar("~".to_string(), TScalarStyle::Plain, 0, None),
             Event::MappingEnd,
             Event::SequenceEnd,
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
@@ -151,7 +151,7 @@ fn test_issue1() {
             Event::SequenceEnd,
             Event::MappingEnd,
             Event::SequenceEnd,
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
@@ -182,7 +182,7 @@ fn test_issue1() {
             Event::SequenceEnd,
             Event::MappingEnd,
             Event::SequenceEnd,
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
@@ -204,7 +204,7 @@ fn test_issue1() {
             Event::SequenceEnd,
             Event::MappingEnd,
             Event::SequenceEnd,
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
@@ -218,9 +218,14 @@ fn test_pr12() {
             Event::StreamStart,
             Event::DocumentStart(true),
             Event::SequenceStart(0, None),
-            Event::Scalar("a\n".to_string(), TScalarStyle::Literal, 0, None),
+            Event::Scalar(
+                "a\n".to_string(),
+                TScalarStyle::Literal(BlockScalarHeader::default()),
+                0,
+                None,
+            ),
             Event::SequenceEnd,
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd,
         ]
     );
@@ -288,7 +293,15 @@ array:
             Event::SequenceStart(0, None),
             Event::MappingStart(0, None),
             Event::Scalar("text".to_string(), TScalarStyle::Plain, 0, None),
-            Event::Scalar("Line 1 Line 2".to_string(), TScalarStyle::Folded, 0, None),
+            Event::Scalar(
+                "Line 1 Line 2".to_string(),
+                TScalarStyle::Folded(BlockScalarHeader {
+                    chomping: Chomping::Strip,
+                    indent: None,
+                }),
+                0,
+                None,
+            ),
             Event::MappingEnd,
             Event::SequenceEnd,
             Event::MappingEnd,
@@ -298,7 +311,7 @@ array:
             Event::MappingEnd,
             Event::SequenceEnd,
             Event::MappingEnd,
-            Event::DocumentEnd,
+            Event::DocumentEnd(true),
             Event::StreamEnd
         ]
     );
@@ -318,7 +331,7 @@ fn test_issue22() {
             Event::Scalar("comment".to_string(), TScalarStyle::Plain, 0, None),
             Event::Scalar("hello ... world".to_string(), TScalarStyle::Plain, 0, None),
             Event::MappingEnd,
-            Event::DocumentEnd,
+            Event::DocumentEnd(false),
             Event::StreamEnd
         ]
     );
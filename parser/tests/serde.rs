@@ -0,0 +1,61 @@
+#![cfg(feature = "serde")]
+
+use saphyr_parser::{Event, Parser, Span};
+
+fn serialize(events: &[(Event, Span)]) -> String {
+    saphyr::to_string(&events).unwrap()
+}
+
+#[test]
+fn test_serialize_events() {
+    let events: Vec<_> = Parser::new_from_str("--- &a !!str x\n")
+        .map(Result::unwrap)
+        .map(|(ev, _)| ev)
+        .collect();
+    assert_eq!(
+        saphyr::to_string(&events).unwrap(),
+        "\
+---
+- StreamStart
+- DocumentStart:
+    explicit: true
+- Scalar:
+    value: x
+    style: Plain
+    anchor_id: 1
+    tag:
+      handle: \"tag:yaml.org,2002:\"
+      suffix: str
+      original_handle: \"!!\"
+- DocumentEnd:
+    explicit: false
+- StreamEnd"
+    );
+}
+
+#[test]
+fn test_serialize_spans_and_styles() {
+    let events: Vec<_> = Parser::new_from_str("- &x |2-\n   a\n- *x")
+        .map(Result::unwrap)
+        .filter(|(ev, _)| matches!(ev, Event::Scalar(..) | Event::Alias(_)))
+        .collect();
+    let out = serialize(&events);
+    let doc = &saphyr::Yaml::load_from_str(&out).unwrap()[0];
+
+    let scalar = &doc[0][0]["Scalar"];
+    assert_eq!(scalar["value"].as_str(), Some(" a"));
+    assert_eq!(
+        scalar["style"]["Literal"]["chomping"].as_str(),
+        Some("Strip")
+    );
+    assert_eq!(scalar["style"]["Literal"]["indent"].as_i64(), Some(2));
+    assert!(scalar["tag"].is_null());
+    let (span, expected) = (&doc[0][1], events[0].1);
+    let as_usize = |node: &saphyr::Yaml| usize::try_from(node.as_i64().unwrap()).unwrap();
+    assert_eq!(as_usize(&span["start"]["index"]), expected.start.index());
+    assert_eq!(as_usize(&span["start"]["line"]), expected.start.line());
+    assert_eq!(as_usize(&span["start"]["col"]), expected.start.col());
+    assert_eq!(as_usize(&span["end"]["index"]), expected.end.index());
+
+    assert_eq!(doc[1][0]["Alias"]["anchor_id"].as_i64(), Some(1));
+}
@@ -1,7 +1,7 @@
 #![allow(clippy::bool_assert_comparison)]
 #![allow(clippy::float_cmp)]
 
-use saphyr_parser::{Event, Parser, ScanError};
+use saphyr_parser::{Event, Parser, ScanError, Span, SpannedEventReceiver};
 
 /// Run the parser through the string, returning all the scalars, and collecting their spans to strings.
 fn run_parser_and_deref_scalar_spans(input: &str) -> Result<Vec<(String, String)>, ScanError> {
@@ -164,3 +164,115 @@ fn test_block_utf8() {
         ],
     );
 }
+
+#[test]
+fn test_block_scalar_header_and_content() {
+    /// Collects the text of the header and of the content of block scalars.
+    struct Collector<'a> {
+        input: &'a str,
+        scalars: Vec<(String, String)>,
+    }
+
+    impl Collector<'_> {
+        fn text(&self, span: Span) -> String {
+            self.input
+                .chars()
+                .skip(span.start.index())
+                .take(span.end.index() - span.start.index())
+                .collect()
+        }
+    }
+
+    impl SpannedEventReceiver for Collector<'_> {
+        fn on_event(&mut self, _: Event, _: Span) {}
+
+        fn on_block_scalar_header(&mut self, header: Span, span: Span) {
+            self.scalars.push((self.text(header), self.text(span)));
+        }
+    }
+
+    let collect = |input| {
+        let mut collector = Collector {
+            input,
+            scalars: vec![],
+        };
+        Parser::new_from_str(input)
+            .load(&mut collector, true)
+            .unwrap();
+        collector.scalars
+    };
+    assert_eq!(deref_pairs(&collect("a: |\n  x\n  \nb: c")), [("|", "x")]);
+    assert_eq!(
+        deref_pairs(&collect("a: >2- # comment\n   x\n  y\n\n")),
+        [(">2-", " x\n  y")]
+    );
+    assert_eq!(
+        deref_pairs(&collect("- |+\n\n  x  \n\n- |\n- &a !t >\n z")),
+        [("|+", "x  "), ("|", ""), (">", "z")]
+    );
+}
+
+#[test]
+fn test_flow_delimiters() {
+    let delimiters = |input: &str| {
+        Parser::new_from_str(input)
+            .map(Result::unwrap)
+            .filter_map(|(ev, span)| match ev {
+                Event::SequenceStart(..)
+                | Event::SequenceEnd
+                | Event::MappingStart(..)
+                | Event::MappingEnd => Some(
+                    input
+                        .chars()
+                        .skip(span.start.index())
+                        .take(span.end.index() - span.start.index())
+                        .collect::<String>(),
+                ),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        delimiters("a: [ b ,{c: d} ]  # comment"),
+        ["", "[", "{", "}", "]", ""]
+    );
+    assert_eq!(
+        delimiters("[\t[a: b]\t,\n{ } ]"),
+        ["[", "[", "", "", "]", "{", "}", "]"]
+    );
+    assert_eq!(delimiters("- a\n- b"), ["", ""]);
+}
+
+#[test]
+fn test_missing_value() {
+    // The empty scalar of a missing value is right after its `:`.
+    for input in ["a:\nb: c", "{a: , b: c}", "[a: , b: c]", "{a:}"] {
+        let span = Parser::new_from_str(input)
+            .map(Result::unwrap)
+            .find_map(|(ev, span)| match ev {
+                Event::Scalar(value, ..) if value == "~" => Some(span),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(span.start, span.end);
+        assert_eq!(
+            span.start.index(),
+            input.find(':').unwrap() + 1,
+            "{input:?}"
+        );
+    }
+}
+
+#[test]
+fn test_quoted_trailing_blanks() {
+    // Blanks and comments after the closing quote are not part of the scalar.
+    for (input, expected) in [
+        ("a: 'b'  # c", ["a", "'b'"]),
+        ("[\"d\" , 'e' ]", ["\"d\"", "'e'"]),
+    ] {
+        let r = run_parser_and_deref_scalar_spans(input).unwrap();
+        let spans: Vec<_> = r.iter().map(|(_, span)| span.as_str()).collect();
+        assert_eq!(spans, expected);
+    }
+}
@@ -27,7 +27,7 @@ impl EventReceiver for YamlChecker {
     fn on_event(&mut self, ev: Event) {
         let tev = match ev {
             Event::DocumentStart(_) => TestEvent::OnDocumentStart,
-            Event::DocumentEnd => TestEvent::OnDocumentEnd,
+            Event::DocumentEnd(_) => TestEvent::OnDocumentEnd,
             Event::SequenceStart(..) => TestEvent::OnSequenceStart,
             Event::SequenceEnd => TestEvent::OnSequenceEnd,
             Event::MappingStart(..) => TestEvent::OnMapStart,
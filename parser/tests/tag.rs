@@ -0,0 +1,75 @@
+use saphyr_parser::{Event, Parser, Tag};
+
+/// Return the tags of the scalars in `input`, in order.
+fn scalar_tags(input: &str) -> Vec<Tag> {
+    Parser::new_from_str(input)
+        .map(|x| x.unwrap().0)
+        .filter_map(|ev| match ev {
+            Event::Scalar(_, _, _, tag) => tag,
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_tag_core() {
+    let tag = Tag::core("int");
+    assert!(tag.is_core_schema());
+    assert!(tag.suffix_is("int"));
+    assert!(!tag.suffix_is("str"));
+    assert_eq!(tag.resolved(), "tag:yaml.org,2002:int");
+    assert_eq!(tag.to_string(), "!!int");
+    assert_eq!(scalar_tags("!!int 3"), [tag]);
+}
+
+#[test]
+fn test_tag_custom() {
+    let tag = Tag::custom("tag:example.com,2000:", "foo");
+    assert!(!tag.is_core_schema());
+    assert!(tag.suffix_is("foo"));
+    assert_eq!(tag.to_string(), "!<tag:example.com,2000:foo>");
+
+    let parsed = &scalar_tags("%TAG !e! tag:example.com,2000:\n--- !e!foo bar")[0];
+    assert_eq!(parsed.resolved(), tag.resolved());
+    assert_eq!(parsed.to_string(), "!e!foo");
+}
+
+#[test]
+fn test_tag_from_str() {
+    assert_eq!("!!str".parse(), Ok(Tag::core("str")));
+    for s in ["!!str", "!foo", "!"] {
+        let tag: Tag = s.parse().unwrap();
+        assert_eq!(tag.to_string(), s);
+        assert_eq!(scalar_tags(&format!("{s} a")), [tag]);
+    }
+
+    let verbatim: Tag = "!<tag:example.com,2000:foo>".parse().unwrap();
+    assert_eq!(verbatim.resolved(), "tag:example.com,2000:foo");
+
+    for s in ["", "str", "!!", "!<", "!<>", "!e!foo"] {
+        assert!(s.parse::<Tag>().is_err(), "{s:?} should not parse");
+    }
+}
+
+#[test]
+fn test_tag_verbatim() {
+    let tags =
+        scalar_tags("- !<tag:example.com,2000:foo> a\n- !<!bar> b\n- !<tag:yaml.org,2002:str> c");
+    assert!(tags.iter().all(Tag::is_verbatim));
+    let formatted: Vec<_> = tags.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        formatted,
+        [
+            "!<tag:example.com,2000:foo>",
+            "!<!bar>",
+            "!<tag:yaml.org,2002:str>"
+        ]
+    );
+    assert_eq!(tags[2].resolved(), Tag::core("str").resolved());
+    assert!(tags[2].is_core_schema() && tags[2].suffix_is("str"));
+
+    for s in ["!!str", "!foo", "!"] {
+        assert!(!s.parse::<Tag>().unwrap().is_verbatim());
+    }
+    assert!(Tag::custom("tag:example.com,2000:", "foo").is_verbatim());
+}
@@ -0,0 +1,77 @@
+use saphyr_parser::{tokenize, ScanErrorKind, Span, TScalarStyle, TokenType};
+
+/// Return the part of `input` covered by `span`.
+fn slice(input: &str, span: Span) -> String {
+    input
+        .chars()
+        .skip(span.start.index())
+        .take(span.end.index() - span.start.index())
+        .collect()
+}
+
+#[test]
+fn test_tokenize() {
+    let input = "a: [1, 2]";
+    let tokens: Vec<_> = tokenize(input).map(Result::unwrap).collect();
+    let types: Vec<_> = tokens.iter().map(|(token, _)| token.clone()).collect();
+    assert_eq!(
+        types,
+        [
+            TokenType::StreamStart(saphyr_parser::TEncoding::Utf8),
+            TokenType::BlockMappingStart,
+            TokenType::Key,
+            TokenType::Scalar(TScalarStyle::Plain, "a".into()),
+            TokenType::Value,
+            TokenType::FlowSequenceStart,
+            TokenType::Scalar(TScalarStyle::Plain, "1".into()),
+            TokenType::FlowEntry,
+            TokenType::Scalar(TScalarStyle::Plain, "2".into()),
+            TokenType::FlowSequenceEnd,
+            TokenType::BlockEnd,
+            TokenType::StreamEnd,
+        ]
+    );
+    assert_eq!(slice(input, tokens[3].1), "a");
+    assert_eq!(slice(input, tokens[8].1), "2");
+}
+
+#[test]
+fn test_tokenize_resumes_after_error() {
+    let input = "ké: `bad\nother: välue\n";
+    let items: Vec<_> = tokenize(input).collect();
+
+    let errors: Vec<_> = items
+        .iter()
+        .filter_map(|item| item.as_ref().err())
+        .collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind(), ScanErrorKind::UnexpectedCharacter);
+    assert_eq!(errors[0].marker().line(), 1);
+
+    let tokens: Vec<_> = items.into_iter().filter_map(Result::ok).collect();
+    let (_, span) = tokens
+        .iter()
+        .find(|(token, _)| *token == TokenType::Scalar(TScalarStyle::Plain, "välue".into()))
+        .expect("tokens after the error should be yielded");
+    assert_eq!((span.start.line(), span.start.col()), (2, 7));
+    assert_eq!(slice(input, *span), "välue");
+
+    // The stream is only started and ended once.
+    assert!(matches!(
+        tokens.first(),
+        Some((TokenType::StreamStart(_), _))
+    ));
+    assert!(matches!(tokens.last(), Some((TokenType::StreamEnd, _))));
+    let starts = tokens
+        .iter()
+        .filter(|(token, _)| matches!(token, TokenType::StreamStart(_)))
+        .count();
+    assert_eq!(starts, 1);
+}
+
+#[test]
+fn test_tokenize_error_on_last_line() {
+    let items: Vec<_> = tokenize("a: b\n`").collect();
+    assert!(items.last().unwrap().is_err());
+    assert_eq!(items.iter().filter(|item| item.is_err()).count(), 1);
+}
@@ -207,7 +207,7 @@ impl SpannedEventReceiver for EventReporter {
             Event::StreamEnd => "-STR".into(),
 
             Event::DocumentStart(_) => "+DOC".into(),
-            Event::DocumentEnd => "-DOC".into(),
+            Event::DocumentEnd(_) => "-DOC".into(),
 
             Event::SequenceStart(idx, tag) => {
                 format!("+SEQ{}{}", format_index(idx), format_tag(&tag))
@@ -224,8 +224,8 @@ impl SpannedEventReceiver for EventReporter {
                     TScalarStyle::Plain => ":",
                     TScalarStyle::SingleQuoted => "'",
                     TScalarStyle::DoubleQuoted => r#"""#,
-                    TScalarStyle::Literal => "|",
-                    TScalarStyle::Folded => ">",
+                    TScalarStyle::Literal(_) => "|",
+                    TScalarStyle::Folded(_) => ">",
                 };
                 format!(
                     "=VAL{}{} {}{}",
@@ -338,8 +338,7 @@ fn expected_events(expected_tree: &str) -> Vec<String> {
                 let idx = anchors
                     .iter()
                     .enumerate()
-                    .filter(|(_, v)| v == &name)
-                    .last()
+                    .rfind(|(_, v)| v == &name)
                     .unwrap()
                     .0;
                 s = s.replace(&s[start..], &format!("*{}", idx + 1));
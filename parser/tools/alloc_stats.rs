@@ -0,0 +1,100 @@
+//! A counting global allocator for the benchmarking tools.
+//!
+//! This module is only compiled with the `alloc_stats` feature, and in the `quoted_scalars`
+//! benchmark of `saphyr-bench`. It replaces the global allocator with a thin wrapper around
+//! [`System`] which keeps track of the number of allocations and of the peak number of bytes
+//! allocated at once. Counting has a (small) cost on every allocation, which is why it is not
+//! enabled by default: timings collected with it are not comparable with timings collected
+//! without it.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Number of allocations (including reallocations) since the last [`reset`].
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+/// Number of bytes currently allocated.
+static CURRENT_BYTES: AtomicUsize = AtomicUsize::new(0);
+/// Highest value [`CURRENT_BYTES`] reached since the last [`reset`].
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+/// Value of [`CURRENT_BYTES`] when [`reset`] was last called.
+static BASELINE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// A [`System`] allocator wrapper counting allocations.
+struct CountingAllocator;
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Record that `size` bytes were just allocated.
+fn record_alloc(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    let current = CURRENT_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK_BYTES.fetch_max(current, Ordering::Relaxed);
+}
+
+/// Record that `size` bytes were just deallocated.
+fn record_dealloc(size: usize) {
+    CURRENT_BYTES.fetch_sub(size, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        record_dealloc(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            // A reallocation may copy the whole buffer. Count it as an allocation of the new size
+            // and a deallocation of the old one.
+            record_alloc(new_size);
+            record_dealloc(layout.size());
+        }
+        new_ptr
+    }
+}
+
+/// Allocation metrics collected between a call to [`reset`] and a call to [`get`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AllocStats {
+    /// Number of allocations and reallocations.
+    pub allocations: usize,
+    /// Peak number of bytes allocated at once, not counting those which were already allocated
+    /// when [`reset`] was called.
+    pub peak_bytes: usize,
+}
+
+/// Reset the counters. Allocations performed prior to this call are not taken into account.
+pub fn reset() {
+    let current = CURRENT_BYTES.load(Ordering::Relaxed);
+    BASELINE_BYTES.store(current, Ordering::Relaxed);
+    PEAK_BYTES.store(current, Ordering::Relaxed);
+    ALLOCATIONS.store(0, Ordering::Relaxed);
+}
+
+/// Return the metrics collected since the last call to [`reset`].
+pub fn get() -> AllocStats {
+    AllocStats {
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        peak_bytes: PEAK_BYTES
+            .load(Ordering::Relaxed)
+            .saturating_sub(BASELINE_BYTES.load(Ordering::Relaxed)),
+    }
+}
@@ -0,0 +1,92 @@
+//! Print the events emitted by the parser, along with their span.
+//!
+//! Reads from the file given as argument, or from the standard input if there is none or if it is
+//! `-`. With `--tokens`, print the tokens emitted by the scanner instead.
+
+use std::io::Read;
+
+use saphyr_parser::{tokenize, Event, Parser, Span};
+
+fn main() {
+    let mut args: Vec<_> = std::env::args().skip(1).collect();
+    let tokens = if let Some(pos) = args.iter().position(|arg| arg == "--tokens") {
+        args.remove(pos);
+        true
+    } else {
+        false
+    };
+    let mut s = String::new();
+    match args.first().map(String::as_str) {
+        None | Some("-") => {
+            std::io::stdin().read_to_string(&mut s).unwrap();
+        }
+        Some(path) => {
+            std::fs::File::open(path)
+                .unwrap()
+                .read_to_string(&mut s)
+                .unwrap();
+        }
+    }
+
+    if tokens {
+        print_tokens(&s);
+        return;
+    }
+
+    let mut depth = 0usize;
+    for result in Parser::new_from_str(&s) {
+        match result {
+            Ok((ev, span)) => {
+                if matches!(
+                    ev,
+                    Event::SequenceEnd
+                        | Event::MappingEnd
+                        | Event::DocumentEnd(_)
+                        | Event::StreamEnd
+                ) {
+                    depth = depth.saturating_sub(1);
+                }
+                println!(
+                    "{} {:indent$}{ev:?}",
+                    format_span(span),
+                    "",
+                    indent = depth * 2
+                );
+                if matches!(
+                    ev,
+                    Event::SequenceStart(..)
+                        | Event::MappingStart(..)
+                        | Event::DocumentStart(_)
+                        | Event::StreamStart
+                ) {
+                    depth += 1;
+                }
+            }
+            Err(e) => {
+                println!("error: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Print the tokens of `input`. Scanning errors are printed but do not stop the output.
+fn print_tokens(input: &str) {
+    for result in tokenize(input) {
+        match result {
+            Ok((token, span)) => println!("{} {token:?}", format_span(span)),
+            Err(e) => println!("error: {e}"),
+        }
+    }
+}
+
+/// Format `span` as `line:column - line:column`, with 1-indexed columns.
+fn format_span(span: Span) -> String {
+    format!(
+        "{:>4}:{:<3} - {:>4}:{:<3}",
+        span.start.line(),
+        span.start.col() + 1,
+        span.end.line(),
+        span.end.col() + 1,
+    )
+}
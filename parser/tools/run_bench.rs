@@ -3,6 +3,9 @@
 use saphyr_parser::{Event, Parser, Span, SpannedEventReceiver};
 use std::{env, fs::File, io::prelude::*};
 
+#[cfg(feature = "alloc_stats")]
+mod alloc_stats;
+
 /// A sink which discards any event sent.
 struct NullSink {}
 
@@ -20,6 +23,16 @@ fn do_parse(input: &str) -> u64 {
     (end - begin).as_nanos() as u64
 }
 
+/// Parse the given input, returning the allocations made by the parser.
+#[cfg(feature = "alloc_stats")]
+fn do_parse_alloc_stats(input: &str) -> alloc_stats::AllocStats {
+    let mut sink = NullSink {};
+    alloc_stats::reset();
+    let mut parser = Parser::new_from_str(input);
+    parser.load(&mut sink, true).unwrap();
+    alloc_stats::get()
+}
+
 fn main() {
     let args: Vec<_> = env::args().collect();
     let iterations: u64 = args[2].parse().unwrap();
@@ -46,6 +59,10 @@ fn main() {
     let max = sorted_times[(iterations - 1) as usize];
     let percentile95 = sorted_times[((95 * iterations) / 100) as usize];
 
+    // Allocations are deterministic, a single run is enough.
+    #[cfg(feature = "alloc_stats")]
+    let stats = do_parse_alloc_stats(&s);
+
     if output_yaml {
         println!("parser: yaml-rust2");
         println!("input: {}", args[1]);
@@ -54,6 +71,11 @@ fn main() {
         println!("max: {max}");
         println!("percentile95: {percentile95}");
         println!("iterations: {iterations}");
+        #[cfg(feature = "alloc_stats")]
+        {
+            println!("allocations: {}", stats.allocations);
+            println!("peak_bytes: {}", stats.peak_bytes);
+        }
         println!("times:");
         for time in &times {
             println!("  - {time}");
@@ -63,5 +85,10 @@ fn main() {
         println!("Min: {}s", (min as f64) / 1_000_000_000.0);
         println!("Max: {}s", (max as f64) / 1_000_000_000.0);
         println!("95%: {}s", (percentile95 as f64) / 1_000_000_000.0);
+        #[cfg(feature = "alloc_stats")]
+        {
+            println!("Allocations: {}", stats.allocations);
+            println!("Peak memory: {} bytes", stats.peak_bytes);
+        }
     }
 }
@@ -4,6 +4,9 @@ use std::io::prelude::*;
 
 use saphyr_parser::{Event, Parser, Span, SpannedEventReceiver};
 
+#[cfg(feature = "alloc_stats")]
+mod alloc_stats;
+
 /// A sink which discards any event sent.
 struct NullSink {}
 
@@ -18,16 +21,25 @@ fn main() {
     f.read_to_string(&mut s).unwrap();
 
     let mut sink = NullSink {};
+    #[cfg(feature = "alloc_stats")]
+    alloc_stats::reset();
     let mut parser = Parser::new_from_str(&s);
 
     // Load events using our sink as the receiver.
     let begin = std::time::Instant::now();
     parser.load(&mut sink, true).unwrap();
     let end = std::time::Instant::now();
+    #[cfg(feature = "alloc_stats")]
+    let stats = alloc_stats::get();
 
     if args.len() == 3 && args[2] == "--short" {
         println!("{}", (end - begin).as_nanos());
     } else {
         println!("Loaded {}MiB in {:?}", s.len() / 1024 / 1024, end - begin);
+        #[cfg(feature = "alloc_stats")]
+        println!(
+            "{} allocations, peaking at {} bytes",
+            stats.allocations, stats.peak_bytes
+        );
     }
 }
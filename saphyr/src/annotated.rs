@@ -1,17 +1,19 @@
 //! Utilities for extracting YAML with certain metadata.
 
 pub mod marked_yaml;
+pub mod shared_yaml;
 
 use std::ops::{Index, IndexMut};
 
-use hashlink::LinkedHashMap;
+use hashlink::{linked_hash_map, LinkedHashMap};
 
-use crate::loader::parse_f64;
+use crate::{loader::parse_core_schema_fp, Yaml};
 
 /// YAML data for nodes that will contain annotations.
 ///
 /// If you want a YAML node without annotations, see [`Yaml`].
 /// If you want a YAML node with annotations, see types using [`YamlData`] such as [`MarkedYaml`]
+/// If you want a YAML node which is cheap to clone, see [`SharedYaml`].
 ///
 /// Unlike [`Yaml`] which only supports storing data, [`YamlData`] allows storing metadata
 /// alongside the YAML data. It is unlikely one would build it directly; it is mostly intended to
@@ -36,8 +38,8 @@ use crate::loader::parse_f64;
 /// Notable differences with [`Yaml`]:
 ///   * Indexing cannot return `BadValue` and will panic instead.
 ///
-/// [`Yaml`]: crate::Yaml
 /// [`MarkedYaml`]: marked_yaml::MarkedYaml
+/// [`SharedYaml`]: shared_yaml::SharedYaml
 #[derive(Clone, PartialEq, PartialOrd, Debug, Eq, Ord, Hash)]
 pub enum YamlData<Node>
 where
@@ -112,7 +114,7 @@ where
     #[must_use]
     pub fn as_f64(&self) -> Option<f64> {
         if let Self::Real(ref v) = self {
-            parse_f64(v)
+            parse_core_schema_fp(v)
         } else {
             None
         }
@@ -151,6 +153,107 @@ where
             this => this,
         }
     }
+
+    /// Take the data out of `self`, leaving a [`YamlData::BadValue`] in its place.
+    ///
+    /// See [`Yaml::take`].
+    ///
+    /// [`Yaml::take`]: crate::Yaml::take
+    #[must_use]
+    pub fn take(&mut self) -> Self {
+        std::mem::replace(self, Self::BadValue)
+    }
+
+    /// Replace the data with `with`, returning the previous data.
+    ///
+    /// See [`Yaml::replace`].
+    ///
+    /// [`Yaml::replace`]: crate::Yaml::replace
+    #[must_use = "use `*data = with` to discard the previous data"]
+    pub fn replace(&mut self, with: Self) -> Self {
+        std::mem::replace(self, with)
+    }
+
+    /// Swap the data with `other`.
+    pub fn swap(&mut self, other: &mut Self) {
+        std::mem::swap(self, other);
+    }
+
+    /// Consume `self` and iterate over its elements if it is a [`YamlData::Array`].
+    ///
+    /// Nothing is yielded if `self` is not an array. This is the same as [`YamlData::into_iter`].
+    ///
+    /// [`YamlData::into_iter`]: IntoIterator::into_iter
+    #[must_use]
+    pub fn into_sequence_iter(self) -> AnnotatedYamlIter<Node> {
+        self.into_iter()
+    }
+
+    /// Consume `self` and iterate over its entries, in order, if it is a [`YamlData::Hash`].
+    ///
+    /// Nothing is yielded if `self` is not a hash. See [`Yaml::into_mapping_iter`].
+    ///
+    /// [`Yaml::into_mapping_iter`]: crate::Yaml::into_mapping_iter
+    #[must_use]
+    pub fn into_mapping_iter(self) -> linked_hash_map::IntoIter<Node, Node> {
+        self.into_hash().unwrap_or_default().into_iter()
+    }
+
+    /// Replace each key of the hash with the result of `f`, keeping entries in order.
+    ///
+    /// This does nothing if `self` is not a [`YamlData::Hash`]. See [`Yaml::map_keys`].
+    ///
+    /// ```
+    /// # use saphyr::{MarkedYaml, YamlData};
+    /// let mut doc = MarkedYaml::load_from_str("B: 1\na: 2").unwrap().remove(0);
+    /// doc.data.map_keys(|mut key| {
+    ///     if let YamlData::String(key) = &mut key.data {
+    ///         key.make_ascii_lowercase();
+    ///     }
+    ///     key
+    /// });
+    /// assert_eq!(doc.data["b"].data.as_i64(), Some(1));
+    /// ```
+    ///
+    /// [`Yaml::map_keys`]: crate::Yaml::map_keys
+    pub fn map_keys(&mut self, mut f: impl FnMut(Node) -> Node) {
+        let result: Result<(), std::convert::Infallible> =
+            self.try_map_entries(|key, value| Ok((f(key), value)));
+        let Ok(()) = result;
+    }
+
+    /// Replace each entry of the hash with the result of `f`, keeping entries in order.
+    ///
+    /// This does nothing if `self` is not a [`YamlData::Hash`]. See [`Yaml::try_map_entries`].
+    ///
+    /// # Errors
+    /// Returns the first error of `f`. The hash then holds the entries mapped before the failing
+    /// one, followed by the entries `f` was not called on. The failing entry is lost.
+    ///
+    /// [`Yaml::try_map_entries`]: crate::Yaml::try_map_entries
+    pub fn try_map_entries<E>(
+        &mut self,
+        mut f: impl FnMut(Node, Node) -> Result<(Node, Node), E>,
+    ) -> Result<(), E> {
+        let Self::Hash(hash) = self else {
+            return Ok(());
+        };
+        let mut entries = std::mem::take(hash).into_iter();
+        for (key, value) in entries.by_ref() {
+            match f(key, value) {
+                Ok((key, value)) => {
+                    hash.replace(key, value);
+                }
+                Err(error) => {
+                    for (key, value) in entries {
+                        hash.replace(key, value);
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 // NOTE(ethiraric, 10/06/2024): We cannot create a "generic static" variable which would act as a
@@ -248,6 +351,31 @@ where
     }
 }
 
+impl<Node> From<YamlData<Node>> for Yaml
+where
+    Node: std::hash::Hash + std::cmp::Eq + From<YamlData<Node>> + Into<Yaml>,
+{
+    /// Convert the data of an annotated node into a [`Yaml`] tree, node by node, dropping the
+    /// annotations.
+    fn from(value: YamlData<Node>) -> Self {
+        match value {
+            YamlData::Real(x) => Yaml::Real(x),
+            YamlData::Integer(x) => Yaml::Integer(x),
+            YamlData::String(x) => Yaml::String(x),
+            YamlData::Boolean(x) => Yaml::Boolean(x),
+            YamlData::Array(array) => Yaml::Array(array.into_iter().map(Into::into).collect()),
+            YamlData::Hash(hash) => Yaml::Hash(
+                hash.into_iter()
+                    .map(|(key, value)| (key.into(), value.into()))
+                    .collect(),
+            ),
+            YamlData::Alias(x) => Yaml::Alias(x),
+            YamlData::Null => Yaml::Null,
+            YamlData::BadValue => Yaml::BadValue,
+        }
+    }
+}
+
 impl<Node> IntoIterator for YamlData<Node>
 where
     Node: std::hash::Hash + std::cmp::Eq + From<Self>,
@@ -3,11 +3,16 @@
 //! This is set aside so as to not clutter `annotated.rs`.
 
 use hashlink::LinkedHashMap;
-use saphyr_parser::{BufferedInput, Input, Parser, ScanError, Span};
+use saphyr_parser::{BufferedInput, Input, Marker, Parser, ScanError, Span};
 
-use crate::{LoadableYamlNode, Yaml, YamlData, YamlLoader};
+use std::collections::{BTreeMap, VecDeque};
 
-/// A YAML node with [`Span`]s pointing to the start of the node.
+use crate::{
+    Hash, LoadableYamlNode, PathSegment, ScalarRepresentation, Yaml, YamlData, YamlEmitter,
+    YamlLoader, YamlPath,
+};
+
+/// A YAML node with the [`Span`] it was loaded from.
 ///
 /// This structure does not implement functions to operate on the YAML object. To access those,
 /// refer to the [`Self::data`] field.
@@ -15,9 +20,44 @@ use crate::{LoadableYamlNode, Yaml, YamlData, YamlLoader};
 pub struct MarkedYaml {
     /// The span indicating where in the input stream the object is.
     ///
-    /// The markers are relative to the start of the input stream that was given to the parser, not
-    /// to the start of the document within the input stream.
+    /// The span of a flow collection runs to its closing `]` or `}`, and that of a block
+    /// collection to the end of its last element. The markers are relative to the start of the
+    /// input stream that was given to the parser, not to the start of the document within the
+    /// input stream.
     pub span: Span,
+    /// The span of the whole `key: value` entry, if the node is the key or the value of a hash.
+    ///
+    /// It runs from the start of the key to the end of the value, the `:` included, so that the
+    /// entry can be highlighted or removed as a whole. It is `None` for other nodes.
+    ///
+    /// ```
+    /// # use saphyr::MarkedYaml;
+    /// let doc = &MarkedYaml::load_from_str("a: b\nc: [d, e]").unwrap()[0];
+    /// let span = doc.data["c"].pair_span.unwrap();
+    /// assert_eq!((span.start.index(), span.end.index()), (5, 14));
+    /// assert_eq!(doc.data["c"].data[0].pair_span, None);
+    /// ```
+    pub pair_span: Option<Span>,
+    /// The span of the anchored node an alias refers to, if the node was loaded from an alias.
+    ///
+    /// As in [`Anchor::span`], this is the span of the start of the anchored node. This allows
+    /// going from an alias to the definition of its anchor. It is `None` for other nodes.
+    ///
+    /// ```
+    /// # use saphyr::MarkedYaml;
+    /// let doc = &MarkedYaml::load_from_str("a: &x [1, 2]\nb: *x").unwrap()[0];
+    /// let span = doc.data["b"].anchor_span.unwrap();
+    /// assert_eq!((span.start.line(), span.start.col()), (1, 6));
+    /// assert_eq!(doc.data["a"].anchor_span, None);
+    /// ```
+    ///
+    /// [`Anchor::span`]: crate::Anchor::span
+    pub anchor_span: Option<Span>,
+    /// How the scalar the node was loaded from was written.
+    ///
+    /// This is only set on scalars, when loading with [`YamlLoader::keep_representations`]. It is
+    /// boxed so that nodes without it stay small.
+    pub representation: Option<Box<ScalarRepresentation>>,
     /// The YAML contents of the node.
     pub data: YamlData<MarkedYaml>,
 }
@@ -32,7 +72,7 @@ impl MarkedYaml {
     ///
     /// [`load_from_str`]: `Yaml::load_from_str`
     pub fn load_from_str(source: &str) -> Result<Vec<Self>, ScanError> {
-        Self::load_from_iter(source.chars())
+        Self::load_from_iter(source.chars()).map_err(|error| error.with_source(source))
     }
 
     /// Load the contents of the given iterator as an array of YAML documents.
@@ -61,6 +101,534 @@ impl MarkedYaml {
         parser.load(&mut loader, true)?;
         Ok(loader.into_documents())
     }
+
+    /// Take the node out of `self`, leaving a [`YamlData::BadValue`] with a default span in its
+    /// place.
+    ///
+    /// To take the data out while keeping the span in place, use [`YamlData::take`] on
+    /// [`Self::data`].
+    ///
+    /// ```
+    /// # use saphyr::MarkedYaml;
+    /// let mut doc = MarkedYaml::load_from_str("[a, b]").unwrap().remove(0);
+    /// let b = doc.data.as_mut_vec().unwrap()[1].take();
+    /// assert_eq!(b.span.start.col(), 4);
+    /// assert!(doc.data[1].data.is_badvalue());
+    /// ```
+    #[must_use]
+    pub fn take(&mut self) -> Self {
+        self.replace(YamlData::BadValue.into())
+    }
+
+    /// Replace the node, span included, with `with`, returning the previous node.
+    #[must_use = "use `*node = with` to discard the previous node"]
+    pub fn replace(&mut self, with: Self) -> Self {
+        std::mem::replace(self, with)
+    }
+
+    /// Swap the node, span included, with `other`.
+    pub fn swap(&mut self, other: &mut Self) {
+        std::mem::swap(self, other);
+    }
+
+    /// Deep-merge `other` into `self`.
+    ///
+    /// See [`Yaml::merge_from`]. Merged hashes keep their span and the spans of their existing
+    /// keys, while the nodes taken from `other` keep theirs. If the trees were loaded from
+    /// different inputs, the spans of the result thus point into either input.
+    ///
+    /// ```
+    /// # use saphyr::MarkedYaml;
+    /// let mut doc = MarkedYaml::load_from_str("a: {b: 1, c: 2}").unwrap().remove(0);
+    /// let other = MarkedYaml::load_from_str("a:\n  c: 3").unwrap().remove(0);
+    /// doc.merge_from(other);
+    /// assert_eq!(doc.data["a"].data["b"].data.as_i64(), Some(1));
+    /// assert_eq!(doc.data["a"].data["c"].data.as_i64(), Some(3));
+    /// assert_eq!(doc.data["a"].data["c"].span.start.line(), 2);
+    /// ```
+    pub fn merge_from(&mut self, other: Self) {
+        self.merge_impl(other, &mut YamlPath::new(), &mut None);
+    }
+
+    /// Deep-merge `other` into `self` like [`Self::merge_from`], recording in `provenance` that
+    /// the nodes taken from `other` come from `source`.
+    ///
+    /// `source` identifies `other` among the documents merged, e.g. as an index into a list of
+    /// files. Each node taken from `other`, descendants included, is recorded with its path and
+    /// its span, replacing the records of the nodes it replaces. Merged hashes keep their record.
+    /// To record the nodes of the first document as well, merge it into a null node.
+    ///
+    /// Nodes under a key which is not a string are merged but not recorded, as a [`YamlPath`]
+    /// cannot tell such keys from strings (e.g. `1` from `"1"`).
+    ///
+    /// ```
+    /// # use saphyr::{MarkedYaml, Provenance, YamlData, YamlPath};
+    /// let docs = ["a: {b: 1, c: 2}", "a:\n  c: 3"];
+    /// let mut merged = MarkedYaml::from(YamlData::Null);
+    /// let mut provenance = Provenance::new();
+    /// for (source, doc) in docs.iter().enumerate() {
+    ///     let doc = MarkedYaml::load_from_str(doc).unwrap().remove(0);
+    ///     merged.merge_from_tracked(doc, source, &mut provenance);
+    /// }
+    ///
+    /// let mut path = YamlPath::new();
+    /// path.push_key("a");
+    /// path.push_key("c");
+    /// let origin = provenance.origin(&path).unwrap();
+    /// assert_eq!(origin.source, 1);
+    /// assert_eq!(origin.span.start.line(), 2);
+    /// path.pop();
+    /// path.push_key("b");
+    /// assert_eq!(provenance.origin(&path).unwrap().source, 0);
+    /// ```
+    pub fn merge_from_tracked(&mut self, other: Self, source: usize, provenance: &mut Provenance) {
+        self.merge_impl(other, &mut YamlPath::new(), &mut Some((source, provenance)));
+    }
+
+    /// Merge `other`, found at `path`, into `self`, recording its nodes if `tracked` is set.
+    fn merge_impl(
+        &mut self,
+        other: Self,
+        path: &mut YamlPath,
+        tracked: &mut Option<(usize, &mut Provenance)>,
+    ) {
+        match (&mut self.data, other) {
+            (
+                YamlData::Hash(hash),
+                MarkedYaml {
+                    data: YamlData::Hash(other),
+                    ..
+                },
+            ) => {
+                for (key, value) in other {
+                    // Nodes under keys which are not strings are not tracked.
+                    let string_key = key.data.is_string();
+                    let mut untracked = None;
+                    let tracked = match key.data.as_str() {
+                        Some(segment) => {
+                            path.push_key(segment);
+                            &mut *tracked
+                        }
+                        None => &mut untracked,
+                    };
+                    if let Some(existing) = hash.get_mut(&key) {
+                        existing.merge_impl(value, path, tracked);
+                    } else {
+                        if let Some((source, provenance)) = tracked {
+                            provenance.record(&value, path, *source);
+                        }
+                        hash.insert(key, value);
+                    }
+                    if string_key {
+                        path.pop();
+                    }
+                }
+            }
+            (_, other) => {
+                if let Some((source, provenance)) = tracked {
+                    provenance.forget(path);
+                    provenance.record(&other, path, *source);
+                }
+                *self = other;
+            }
+        }
+    }
+
+    /// Dump the tree, spans included, as a YAML document.
+    ///
+    /// Each node is written as a hash holding its span, its type and either its value (for
+    /// scalars), its items (for arrays) or its entries (for hashes, as a list of `key`/`value`
+    /// pairs). Spans are written as `line:col-line:col`, with 1-based columns. This is meant for
+    /// debugging and snapshot tests of the annotated structure; the format may change.
+    ///
+    /// ```
+    /// # use saphyr::MarkedYaml;
+    /// let doc = &MarkedYaml::load_from_str("a: [1]").unwrap()[0];
+    /// assert_eq!(
+    ///     doc.to_debug_yaml(),
+    ///     r#"---
+    /// span: "1:1-1:7"
+    /// type: hash
+    /// entries:
+    ///   - key:
+    ///       span: "1:1-1:2"
+    ///       type: string
+    ///       value: a
+    ///     value:
+    ///       span: "1:4-1:7"
+    ///       type: array
+    ///       items:
+    ///         - span: "1:5-1:6"
+    ///           type: integer
+    ///           value: 1"#
+    /// );
+    /// ```
+    #[must_use]
+    pub fn to_debug_yaml(&self) -> String {
+        let mut out = String::new();
+        // Writing to a `String` cannot fail.
+        YamlEmitter::new(&mut out)
+            .dump(&self.to_debug_node())
+            .unwrap_or_default();
+        out
+    }
+
+    /// Build the node describing `self` for [`Self::to_debug_yaml`].
+    fn to_debug_node(&self) -> Yaml {
+        let mut node = Hash::new();
+        let mut insert = |key: &str, value: Yaml| {
+            node.insert(Yaml::String(key.to_owned()), value);
+        };
+        let (start, end) = (self.span.start, self.span.end);
+        insert(
+            "span",
+            Yaml::String(format!(
+                "{}:{}-{}:{}",
+                start.line(),
+                start.col() + 1,
+                end.line(),
+                end.col() + 1
+            )),
+        );
+        let (kind, value) = match &self.data {
+            YamlData::Real(v) => ("real", Some(("value", Yaml::Real(v.clone())))),
+            YamlData::Integer(v) => ("integer", Some(("value", Yaml::Integer(*v)))),
+            YamlData::String(v) => ("string", Some(("value", Yaml::String(v.clone())))),
+            YamlData::Boolean(v) => ("boolean", Some(("value", Yaml::Boolean(*v)))),
+            YamlData::Array(items) => {
+                let items = items.iter().map(Self::to_debug_node).collect();
+                ("array", Some(("items", Yaml::Array(items))))
+            }
+            YamlData::Hash(hash) => {
+                let entries = hash
+                    .iter()
+                    .map(|(key, value)| {
+                        let mut entry = Hash::new();
+                        entry.insert(Yaml::String("key".to_owned()), key.to_debug_node());
+                        entry.insert(Yaml::String("value".to_owned()), value.to_debug_node());
+                        Yaml::Hash(entry)
+                    })
+                    .collect();
+                ("hash", Some(("entries", Yaml::Array(entries))))
+            }
+            YamlData::Alias(id) => {
+                let id = Yaml::Integer(i64::try_from(*id).unwrap_or(i64::MAX));
+                ("alias", Some(("id", id)))
+            }
+            YamlData::Null => ("null", None),
+            YamlData::BadValue => ("bad value", None),
+        };
+        insert("type", Yaml::String(kind.to_owned()));
+        if let Some((key, value)) = value {
+            insert(key, value);
+        }
+        Yaml::Hash(node)
+    }
+
+    /// Return the node at `path` from `self`, along with its span.
+    ///
+    /// `path` is made of hash keys and array indices separated by dots (`.`), as in a
+    /// [`PathPattern`] without wildcards. A segment which is a number is an index if the node is
+    /// an array and a key otherwise. The empty path designates `self`. Return `None` if there is
+    /// no node at `path`.
+    ///
+    /// ```
+    /// # use saphyr::MarkedYaml;
+    /// let doc = &MarkedYaml::load_from_str("a:\n  - b: c\n  - d: e").unwrap()[0];
+    /// let (node, span) = doc.extract("a.1.d").unwrap();
+    /// assert_eq!(node.data.as_str(), Some("e"));
+    /// assert_eq!((span.start.line(), span.start.col()), (3, 7));
+    /// assert!(doc.extract("a.2").is_none());
+    /// ```
+    ///
+    /// [`PathPattern`]: crate::PathPattern
+    #[must_use]
+    pub fn extract(&self, path: &str) -> Option<(&Self, Span)> {
+        let mut node = self;
+        if !path.is_empty() {
+            for segment in path.split('.') {
+                node = match &node.data {
+                    YamlData::Array(array) => array.get(segment.parse::<usize>().ok()?)?,
+                    YamlData::Hash(hash) => {
+                        hash.get(&YamlData::String(segment.to_owned()).into())?
+                    }
+                    _ => return None,
+                };
+            }
+        }
+        Some((node, node.span))
+    }
+
+    /// Return the text of `self` in `input`, the string it was loaded from.
+    ///
+    /// This is the text covered by [`Self::span`]. Comments and blank lines after the last element
+    /// of a block collection are not included.
+    ///
+    /// Markers count characters, not bytes, so `input` must be the whole input given to the
+    /// parser. Return `None` if `input` is too short for the span of `self`, or if the text does
+    /// not look like `self`: collections must start and end with their delimiters or with their
+    /// first and last elements, aliases with `*`, and scalars must start and end with the same
+    /// characters as their value or with their quotes. Other mismatches cannot be detected, and
+    /// yield the wrong text. The text of nodes loaded from an included file is not in `input`.
+    ///
+    /// ```
+    /// # use saphyr::MarkedYaml;
+    /// let input = "name: 'zoë'\nitems: [a, {b: c}]  # comment\nnested:\n  - x\n  - y\n\n";
+    /// let doc = &MarkedYaml::load_from_str(input).unwrap()[0];
+    /// assert_eq!(doc.data["name"].source_slice(input), Some("'zoë'"));
+    /// assert_eq!(doc.data["items"].source_slice(input), Some("[a, {b: c}]"));
+    /// assert_eq!(doc.data["nested"].source_slice(input), Some("- x\n  - y"));
+    /// assert_eq!(doc.data["items"].source_slice("items"), None);
+    /// assert_eq!(doc.data["items"].source_slice(&input.replace('[', "(")), None);
+    /// ```
+    #[must_use]
+    pub fn source_slice<'input>(&self, input: &'input str) -> Option<&'input str> {
+        let mut byte_indices = input
+            .char_indices()
+            .map(|(byte, _)| byte)
+            .chain(std::iter::once(input.len()));
+        let (start, end) = (self.span.start.index(), self.span.end.index());
+        let start_byte = byte_indices.nth(start)?;
+        let end_byte = match end.checked_sub(start + 1) {
+            Some(n) => byte_indices.nth(n)?,
+            None => start_byte,
+        };
+        input
+            .get(start_byte..end_byte)
+            .filter(|text| self.matches_source(text, input))
+    }
+
+    /// Return whether `text`, the text of the span of `self` in `input`, looks like `self`.
+    ///
+    /// See [`Self::source_slice`].
+    fn matches_source(&self, text: &str, input: &str) -> bool {
+        let text = text.trim();
+        let (first, last) = (text.chars().next(), text.chars().next_back());
+        if self.anchor_span.is_some() {
+            // An alias, whose span is that of `*name`.
+            return first == Some('*');
+        }
+        // The first and the last elements of a block collection must match too.
+        let ends_match = |first: Option<&Self>, last: Option<&Self>| {
+            first.map_or(true, |node| node.source_slice(input).is_some())
+                && last.map_or(true, |node| node.source_slice(input).is_some())
+        };
+        match (&self.data, first) {
+            (YamlData::Array(_), Some('[')) => last == Some(']'),
+            (YamlData::Hash(_), Some('{')) => last == Some('}'),
+            (YamlData::Array(array), Some('-')) => ends_match(None, array.last()),
+            (YamlData::Array(_), _) => false,
+            (YamlData::Hash(hash), _) => ends_match(hash.keys().next(), hash.values().next_back()),
+            (_, Some(quote @ ('"' | '\''))) => text.len() > 1 && last == Some(quote),
+            (YamlData::String(value), _) => {
+                let value = value.trim();
+                first == value.chars().next() && last == value.chars().next_back()
+            }
+            (YamlData::Integer(_) | YamlData::Real(_), Some(c)) => {
+                c.is_ascii_digit() || matches!(c, '+' | '-' | '.')
+            }
+            (YamlData::Boolean(_), Some(c)) => c.is_alphabetic(),
+            (YamlData::Null, None) | (YamlData::BadValue, _) => true,
+            (YamlData::Null, Some(c)) => c == '~' || c.is_alphabetic(),
+            _ => false,
+        }
+    }
+
+    /// Iterate over `self` and all its descendants, depth-first.
+    ///
+    /// Nodes are yielded before their children, along with the path to them from `self`. Hash
+    /// keys are not yielded; they are part of the path to the values.
+    ///
+    /// ```
+    /// # use saphyr::MarkedYaml;
+    /// let doc = &MarkedYaml::load_from_str("a: [b, c]\nd: e").unwrap()[0];
+    /// let scalars: Vec<_> = doc
+    ///     .iter_depth_first()
+    ///     .filter_map(|(_, node)| node.data.as_str())
+    ///     .collect();
+    /// assert_eq!(scalars, ["b", "c", "e"]);
+    /// ```
+    #[must_use]
+    pub fn iter_depth_first(&self) -> DepthFirstIter<'_> {
+        DepthFirstIter {
+            stack: vec![(vec![], self)],
+        }
+    }
+
+    /// Iterate over `self` and all its descendants, breadth-first.
+    ///
+    /// Nodes are yielded level by level, along with the path to them from `self`. Hash keys are
+    /// not yielded; they are part of the path to the values.
+    ///
+    /// ```
+    /// # use saphyr::MarkedYaml;
+    /// let doc = &MarkedYaml::load_from_str("a: [b, c]\nd: e").unwrap()[0];
+    /// let scalars: Vec<_> = doc
+    ///     .iter_breadth_first()
+    ///     .filter_map(|(_, node)| node.data.as_str())
+    ///     .collect();
+    /// assert_eq!(scalars, ["e", "b", "c"]);
+    /// ```
+    #[must_use]
+    pub fn iter_breadth_first(&self) -> BreadthFirstIter<'_> {
+        BreadthFirstIter {
+            queue: VecDeque::from([(vec![], self)]),
+        }
+    }
+
+    /// Return the children of `self` along with their path, in document order.
+    fn children<'a>(&'a self, path: &[PathSegment<'a, Self>]) -> Vec<MarkedYamlPathNode<'a>> {
+        let child_path = |segment| {
+            let mut child_path = path.to_vec();
+            child_path.push(segment);
+            child_path
+        };
+        match &self.data {
+            YamlData::Array(array) => array
+                .iter()
+                .enumerate()
+                .map(|(i, child)| (child_path(PathSegment::Index(i)), child))
+                .collect(),
+            YamlData::Hash(hash) => hash
+                .iter()
+                .map(|(key, child)| (child_path(PathSegment::Key(key)), child))
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+/// A node of a [`MarkedYaml`] tree, along with the path to it.
+type MarkedYamlPathNode<'a> = (Vec<PathSegment<'a, MarkedYaml>>, &'a MarkedYaml);
+
+/// A depth-first iterator over a [`MarkedYaml`] tree.
+///
+/// See [`MarkedYaml::iter_depth_first`].
+#[derive(Debug)]
+pub struct DepthFirstIter<'a> {
+    /// The nodes left to visit. The next node is at the end.
+    stack: Vec<MarkedYamlPathNode<'a>>,
+}
+
+impl<'a> Iterator for DepthFirstIter<'a> {
+    type Item = MarkedYamlPathNode<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, node) = self.stack.pop()?;
+        self.stack.extend(node.children(&path).into_iter().rev());
+        Some((path, node))
+    }
+}
+
+/// A breadth-first iterator over a [`MarkedYaml`] tree.
+///
+/// See [`MarkedYaml::iter_breadth_first`].
+#[derive(Debug)]
+pub struct BreadthFirstIter<'a> {
+    /// The nodes left to visit. The next node is at the front.
+    queue: VecDeque<MarkedYamlPathNode<'a>>,
+}
+
+impl<'a> Iterator for BreadthFirstIter<'a> {
+    type Item = MarkedYamlPathNode<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, node) = self.queue.pop_front()?;
+        self.queue.extend(node.children(&path));
+        Some((path, node))
+    }
+}
+
+/// Where the nodes of a tree built with [`MarkedYaml::merge_from_tracked`] come from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Provenance {
+    /// The origin of each recorded node, by path.
+    origins: BTreeMap<YamlPath, NodeOrigin>,
+}
+
+/// The origin of a node, as recorded in a [`Provenance`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NodeOrigin {
+    /// The document the node comes from, as given to [`MarkedYaml::merge_from_tracked`].
+    pub source: usize,
+    /// The span of the node in its document.
+    pub span: Span,
+}
+
+impl Provenance {
+    /// Create an empty record.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the origin of the node at `path`, if it was recorded.
+    #[must_use]
+    pub fn origin(&self, path: &YamlPath) -> Option<NodeOrigin> {
+        self.origins.get(path).copied()
+    }
+
+    /// Iterate over the recorded nodes, ordered by path.
+    pub fn iter(&self) -> impl Iterator<Item = (&YamlPath, NodeOrigin)> {
+        self.origins.iter().map(|(path, origin)| (path, *origin))
+    }
+
+    /// Return the number of recorded nodes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.origins.len()
+    }
+
+    /// Return whether no node was recorded.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.origins.is_empty()
+    }
+
+    /// Record that `node`, found at `path`, and its descendants come from `source`.
+    fn record(&mut self, node: &MarkedYaml, path: &mut YamlPath, source: usize) {
+        self.origins.insert(
+            path.clone(),
+            NodeOrigin {
+                source,
+                span: node.span,
+            },
+        );
+        match &node.data {
+            YamlData::Array(array) => {
+                for (i, item) in array.iter().enumerate() {
+                    path.push_index(i);
+                    self.record(item, path, source);
+                    path.pop();
+                }
+            }
+            YamlData::Hash(hash) => {
+                for (key, value) in hash {
+                    if let Some(segment) = key.data.as_str() {
+                        path.push_key(segment);
+                        self.record(value, path, source);
+                        path.pop();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Remove the records of the node at `path` and of its descendants.
+    fn forget(&mut self, path: &YamlPath) {
+        // Paths sharing a prefix are contiguous.
+        let stale: Vec<_> = self
+            .origins
+            .range(path..)
+            .map(|(stale, _)| stale)
+            .take_while(|stale| stale.segments().starts_with(path.segments()))
+            .cloned()
+            .collect();
+        for stale in stale {
+            self.origins.remove(&stale);
+        }
+    }
 }
 
 impl PartialEq for MarkedYaml {
@@ -82,15 +650,46 @@ impl From<YamlData<MarkedYaml>> for MarkedYaml {
     fn from(value: YamlData<MarkedYaml>) -> Self {
         Self {
             span: Span::default(),
+            pair_span: None,
+            anchor_span: None,
+            representation: None,
             data: value,
         }
     }
 }
 
+impl From<Yaml> for MarkedYaml {
+    /// Convert a [`Yaml`] tree into a [`MarkedYaml`] tree, node by node. All spans are default.
+    fn from(value: Yaml) -> Self {
+        match value {
+            Yaml::Array(array) => {
+                YamlData::Array(array.into_iter().map(Self::from).collect()).into()
+            }
+            Yaml::Hash(hash) => YamlData::Hash(
+                hash.into_iter()
+                    .map(|(key, value)| (Self::from(key), Self::from(value)))
+                    .collect(),
+            )
+            .into(),
+            yaml => Self::from_bare_yaml(yaml),
+        }
+    }
+}
+
+impl From<MarkedYaml> for Yaml {
+    /// Convert a [`MarkedYaml`] tree into a [`Yaml`] tree, node by node, dropping the spans.
+    fn from(value: MarkedYaml) -> Self {
+        value.data.into()
+    }
+}
+
 impl LoadableYamlNode for MarkedYaml {
     fn from_bare_yaml(yaml: Yaml) -> Self {
         Self {
             span: Span::default(),
+            pair_span: None,
+            anchor_span: None,
+            representation: None,
             data: match yaml {
                 Yaml::Real(x) => YamlData::Real(x),
                 Yaml::Integer(x) => YamlData::Integer(x),
@@ -135,16 +734,31 @@ impl LoadableYamlNode for MarkedYaml {
     }
 
     fn take(&mut self) -> Self {
-        let mut taken_out = MarkedYaml {
-            span: Span::default(),
-            data: YamlData::BadValue,
-        };
-        std::mem::swap(&mut taken_out, self);
-        taken_out
+        MarkedYaml::take(self)
     }
 
     fn with_span(mut self, span: Span) -> Self {
         self.span = span;
         self
     }
+
+    fn with_end_marker(mut self, end: Marker) -> Self {
+        self.span.end = end;
+        self
+    }
+
+    fn with_pair_span(mut self, span: Span) -> Self {
+        self.pair_span = Some(span);
+        self
+    }
+
+    fn with_anchor_span(mut self, span: Span) -> Self {
+        self.anchor_span = Some(span);
+        self
+    }
+
+    fn with_representation(mut self, representation: ScalarRepresentation) -> Self {
+        self.representation = Some(Box::new(representation));
+        self
+    }
 }
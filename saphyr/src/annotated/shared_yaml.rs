@@ -0,0 +1,191 @@
+//! A YAML node whose contents are shared between its clones.
+//!
+//! This is set aside so as to not clutter `annotated.rs`.
+
+use std::{ops::Deref, sync::Arc};
+
+use hashlink::LinkedHashMap;
+use saphyr_parser::{BufferedInput, Input, Parser, ScanError};
+
+use crate::{LoadableYamlNode, Yaml, YamlData, YamlLoader};
+
+/// A YAML node whose data is reference-counted and shared between its clones.
+///
+/// Cloning a [`SharedYaml`] only increments a reference count, regardless of the size of the tree
+/// under it. This makes it cheap to cache documents or to hand them to other threads. Nodes are
+/// copied on write: [`Self::data_mut`] clones the data of the node (but not of its children,
+/// which are shared in turn) only if it is shared with another [`SharedYaml`].
+///
+/// The data of the node can be read through [`Deref`].
+///
+/// ```
+/// # use saphyr::SharedYaml;
+/// let doc = SharedYaml::load_from_str("a: [1, 2]\nb: 3").unwrap().remove(0);
+/// let mut copy = doc.clone();
+/// assert!(copy.ptr_eq(&doc));
+///
+/// copy.data_mut()["b"] = saphyr::YamlData::Integer(4).into();
+/// assert_eq!(doc["b"].as_i64(), Some(3));
+/// assert_eq!(copy["b"].as_i64(), Some(4));
+/// // The untouched subtree is still shared.
+/// assert!(copy["a"].ptr_eq(&doc["a"]));
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SharedYaml(Arc<YamlData<SharedYaml>>);
+
+impl SharedYaml {
+    /// Load the given string as an array of YAML documents.
+    ///
+    /// See the function [`load_from_str`] for more details.
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading fails.
+    ///
+    /// [`load_from_str`]: `Yaml::load_from_str`
+    pub fn load_from_str(source: &str) -> Result<Vec<Self>, ScanError> {
+        Self::load_from_iter(source.chars()).map_err(|error| error.with_source(source))
+    }
+
+    /// Load the contents of the given iterator as an array of YAML documents.
+    ///
+    /// See the function [`load_from_str`] for more details.
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading fails.
+    ///
+    /// [`load_from_str`]: `Yaml::load_from_str`
+    pub fn load_from_iter<I: Iterator<Item = char>>(source: I) -> Result<Vec<Self>, ScanError> {
+        let mut parser = Parser::new(BufferedInput::new(source));
+        Self::load_from_parser(&mut parser)
+    }
+
+    /// Load the contents from the specified [`Parser`] as an array of YAML documents.
+    ///
+    /// See the function [`load_from_str`] for more details.
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading fails.
+    ///
+    /// [`load_from_str`]: `Yaml::load_from_str`
+    pub fn load_from_parser<I: Input>(parser: &mut Parser<I>) -> Result<Vec<Self>, ScanError> {
+        let mut loader = YamlLoader::<Self>::default();
+        parser.load(&mut loader, true)?;
+        Ok(loader.into_documents())
+    }
+
+    /// Return a mutable reference to the data of the node.
+    ///
+    /// If the node is shared with other [`SharedYaml`]s, its data is first cloned so that they are
+    /// left untouched. The children of the node are not cloned; they are only cloned themselves if
+    /// they are mutated.
+    pub fn data_mut(&mut self) -> &mut YamlData<Self> {
+        Arc::make_mut(&mut self.0)
+    }
+
+    /// Return whether `self` and `other` share the same data.
+    ///
+    /// This is the case if one is a clone of the other and neither has been mutated since.
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Take the node out of `self`, leaving a [`YamlData::BadValue`] in its place.
+    #[must_use]
+    pub fn take(&mut self) -> Self {
+        std::mem::replace(self, YamlData::BadValue.into())
+    }
+}
+
+impl Deref for SharedYaml {
+    type Target = YamlData<SharedYaml>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<YamlData<SharedYaml>> for SharedYaml {
+    fn from(value: YamlData<SharedYaml>) -> Self {
+        Self(Arc::new(value))
+    }
+}
+
+impl From<Yaml> for SharedYaml {
+    /// Convert a [`Yaml`] tree into a [`SharedYaml`] tree, node by node.
+    fn from(value: Yaml) -> Self {
+        match value {
+            Yaml::Array(array) => {
+                YamlData::Array(array.into_iter().map(Self::from).collect()).into()
+            }
+            Yaml::Hash(hash) => YamlData::Hash(
+                hash.into_iter()
+                    .map(|(key, value)| (Self::from(key), Self::from(value)))
+                    .collect(),
+            )
+            .into(),
+            yaml => Self::from_bare_yaml(yaml),
+        }
+    }
+}
+
+impl From<SharedYaml> for Yaml {
+    /// Convert a [`SharedYaml`] tree into a [`Yaml`] tree, node by node.
+    ///
+    /// Nodes which are not shared with another [`SharedYaml`] are moved rather than cloned.
+    fn from(value: SharedYaml) -> Self {
+        Arc::try_unwrap(value.0)
+            .unwrap_or_else(|shared| (*shared).clone())
+            .into()
+    }
+}
+
+impl LoadableYamlNode for SharedYaml {
+    fn from_bare_yaml(yaml: Yaml) -> Self {
+        match yaml {
+            Yaml::Real(x) => YamlData::Real(x),
+            Yaml::Integer(x) => YamlData::Integer(x),
+            Yaml::String(x) => YamlData::String(x),
+            Yaml::Boolean(x) => YamlData::Boolean(x),
+            // Array and Hash will always have their container empty.
+            Yaml::Array(_) => YamlData::Array(vec![]),
+            Yaml::Hash(_) => YamlData::Hash(LinkedHashMap::new()),
+            Yaml::Alias(x) => YamlData::Alias(x),
+            Yaml::Null => YamlData::Null,
+            Yaml::BadValue => YamlData::BadValue,
+        }
+        .into()
+    }
+
+    fn is_array(&self) -> bool {
+        self.0.is_array()
+    }
+
+    fn is_hash(&self) -> bool {
+        self.0.is_hash()
+    }
+
+    fn is_badvalue(&self) -> bool {
+        self.0.is_badvalue()
+    }
+
+    fn array_mut(&mut self) -> &mut Vec<Self> {
+        if let YamlData::Array(x) = self.data_mut() {
+            x
+        } else {
+            panic!("Called array_mut on a non-array");
+        }
+    }
+
+    fn hash_mut(&mut self) -> &mut LinkedHashMap<Self, Self> {
+        if let YamlData::Hash(x) = self.data_mut() {
+            x
+        } else {
+            panic!("Called hash_mut on a non-hash");
+        }
+    }
+
+    fn take(&mut self) -> Self {
+        SharedYaml::take(self)
+    }
+}
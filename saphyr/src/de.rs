@@ -0,0 +1,1562 @@
+//! Deserialization of typed values from YAML trees with `serde`.
+
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    marker::PhantomData,
+    ops::Range,
+};
+
+use hashlink::LinkedHashMap;
+use saphyr_parser::{Event, Input, Parser, ScanError, Span, SpannedEventReceiver, StrInput};
+use serde::de::{
+    self, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    Unexpected, VariantAccess, Visitor,
+};
+
+#[cfg(feature = "encoding")]
+use crate::YamlDecoder;
+use crate::{
+    loader::parse_core_schema_fp, ser::real, LoadError, LoadableYamlNode, MarkedYaml, Provenance,
+    Yaml, YamlData, YamlLoader, YamlPath,
+};
+
+/// The category of a [`DeserializeError`].
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Hash)]
+#[non_exhaustive]
+pub enum DeserializeErrorKind {
+    /// Collections are nested deeper than allowed by [`DeserializeLimits::max_depth`].
+    DepthLimitExceeded,
+    /// More nodes were visited than allowed by [`DeserializeLimits::max_nodes`].
+    NodeLimitExceeded,
+    /// A key appears more than once in a hash, which [`ReadOptions::deny_duplicate_keys`] forbids.
+    DuplicateKey,
+    /// The node does not match the structure of the type being deserialized, or any other error
+    /// raised by a `Deserialize` implementation.
+    Other,
+}
+
+/// An error when deserializing a value from a [`Yaml`] or a [`MarkedYaml`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeserializeError {
+    /// The category of the error.
+    kind: DeserializeErrorKind,
+    /// The description of the error.
+    message: String,
+    /// The span of the node at which the error happened, if deserializing a [`MarkedYaml`].
+    span: Option<Span>,
+    /// The path to the node at which the error happened, if known.
+    path: Option<YamlPath>,
+}
+
+impl DeserializeError {
+    /// Create an error of the given kind.
+    fn new(kind: DeserializeErrorKind, message: String) -> Self {
+        DeserializeError {
+            kind,
+            message,
+            span: None,
+            path: None,
+        }
+    }
+
+    /// Return the category of the error.
+    #[must_use]
+    pub fn kind(&self) -> DeserializeErrorKind {
+        self.kind
+    }
+
+    /// Return the span of the node at which the error happened.
+    ///
+    /// This is `None` when deserializing a [`Yaml`], which has no spans. Otherwise, it is the span
+    /// of the innermost node which could not be deserialized. For a missing field, this is the
+    /// hash the field is missing from. For an unknown field (with `#[serde(deny_unknown_fields)]`),
+    /// this is its key.
+    #[must_use]
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// Return the path to the node at which the error happened, from the root of the tree being
+    /// deserialized.
+    ///
+    /// This is the path to the node whose span is returned by [`Self::span`], and is also known
+    /// when deserializing a [`Yaml`]. It is `None` for errors which are not about a single node,
+    /// such as duplicate keys or documents.
+    ///
+    /// ```
+    /// # use saphyr::ReadError;
+    /// # use std::collections::HashMap;
+    /// let input = "servers:\n  - port: 80\n  - port: http";
+    /// let result = saphyr::from_str::<HashMap<String, Vec<HashMap<String, u16>>>>(input);
+    /// let Err(ReadError::Deserialize(error)) = result else { panic!() };
+    /// assert_eq!(error.path().unwrap().to_string(), "servers[1].port");
+    /// ```
+    #[must_use]
+    pub fn path(&self) -> Option<&YamlPath> {
+        self.path.as_ref()
+    }
+
+    /// Return the range of bytes of `input` covered by the span of the error.
+    ///
+    /// The markers of spans count characters, not bytes. This converts them into byte offsets, so
+    /// that `input` can be sliced to show the context of the error. `input` must be the text the
+    /// node was loaded from, e.g. the one given to [`from_str`]. This is `None` if the error has no
+    /// span, or if the span is outside of `input`.
+    ///
+    /// ```
+    /// # use saphyr::ReadError;
+    /// # use std::collections::HashMap;
+    /// let input = "température: tiède";
+    /// let result = saphyr::from_str::<HashMap<String, f64>>(input);
+    /// let Err(ReadError::Deserialize(error)) = result else { panic!() };
+    /// assert_eq!(&input[error.byte_range(input).unwrap()], "tiède");
+    /// ```
+    #[must_use]
+    pub fn byte_range(&self, input: &str) -> Option<Range<usize>> {
+        self.span?.byte_range(input)
+    }
+
+    /// Set the span of the error, unless it already has one.
+    fn or_span(mut self, span: Option<Span>) -> Self {
+        self.span = self.span.or(span);
+        self
+    }
+
+    /// Set the path of the error, unless it already has one.
+    fn or_path(mut self, path: &YamlPath) -> Self {
+        if self.path.is_none() {
+            self.path = Some(path.clone());
+        }
+        self
+    }
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some(span) = self.span {
+            write!(
+                f,
+                " at line {} column {}",
+                span.start.line(),
+                span.start.col() + 1
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+impl de::Error for DeserializeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DeserializeError::new(DeserializeErrorKind::Other, msg.to_string())
+    }
+}
+
+/// Limits on the work done when deserializing a single value.
+///
+/// The loader expands aliases into copies of their anchored node, so a small document can load
+/// into a very large tree. Limiting the number of nodes bounds the work spent deserializing such
+/// a tree, including any expanded alias, while limiting the depth bounds the recursion of
+/// `Deserialize` implementations.
+///
+/// By default, there is no limit.
+///
+/// ```
+/// # use saphyr::{DeserializeErrorKind, DeserializeLimits, Yaml};
+/// let docs = Yaml::load_from_str("[[[[1]]]]").unwrap();
+/// let limits = DeserializeLimits::default().max_depth(3);
+/// let error = docs[0]
+///     .deserialize_with_limits::<Vec<Vec<Vec<Vec<i32>>>>>(limits)
+///     .unwrap_err();
+/// assert_eq!(error.kind(), DeserializeErrorKind::DepthLimitExceeded);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeserializeLimits {
+    /// The maximum number of nested arrays and hashes.
+    max_depth: usize,
+    /// The maximum number of nodes visited.
+    max_nodes: usize,
+}
+
+impl Default for DeserializeLimits {
+    fn default() -> Self {
+        DeserializeLimits {
+            max_depth: usize::MAX,
+            max_nodes: usize::MAX,
+        }
+    }
+}
+
+impl DeserializeLimits {
+    /// Set the maximum number of arrays and hashes which may be nested in one another.
+    ///
+    /// Deserializing fails with [`DeserializeErrorKind::DepthLimitExceeded`] when entering a
+    /// collection nested deeper than `limit`. A scalar has a depth of 0.
+    #[must_use]
+    pub fn max_depth(mut self, limit: usize) -> Self {
+        self.max_depth = limit;
+        self
+    }
+
+    /// Set the maximum number of nodes which may be visited.
+    ///
+    /// Deserializing fails with [`DeserializeErrorKind::NodeLimitExceeded`] when visiting more
+    /// than `limit` nodes. Every key and value of a hash counts as a node, as do the nodes of
+    /// expanded aliases. Nodes skipped by the `Deserialize` implementation are not counted.
+    #[must_use]
+    pub fn max_nodes(mut self, limit: usize) -> Self {
+        self.max_nodes = limit;
+        self
+    }
+}
+
+/// The work left before reaching the [`DeserializeLimits`] of a deserialization, along with
+/// where the deserialization is.
+struct Budget {
+    /// The limits to enforce.
+    limits: DeserializeLimits,
+    /// The number of collections currently being deserialized.
+    depth: Cell<usize>,
+    /// The number of nodes visited so far.
+    nodes: Cell<usize>,
+    /// The path to the node being deserialized.
+    path: RefCell<YamlPath>,
+}
+
+impl Budget {
+    /// Create a budget without limits.
+    fn unlimited() -> Self {
+        Budget::new(DeserializeLimits::default())
+    }
+
+    /// Create a budget with nothing spent yet.
+    fn new(limits: DeserializeLimits) -> Self {
+        Budget {
+            limits,
+            depth: Cell::new(0),
+            nodes: Cell::new(0),
+            path: RefCell::new(YamlPath::new()),
+        }
+    }
+
+    /// Count a node as visited.
+    fn visit_node(&self) -> Result<(), DeserializeError> {
+        let nodes = self.nodes.get() + 1;
+        if nodes > self.limits.max_nodes {
+            return Err(DeserializeError::new(
+                DeserializeErrorKind::NodeLimitExceeded,
+                format!(
+                    "more than the maximum of {} nodes visited",
+                    self.limits.max_nodes
+                ),
+            ));
+        }
+        self.nodes.set(nodes);
+        Ok(())
+    }
+
+    /// Run `f`, which deserializes the contents of a collection, one level deeper.
+    fn nested<T>(
+        &self,
+        f: impl FnOnce() -> Result<T, DeserializeError>,
+    ) -> Result<T, DeserializeError> {
+        let depth = self.depth.get() + 1;
+        if depth > self.limits.max_depth {
+            return Err(DeserializeError::new(
+                DeserializeErrorKind::DepthLimitExceeded,
+                format!(
+                    "collections nested deeper than the maximum of {}",
+                    self.limits.max_depth
+                ),
+            ));
+        }
+        self.depth.set(depth);
+        let result = f();
+        self.depth.set(depth - 1);
+        result
+    }
+
+    /// Run `f`, which deserializes a child of the current node, with the segment appended by
+    /// `push` added to the path.
+    fn child<T>(&self, push: impl FnOnce(&mut YamlPath), f: impl FnOnce() -> T) -> T {
+        push(&mut self.path.borrow_mut());
+        let result = f();
+        self.path.borrow_mut().pop();
+        result
+    }
+
+    /// Give `error` the span `span` and the current path, unless it already has them.
+    fn locate(&self, error: DeserializeError, span: Option<Span>) -> DeserializeError {
+        error.or_span(span).or_path(&self.path.borrow())
+    }
+}
+
+impl Yaml {
+    /// Deserialize `self` into a value of type `T`.
+    ///
+    /// This allows loading a document once, inspecting it, and deserializing only some of its
+    /// subtrees into typed values. It is a shorthand for `T::deserialize(self)`, as `&Yaml`
+    /// implements [`Deserializer`].
+    ///
+    /// Reals are parsed as written in the document, and integers may be deserialized into
+    /// reals. Aliases and `BadValue`s cannot be deserialized.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// #[derive(serde::Deserialize, Debug, PartialEq)]
+    /// struct Server {
+    ///     host: String,
+    ///     port: u16,
+    /// }
+    ///
+    /// let docs = Yaml::load_from_str("servers:\n  - host: example.com\n    port: 8080").unwrap();
+    /// let server: Server = docs[0]["servers"][0].deserialize_into().unwrap();
+    /// assert_eq!(server, Server { host: "example.com".into(), port: 8080 });
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `DeserializeError` if `self` does not match the structure of `T`.
+    pub fn deserialize_into<'de, T: de::Deserialize<'de>>(
+        &'de self,
+    ) -> Result<T, DeserializeError> {
+        T::deserialize(self)
+    }
+
+    /// Deserialize `self` into a value of type `T`, within the given limits.
+    ///
+    /// See [`Yaml::deserialize_into`] and [`DeserializeLimits`].
+    ///
+    /// # Errors
+    /// Returns `DeserializeError` if `self` does not match the structure of `T`, or if
+    /// deserializing it exceeds `limits`.
+    pub fn deserialize_with_limits<'de, T: de::Deserialize<'de>>(
+        &'de self,
+        limits: DeserializeLimits,
+    ) -> Result<T, DeserializeError> {
+        T::deserialize(NodeDeserializer::new(self, &Budget::new(limits)))
+    }
+}
+
+impl MarkedYaml {
+    /// Deserialize `self` into a value of type `T`.
+    ///
+    /// See [`Yaml::deserialize_into`]. Errors have the span of the node at which they happened.
+    ///
+    /// ```
+    /// # use saphyr::MarkedYaml;
+    /// #[derive(serde::Deserialize, Debug)]
+    /// #[serde(deny_unknown_fields)]
+    /// struct Server {
+    ///     host: String,
+    /// }
+    ///
+    /// let docs = MarkedYaml::load_from_str("host: example.com\nport: 8080").unwrap();
+    /// let error = docs[0].deserialize_into::<Server>().unwrap_err();
+    /// assert_eq!(error.span().unwrap().start.line(), 2);
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "unknown field `port`, expected `host` at line 2 column 1"
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `DeserializeError` if `self` does not match the structure of `T`.
+    pub fn deserialize_into<'de, T: de::Deserialize<'de>>(
+        &'de self,
+    ) -> Result<T, DeserializeError> {
+        T::deserialize(self)
+    }
+
+    /// Deserialize `self` into a value of type `T`, within the given limits.
+    ///
+    /// See [`Yaml::deserialize_with_limits`]. Errors have the span of the node at which they
+    /// happened.
+    ///
+    /// # Errors
+    /// Returns `DeserializeError` if `self` does not match the structure of `T`, or if
+    /// deserializing it exceeds `limits`.
+    pub fn deserialize_with_limits<'de, T: de::Deserialize<'de>>(
+        &'de self,
+        limits: DeserializeLimits,
+    ) -> Result<T, DeserializeError> {
+        T::deserialize(NodeDeserializer::new(self, &Budget::new(limits)))
+    }
+}
+
+/// An error when reading a typed value from YAML input, with [`from_str`], `from_reader` or
+/// [`iter_seq`].
+#[derive(Clone, Debug)]
+pub enum ReadError {
+    /// The input could not be read, decoded or parsed.
+    Load(LoadError),
+    /// The document does not match the structure of the type being deserialized.
+    Deserialize(DeserializeError),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Load(e) => e.fmt(f),
+            ReadError::Deserialize(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(match self {
+            ReadError::Load(e) => e,
+            ReadError::Deserialize(e) => e,
+        })
+    }
+}
+
+impl From<LoadError> for ReadError {
+    fn from(error: LoadError) -> Self {
+        ReadError::Load(error)
+    }
+}
+
+impl From<DeserializeError> for ReadError {
+    fn from(error: DeserializeError) -> Self {
+        ReadError::Deserialize(error)
+    }
+}
+
+/// Deserialize a value of type `T` from the YAML document in `input`.
+///
+/// An empty input is deserialized as a null value, while an input with more than one document is
+/// an error. Deserialization errors have the span of the node at which they happened.
+///
+/// This uses the default [`ReadOptions`].
+///
+/// ```
+/// let ports: Vec<u16> = saphyr::from_str("[80, 443]").unwrap();
+/// assert_eq!(ports, [80, 443]);
+/// ```
+///
+/// # Errors
+/// Returns [`ReadError::Load`] if `input` cannot be parsed, and [`ReadError::Deserialize`] if the
+/// document does not match the structure of `T`.
+pub fn from_str<T: de::DeserializeOwned>(input: &str) -> Result<T, ReadError> {
+    ReadOptions::default().from_str(input)
+}
+
+/// Deserialize a value of type `T` from the YAML document in `reader`.
+///
+/// The input is decoded like with [`YamlDecoder`]: its encoding (UTF-8, UTF-16LE or UTF-16BE) is
+/// detected from its byte order mark, or from the pattern of null bytes of its first character.
+/// Malformed input is an error. An empty input is deserialized as a null value, while an input
+/// with more than one document is an error.
+///
+/// Deserialization errors have the span of the node at which they happened. This uses the default
+/// [`ReadOptions`].
+///
+/// ```
+/// # use saphyr::from_reader;
+/// #[derive(serde::Deserialize, Debug, PartialEq)]
+/// struct Server {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// // A UTF-16LE file with a byte order mark, as written by some Windows editors.
+/// let bytes: Vec<u8> = "\u{feff}host: example.com\nport: 8080\n"
+///     .encode_utf16()
+///     .flat_map(u16::to_le_bytes)
+///     .collect();
+/// let server: Server = from_reader(bytes.as_slice()).unwrap();
+/// assert_eq!(server, Server { host: "example.com".into(), port: 8080 });
+/// ```
+///
+/// # Errors
+/// Returns [`ReadError::Load`] if `reader` cannot be read, decoded or parsed, and
+/// [`ReadError::Deserialize`] if the document does not match the structure of `T`.
+#[cfg(feature = "encoding")]
+pub fn from_reader<R: std::io::Read, T: de::DeserializeOwned>(reader: R) -> Result<T, ReadError> {
+    ReadOptions::default().from_reader(reader)
+}
+
+/// Deserialize the items of the sequence at the root of `input` one at a time.
+///
+/// Each item is loaded and deserialized only when the iterator reaches it, so a large sequence
+/// never needs to be held in memory as a whole, either as a tree or as a `Vec<T>`. Only anchored
+/// nodes are kept, so that later aliases to them can be expanded.
+///
+/// Items which fail to deserialize are returned as errors, and the iteration continues with the
+/// next item. The iteration stops after an error in the input. An empty input has no items, while
+/// a document whose root is not a sequence is an error. Documents after the first are ignored.
+///
+/// ```
+/// # use saphyr::iter_seq;
+/// #[derive(serde::Deserialize, Debug, PartialEq)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let mut points = iter_seq::<Point>("- {x: 1, y: 2}\n- {x: 3, y: 4}");
+/// assert_eq!(points.next().unwrap().unwrap(), Point { x: 1, y: 2 });
+/// assert_eq!(points.next().unwrap().unwrap(), Point { x: 3, y: 4 });
+/// assert!(points.next().is_none());
+/// ```
+#[must_use]
+pub fn iter_seq<T: de::DeserializeOwned>(input: &str) -> SeqIter<StrInput<'_>, T> {
+    ReadOptions::default().iter_seq(input)
+}
+
+/// Options for reading typed values from YAML input.
+///
+/// The functions [`from_str`], `from_reader`, [`iter_seq`] and [`load_config`] use the default
+/// options. To use other options, call the methods of the same name on a `ReadOptions`.
+///
+/// ```
+/// # use saphyr::{DeserializeErrorKind, ReadError, ReadOptions};
+/// # use std::collections::HashMap;
+/// let options = ReadOptions::default().deny_duplicate_keys(true);
+/// let result = options.from_str::<HashMap<String, u16>>("port: 80\nport: 8080");
+/// let Err(ReadError::Deserialize(error)) = result else { panic!() };
+/// assert_eq!(error.kind(), DeserializeErrorKind::DuplicateKey);
+/// assert_eq!(error.to_string(), "key `port` is defined more than once at line 2 column 1");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// Whether a key appearing more than once in a hash is an error.
+    deny_duplicate_keys: bool,
+    /// Whether to resolve numbers in the YAML 1.1 formats.
+    yaml_1_1_numbers: bool,
+    /// Whether to resolve booleans in the YAML 1.1 formats.
+    yaml_1_1_booleans: bool,
+    /// The maximum number of nodes loaded through aliases, if any.
+    max_alias_nodes: Option<usize>,
+}
+
+impl ReadOptions {
+    /// Set whether a key appearing more than once in a hash is an error.
+    ///
+    /// By default, only the last entry with a given key is kept, as when loading a [`Yaml`], and
+    /// the others are silently discarded. With this setting, reading fails with
+    /// [`DeserializeErrorKind::DuplicateKey`] instead, with the span of the key where it is
+    /// defined again. When iterating with [`ReadOptions::iter_seq`], only the item with the
+    /// duplicate key is an error.
+    #[must_use]
+    pub fn deny_duplicate_keys(mut self, value: bool) -> Self {
+        self.deny_duplicate_keys = value;
+        self
+    }
+
+    /// Set whether plain scalars may be numbers in the formats of YAML 1.1.
+    ///
+    /// See [`YamlLoader::yaml_1_1_numbers`].
+    #[must_use]
+    pub fn yaml_1_1_numbers(mut self, value: bool) -> Self {
+        self.yaml_1_1_numbers = value;
+        self
+    }
+
+    /// Set whether plain scalars may be booleans in the formats of YAML 1.1 (e.g. `yes`, `off`).
+    ///
+    /// This is useful to read configuration files written for YAML 1.1 parsers, where `enabled:
+    /// yes` is a boolean. See [`YamlLoader::yaml_1_1_booleans`].
+    ///
+    /// ```
+    /// # use saphyr::ReadOptions;
+    /// assert!(ReadOptions::default().from_str::<bool>("yes").is_err());
+    /// let options = ReadOptions::default().yaml_1_1_booleans(true);
+    /// assert_eq!(options.from_str::<bool>("yes").unwrap(), true);
+    /// ```
+    #[must_use]
+    pub fn yaml_1_1_booleans(mut self, value: bool) -> Self {
+        self.yaml_1_1_booleans = value;
+        self
+    }
+
+    /// Limit the number of nodes which are loaded through aliases.
+    ///
+    /// Reading fails with a [`ReadError::Load`] of kind [`ScanErrorKind::AliasLimitExceeded`]
+    /// once aliases expand to more than `limit` nodes in total, before the expanded tree is built.
+    /// See [`YamlLoader::max_alias_nodes`]. Unlike [`DeserializeLimits::max_nodes`], this protects
+    /// against "billion laughs" documents when loading, and not only when deserializing.
+    ///
+    /// ```
+    /// # use saphyr::{LoadError, ReadError, ReadOptions};
+    /// # use saphyr_parser::ScanErrorKind;
+    /// let input = "a: &a [x, x]\nb: &b [*a, *a]\nc: [*b, *b]";
+    /// let options = ReadOptions::default().max_alias_nodes(10);
+    /// let result = options.from_str::<saphyr::Yaml>(input);
+    /// let Err(ReadError::Load(LoadError::Scan(error))) = result else { panic!() };
+    /// assert_eq!(error.kind(), ScanErrorKind::AliasLimitExceeded);
+    /// ```
+    ///
+    /// [`ScanErrorKind::AliasLimitExceeded`]: saphyr_parser::ScanErrorKind::AliasLimitExceeded
+    #[must_use]
+    pub fn max_alias_nodes(mut self, limit: usize) -> Self {
+        self.max_alias_nodes = Some(limit);
+        self
+    }
+
+    /// Deserialize a value of type `T` from the YAML document in `input`, with these options.
+    ///
+    /// See [`from_str`].
+    ///
+    /// # Errors
+    /// Returns [`ReadError::Load`] if `input` cannot be parsed, and [`ReadError::Deserialize`] if
+    /// the document does not match the structure of `T` or these options.
+    pub fn from_str<T: de::DeserializeOwned>(self, input: &str) -> Result<T, ReadError> {
+        let doc = self
+            .load_single_document(input)?
+            .unwrap_or_else(|| MarkedYaml::from_bare_yaml(Yaml::Null));
+        Ok(doc.deserialize_into()?)
+    }
+
+    /// Deep-merge the YAML documents in `layers`, in order, and deserialize the result into a
+    /// value of type `T`, with these options.
+    ///
+    /// See [`load_config`].
+    ///
+    /// # Errors
+    /// Returns a [`ConfigError`] holding the layer which failed to load or which provided the
+    /// value that failed to deserialize.
+    pub fn load_config<T: de::DeserializeOwned>(self, layers: &[&str]) -> Result<T, ConfigError> {
+        let mut merged = MarkedYaml::from_bare_yaml(Yaml::Null);
+        // Where each node of `merged` comes from, to find the layer of the node at which
+        // deserialization fails.
+        let mut provenance = Provenance::new();
+        for (layer, input) in layers.iter().enumerate() {
+            let doc = self
+                .load_single_document(input)
+                .map_err(|error| ConfigError::new(Some(layer), None, error))?;
+            if let Some(doc) = doc {
+                merged.merge_from_tracked(doc, layer, &mut provenance);
+            }
+        }
+        merged.deserialize_into().map_err(|error| {
+            let path = error.path().cloned().unwrap_or_default();
+            // Nodes under keys which are not strings are not recorded: blame the nearest recorded
+            // ancestor instead, with its span. Only the root is left unrecorded if no layer had a
+            // document, in which case the span of the placeholder root is meaningless.
+            let mut ancestor = path.clone();
+            let origin = loop {
+                if let Some(origin) = provenance.origin(&ancestor) {
+                    break Some(origin);
+                }
+                if ancestor.pop().is_none() {
+                    break None;
+                }
+            };
+            let error = match origin {
+                Some(_) if ancestor == path => error,
+                Some(origin) => DeserializeError {
+                    span: Some(origin.span),
+                    ..error
+                },
+                None => DeserializeError {
+                    span: None,
+                    ..error
+                },
+            };
+            let layer = origin.map(|origin| origin.source);
+            ConfigError::new(layer, Some(path), error.into())
+        })
+    }
+
+    /// Deserialize a value of type `T` from the YAML document in `reader`, with these options.
+    ///
+    /// See `from_reader`.
+    ///
+    /// # Errors
+    /// Returns [`ReadError::Load`] if `reader` cannot be read, decoded or parsed, and
+    /// [`ReadError::Deserialize`] if the document does not match the structure of `T` or these
+    /// options.
+    #[cfg(feature = "encoding")]
+    pub fn from_reader<R: std::io::Read, T: de::DeserializeOwned>(
+        self,
+        reader: R,
+    ) -> Result<T, ReadError> {
+        let input = YamlDecoder::read(reader).decode_to_string()?;
+        self.from_str(&input)
+    }
+
+    /// Deserialize the items of the sequence at the root of `input` one at a time, with these
+    /// options.
+    ///
+    /// See [`iter_seq`].
+    #[must_use]
+    pub fn iter_seq<T: de::DeserializeOwned>(self, input: &str) -> SeqIter<StrInput<'_>, T> {
+        SeqIter::with_options(Parser::new_from_str(input), self)
+    }
+
+    /// Load the only document of `input`, if any, following these options.
+    fn load_single_document(self, input: &str) -> Result<Option<MarkedYaml>, ReadError> {
+        let mut loader = self.loader();
+        Parser::new_from_str(input)
+            .load(&mut loader, true)
+            .map_err(|error| LoadError::Scan(error.with_source(input)))?;
+        check_hook_errors(&loader).map_err(|error| LoadError::Scan(error.with_source(input)))?;
+        check_duplicate_keys(&mut loader)?;
+        let mut docs = loader.into_documents();
+        if let Some(extra) = docs.get(1) {
+            return Err(DeserializeError::new(
+                DeserializeErrorKind::Other,
+                "expected a single document, found more".into(),
+            )
+            .or_span(Some(extra.span))
+            .into());
+        }
+        Ok(docs.pop())
+    }
+
+    /// Create a loader for the input, following these options.
+    fn loader(self) -> YamlLoader<MarkedYaml> {
+        let loader = YamlLoader::default()
+            .record_duplicate_keys(self.deny_duplicate_keys)
+            .yaml_1_1_numbers(self.yaml_1_1_numbers)
+            .yaml_1_1_booleans(self.yaml_1_1_booleans);
+        match self.max_alias_nodes {
+            Some(limit) => loader.max_alias_nodes(limit),
+            None => loader,
+        }
+    }
+}
+
+/// An error when loading a configuration with [`load_config`].
+#[derive(Clone, Debug)]
+pub struct ConfigError {
+    /// The index of the layer the error comes from, if known.
+    layer: Option<usize>,
+    /// The path to the value which failed to deserialize in the merged document, if any.
+    path: Option<YamlPath>,
+    /// The error, with spans relative to the input of its layer.
+    ///
+    /// It is boxed to keep results small.
+    error: Box<ReadError>,
+}
+
+impl ConfigError {
+    /// Create an error coming from the given layer.
+    fn new(layer: Option<usize>, path: Option<YamlPath>, error: ReadError) -> Self {
+        Self {
+            layer,
+            path,
+            error: Box::new(error),
+        }
+    }
+
+    /// Return the index of the layer the error comes from.
+    ///
+    /// This is the layer which failed to load, or the layer which provided the node at which
+    /// deserialization failed. For a missing field, this is the layer which provided the hash the
+    /// field is missing from. It is `None` if no layer could be blamed, e.g. when all layers are
+    /// empty.
+    #[must_use]
+    pub fn layer(&self) -> Option<usize> {
+        self.layer
+    }
+
+    /// Return the path to the value which failed to deserialize.
+    ///
+    /// The path is that of the value in the merged document. It is `None` if a layer failed to
+    /// load.
+    #[must_use]
+    pub fn path(&self) -> Option<&YamlPath> {
+        self.path.as_ref()
+    }
+
+    /// Return the error.
+    ///
+    /// Its span, if any, is relative to the input of [`Self::layer`]. In particular,
+    /// [`DeserializeError::byte_range`] must be given the input of that layer.
+    #[must_use]
+    pub fn error(&self) -> &ReadError {
+        &self.error
+    }
+
+    /// Return the error, dropping the layer.
+    #[must_use]
+    pub fn into_error(self) -> ReadError {
+        *self.error
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.layer {
+            Some(layer) => write!(f, "in layer {layer}: {}", self.error),
+            None => self.error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.error)
+    }
+}
+
+/// Deep-merge the YAML documents in `layers`, in order, and deserialize the result into a value
+/// of type `T`.
+///
+/// Each layer holds at most one document. Layers are merged with
+/// [`MarkedYaml::merge_from_tracked`], so that later layers override the values of earlier ones,
+/// key by key within hashes. Layers without a document are skipped. This is typically used to
+/// apply user settings over defaults.
+///
+/// This uses the default [`ReadOptions`].
+///
+/// ```
+/// # use saphyr::{load_config, ReadError};
+/// #[derive(serde::Deserialize, Debug, PartialEq)]
+/// struct Server {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// let defaults = "host: localhost\nport: 8080";
+/// let server: Server = load_config(&[defaults, "port: 80"]).unwrap();
+/// assert_eq!(server, Server { host: "localhost".into(), port: 80 });
+///
+/// let user = "# Production\nport: http";
+/// let error = load_config::<Server>(&[defaults, user]).unwrap_err();
+/// assert_eq!(error.layer(), Some(1));
+/// assert_eq!(error.path().unwrap().to_string(), "port");
+/// let ReadError::Deserialize(error) = error.error() else { panic!() };
+/// assert_eq!(&user[error.byte_range(user).unwrap()], "http");
+/// ```
+///
+/// # Errors
+/// Returns a [`ConfigError`] holding the layer which failed to load or which provided the value
+/// that failed to deserialize.
+pub fn load_config<T: de::DeserializeOwned>(layers: &[&str]) -> Result<T, ConfigError> {
+    ReadOptions::default().load_config(layers)
+}
+
+/// Return the first error recorded by `loader` while loading, if any.
+///
+/// The loader records errors only if [`ReadOptions::max_alias_nodes`] is set.
+fn check_hook_errors(loader: &YamlLoader<MarkedYaml>) -> Result<(), ScanError> {
+    match loader.hook_errors().first() {
+        Some(error) => Err(error.clone()),
+        None => Ok(()),
+    }
+}
+
+/// Return an error for the first duplicate key recorded by `loader`.
+///
+/// The loader records duplicate keys only if [`ReadOptions::deny_duplicate_keys`] is set.
+fn check_duplicate_keys(loader: &mut YamlLoader<MarkedYaml>) -> Result<(), DeserializeError> {
+    let duplicates = loader.take_duplicate_keys();
+    let Some(first) = duplicates
+        .iter()
+        .min_by_key(|duplicate| duplicate.key.span.start.index())
+    else {
+        return Ok(());
+    };
+    let message = match first.key.data.as_str() {
+        Some(key) => format!("key `{key}` is defined more than once"),
+        None => "a key is defined more than once".into(),
+    };
+    Err(
+        DeserializeError::new(DeserializeErrorKind::DuplicateKey, message)
+            .or_span(Some(first.key.span)),
+    )
+}
+
+/// An iterator deserializing the items of the sequence at the root of a document.
+///
+/// See [`iter_seq`].
+pub struct SeqIter<I: Input, T> {
+    /// The parser the events of the items are read from.
+    parser: Parser<I>,
+    /// The loader of the items, which keeps the anchored nodes between items.
+    loader: YamlLoader<MarkedYaml>,
+    /// Whether the start of the sequence was reached.
+    started: bool,
+    /// Whether the end of the sequence or an error was reached.
+    done: bool,
+    /// The type of the items.
+    items: PhantomData<fn() -> T>,
+}
+
+impl<I: Input, T: de::DeserializeOwned> SeqIter<I, T> {
+    /// Create an iterator over the sequence at the root of the document `parser` reads.
+    ///
+    /// This allows reading from any input the parser supports, or configuring the parser first.
+    #[must_use]
+    pub fn new(parser: Parser<I>) -> Self {
+        Self::with_options(parser, ReadOptions::default())
+    }
+
+    /// Create an iterator over the sequence at the root of the document `parser` reads, reading
+    /// the items with `options`.
+    #[must_use]
+    pub fn with_options(parser: Parser<I>, options: ReadOptions) -> Self {
+        SeqIter {
+            parser,
+            loader: options.loader(),
+            started: false,
+            done: false,
+            items: PhantomData,
+        }
+    }
+
+    /// Return the next event, or `None` at the end of the input.
+    fn next_event(&mut self) -> Result<Option<(Event, Span)>, ReadError> {
+        self.parser
+            .next_event()
+            .transpose()
+            .map_err(|e| LoadError::Scan(e).into())
+    }
+
+    /// Skip the events before the sequence, returning `false` if there is no sequence to iterate.
+    fn start(&mut self) -> Result<bool, ReadError> {
+        loop {
+            match self.next_event()? {
+                Some((Event::StreamStart | Event::DocumentStart(_) | Event::Nothing, _)) => {}
+                Some((Event::SequenceStart(..), _)) => return Ok(true),
+                Some((Event::DocumentEnd(_) | Event::StreamEnd, _)) | None => return Ok(false),
+                Some((_, span)) => {
+                    return Err(DeserializeError::new(
+                        DeserializeErrorKind::Other,
+                        "expected a sequence at the root of the document".into(),
+                    )
+                    .or_span(Some(span))
+                    .into())
+                }
+            }
+        }
+    }
+
+    /// Load the next item of the sequence, or return `None` at its end.
+    fn load_item(&mut self) -> Result<Option<MarkedYaml>, ReadError> {
+        let mut depth = 0_usize;
+        loop {
+            let Some((event, span)) = self.next_event()? else {
+                return Ok(None);
+            };
+            match event {
+                Event::SequenceEnd | Event::MappingEnd if depth == 0 => return Ok(None),
+                Event::SequenceStart(..) | Event::MappingStart(..) => depth += 1,
+                Event::SequenceEnd | Event::MappingEnd => depth -= 1,
+                _ => {}
+            }
+            self.loader.on_event(event, span);
+            if depth == 0 {
+                self.loader.on_event(Event::DocumentEnd(false), span);
+                return Ok(self.loader.pop_document());
+            }
+        }
+    }
+}
+
+impl<I: Input, T: de::DeserializeOwned> Iterator for SeqIter<I, T> {
+    type Item = Result<T, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let item = if self.started {
+            self.load_item()
+        } else {
+            self.started = true;
+            self.start()
+                .and_then(|found| if found { self.load_item() } else { Ok(None) })
+        };
+        let item = item.and_then(|item| {
+            check_hook_errors(&self.loader).map_err(LoadError::Scan)?;
+            Ok(item)
+        });
+        match item {
+            Ok(Some(item)) => Some(
+                check_duplicate_keys(&mut self.loader)
+                    .and_then(|()| item.deserialize_into())
+                    .map_err(ReadError::from),
+            ),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(error) => {
+                self.done = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+impl<'de> de::Deserialize<'de> for Yaml {
+    /// Deserialize a node from any deserializer, e.g. as part of a larger typed value.
+    ///
+    /// This keeps the parts of a typed value whose structure is not known as a tree. Unit values
+    /// and `None` become `Null`, and sequences and maps become arrays and hashes. Reals are written
+    /// like with [`to_node`](crate::to_node).
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(YamlVisitor)
+    }
+}
+
+/// A visitor building a [`Yaml`] from any value.
+struct YamlVisitor;
+
+impl<'de> Visitor<'de> for YamlVisitor {
+    type Value = Yaml;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any YAML value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Yaml, E> {
+        Ok(Yaml::Boolean(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Yaml, E> {
+        Ok(Yaml::Integer(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Yaml, E> {
+        i64::try_from(v)
+            .map(Yaml::Integer)
+            .map_err(|_| E::invalid_value(Unexpected::Unsigned(v), &"an integer fitting in an i64"))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Yaml, E> {
+        Ok(real(v, v.is_nan(), v.is_infinite(), v.is_sign_negative()))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Yaml, E> {
+        Ok(Yaml::String(v.into()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Yaml, E> {
+        Ok(Yaml::String(v))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Yaml, E> {
+        Ok(Yaml::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Yaml, E> {
+        Ok(Yaml::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Yaml, D::Error> {
+        de::Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Yaml, A::Error> {
+        let mut array = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            array.push(item);
+        }
+        Ok(Yaml::Array(array))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Yaml, A::Error> {
+        let mut hash = LinkedHashMap::new();
+        while let Some((key, value)) = map.next_entry()? {
+            hash.insert(key, value);
+        }
+        Ok(Yaml::Hash(hash))
+    }
+}
+
+/// A view over a node, common to [`Yaml`] and [`MarkedYaml`].
+enum NodeView<'a, Node> {
+    /// A null value.
+    Null,
+    /// A boolean.
+    Boolean(bool),
+    /// An integer.
+    Integer(i64),
+    /// A real, as written in YAML.
+    Real(&'a str),
+    /// A string.
+    String(&'a str),
+    /// An array.
+    Array(&'a [Node]),
+    /// A hash.
+    Hash(&'a LinkedHashMap<Node, Node>),
+    /// An alias.
+    Alias,
+    /// A `BadValue`.
+    BadValue,
+}
+
+impl<Node> NodeView<'_, Node> {
+    /// Return the text of the node as a hash key in a [`YamlPath`].
+    ///
+    /// Keys which are not scalars are represented as `?`.
+    fn key_text(&self) -> String {
+        match self {
+            NodeView::String(s) | NodeView::Real(s) => (*s).to_owned(),
+            NodeView::Integer(i) => i.to_string(),
+            NodeView::Boolean(b) => b.to_string(),
+            NodeView::Null => "null".into(),
+            _ => "?".into(),
+        }
+    }
+
+    /// Describe the node for an "invalid type" error.
+    fn unexpected(&self) -> Unexpected<'_> {
+        match self {
+            NodeView::Real(s) => match parse_core_schema_fp(s) {
+                Some(f) => Unexpected::Float(f),
+                None => Unexpected::Other("real"),
+            },
+            NodeView::Integer(i) => Unexpected::Signed(*i),
+            NodeView::String(s) => Unexpected::Str(s),
+            NodeView::Boolean(b) => Unexpected::Bool(*b),
+            NodeView::Array(_) => Unexpected::Seq,
+            NodeView::Hash(_) => Unexpected::Map,
+            NodeView::Alias => Unexpected::Other("alias"),
+            NodeView::Null => Unexpected::Unit,
+            NodeView::BadValue => Unexpected::Other("bad value"),
+        }
+    }
+}
+
+/// A node which can be deserialized.
+trait DeserializableNode: Sized {
+    /// Return a view over the contents of the node.
+    fn view(&self) -> NodeView<'_, Self>;
+
+    /// Return the span of the node, if it has one.
+    fn span(&self) -> Option<Span>;
+}
+
+impl DeserializableNode for Yaml {
+    fn view(&self) -> NodeView<'_, Self> {
+        match self {
+            Yaml::Null => NodeView::Null,
+            Yaml::Boolean(b) => NodeView::Boolean(*b),
+            Yaml::Integer(i) => NodeView::Integer(*i),
+            Yaml::Real(s) => NodeView::Real(s),
+            Yaml::String(s) => NodeView::String(s),
+            Yaml::Array(array) => NodeView::Array(array),
+            Yaml::Hash(hash) => NodeView::Hash(hash),
+            Yaml::Alias(_) => NodeView::Alias,
+            Yaml::BadValue => NodeView::BadValue,
+        }
+    }
+
+    fn span(&self) -> Option<Span> {
+        None
+    }
+}
+
+impl DeserializableNode for MarkedYaml {
+    fn view(&self) -> NodeView<'_, Self> {
+        match &self.data {
+            YamlData::Null => NodeView::Null,
+            YamlData::Boolean(b) => NodeView::Boolean(*b),
+            YamlData::Integer(i) => NodeView::Integer(*i),
+            YamlData::Real(s) => NodeView::Real(s),
+            YamlData::String(s) => NodeView::String(s),
+            YamlData::Array(array) => NodeView::Array(array),
+            YamlData::Hash(hash) => NodeView::Hash(hash),
+            YamlData::Alias(_) => NodeView::Alias,
+            YamlData::BadValue => NodeView::BadValue,
+        }
+    }
+
+    fn span(&self) -> Option<Span> {
+        Some(self.span)
+    }
+}
+
+/// Implement [`Deserializer`] and [`IntoDeserializer`] for a reference to a node, by delegating
+/// to [`NodeDeserializer`] without limits.
+macro_rules! impl_deserializer {
+    ($node:ty) => {
+        impl<'de> Deserializer<'de> for &'de $node {
+            type Error = DeserializeError;
+
+            fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                NodeDeserializer::new(self, &Budget::unlimited()).deserialize_any(visitor)
+            }
+
+            fn deserialize_option<V: Visitor<'de>>(
+                self,
+                visitor: V,
+            ) -> Result<V::Value, Self::Error> {
+                NodeDeserializer::new(self, &Budget::unlimited()).deserialize_option(visitor)
+            }
+
+            fn deserialize_unit<V: Visitor<'de>>(
+                self,
+                visitor: V,
+            ) -> Result<V::Value, Self::Error> {
+                NodeDeserializer::new(self, &Budget::unlimited()).deserialize_unit(visitor)
+            }
+
+            fn deserialize_unit_struct<V: Visitor<'de>>(
+                self,
+                name: &'static str,
+                visitor: V,
+            ) -> Result<V::Value, Self::Error> {
+                NodeDeserializer::new(self, &Budget::unlimited())
+                    .deserialize_unit_struct(name, visitor)
+            }
+
+            fn deserialize_newtype_struct<V: Visitor<'de>>(
+                self,
+                name: &'static str,
+                visitor: V,
+            ) -> Result<V::Value, Self::Error> {
+                NodeDeserializer::new(self, &Budget::unlimited())
+                    .deserialize_newtype_struct(name, visitor)
+            }
+
+            fn deserialize_enum<V: Visitor<'de>>(
+                self,
+                name: &'static str,
+                variants: &'static [&'static str],
+                visitor: V,
+            ) -> Result<V::Value, Self::Error> {
+                NodeDeserializer::new(self, &Budget::unlimited())
+                    .deserialize_enum(name, variants, visitor)
+            }
+
+            fn deserialize_ignored_any<V: Visitor<'de>>(
+                self,
+                visitor: V,
+            ) -> Result<V::Value, Self::Error> {
+                NodeDeserializer::new(self, &Budget::unlimited()).deserialize_ignored_any(visitor)
+            }
+
+            serde::forward_to_deserialize_any! {
+                bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+                bytes byte_buf seq tuple tuple_struct map struct identifier
+            }
+        }
+
+        impl<'de> IntoDeserializer<'de, DeserializeError> for &'de $node {
+            type Deserializer = Self;
+
+            fn into_deserializer(self) -> Self::Deserializer {
+                self
+            }
+        }
+    };
+}
+
+impl_deserializer!(Yaml);
+impl_deserializer!(MarkedYaml);
+
+/// A deserializer for any kind of node.
+///
+/// Errors returned from its methods have the span and the path of the node, unless they already
+/// had them.
+struct NodeDeserializer<'de, 'b, Node> {
+    /// The node to deserialize.
+    node: &'de Node,
+    /// The budget shared by all nodes of the deserialization.
+    budget: &'b Budget,
+}
+
+impl<'de, 'b, Node: DeserializableNode> NodeDeserializer<'de, 'b, Node> {
+    /// Create a deserializer for `node`, spending from `budget`.
+    fn new(node: &'de Node, budget: &'b Budget) -> Self {
+        NodeDeserializer { node, budget }
+    }
+
+    /// Return an "invalid type" error for the node.
+    fn invalid_type(&self, expected: &dyn de::Expected) -> DeserializeError {
+        de::Error::invalid_type(self.node.view().unexpected(), expected)
+    }
+}
+
+impl<'de, Node: DeserializableNode> Deserializer<'de> for NodeDeserializer<'de, '_, Node> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let budget = self.budget;
+        let result = budget.visit_node().and_then(|()| match self.node.view() {
+            NodeView::Real(s) => match parse_core_schema_fp(s) {
+                Some(f) => visitor.visit_f64(f),
+                None => Err(de::Error::invalid_value(
+                    Unexpected::Other("real"),
+                    &visitor,
+                )),
+            },
+            NodeView::Integer(i) => visitor.visit_i64(i),
+            NodeView::String(s) => visitor.visit_borrowed_str(s),
+            NodeView::Boolean(b) => visitor.visit_bool(b),
+            NodeView::Array(array) => budget.nested(|| {
+                visitor.visit_seq(SeqDeserializer {
+                    iter: array.iter().enumerate(),
+                    budget,
+                })
+            }),
+            NodeView::Hash(hash) => budget.nested(|| {
+                visitor.visit_map(MapDeserializer {
+                    iter: hash.iter(),
+                    value: None,
+                    budget,
+                })
+            }),
+            NodeView::Null => visitor.visit_unit(),
+            NodeView::Alias | NodeView::BadValue => Err(self.invalid_type(&visitor)),
+        });
+        result.map_err(|e| self.budget.locate(e, self.node.span()))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let result = match self.node.view() {
+            NodeView::Null => self.budget.visit_node().and_then(|()| visitor.visit_none()),
+            _ => visitor.visit_some(NodeDeserializer::new(self.node, self.budget)),
+        };
+        result.map_err(|e| self.budget.locate(e, self.node.span()))
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let result = self
+            .budget
+            .visit_node()
+            .and_then(|()| match self.node.view() {
+                NodeView::Null => visitor.visit_unit(),
+                _ => Err(self.invalid_type(&visitor)),
+            });
+        result.map_err(|e| self.budget.locate(e, self.node.span()))
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (span, budget) = (self.node.span(), self.budget);
+        visitor
+            .visit_newtype_struct(self)
+            .map_err(|e| budget.locate(e, span))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let budget = self.budget;
+        let result = budget.visit_node().and_then(|()| match self.node.view() {
+            // A unit variant.
+            NodeView::String(_) => visitor.visit_enum(EnumDeserializer {
+                variant: self.node,
+                value: None,
+                budget,
+            }),
+            // A variant with contents, as a hash with a single entry.
+            NodeView::Hash(hash) if hash.len() == 1 => {
+                let (variant, value) = hash.iter().next().unwrap();
+                budget.nested(|| {
+                    visitor.visit_enum(EnumDeserializer {
+                        variant,
+                        value: Some(value),
+                        budget,
+                    })
+                })
+            }
+            _ => Err(self.invalid_type(&visitor)),
+        });
+        result.map_err(|e| self.budget.locate(e, self.node.span()))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf seq tuple tuple_struct map struct identifier
+    }
+}
+
+/// Access to the items of an array.
+struct SeqDeserializer<'de, 'b, Node> {
+    /// The items left to deserialize, along with their index.
+    iter: std::iter::Enumerate<std::slice::Iter<'de, Node>>,
+    /// The budget shared by all nodes of the deserialization.
+    budget: &'b Budget,
+}
+
+impl<'de, Node: DeserializableNode> SeqAccess<'de> for SeqDeserializer<'de, '_, Node> {
+    type Error = DeserializeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        self.iter
+            .next()
+            .map(|(i, item)| {
+                self.budget.child(
+                    |path| path.push_index(i),
+                    || seed.deserialize(NodeDeserializer::new(item, self.budget)),
+                )
+            })
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Access to the entries of a hash.
+struct MapDeserializer<'de, 'b, Node> {
+    /// The entries left to deserialize.
+    iter: hashlink::linked_hash_map::Iter<'de, Node, Node>,
+    /// The entry whose key was just deserialized.
+    value: Option<(&'de Node, &'de Node)>,
+    /// The budget shared by all nodes of the deserialization.
+    budget: &'b Budget,
+}
+
+impl<'de, Node: DeserializableNode> MapAccess<'de> for MapDeserializer<'de, '_, Node> {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some((key, value));
+                self.budget
+                    .child(
+                        |path| path.push_key(key.view().key_text()),
+                        || seed.deserialize(NodeDeserializer::new(key, self.budget)),
+                    )
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (key, value) = self
+            .value
+            .take()
+            .expect("`next_value_seed` called before `next_key_seed`");
+        self.budget.child(
+            |path| path.push_key(key.view().key_text()),
+            || seed.deserialize(NodeDeserializer::new(value, self.budget)),
+        )
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Access to an enum variant, written either as a string or as a hash with a single entry.
+struct EnumDeserializer<'de, 'b, Node> {
+    /// The name of the variant.
+    variant: &'de Node,
+    /// The contents of the variant, if any.
+    value: Option<&'de Node>,
+    /// The budget shared by all nodes of the deserialization.
+    budget: &'b Budget,
+}
+
+impl<'de, 'b, Node: DeserializableNode> EnumAccess<'de> for EnumDeserializer<'de, 'b, Node> {
+    type Error = DeserializeError;
+    type Variant = VariantDeserializer<'de, 'b, Node>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let variant = seed.deserialize(NodeDeserializer::new(self.variant, self.budget))?;
+        Ok((
+            variant,
+            VariantDeserializer {
+                value: self.value,
+                budget: self.budget,
+            },
+        ))
+    }
+}
+
+/// Access to the contents of an enum variant.
+struct VariantDeserializer<'de, 'b, Node> {
+    /// The contents of the variant, if any.
+    value: Option<&'de Node>,
+    /// The budget shared by all nodes of the deserialization.
+    budget: &'b Budget,
+}
+
+impl<'de, Node: DeserializableNode> VariantDeserializer<'de, '_, Node> {
+    /// Deserialize the contents of the variant, which must be an array or a hash.
+    fn deserialize_contents<V: Visitor<'de>>(
+        self,
+        visitor: V,
+        is_expected: fn(&NodeView<'de, Node>) -> bool,
+    ) -> Result<V::Value, DeserializeError> {
+        match self.value {
+            Some(value) if is_expected(&value.view()) => {
+                NodeDeserializer::new(value, self.budget).deserialize_any(visitor)
+            }
+            Some(value) => Err(NodeDeserializer::new(value, self.budget)
+                .invalid_type(&visitor)
+                .or_span(value.span())),
+            None => Err(de::Error::invalid_type(Unexpected::UnitVariant, &visitor)),
+        }
+    }
+}
+
+impl<'de, Node: DeserializableNode> VariantAccess<'de> for VariantDeserializer<'de, '_, Node> {
+    type Error = DeserializeError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        match self.value {
+            None => Ok(()),
+            Some(value) => NodeDeserializer::new(value, self.budget)
+                .deserialize_unit(de::IgnoredAny)
+                .map(|_| ()),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        match self.value {
+            Some(value) => seed.deserialize(NodeDeserializer::new(value, self.budget)),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_contents(visitor, |view| matches!(view, NodeView::Array(_)))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_contents(visitor, |view| matches!(view, NodeView::Hash(_)))
+    }
+}
@@ -0,0 +1,119 @@
+//! YAML documents along with their metadata.
+
+use std::collections::BTreeMap;
+
+use saphyr_parser::{Event, Input, Parser, ScanError, Span, SpannedEventReceiver};
+
+use crate::{loader::Anchor, LoadableYamlNode, YamlLoader};
+
+/// A YAML document, along with the information about it that is not part of its nodes.
+///
+/// Loading documents with [`YamlDocument::load_from_str`] rather than [`Yaml::load_from_str`]
+/// keeps the directives, document markers and anchors that the loader otherwise discards.
+///
+/// ```
+/// # use saphyr::{Yaml, YamlDocument};
+/// let docs = YamlDocument::<Yaml>::load_from_str("%YAML 1.2\n--- &a foo\n...").unwrap();
+/// let doc = &docs[0];
+/// assert_eq!(doc.root.as_str(), Some("foo"));
+/// assert_eq!(doc.version, Some((1, 2)));
+/// assert!(doc.explicit_start && doc.explicit_end);
+/// assert_eq!(doc.anchors[&1].name, "a");
+/// ```
+///
+/// [`Yaml::load_from_str`]: crate::Yaml::load_from_str
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct YamlDocument<Node> {
+    /// The root node of the document.
+    pub root: Node,
+    /// The span of the document.
+    ///
+    /// It starts at the document start marker (`---`) or, if there is none, at the contents of
+    /// the document. Directives are not included. It ends after the document end marker (`...`)
+    /// or, if there is none, where whatever follows the document starts.
+    pub span: Span,
+    /// The `%YAML` directive of the document as `(major, minor)`, if any.
+    pub version: Option<(u32, u32)>,
+    /// The `%TAG` directives of the document as `(handle, prefix)` pairs, in order.
+    pub tags: Vec<(String, String)>,
+    /// Whether the document started with a document start marker (`---`).
+    pub explicit_start: bool,
+    /// Whether the document ended with a document end marker (`...`).
+    pub explicit_end: bool,
+    /// The anchors of the document, indexed by their ID.
+    pub anchors: BTreeMap<usize, Anchor>,
+}
+
+impl<Node: LoadableYamlNode> YamlDocument<Node> {
+    /// Load the given string as an array of YAML documents.
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading fails.
+    pub fn load_from_str(source: &str) -> Result<Vec<Self>, ScanError> {
+        Self::load_from_parser(&mut Parser::new_from_str(source))
+            .map_err(|error| error.with_source(source))
+    }
+
+    /// Load the contents from the specified [`Parser`] as an array of YAML documents.
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading fails.
+    pub fn load_from_parser<I: Input>(parser: &mut Parser<I>) -> Result<Vec<Self>, ScanError> {
+        let mut documents = vec![];
+        loop {
+            let mut receiver = DocumentReceiver {
+                loader: YamlLoader::default()
+                    .record_anchors(true)
+                    .keep_representations(true),
+                start: None,
+                end: None,
+            };
+            parser.load(&mut receiver, false)?;
+            let (Some((explicit_start, start)), Some((explicit_end, end))) =
+                (receiver.start, receiver.end)
+            else {
+                break;
+            };
+            let (mut roots, anchors) = receiver.loader.into_parts();
+            let Some(root) = roots.pop() else {
+                break;
+            };
+            let end = if explicit_end { end.end } else { end.start };
+            documents.push(YamlDocument {
+                root,
+                span: Span::new(start.start, end),
+                version: parser.version_directive(),
+                tags: parser.tag_directives().to_vec(),
+                explicit_start,
+                explicit_end,
+                anchors,
+            });
+        }
+        Ok(documents)
+    }
+}
+
+/// A receiver forwarding events to a [`YamlLoader`] and recording the document markers.
+struct DocumentReceiver<Node: LoadableYamlNode> {
+    /// The loader building the document.
+    loader: YamlLoader<Node>,
+    /// Whether the document start was explicit and its span.
+    start: Option<(bool, Span)>,
+    /// Whether the document end was explicit and its span.
+    end: Option<(bool, Span)>,
+}
+
+impl<Node: LoadableYamlNode> SpannedEventReceiver for DocumentReceiver<Node> {
+    fn on_event(&mut self, ev: Event, span: Span) {
+        match ev {
+            Event::DocumentStart(explicit) => self.start = Some((explicit, span)),
+            Event::DocumentEnd(explicit) => self.end = Some((explicit, span)),
+            _ => {}
+        }
+        self.loader.on_event(ev, span);
+    }
+
+    fn on_anchor(&mut self, id: usize, name: &str, span: Span) {
+        self.loader.on_anchor(id, name, span);
+    }
+}
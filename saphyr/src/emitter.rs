@@ -1,7 +1,15 @@
 //! YAML serialization helpers.
 
-use crate::char_traits;
+pub mod util;
+
+use crate::document::YamlDocument;
+use crate::loader::{parse_core_schema_fp, Anchor};
+use crate::visit::PathSegment;
 use crate::yaml::{Hash, Yaml};
+use crate::{MarkedYaml, YamlData};
+use saphyr_parser::Tag;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::From;
 use std::error::Error;
 use std::fmt::{self, Display};
@@ -35,6 +43,9 @@ impl From<fmt::Error> for EmitError {
 
 /// The YAML serializer.
 ///
+/// `'a` is the lifetime of the writer, and `'h` that of the hook set with
+/// [`YamlEmitter::scalar_hook`], which may borrow local state.
+///
 /// ```
 /// # use saphyr::{Yaml, YamlEmitter};
 /// let input_string = "a: b\nc: d";
@@ -48,12 +59,51 @@ impl From<fmt::Error> for EmitError {
 /// c: d"#);
 /// ```
 #[allow(clippy::module_name_repetitions)]
-pub struct YamlEmitter<'a> {
+pub struct YamlEmitter<'a, 'h> {
     writer: &'a mut dyn fmt::Write,
     best_indent: usize,
     compact: bool,
     level: isize,
     multiline_strings: bool,
+    /// The maximum number of items of collections written in flow style, 0 to disable it.
+    flow_max_items: usize,
+    /// The maximum width of collections written in flow style.
+    flow_max_width: usize,
+    scalar_hook: Option<ScalarHook<'h>>,
+    /// Whether a hash key is being emitted, in which case the scalar hook is not called.
+    in_key: bool,
+    /// The names of the anchors on the nodes being dumped, by the address of the node.
+    anchored_nodes: BTreeMap<*const Yaml, String>,
+    /// The names of the anchors [`Yaml::Alias`] nodes refer to, by anchor ID.
+    anchor_names: BTreeMap<usize, String>,
+    /// The tags of the scalars being dumped, by the address of the node.
+    tagged_nodes: BTreeMap<*const Yaml, Tag>,
+}
+
+/// See [`YamlEmitter::scalar_hook`].
+type ScalarHook<'h> = Box<dyn FnMut(&[PathSegment], &Yaml) -> Option<ScalarRendering> + 'h>;
+
+/// The style in which to write a scalar, as returned by the hook set with
+/// [`YamlEmitter::scalar_hook`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScalarStyle {
+    /// Write the scalar unquoted, as is. It may not load back as the same value.
+    Plain,
+    /// Write the scalar between single quotes.
+    SingleQuoted,
+    /// Write the scalar between double quotes, escaping special characters.
+    DoubleQuoted,
+    /// Write the scalar as a literal block scalar (`|`).
+    Literal,
+}
+
+/// How to write a scalar, as returned by the hook set with [`YamlEmitter::scalar_hook`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScalarRendering {
+    /// Write the scalar in the given style.
+    Style(ScalarStyle),
+    /// Write the given text in place of the scalar, as is.
+    Text(String),
 }
 
 /// A convenience alias for emitter functions that may fail without returning a value.
@@ -122,7 +172,7 @@ fn escape_str(wr: &mut dyn fmt::Write, v: &str) -> Result<(), fmt::Error> {
     Ok(())
 }
 
-impl<'a> YamlEmitter<'a> {
+impl<'a, 'h> YamlEmitter<'a, 'h> {
     /// Create a new emitter serializing into `writer`.
     pub fn new(writer: &'a mut dyn fmt::Write) -> Self {
         YamlEmitter {
@@ -131,6 +181,13 @@ impl<'a> YamlEmitter<'a> {
             compact: true,
             level: -1,
             multiline_strings: false,
+            flow_max_items: 0,
+            flow_max_width: 0,
+            scalar_hook: None,
+            in_key: false,
+            anchored_nodes: BTreeMap::new(),
+            anchor_names: BTreeMap::new(),
+            tagged_nodes: BTreeMap::new(),
         }
     }
 
@@ -140,8 +197,8 @@ impl<'a> YamlEmitter<'a> {
     /// [mappings](http://www.yaml.org/spec/1.2/spec.html#id2798057).
     ///
     /// In this form, blocks cannot have any properties (such as anchors
-    /// or tags), which should be OK, because this emitter doesn't
-    /// (currently) emit those anyways.
+    /// or tags). Anchored collections (see [`YamlEmitter::dump_marked_document`])
+    /// are written on their own lines instead.
     ///
     /// TODO(ethiraric, 2024/04/02): We can support those now.
     pub fn compact(&mut self, compact: bool) {
@@ -188,6 +245,79 @@ impl<'a> YamlEmitter<'a> {
         self.multiline_strings
     }
 
+    /// Write small collections of scalars in flow style (e.g. `ports: [80, 443]`).
+    ///
+    /// An array or a hash is written in flow style if it holds between 1 and `max_items` items or
+    /// entries, all of which are scalars (keys included), and if it then takes at most
+    /// `max_width` characters, brackets included. Other collections are written in block style
+    /// as usual, even if some of their children are written in flow style. Setting `max_items` to
+    /// 0, the default, disables this.
+    ///
+    /// Strings which [`Self::multiline_strings`] would write as literal block scalars prevent
+    /// their collection from being written in flow style, as do scalars the hook set with
+    /// [`Self::scalar_hook`] renders, so that they can be written as requested. The hook may thus
+    /// be called twice on the items of a small collection.
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, YamlEmitter};
+    /// let input = "ports: [80, 443]\nlabels: {app: web, tier: front}\nhosts: [a, b, c]";
+    /// let doc = &Yaml::load_from_str(input).unwrap()[0];
+    /// let mut out = String::new();
+    /// let mut emitter = YamlEmitter::new(&mut out);
+    /// emitter.inline_leaf_collections(2, 40);
+    /// emitter.dump(doc).unwrap();
+    /// assert_eq!(
+    ///     out,
+    ///     "---\nports: [80, 443]\nlabels: {app: web, tier: front}\nhosts:\n  - a\n  - b\n  - c"
+    /// );
+    /// ```
+    pub fn inline_leaf_collections(&mut self, max_items: usize, max_width: usize) {
+        self.flow_max_items = max_items;
+        self.flow_max_width = max_width;
+    }
+
+    /// Return the maximum number of items and width of the collections written in flow style.
+    ///
+    /// See [`Self::inline_leaf_collections`].
+    #[must_use]
+    pub fn inline_leaf_collections_limits(&self) -> (usize, usize) {
+        (self.flow_max_items, self.flow_max_width)
+    }
+
+    /// Set a hook deciding how to write scalars.
+    ///
+    /// The hook is called on each scalar (but not on hash keys) with the path to it and the
+    /// scalar itself. It may return how to write the scalar, or `None` to let the emitter decide.
+    /// The text of non-string scalars is the one the emitter would write (see
+    /// [`Yaml::to_yaml_string`]); writing it in quotes makes it load back as a string.
+    ///
+    /// Single quotes cannot hold control characters and line breaks, and literal block scalars
+    /// cannot hold some of the strings satisfying [`util::is_valid_literal_block_scalar`] (leading
+    /// spaces or line breaks, several trailing line breaks). Scalars which cannot be written in the
+    /// requested style are written between double quotes instead.
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, YamlEmitter, PathSegment};
+    /// # use saphyr::emitter::{ScalarRendering, ScalarStyle};
+    /// let doc = &Yaml::load_from_str("name: foo\nversion: 1.10\nport: 80").unwrap()[0];
+    /// let mut out = String::new();
+    /// let mut emitter = YamlEmitter::new(&mut out);
+    /// emitter.scalar_hook(|path, _| match path {
+    ///     [PathSegment::Key(key)] if key.as_str() == Some("version") => {
+    ///         Some(ScalarRendering::Style(ScalarStyle::SingleQuoted))
+    ///     }
+    ///     _ => None,
+    /// });
+    /// emitter.dump(doc).unwrap();
+    /// assert_eq!(out, "---\nname: foo\nversion: '1.10'\nport: 80");
+    /// ```
+    pub fn scalar_hook(
+        &mut self,
+        hook: impl FnMut(&[PathSegment], &Yaml) -> Option<ScalarRendering> + 'h,
+    ) {
+        self.scalar_hook = Some(Box::new(hook));
+    }
+
     /// Dump Yaml to an output stream.
     /// # Errors
     /// Returns `EmitError` when an error occurs.
@@ -195,7 +325,128 @@ impl<'a> YamlEmitter<'a> {
         // write DocumentStart
         writeln!(self.writer, "---")?;
         self.level = -1;
-        self.emit_node(doc)
+        self.in_key = false;
+        self.emit_node(doc, &mut vec![])
+    }
+
+    /// Dump a [`YamlDocument`] to an output stream, along with its directives and markers.
+    ///
+    /// The `%YAML` and `%TAG` directives of the document are written first. The document start
+    /// marker (`---`) is written if the document had one or if there are directives, which require
+    /// it. The document end marker (`...`) is written if the document had one.
+    ///
+    /// [`YamlDocument::anchors`] is not used: the nodes of a [`Yaml`] tree have no span to tell
+    /// which of them the anchors were on, so aliases are written in full as the node they refer
+    /// to. Use [`YamlEmitter::dump_marked_document`] to write anchors and aliases back.
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, YamlDocument, YamlEmitter};
+    /// let docs = YamlDocument::<Yaml>::load_from_str("%YAML 1.2\n--- foo\n...").unwrap();
+    /// let mut out = String::new();
+    /// YamlEmitter::new(&mut out).dump_document(&docs[0]).unwrap();
+    /// assert_eq!(out, "%YAML 1.2\n---\nfoo\n...");
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `EmitError` when an error occurs.
+    pub fn dump_document(&mut self, doc: &YamlDocument<Yaml>) -> EmitResult {
+        self.emit_document(doc, &doc.root)
+    }
+
+    /// Dump a [`YamlDocument`] of [`MarkedYaml`] nodes to an output stream, along with its
+    /// directives, markers, anchors, aliases and tags.
+    ///
+    /// This is [`YamlEmitter::dump_document`], except that the anchors of
+    /// [`YamlDocument::anchors`] are written back. The node an anchor was on is the one whose
+    /// span starts where the [`Anchor::span`] does, and aliases are the nodes whose
+    /// [`MarkedYaml::anchor_span`] is the span of an anchor written before them. Other aliases
+    /// are written in full as the node they refer to.
+    ///
+    /// The tags of scalars are written back from their [`MarkedYaml::representation`], in the
+    /// form they were written in: verbatim tags as `!<...>` and other tags with their handle.
+    /// Collections have no representation, and their tags are not written.
+    ///
+    /// ```
+    /// # use saphyr::{MarkedYaml, YamlDocument, YamlEmitter};
+    /// let input = "base: &base\n  port: 80\nprod: *base";
+    /// let docs = YamlDocument::<MarkedYaml>::load_from_str(input).unwrap();
+    /// let mut out = String::new();
+    /// YamlEmitter::new(&mut out).dump_marked_document(&docs[0]).unwrap();
+    /// assert_eq!(out, input);
+    ///
+    /// let input = "a: !<tag:example.com,2000:foo> b\nc: !!str 1";
+    /// let docs = YamlDocument::<MarkedYaml>::load_from_str(input).unwrap();
+    /// let mut out = String::new();
+    /// YamlEmitter::new(&mut out).dump_marked_document(&docs[0]).unwrap();
+    /// assert_eq!(out, "a: !<tag:example.com,2000:foo> b\nc: !!str \"1\"");
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `EmitError` when an error occurs.
+    pub fn dump_marked_document(&mut self, doc: &YamlDocument<MarkedYaml>) -> EmitResult {
+        let mut anchored = vec![];
+        let root = unmark(&doc.root, &doc.anchors, &mut BTreeSet::new(), &mut anchored);
+        let mut anchored = anchored.into_iter();
+        let mut nodes = vec![&root];
+        // The nodes are visited in the order `unmark` converted them.
+        while let Some(node) = nodes.pop() {
+            let (anchor, tag) = anchored.next().unwrap_or_default();
+            if let Some(id) = anchor {
+                self.anchored_nodes
+                    .insert(node as *const Yaml, doc.anchors[&id].name.clone());
+            }
+            if let Some(tag) = tag {
+                self.tagged_nodes.insert(node as *const Yaml, tag);
+            }
+            match node {
+                Yaml::Array(array) => nodes.extend(array.iter().rev()),
+                Yaml::Hash(hash) => {
+                    for (key, value) in hash.iter().rev() {
+                        nodes.push(value);
+                        nodes.push(key);
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.anchor_names = doc
+            .anchors
+            .iter()
+            .map(|(&id, anchor)| (id, anchor.name.clone()))
+            .collect();
+        let result = self.emit_document(doc, &root);
+        self.anchored_nodes.clear();
+        self.anchor_names.clear();
+        self.tagged_nodes.clear();
+        result
+    }
+
+    /// Write the directives and markers of `doc` around `root`.
+    fn emit_document<Node>(&mut self, doc: &YamlDocument<Node>, root: &Yaml) -> EmitResult {
+        if let Some((major, minor)) = doc.version {
+            writeln!(self.writer, "%YAML {major}.{minor}")?;
+        }
+        for (handle, prefix) in &doc.tags {
+            writeln!(self.writer, "%TAG {handle} {prefix}")?;
+        }
+        if doc.explicit_start || doc.version.is_some() || !doc.tags.is_empty() {
+            writeln!(self.writer, "---")?;
+        }
+        self.level = -1;
+        self.in_key = false;
+        self.emit_node(root, &mut vec![])?;
+        if doc.explicit_end {
+            write!(self.writer, "\n...")?;
+        }
+        Ok(())
+    }
+
+    /// Return the name of the anchor on `node`, if any.
+    fn anchor_of(&self, node: &Yaml) -> Option<String> {
+        if self.anchored_nodes.is_empty() {
+            return None;
+        }
+        self.anchored_nodes.get(&(node as *const Yaml)).cloned()
     }
 
     fn write_indent(&mut self) -> EmitResult {
@@ -210,46 +461,156 @@ impl<'a> YamlEmitter<'a> {
         Ok(())
     }
 
-    fn emit_node(&mut self, node: &Yaml) -> EmitResult {
+    fn emit_node<'n>(&mut self, node: &'n Yaml, path: &mut Vec<PathSegment<'n>>) -> EmitResult {
+        let flow = self.flow_collection(node, path);
+        if let Some(name) = self.anchor_of(node) {
+            // The content of a block collection starts on the next line.
+            let block = flow.is_none()
+                && match node {
+                    Yaml::Array(v) => !v.is_empty(),
+                    Yaml::Hash(h) => !h.is_empty(),
+                    _ => false,
+                };
+            write!(self.writer, "&{name}{}", if block { '\n' } else { ' ' })?;
+        }
+        if let Some(tag) = self.tagged_nodes.get(&(node as *const Yaml)) {
+            write!(self.writer, "{tag} ")?;
+        }
+        if !self.in_key && !matches!(node, Yaml::Array(_) | Yaml::Hash(_) | Yaml::Alias(_)) {
+            let rendering = self.scalar_hook.as_mut().and_then(|hook| hook(path, node));
+            if let Some(rendering) = rendering {
+                return self.emit_rendering(node, rendering);
+            }
+        }
+        if let Some(text) = flow {
+            self.writer.write_str(&text)?;
+            return Ok(());
+        }
         match *node {
-            Yaml::Array(ref v) => self.emit_array(v),
-            Yaml::Hash(ref h) => self.emit_hash(h),
+            Yaml::Array(ref v) => self.emit_array(v, path),
+            Yaml::Hash(ref h) => self.emit_hash(h, path),
             Yaml::String(ref v) => {
                 if self.multiline_strings
                     && v.contains('\n')
-                    && char_traits::is_valid_literal_block_scalar(v)
+                    && util::is_valid_literal_block_scalar(v)
                 {
                     self.emit_literal_block(v)?;
-                } else if need_quotes(v) {
-                    escape_str(self.writer, v)?;
                 } else {
-                    write!(self.writer, "{v}")?;
+                    emit_scalar(self.writer, node)?;
                 }
                 Ok(())
             }
-            Yaml::Boolean(v) => {
-                if v {
-                    self.writer.write_str("true")?;
-                } else {
-                    self.writer.write_str("false")?;
-                }
+            Yaml::Boolean(_) | Yaml::Integer(_) | Yaml::Real(_) | Yaml::Null | Yaml::BadValue => {
+                emit_scalar(self.writer, node)?;
                 Ok(())
             }
-            Yaml::Integer(v) => {
-                write!(self.writer, "{v}")?;
+            Yaml::Alias(id) => {
+                if let Some(name) = self.anchor_names.get(&id) {
+                    write!(self.writer, "*{name}")?;
+                }
                 Ok(())
             }
-            Yaml::Real(ref v) => {
-                write!(self.writer, "{v}")?;
-                Ok(())
+        }
+    }
+
+    /// Write the scalar `node` as returned by the scalar hook.
+    fn emit_rendering(&mut self, node: &Yaml, rendering: ScalarRendering) -> EmitResult {
+        let style = match rendering {
+            ScalarRendering::Text(text) => return Ok(self.writer.write_str(&text)?),
+            ScalarRendering::Style(style) => style,
+        };
+        let text = match node {
+            Yaml::String(v) => Cow::Borrowed(v.as_str()),
+            _ => Cow::Owned(node.to_yaml_string().unwrap_or_default()),
+        };
+        match style {
+            ScalarStyle::Plain => self.writer.write_str(&text)?,
+            ScalarStyle::SingleQuoted if text.chars().all(|c| c == '\t' || !c.is_control()) => {
+                write!(self.writer, "'{}'", text.replace('\'', "''"))?;
             }
-            Yaml::Null | Yaml::BadValue => {
-                write!(self.writer, "~")?;
-                Ok(())
+            ScalarStyle::Literal
+                if util::is_valid_literal_block_scalar(&text)
+                    && !text.starts_with([' ', '\n'])
+                    && !text.ends_with("\n\n") =>
+            {
+                self.emit_literal_block(&text)?;
             }
-            // XXX(chenyh) Alias
-            Yaml::Alias(_) => Ok(()),
+            _ => escape_str(self.writer, &text)?,
         }
+        Ok(())
+    }
+
+    /// Return the text of `node` in flow style, if it is a collection which
+    /// [`Self::inline_leaf_collections`] allows to write in flow style.
+    fn flow_collection<'n>(
+        &mut self,
+        node: &'n Yaml,
+        path: &mut Vec<PathSegment<'n>>,
+    ) -> Option<String> {
+        let len = match node {
+            Yaml::Array(v) => v.len(),
+            Yaml::Hash(h) => h.len(),
+            _ => return None,
+        };
+        if len == 0 || len > self.flow_max_items {
+            return None;
+        }
+        let mut text = String::new();
+        match node {
+            Yaml::Array(v) => {
+                text.push('[');
+                for (i, item) in v.iter().enumerate() {
+                    if i > 0 {
+                        text.push_str(", ");
+                    }
+                    path.push(PathSegment::Index(i));
+                    let item = self.flow_scalar(item, path, false);
+                    path.pop();
+                    text.push_str(&item?);
+                }
+                text.push(']');
+            }
+            Yaml::Hash(h) => {
+                text.push('{');
+                for (i, (k, v)) in h.iter().enumerate() {
+                    if i > 0 {
+                        text.push_str(", ");
+                    }
+                    text.push_str(&self.flow_scalar(k, path, true)?);
+                    text.push_str(": ");
+                    path.push(PathSegment::Key(k));
+                    let value = self.flow_scalar(v, path, false);
+                    path.pop();
+                    text.push_str(&value?);
+                }
+                text.push('}');
+            }
+            _ => unreachable!(),
+        }
+        (text.chars().count() <= self.flow_max_width).then_some(text)
+    }
+
+    /// Return the text of `node` as an item of a collection in flow style, if it is a scalar
+    /// which can be written as such.
+    fn flow_scalar(&mut self, node: &Yaml, path: &[PathSegment], key: bool) -> Option<String> {
+        if self.anchor_of(node).is_some() || self.tagged_nodes.contains_key(&(node as *const Yaml))
+        {
+            return None;
+        }
+        if let Yaml::String(v) = node {
+            if self.multiline_strings && v.contains('\n') && util::is_valid_literal_block_scalar(v)
+            {
+                return None;
+            }
+        }
+        if !key && !self.in_key {
+            if let Some(hook) = &mut self.scalar_hook {
+                if hook(path, node).is_some() {
+                    return None;
+                }
+            }
+        }
+        node.to_yaml_string()
     }
 
     fn emit_literal_block(&mut self, v: &str) -> EmitResult {
@@ -272,7 +633,7 @@ impl<'a> YamlEmitter<'a> {
         Ok(())
     }
 
-    fn emit_array(&mut self, v: &[Yaml]) -> EmitResult {
+    fn emit_array<'n>(&mut self, v: &'n [Yaml], path: &mut Vec<PathSegment<'n>>) -> EmitResult {
         if v.is_empty() {
             write!(self.writer, "[]")?;
         } else {
@@ -283,14 +644,16 @@ impl<'a> YamlEmitter<'a> {
                     self.write_indent()?;
                 }
                 write!(self.writer, "-")?;
-                self.emit_val(true, x)?;
+                path.push(PathSegment::Index(cnt));
+                self.emit_val(true, x, path)?;
+                path.pop();
             }
             self.level -= 1;
         }
         Ok(())
     }
 
-    fn emit_hash(&mut self, h: &Hash) -> EmitResult {
+    fn emit_hash<'n>(&mut self, h: &'n Hash, path: &mut Vec<PathSegment<'n>>) -> EmitResult {
         if h.is_empty() {
             self.writer.write_str("{}")?;
         } else {
@@ -301,18 +664,28 @@ impl<'a> YamlEmitter<'a> {
                     writeln!(self.writer)?;
                     self.write_indent()?;
                 }
+                let in_key = std::mem::replace(&mut self.in_key, true);
                 if complex_key {
                     write!(self.writer, "?")?;
-                    self.emit_val(true, k)?;
+                    self.emit_val(true, k, path)?;
+                    self.in_key = in_key;
                     writeln!(self.writer)?;
                     self.write_indent()?;
                     write!(self.writer, ":")?;
-                    self.emit_val(true, v)?;
                 } else {
-                    self.emit_node(k)?;
-                    write!(self.writer, ":")?;
-                    self.emit_val(false, v)?;
+                    self.emit_node(k, path)?;
+                    self.in_key = in_key;
+                    // The name of an alias may contain `:`.
+                    let separator = if matches!(k, Yaml::Alias(_)) {
+                        " :"
+                    } else {
+                        ":"
+                    };
+                    self.writer.write_str(separator)?;
                 }
+                path.push(PathSegment::Key(k));
+                self.emit_val(complex_key, v, path)?;
+                path.pop();
             }
             self.level -= 1;
         }
@@ -323,7 +696,25 @@ impl<'a> YamlEmitter<'a> {
     /// following a ":" or "-", either after a space, or on a new line.
     /// If `inline` is true, then the preceding characters are distinct
     /// and short enough to respect the compact flag.
-    fn emit_val(&mut self, inline: bool, val: &Yaml) -> EmitResult {
+    fn emit_val<'n>(
+        &mut self,
+        inline: bool,
+        val: &'n Yaml,
+        path: &mut Vec<PathSegment<'n>>,
+    ) -> EmitResult {
+        let anchor = self.anchor_of(val);
+        if let Some(text) = self.flow_collection(val, path) {
+            match anchor {
+                Some(name) => write!(self.writer, " &{name} {text}")?,
+                None => write!(self.writer, " {text}")?,
+            }
+            return Ok(());
+        }
+        if let (Some(name), Yaml::Array(_) | Yaml::Hash(_)) = (&anchor, val) {
+            write!(self.writer, " &{name}")?;
+        }
+        // Properties in compact notation would be those of the first item or key.
+        let inline = inline && anchor.is_none();
         match *val {
             Yaml::Array(ref v) => {
                 if (inline && self.compact) || v.is_empty() {
@@ -334,7 +725,7 @@ impl<'a> YamlEmitter<'a> {
                     self.write_indent()?;
                     self.level -= 1;
                 }
-                self.emit_array(v)
+                self.emit_array(v, path)
             }
             Yaml::Hash(ref h) => {
                 if (inline && self.compact) || h.is_empty() {
@@ -345,78 +736,134 @@ impl<'a> YamlEmitter<'a> {
                     self.write_indent()?;
                     self.level -= 1;
                 }
-                self.emit_hash(h)
+                self.emit_hash(h, path)
             }
             _ => {
                 write!(self.writer, " ")?;
-                self.emit_node(val)
+                self.emit_node(val, path)
             }
         }
     }
 }
 
-/// Check if the string requires quoting.
-/// Strings starting with any of the following characters must be quoted.
-/// :, &, *, ?, |, -, <, >, =, !, %, @
-/// Strings containing any of the following characters must be quoted.
-/// {, }, \[, t \], ,, #, `
-///
-/// If the string contains any of the following control characters, it must be escaped with double quotes:
-/// \0, \x01, \x02, \x03, \x04, \x05, \x06, \a, \b, \t, \n, \v, \f, \r, \x0e, \x0f, \x10, \x11, \x12, \x13, \x14, \x15, \x16, \x17, \x18, \x19, \x1a, \e, \x1c, \x1d, \x1e, \x1f, \N, \_, \L, \P
+impl Yaml {
+    /// Return the YAML text of `self` if it is a scalar, as [`YamlEmitter`] writes it.
+    ///
+    /// Strings are double-quoted if they would otherwise be loaded as another type (e.g. `"true"`
+    /// or `"12"`) or contain special characters. Null is written as `~` and non-finite reals as
+    /// `.inf`, `-.inf` and `.nan`. Other reals are written as they were loaded.
+    ///
+    /// Returns `None` for arrays, hashes and aliases. `BadValue` is written as `~`, like the
+    /// emitter does.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// assert_eq!(Yaml::Boolean(true).to_yaml_string().unwrap(), "true");
+    /// assert_eq!(Yaml::Real(".INF".into()).to_yaml_string().unwrap(), ".inf");
+    /// assert_eq!(Yaml::String("12".into()).to_yaml_string().unwrap(), r#""12""#);
+    /// assert_eq!(Yaml::String("foo".into()).to_yaml_string().unwrap(), "foo");
+    /// assert_eq!(Yaml::Array(vec![]).to_yaml_string(), None);
+    /// ```
+    #[must_use]
+    pub fn to_yaml_string(&self) -> Option<String> {
+        if matches!(self, Yaml::Array(_) | Yaml::Hash(_) | Yaml::Alias(_)) {
+            return None;
+        }
+        let mut out = String::new();
+        emit_scalar(&mut out, self).ok()?;
+        Some(out)
+    }
+}
+
+/// Convert `node` to a [`Yaml`] tree for [`YamlEmitter::dump_marked_document`].
 ///
-/// Finally, there are other cases when the strings must be quoted, no matter if you're using single or double quotes:
-/// * When the string is true or false (otherwise, it would be treated as a boolean value);
-/// * When the string is null or ~ (otherwise, it would be considered as a null value);
-/// * When the string looks like a number, such as integers (e.g. 2, 14, etc.), floats (e.g. 2.6, 14.9) and exponential numbers (e.g. 12e7, etc.) (otherwise, it would be treated as a numeric value);
-/// * When the string looks like a date (e.g. 2014-12-31) (otherwise it would be automatically converted into a Unix timestamp).
-#[allow(clippy::doc_markdown)]
-fn need_quotes(string: &str) -> bool {
-    fn need_quotes_spaces(string: &str) -> bool {
-        string.starts_with(' ') || string.ends_with(' ')
-    }
-
-    string.is_empty()
-        || need_quotes_spaces(string)
-        || string.starts_with(|character: char| {
-            matches!(
-                character,
-                '&' | '*' | '?' | '|' | '-' | '<' | '>' | '=' | '!' | '%' | '@'
-            )
-        })
-        || string.contains(|character: char| {
-            matches!(character, ':'
-            | '{'
-            | '}'
-            | '['
-            | ']'
-            | ','
-            | '#'
-            | '`'
-            | '\"'
-            | '\''
-            | '\\'
-            | '\0'..='\x06'
-            | '\t'
-            | '\n'
-            | '\r'
-            | '\x0e'..='\x1a'
-            | '\x1c'..='\x1f')
+/// Aliases to the anchors in `written`, which were found on a node converted before them, are
+/// converted to [`Yaml::Alias`]. The ID of the anchor on each converted node, if any, and the tag
+/// of its representation, if any, are pushed to `anchored`, in the order nodes are converted: a
+/// node before its items, a key before its value.
+fn unmark(
+    node: &MarkedYaml,
+    anchors: &BTreeMap<usize, Anchor>,
+    written: &mut BTreeSet<usize>,
+    anchored: &mut Vec<(Option<usize>, Option<Tag>)>,
+) -> Yaml {
+    if let Some(anchor_span) = node.anchor_span {
+        // The anchor may have been redefined: refer to its latest definition.
+        let alias = written
+            .iter()
+            .rev()
+            .find(|id| anchors[id].span == anchor_span);
+        if let Some(&id) = alias {
+            anchored.push((None, None));
+            return Yaml::Alias(id);
+        }
+    }
+    let id = anchors
+        .iter()
+        .find(|(id, anchor)| {
+            anchor.span.start == node.span.start
+                && !written.contains(id)
+                && node.anchor_span.is_none()
+                && match &node.data {
+                    // The anchor of a block collection starts where its first item or key does.
+                    YamlData::Array(array) => {
+                        array.first().map_or(true, |item| item.span != anchor.span)
+                    }
+                    YamlData::Hash(hash) => hash
+                        .keys()
+                        .next()
+                        .map_or(true, |key| key.span != anchor.span),
+                    _ => anchor.span == node.span,
+                }
         })
-        || [
-            // http://yaml.org/type/bool.html
-            // Note: 'y', 'Y', 'n', 'N', is not quoted deliberately, as in libyaml. PyYAML also parse
-            // them as string, not booleans, although it is violating the YAML 1.1 specification.
-            // See https://github.com/dtolnay/serde-yaml/pull/83#discussion_r152628088.
-            "yes", "Yes", "YES", "no", "No", "NO", "True", "TRUE", "true", "False", "FALSE",
-            "false", "on", "On", "ON", "off", "Off", "OFF",
-            // http://yaml.org/type/null.html
-            "null", "Null", "NULL", "~",
-        ]
-        .contains(&string)
-        || string.starts_with('.')
-        || string.starts_with("0x")
-        || string.parse::<i64>().is_ok()
-        || string.parse::<f64>().is_ok()
+        .map(|(&id, _)| id);
+    if let Some(id) = id {
+        written.insert(id);
+    }
+    let tag = node
+        .representation
+        .as_ref()
+        .and_then(|repr| repr.tag.clone());
+    anchored.push((id, tag));
+    match &node.data {
+        YamlData::Array(array) => Yaml::Array(
+            array
+                .iter()
+                .map(|item| unmark(item, anchors, written, anchored))
+                .collect(),
+        ),
+        YamlData::Hash(hash) => Yaml::Hash(
+            hash.iter()
+                .map(|(key, value)| {
+                    let key = unmark(key, anchors, written, anchored);
+                    (key, unmark(value, anchors, written, anchored))
+                })
+                .collect(),
+        ),
+        data => data.clone().into(),
+    }
+}
+
+/// Write the scalar `node` on a single line. Nothing is written for collections and aliases.
+fn emit_scalar(wr: &mut dyn fmt::Write, node: &Yaml) -> fmt::Result {
+    match node {
+        Yaml::String(v) => {
+            if util::need_quotes(v) {
+                escape_str(wr, v)
+            } else {
+                wr.write_str(v)
+            }
+        }
+        Yaml::Boolean(v) => wr.write_str(if *v { "true" } else { "false" }),
+        Yaml::Integer(v) => write!(wr, "{v}"),
+        Yaml::Real(v) => match parse_core_schema_fp(v) {
+            Some(f) if f.is_nan() => wr.write_str(".nan"),
+            Some(f) if f.is_infinite() => wr.write_str(if f > 0.0 { ".inf" } else { "-.inf" }),
+            _ => wr.write_str(v),
+        },
+        Yaml::Null | Yaml::BadValue => wr.write_str("~"),
+        Yaml::Array(_) | Yaml::Hash(_) | Yaml::Alias(_) => Ok(()),
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,114 @@
+//! The quoting decisions of [`YamlEmitter`].
+//!
+//! These are the functions [`YamlEmitter`] uses to decide how to write strings. They are exposed
+//! so that custom emitters and templating tools can make exactly the same decisions.
+//!
+//! [`YamlEmitter`]: crate::YamlEmitter
+
+/// Return whether `string` must be quoted to be emitted as a string scalar.
+///
+/// A string which does not need quotes is emitted as a plain scalar and loads back as the same
+/// string. [`YamlEmitter`] quotes a string (with double quotes) if:
+///   - it is empty, or starts or ends with a space;
+///   - it starts with any of `&`, `*`, `?`, `|`, `-`, `<`, `>`, `=`, `!`, `%`, `@` or `.`, or with
+///     `0x`;
+///   - it contains any of `:`, `{`, `}`, `[`, `]`, `,`, `#`, `` ` ``, `"`, `'`, `\`, a tab, a line
+///     break or another control character;
+///   - it would be loaded as a boolean or null in YAML 1.1 (`yes`, `No`, `ON`, `off`, `true`,
+///     `Null`, `~`, ...) or as a number (`12`, `-3`, `2.6`, `12e7`, `inf`, ...).
+///
+/// `y`, `Y`, `n` and `N` are not quoted, as in libyaml.
+///
+/// ```
+/// use saphyr::emitter::util::need_quotes;
+///
+/// assert!(!need_quotes("foo bar"));
+/// assert!(need_quotes("true"));
+/// assert!(need_quotes("12"));
+/// assert!(need_quotes("a: b"));
+/// assert!(need_quotes(" padded"));
+/// assert!(need_quotes(""));
+/// ```
+///
+/// [`YamlEmitter`]: crate::YamlEmitter
+#[must_use]
+pub fn need_quotes(string: &str) -> bool {
+    fn need_quotes_spaces(string: &str) -> bool {
+        string.starts_with(' ') || string.ends_with(' ')
+    }
+
+    string.is_empty()
+        || need_quotes_spaces(string)
+        || string.starts_with(|character: char| {
+            matches!(
+                character,
+                '&' | '*' | '?' | '|' | '-' | '<' | '>' | '=' | '!' | '%' | '@'
+            )
+        })
+        || string.contains(|character: char| {
+            matches!(character, ':'
+            | '{'
+            | '}'
+            | '['
+            | ']'
+            | ','
+            | '#'
+            | '`'
+            | '\"'
+            | '\''
+            | '\\'
+            | '\0'..='\x06'
+            | '\t'
+            | '\n'
+            | '\r'
+            | '\x0e'..='\x1a'
+            | '\x1c'..='\x1f')
+        })
+        || [
+            // http://yaml.org/type/bool.html
+            // Note: 'y', 'Y', 'n', 'N', is not quoted deliberately, as in libyaml. PyYAML also parse
+            // them as string, not booleans, although it is violating the YAML 1.1 specification.
+            // See https://github.com/dtolnay/serde-yaml/pull/83#discussion_r152628088.
+            "yes", "Yes", "YES", "no", "No", "NO", "True", "TRUE", "true", "False", "FALSE",
+            "false", "on", "On", "ON", "off", "Off", "OFF",
+            // http://yaml.org/type/null.html
+            "null", "Null", "NULL", "~",
+        ]
+        .contains(&string)
+        || string.starts_with('.')
+        || string.starts_with("0x")
+        || string.parse::<i64>().is_ok()
+        || string.parse::<f64>().is_ok()
+}
+
+/// Return whether `string` can be emitted as a literal block scalar (`|`).
+///
+/// This is the case if all of its characters can appear in a literal block scalar, that is tabs,
+/// line feeds and printable characters:
+/// ```no_compile
+///     #x9 | #xA | [#x20-#x7E]                /* 8 bit */
+///   | #x85 | [#xA0-#xD7FF] | [#xE000-#xFFFD] /* 16 bit */
+///   | [#x10000-#x10FFFF]                     /* 32 bit */
+/// ```
+///
+/// With [`YamlEmitter::multiline_strings`] enabled, [`YamlEmitter`] emits strings which contain a
+/// line feed and satisfy this function as literal block scalars, and quotes the others.
+///
+/// ```
+/// use saphyr::emitter::util::is_valid_literal_block_scalar;
+///
+/// assert!(is_valid_literal_block_scalar("foo\n\tbar\n"));
+/// assert!(!is_valid_literal_block_scalar("foo\r\nbar"));
+/// assert!(!is_valid_literal_block_scalar("bell\x07"));
+/// ```
+///
+/// [`YamlEmitter`]: crate::YamlEmitter
+/// [`YamlEmitter::multiline_strings`]: crate::YamlEmitter::multiline_strings
+#[inline]
+#[must_use]
+pub fn is_valid_literal_block_scalar(string: &str) -> bool {
+    string.chars().all(|character: char| {
+        matches!(character, '\t' | '\n' | '\x20'..='\x7e' | '\u{0085}' | '\u{00a0}'..='\u{d7ff}'
+            | '\u{e000}'..='\u{fffd}' | '\u{10000}'..='\u{10ffff}')
+    })
+}
@@ -34,6 +34,7 @@ pub type YAMLDecodingTrapFn = fn(
 ) -> ControlFlow<Cow<'static, str>>;
 
 /// The behavior [`YamlDecoder`] must have when an decoding error occurs.
+#[allow(unknown_lints, unpredictable_function_pointer_comparisons)]
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum YAMLDecodingTrap {
     /// Ignore the offending bytes, remove them from the output.
@@ -89,6 +90,12 @@ impl<T: std::io::Read> YamlDecoder<T> {
     /// # Errors
     /// Returns `LoadError` when decoding fails.
     pub fn decode(&mut self) -> Result<Vec<Yaml>, LoadError> {
+        let output = self.decode_to_string()?;
+        Yaml::load_from_str(&output).map_err(LoadError::Scan)
+    }
+
+    /// Read the whole source and decode it into a string, detecting its encoding.
+    pub(crate) fn decode_to_string(&mut self) -> Result<String, LoadError> {
         let mut buffer = Vec::new();
         self.source.read_to_end(&mut buffer)?;
 
@@ -102,7 +109,7 @@ impl<T: std::io::Read> YamlDecoder<T> {
         // Decode the input buffer.
         decode_loop(&buffer, &mut output, &mut decoder, self.trap)?;
 
-        Yaml::load_from_str(&output).map_err(LoadError::Scan)
+        Ok(output)
     }
 }
 
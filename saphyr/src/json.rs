@@ -0,0 +1,313 @@
+//! Conversion of YAML trees to JSON.
+
+use std::fmt::{self, Write};
+
+use hashlink::LinkedHashMap;
+use saphyr_parser::Span;
+
+use crate::{loader::parse_core_schema_fp, MarkedYaml, Yaml, YamlData, YamlPath};
+
+/// A node which cannot be converted to JSON as is.
+///
+/// See [`Yaml::to_json_lossy`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonIncompatibility {
+    /// The location of the node in the converted document (e.g. `a[0]`).
+    ///
+    /// For keys, this is the path to the entry the key belongs to or, for collection keys, which
+    /// have no entry in the converted document, to the hash. [`YamlPath::to_json_pointer`]
+    /// returns it as a JSON pointer into the converted document (e.g. `/a/0`).
+    pub path: YamlPath,
+    /// The span of the node, if it was converted from a [`MarkedYaml`].
+    pub span: Option<Span>,
+    /// Why the node cannot be converted.
+    pub kind: JsonIncompatibilityKind,
+}
+
+/// The reason why a node cannot be converted to JSON.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JsonIncompatibilityKind {
+    /// A hash key is a scalar which is not a string. It is converted to its YAML representation
+    /// (e.g. `1` or `true`).
+    NonStringKey,
+    /// A hash key is a collection. The entry is left out.
+    CollectionKey,
+    /// A real is infinite or NaN. It is converted to `null`.
+    NonFiniteReal,
+    /// The node is an alias or a `BadValue`. It is converted to `null`, or to `"null"` if it is a
+    /// hash key.
+    InvalidValue,
+}
+
+impl fmt::Display for JsonIncompatibilityKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::NonStringKey => "hash key is not a string",
+            Self::CollectionKey => "hash key is a collection",
+            Self::NonFiniteReal => "real is not finite",
+            Self::InvalidValue => "value is invalid",
+        })
+    }
+}
+
+impl Yaml {
+    /// Return whether `self` can be converted to JSON without loss.
+    ///
+    /// This is the case if all hash keys are strings, all reals are finite and there are no
+    /// aliases nor `BadValue`s.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let docs = Yaml::load_from_str("[{a: 1.5}, {1: .inf}]").unwrap();
+    /// assert!(docs[0][0].is_json_compatible());
+    /// assert!(!docs[0][1].is_json_compatible());
+    /// ```
+    #[must_use]
+    pub fn is_json_compatible(&self) -> bool {
+        is_json_compatible(self)
+    }
+
+    /// Convert `self` to compact JSON, along with the nodes which could not be converted as is.
+    ///
+    /// Nodes which have no JSON equivalent are replaced (see [`JsonIncompatibilityKind`]) and
+    /// reported. If no incompatibility is reported, the conversion is lossless.
+    ///
+    /// ```
+    /// # use saphyr::{JsonIncompatibilityKind, Yaml};
+    /// let doc = &Yaml::load_from_str("a: [1, .nan]\n2: x").unwrap()[0];
+    /// let (json, incompatibilities) = doc.to_json_lossy();
+    /// assert_eq!(json, r#"{"a":[1,null],"2":"x"}"#);
+    /// assert_eq!(incompatibilities[0].path.to_string(), "a[1]");
+    /// assert_eq!(incompatibilities[0].kind, JsonIncompatibilityKind::NonFiniteReal);
+    /// assert_eq!(incompatibilities[1].path.to_json_pointer(), "/2");
+    /// assert_eq!(incompatibilities[1].kind, JsonIncompatibilityKind::NonStringKey);
+    /// ```
+    #[must_use]
+    pub fn to_json_lossy(&self) -> (String, Vec<JsonIncompatibility>) {
+        to_json_lossy(self)
+    }
+}
+
+impl MarkedYaml {
+    /// Return whether `self` can be converted to JSON without loss.
+    ///
+    /// See [`Yaml::is_json_compatible`].
+    #[must_use]
+    pub fn is_json_compatible(&self) -> bool {
+        is_json_compatible(self)
+    }
+
+    /// Convert `self` to compact JSON, along with the nodes which could not be converted as is.
+    ///
+    /// See [`Yaml::to_json_lossy`]. The incompatibilities reported have the span of the nodes.
+    #[must_use]
+    pub fn to_json_lossy(&self) -> (String, Vec<JsonIncompatibility>) {
+        to_json_lossy(self)
+    }
+}
+
+/// A view over a node, common to [`Yaml`] and [`MarkedYaml`].
+enum JsonView<'a, Node> {
+    /// A null value.
+    Null,
+    /// A boolean.
+    Boolean(bool),
+    /// An integer.
+    Integer(i64),
+    /// A real, as written in YAML.
+    Real(&'a str),
+    /// A string.
+    String(&'a str),
+    /// An array.
+    Array(&'a [Node]),
+    /// A hash.
+    Hash(&'a LinkedHashMap<Node, Node>),
+    /// An alias or a `BadValue`.
+    Invalid,
+}
+
+/// A node which can be converted to JSON.
+trait JsonNode: Sized {
+    /// Return a view over the contents of the node.
+    fn view(&self) -> JsonView<'_, Self>;
+
+    /// Return the span of the node, if it has one.
+    fn span(&self) -> Option<Span>;
+}
+
+impl JsonNode for Yaml {
+    fn view(&self) -> JsonView<'_, Self> {
+        match self {
+            Yaml::Null => JsonView::Null,
+            Yaml::Boolean(b) => JsonView::Boolean(*b),
+            Yaml::Integer(i) => JsonView::Integer(*i),
+            Yaml::Real(s) => JsonView::Real(s),
+            Yaml::String(s) => JsonView::String(s),
+            Yaml::Array(array) => JsonView::Array(array),
+            Yaml::Hash(hash) => JsonView::Hash(hash),
+            Yaml::Alias(_) | Yaml::BadValue => JsonView::Invalid,
+        }
+    }
+
+    fn span(&self) -> Option<Span> {
+        None
+    }
+}
+
+impl JsonNode for MarkedYaml {
+    fn view(&self) -> JsonView<'_, Self> {
+        match &self.data {
+            YamlData::Null => JsonView::Null,
+            YamlData::Boolean(b) => JsonView::Boolean(*b),
+            YamlData::Integer(i) => JsonView::Integer(*i),
+            YamlData::Real(s) => JsonView::Real(s),
+            YamlData::String(s) => JsonView::String(s),
+            YamlData::Array(array) => JsonView::Array(array),
+            YamlData::Hash(hash) => JsonView::Hash(hash),
+            YamlData::Alias(_) | YamlData::BadValue => JsonView::Invalid,
+        }
+    }
+
+    fn span(&self) -> Option<Span> {
+        Some(self.span)
+    }
+}
+
+/// See [`Yaml::is_json_compatible`].
+fn is_json_compatible<Node: JsonNode>(node: &Node) -> bool {
+    match node.view() {
+        JsonView::Null | JsonView::Boolean(_) | JsonView::Integer(_) | JsonView::String(_) => true,
+        JsonView::Real(s) => parse_core_schema_fp(s).is_some_and(f64::is_finite),
+        JsonView::Array(array) => array.iter().all(is_json_compatible),
+        JsonView::Hash(hash) => hash.iter().all(|(key, value)| {
+            matches!(key.view(), JsonView::String(_)) && is_json_compatible(value)
+        }),
+        JsonView::Invalid => false,
+    }
+}
+
+/// See [`Yaml::to_json_lossy`].
+fn to_json_lossy<Node: JsonNode>(node: &Node) -> (String, Vec<JsonIncompatibility>) {
+    let mut converter = Converter {
+        out: String::new(),
+        path: YamlPath::new(),
+        incompatibilities: vec![],
+    };
+    converter.write(node);
+    (converter.out, converter.incompatibilities)
+}
+
+/// The state of an ongoing conversion to JSON.
+struct Converter {
+    /// The JSON output.
+    out: String,
+    /// The path to the node being converted.
+    path: YamlPath,
+    /// The incompatibilities found so far.
+    incompatibilities: Vec<JsonIncompatibility>,
+}
+
+impl Converter {
+    /// Write `node` as JSON.
+    fn write<Node: JsonNode>(&mut self, node: &Node) {
+        match node.view() {
+            JsonView::Null => self.out.push_str("null"),
+            JsonView::Boolean(b) => write!(self.out, "{b}").unwrap(),
+            JsonView::Integer(i) => write!(self.out, "{i}").unwrap(),
+            JsonView::Real(s) => match parse_core_schema_fp(s) {
+                Some(f) if f.is_finite() => write!(self.out, "{f:?}").unwrap(),
+                _ => {
+                    self.report(node, JsonIncompatibilityKind::NonFiniteReal);
+                    self.out.push_str("null");
+                }
+            },
+            JsonView::String(s) => self.write_str(s),
+            JsonView::Array(array) => {
+                self.out.push('[');
+                for (i, item) in array.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push(',');
+                    }
+                    self.path.push_index(i);
+                    self.write(item);
+                    self.path.pop();
+                }
+                self.out.push(']');
+            }
+            JsonView::Hash(hash) => {
+                self.out.push('{');
+                let mut first = true;
+                for (key, value) in hash {
+                    let Some(key) = self.key_to_string(key) else {
+                        continue;
+                    };
+                    if !first {
+                        self.out.push(',');
+                    }
+                    first = false;
+                    self.write_str(&key);
+                    self.out.push(':');
+                    self.path.push_key(key);
+                    self.write(value);
+                    self.path.pop();
+                }
+                self.out.push('}');
+            }
+            JsonView::Invalid => {
+                self.report(node, JsonIncompatibilityKind::InvalidValue);
+                self.out.push_str("null");
+            }
+        }
+    }
+
+    /// Convert a hash key to the string used as a JSON object key.
+    ///
+    /// Return `None` if the key is a collection, in which case the entry must be left out.
+    fn key_to_string<Node: JsonNode>(&mut self, key: &Node) -> Option<String> {
+        let (string, kind) = match key.view() {
+            JsonView::String(s) => return Some(s.to_owned()),
+            JsonView::Null => ("null".to_owned(), JsonIncompatibilityKind::NonStringKey),
+            JsonView::Boolean(b) => (b.to_string(), JsonIncompatibilityKind::NonStringKey),
+            JsonView::Integer(i) => (i.to_string(), JsonIncompatibilityKind::NonStringKey),
+            JsonView::Real(s) => (s.to_owned(), JsonIncompatibilityKind::NonStringKey),
+            JsonView::Invalid => ("null".to_owned(), JsonIncompatibilityKind::InvalidValue),
+            JsonView::Array(_) | JsonView::Hash(_) => {
+                (String::new(), JsonIncompatibilityKind::CollectionKey)
+            }
+        };
+        if kind == JsonIncompatibilityKind::CollectionKey {
+            self.report(key, kind);
+            return None;
+        }
+        self.path.push_key(string.clone());
+        self.report(key, kind);
+        self.path.pop();
+        Some(string)
+    }
+
+    /// Write `s` as a quoted and escaped JSON string.
+    fn write_str(&mut self, s: &str) {
+        self.out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.out.push_str("\\\""),
+                '\\' => self.out.push_str("\\\\"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\t' => self.out.push_str("\\t"),
+                c if c < ' ' => write!(self.out, "\\u{:04x}", c as u32).unwrap(),
+                c => self.out.push(c),
+            }
+        }
+        self.out.push('"');
+    }
+
+    /// Record that `node`, at the current path, cannot be converted as is.
+    fn report<Node: JsonNode>(&mut self, node: &Node, kind: JsonIncompatibilityKind) {
+        self.incompatibilities.push(JsonIncompatibility {
+            path: self.path.clone(),
+            span: node.span(),
+            kind,
+        });
+    }
+}
@@ -32,6 +32,26 @@
 //! emitter.dump(doc).unwrap(); // dump the YAML object to a String
 //! ```
 //!
+//! # Thread safety
+//! Loaded documents ([`Yaml`], [`MarkedYaml`], [`SharedYaml`], [`YamlDocument`]) and errors
+//! ([`ScanError`], [`LoadError`]) are `Send` and `Sync`. They can be moved to other threads or
+//! shared across them (e.g. in an [`Arc`](std::sync::Arc)) once loaded. [`YamlLoader`] itself is
+//! neither, since the hooks it holds need not be.
+//!
+//! # Converting between trees
+//! [`Yaml`], [`MarkedYaml`] and [`SharedYaml`] trees convert to and from [`Yaml`] with [`From`],
+//! and so to one another through [`Yaml`]. Converting to [`Yaml`] drops the spans of a
+//! [`MarkedYaml`], and converting from it gives [`MarkedYaml`] nodes default spans. The
+//! [`YamlData`] of an annotated node converts to [`Yaml`] as well.
+//!
+//! ```
+//! use saphyr::{MarkedYaml, SharedYaml, Yaml};
+//!
+//! let marked = MarkedYaml::load_from_str("a: [1, 2]").unwrap().remove(0);
+//! let shared = SharedYaml::from(Yaml::from(marked));
+//! assert_eq!(Yaml::from(shared), Yaml::load_from_str("{a: [1, 2]}").unwrap()[0]);
+//! ```
+//!
 //! # Features
 //! **Note:** With all features disabled, this crate's MSRV is `1.65.0`.
 //!
@@ -39,6 +59,42 @@
 //! Enables encoding-aware decoding of Yaml documents.
 //!
 //! The MSRV for this feature is `1.70.0`.
+//!
+//! #### `tracing`
+//! Emits diagnostics through the [`tracing`](https://docs.rs/tracing) crate. This enables the
+//! `tracing` feature of `saphyr-parser` and additionally reports the decisions of the loader:
+//! anchors being registered and scalars being resolved are emitted as `TRACE` events, while
+//! values which end up as [`Yaml::BadValue`] (unknown aliases, scalars not matching their tag) are
+//! emitted as `DEBUG` events.
+//!
+//! #### `diagnostics`
+//! Implements [`miette::Diagnostic`](https://docs.rs/miette/latest/miette/trait.Diagnostic.html)
+//! for [`ScanError`] and [`LoadError`], and, as warnings, for [`LoaderLint`]. This enables the
+//! `diagnostics` feature of `saphyr-parser`, whose documentation details how errors are labelled.
+//! Errors returned by the functions loading a `&str` are already located in it (see
+//! [`ScanError::with_source`]).
+//!
+//! The MSRV for this feature is `1.70.0`.
+//!
+//! #### `include`
+//! Allows [`YamlLoader`] to replace scalars tagged `!include` with the contents of other files.
+//! Files are fetched by a user-provided handler; see [`YamlLoader::include_handler`].
+//!
+//! #### `serde`
+//! Implements [`serde::Deserializer`](https://docs.rs/serde/latest/serde/trait.Deserializer.html)
+//! for `&Yaml`, allowing typed values to be deserialized from a loaded document or any of its
+//! subtrees. See `Yaml::deserialize_into`, and `Yaml::deserialize_with_limits` to bound the work
+//! spent on untrusted documents. Conversely, `to_node` serializes a typed value into a [`Yaml`]
+//! node which can be inserted into a tree, and `to_string` emits it as a document. `from_str`
+//! deserializes a typed value from text, and with the `encoding` feature, `from_reader` from a byte
+//! stream in any of the encodings `YamlDecoder` detects. `ReadOptions` configures both. `iter_seq`
+//! deserializes the items of a large sequence one at a time. `load_config` merges layers of
+//! configuration, such as defaults and user settings, before deserializing the result.
+//!
+//! #### `testing`
+//! Adds the `testing` module and the `assert_yaml_eq!` macro, which compares two trees (or YAML
+//! strings) semantically and reports the paths at which they differ. Meant to be enabled in the
+//! `dev-dependencies` of crates consuming `saphyr`.
 
 #![warn(missing_docs, clippy::pedantic)]
 
@@ -46,18 +102,49 @@
 mod macros;
 
 mod annotated;
-mod char_traits;
-mod emitter;
+mod document;
+pub mod emitter;
+mod json;
 mod loader;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod validate;
+mod visit;
 mod yaml;
 
 // Re-export main components.
 pub use crate::annotated::{
-    marked_yaml::MarkedYaml, AnnotatedArray, AnnotatedHash, AnnotatedYamlIter, YamlData,
+    marked_yaml::{BreadthFirstIter, DepthFirstIter, MarkedYaml, NodeOrigin, Provenance},
+    shared_yaml::SharedYaml,
+    AnnotatedArray, AnnotatedHash, AnnotatedYamlIter, YamlData,
 };
+pub use crate::document::YamlDocument;
 pub use crate::emitter::YamlEmitter;
-pub use crate::loader::{LoadableYamlNode, YamlLoader};
-pub use crate::yaml::{Array, Hash, Yaml, YamlIter};
+pub use crate::json::{JsonIncompatibility, JsonIncompatibilityKind};
+pub use crate::loader::{
+    parse_core_schema_bool, parse_core_schema_fp, parse_core_schema_int, parse_core_schema_null,
+    parse_yaml_1_1_bool, Anchor, DuplicateKey, LoadError, LoadableYamlNode, LoaderLint,
+    LoaderLintKind, NanKeyPolicy, ScalarRepresentation, YamlLoader,
+};
+pub use crate::validate::{Schema, Violation};
+pub use crate::visit::{
+    PathPattern, PathSegment, Visit, Visitor, VisitorMut, YamlPath, YamlPathSegment,
+};
+pub use crate::yaml::{Array, Hash, TryFromYamlError, Yaml, YamlIter};
+
+#[cfg(feature = "serde")]
+mod de;
+#[cfg(feature = "serde")]
+mod ser;
+#[cfg(all(feature = "serde", feature = "encoding"))]
+pub use crate::de::from_reader;
+#[cfg(feature = "serde")]
+pub use crate::de::{
+    from_str, iter_seq, load_config, ConfigError, DeserializeError, DeserializeErrorKind,
+    DeserializeLimits, ReadError, ReadOptions, SeqIter,
+};
+#[cfg(feature = "serde")]
+pub use crate::ser::{to_node, to_string, to_string_multiline, SerializeError, WriteOptions};
 
 #[cfg(feature = "encoding")]
 mod encoding;
@@ -66,6 +153,23 @@ pub use crate::encoding::{YAMLDecodingTrap, YAMLDecodingTrapFn, YamlDecoder};
 
 // Re-export `ScanError` as it is used as part of our public API and we want consumers to be able
 // to inspect it (e.g. perform a `match`). They wouldn't be able without it.
-pub use saphyr_parser::ScanError;
+pub use saphyr_parser::{ScanError, ScanErrorKind};
 // Re-export [`Marker`] which is used for annotated YAMLs.
 pub use saphyr_parser::Marker;
+// Re-export `LinkedHashMap`, which is part of the `LoadableYamlNode` trait, so that implementors
+// don't need to depend on the exact same version of `hashlink`.
+pub use hashlink::LinkedHashMap;
+
+// As in `saphyr-parser`, for the trees and errors of this crate. `YamlLoader` is not `Send`, as its
+// hooks need not be.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Yaml>();
+    assert_send_sync::<MarkedYaml>();
+    assert_send_sync::<SharedYaml>();
+    assert_send_sync::<YamlData<MarkedYaml>>();
+    assert_send_sync::<YamlDocument<MarkedYaml>>();
+    assert_send_sync::<LoadError>();
+    assert_send_sync::<Schema>();
+    assert_send_sync::<Violation>();
+};
@@ -1,9 +1,16 @@
 //! The default loader.
 
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
 
-use hashlink::LinkedHashMap;
-use saphyr_parser::{Event, ScanError, Span, SpannedEventReceiver, TScalarStyle, Tag};
+use hashlink::{linked_hash_map::RawEntryMut, LinkedHashMap};
+#[cfg(feature = "include")]
+use saphyr_parser::Parser;
+use saphyr_parser::{
+    Event, Marker, ScanError, ScanErrorKind, Span, SpannedEventReceiver, TScalarStyle, Tag,
+};
 
 use crate::{Hash, Yaml};
 
@@ -14,6 +21,19 @@ use crate::{Hash, Yaml};
 ///
 /// Each node must implement [`LoadableYamlNode`]. The methods are required for the loader to
 /// manipulate and populate the `Node`.
+///
+/// The loader is a [`SpannedEventReceiver`] and is driven by [`Parser::load`]:
+///
+/// ```
+/// # use saphyr::{MarkedYaml, YamlLoader};
+/// # use saphyr_parser::Parser;
+/// let mut loader = YamlLoader::<MarkedYaml>::default();
+/// Parser::new_from_str("a: b").load(&mut loader, true).unwrap();
+/// let docs = loader.into_documents();
+/// assert_eq!(docs[0].data["a"].data.as_str(), Some("b"));
+/// ```
+///
+/// [`Parser::load`]: saphyr_parser::Parser::load
 #[allow(clippy::module_name_repetitions)]
 pub struct YamlLoader<Node>
 where
@@ -22,10 +42,268 @@ where
     /// The different YAML documents that are loaded.
     docs: Vec<Node>,
     // states
-    // (current node, anchor_id) tuple
-    doc_stack: Vec<(Node, usize)>,
-    key_stack: Vec<Node>,
+    // (current node, anchor_id, start of the node) tuple
+    doc_stack: Vec<(Node, usize, Marker)>,
+    // (key, span of the key) tuple
+    key_stack: Vec<(Node, Span)>,
+    /// The end of the last scalar, alias or closing flow indicator that was loaded.
+    ///
+    /// This is where the last node that was loaded ends. Unlike the span of their closing event,
+    /// this leaves out what follows the last item of a block collection.
+    content_end: Marker,
     anchor_map: BTreeMap<usize, Node>,
+    /// The span of the event of each anchored node, by anchor ID.
+    anchor_spans: BTreeMap<usize, Span>,
+    /// The span of the `&name` of each anchor, by anchor ID.
+    anchor_tokens: BTreeMap<usize, Span>,
+    /// The IDs of the anchors which aliases referred to.
+    used_anchors: BTreeSet<usize>,
+    /// The lowest anchor ID which was not checked for use yet.
+    unchecked_anchors: usize,
+    /// The lints about anchors and aliases found so far.
+    lints: Vec<LoaderLint>,
+    /// The options set with the builder methods.
+    options: LoaderOptions,
+    /// The anchors encountered, if `record_anchors` is set.
+    anchors: BTreeMap<usize, Anchor>,
+    /// The hash entries that were superseded, if `record_duplicate_keys` is set.
+    duplicate_keys: Vec<DuplicateKey<Node>>,
+    /// The number of documents past which documents are not loaded.
+    max_documents: Option<usize>,
+    /// Whether a document past `max_documents` was found.
+    documents_exceeded: bool,
+    /// The number of nodes past which aliases are not expanded.
+    max_alias_nodes: Option<usize>,
+    /// The number of nodes loaded through aliases so far, if `max_alias_nodes` is set.
+    alias_nodes: usize,
+    /// The number of nodes of each anchored node, by anchor ID, if `max_alias_nodes` is set.
+    anchor_sizes: BTreeMap<usize, usize>,
+    /// The number of nodes loaded so far in each collection being loaded, the collection included,
+    /// if `max_alias_nodes` is set.
+    collection_sizes: Vec<usize>,
+    /// A hook called on each hash entry before it is inserted.
+    hash_entry_hook: Option<HashEntryHook<Node>>,
+    /// A hook called on the contents of each scalar before it is resolved.
+    scalar_hook: Option<ScalarHook>,
+    /// The errors returned by `scalar_hook` and `include_handler`.
+    hook_errors: Vec<ScanError>,
+    /// The handler fetching the contents of `!include`d files.
+    #[cfg(feature = "include")]
+    include_handler: Option<IncludeHandler>,
+    /// The paths of the files being included, outermost first.
+    #[cfg(feature = "include")]
+    include_chain: Vec<String>,
+}
+
+/// The options of a [`YamlLoader`], which included files are loaded with as well.
+#[derive(Clone, Copy, Debug, Default)]
+struct LoaderOptions {
+    /// Whether to fill the anchors of the loader.
+    record_anchors: bool,
+    /// Whether to fill the duplicate keys of the loader.
+    record_duplicate_keys: bool,
+    /// Whether to give scalar nodes their [`ScalarRepresentation`].
+    keep_representations: bool,
+    /// The formats plain scalars are resolved in.
+    formats: ScalarFormats,
+    /// How to load hash keys which are NaN.
+    nan_keys: NanKeyPolicy,
+}
+
+/// The formats plain scalars are resolved in, in addition to those of the core schema.
+#[derive(Clone, Copy, Debug, Default)]
+struct ScalarFormats {
+    /// Whether to resolve numbers in the YAML 1.1 formats.
+    yaml_1_1_numbers: bool,
+    /// Whether to resolve booleans in the YAML 1.1 formats.
+    yaml_1_1_booleans: bool,
+}
+
+/// How a [`YamlLoader`] loads hash keys which are NaN.
+///
+/// Reals are stored as they are written (see [`Yaml::Real`]) and keys are compared as such: `1.0`
+/// and `1.00` are different keys, and so are `.nan` and `.NAN`, while two `.nan` keys are the same
+/// key, even though NaN is not equal to itself as a float. This policy makes NaN keys behave
+/// predictably regardless of how they are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NanKeyPolicy {
+    /// NaN keys are compared as written, like other reals.
+    #[default]
+    AsWritten,
+    /// All NaN keys are the same key. They are loaded as `.nan`.
+    Equal,
+    /// NaN keys are errors, located at the key, which can be retrieved after loading with
+    /// [`YamlLoader::hook_errors`]. The keys are loaded as written.
+    Error,
+}
+
+/// See [`YamlLoader::hash_entry_hook`].
+type HashEntryHook<Node> = Box<dyn FnMut(&mut Node, Span, &mut Node, Span)>;
+
+/// See [`YamlLoader::scalar_hook`].
+type ScalarHook = Box<dyn FnMut(&mut String, TScalarStyle, Span) -> Result<(), String>>;
+
+/// How deeply files may include each other. See [`YamlLoader::include_handler`].
+#[cfg(feature = "include")]
+const MAX_INCLUDE_DEPTH: usize = 32;
+
+/// See [`YamlLoader::include_handler`].
+#[cfg(feature = "include")]
+type IncludeHandler = Box<dyn FnMut(&str) -> Result<String, String>>;
+
+/// A key defined again in a hash, superseding the value of its previous entry.
+///
+/// See [`YamlLoader::record_duplicate_keys`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateKey<Node> {
+    /// The key where it is defined again.
+    pub key: Node,
+    /// The value it superseded.
+    pub value: Node,
+}
+
+/// An anchor recorded by the [`YamlLoader`].
+///
+/// See [`YamlLoader::record_anchors`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Anchor {
+    /// The name of the anchor, without the leading `&`.
+    pub name: String,
+    /// The span of the start of the anchored node.
+    ///
+    /// For scalars, this is the span of the scalar. For collections, this is the span of their
+    /// opening indicator.
+    pub span: Span,
+}
+
+/// How a scalar was written in the document, recorded by the [`YamlLoader`].
+///
+/// See [`YamlLoader::keep_representations`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScalarRepresentation {
+    /// The contents of the scalar, as read by the parser and before it was resolved.
+    ///
+    /// Escape sequences, line folding and block indentation are already processed. The exact text
+    /// of the scalar can be retrieved from the input with the span of its node.
+    pub value: String,
+    /// The style the scalar was written in.
+    pub style: TScalarStyle,
+    /// The tag of the scalar, if any.
+    pub tag: Option<Tag>,
+}
+
+/// The kind of a [`LoaderLint`].
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Hash)]
+#[non_exhaustive]
+pub enum LoaderLintKind {
+    /// An anchor is not referred to by any alias of its document, as in `a: &x 1`.
+    ///
+    /// This includes anchors which are redefined before being used.
+    UnusedAnchor,
+    /// An alias refers to a node which was not loaded when the alias was met, as in `&x [*x]`.
+    ///
+    /// The parser rejects aliases to anchors which are not defined. Those which refer to a node
+    /// that contains them are loaded as bad values.
+    UndefinedAlias,
+}
+
+/// A use of anchors or aliases which is valid YAML but likely a mistake, found by the
+/// [`YamlLoader`].
+///
+/// See [`YamlLoader::lints`]. Lints about the syntax are found by the parser (see
+/// [`saphyr_parser::Lint`]).
+#[derive(Clone, Copy, PartialEq, Debug, Eq)]
+pub struct LoaderLint {
+    /// What was found.
+    kind: LoaderLintKind,
+    /// The span of the offending anchor or alias.
+    span: Span,
+    /// The byte range of `span` in its source, if it was given one with
+    /// [`LoaderLint::with_source`].
+    byte_range: Option<(usize, usize)>,
+}
+
+impl LoaderLint {
+    /// Create a lint of the given kind, about the anchor or alias in `span`.
+    #[must_use]
+    pub fn new(kind: LoaderLintKind, span: Span) -> Self {
+        Self {
+            kind,
+            span,
+            byte_range: None,
+        }
+    }
+
+    /// Locate the lint in `source`, the input it was found in.
+    ///
+    /// This places the label of its `miette` diagnostic at the right byte offsets. See
+    /// [`ScanError::with_source`].
+    #[must_use]
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.byte_range = self
+            .span
+            .byte_range(source)
+            .map(|range| (range.start, range.end));
+        self
+    }
+
+    /// Return the kind of the lint.
+    #[must_use]
+    pub fn kind(&self) -> LoaderLintKind {
+        self.kind
+    }
+
+    /// Return the span of the offending anchor or alias.
+    #[must_use]
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Return a human-readable description of the lint.
+    #[must_use]
+    pub fn message(&self) -> &'static str {
+        match self.kind {
+            LoaderLintKind::UnusedAnchor => "anchor is never referred to by an alias",
+            LoaderLintKind::UndefinedAlias => "alias refers to a node which is not loaded yet",
+        }
+    }
+}
+
+impl std::fmt::Display for LoaderLint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at index {} line {} column {}",
+            self.message(),
+            self.span.start.index(),
+            self.span.start.line(),
+            self.span.start.col() + 1,
+        )
+    }
+}
+
+impl std::error::Error for LoaderLint {}
+
+#[cfg(feature = "diagnostics")]
+impl miette::Diagnostic for LoaderLint {
+    fn code<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new("saphyr::loader_lint"))
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(miette::Severity::Warning)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let (start, end) = self
+            .byte_range
+            .unwrap_or((self.span.start.index(), self.span.end.index()));
+        Some(Box::new(std::iter::once(miette::LabeledSpan::new(
+            None,
+            start,
+            end - start,
+        ))))
+    }
 }
 
 // For some reason, rustc wants `Node: Default` if I `#[derive(Default)]`.
@@ -38,7 +316,29 @@ where
             docs: vec![],
             doc_stack: vec![],
             key_stack: vec![],
+            content_end: Marker::default(),
             anchor_map: BTreeMap::new(),
+            anchor_spans: BTreeMap::new(),
+            anchor_tokens: BTreeMap::new(),
+            used_anchors: BTreeSet::new(),
+            unchecked_anchors: 0,
+            lints: vec![],
+            options: LoaderOptions::default(),
+            anchors: BTreeMap::new(),
+            duplicate_keys: vec![],
+            max_documents: None,
+            documents_exceeded: false,
+            max_alias_nodes: None,
+            alias_nodes: 0,
+            anchor_sizes: BTreeMap::new(),
+            collection_sizes: vec![],
+            hash_entry_hook: None,
+            scalar_hook: None,
+            hook_errors: vec![],
+            #[cfg(feature = "include")]
+            include_handler: None,
+            #[cfg(feature = "include")]
+            include_chain: vec![],
         }
     }
 }
@@ -48,11 +348,29 @@ where
     Node: LoadableYamlNode,
 {
     fn on_event(&mut self, ev: Event, span: Span) {
+        if self
+            .max_documents
+            .is_some_and(|limit| self.docs.len() >= limit)
+        {
+            if matches!(ev, Event::DocumentStart(_)) && !self.documents_exceeded {
+                self.documents_exceeded = true;
+                self.hook_errors.push(
+                    ScanError::new_with_kind(
+                        span.start,
+                        ScanErrorKind::DocumentNotAllowed,
+                        format!("found more documents than the limit of {}", self.docs.len()),
+                    )
+                    .with_span(span),
+                );
+            }
+            return;
+        }
         match ev {
             Event::DocumentStart(_) | Event::Nothing | Event::StreamStart | Event::StreamEnd => {
                 // do nothing
             }
-            Event::DocumentEnd => {
+            Event::DocumentEnd(_) => {
+                self.check_anchors();
                 match self.doc_stack.len() {
                     // empty document
                     0 => self
@@ -63,85 +381,315 @@ where
                 }
             }
             Event::SequenceStart(aid, _) => {
+                self.register_anchor_span(aid, span);
+                self.start_collection();
                 self.doc_stack.push((
                     Node::from_bare_yaml(Yaml::Array(Vec::new())).with_span(span),
                     aid,
+                    span.start,
                 ));
             }
             Event::SequenceEnd => {
-                let node = self.doc_stack.pop().unwrap();
-                self.insert_new_node(node);
+                if span.start != span.end {
+                    self.content_end = span.end;
+                }
+                let (node, aid, start) = self.doc_stack.pop().unwrap();
+                self.end_collection(aid);
+                let end = self.content_end;
+                self.insert_new_node((node.with_end_marker(end), aid), Span::new(start, end));
             }
             Event::MappingStart(aid, _) => {
+                self.register_anchor_span(aid, span);
+                self.start_collection();
                 self.doc_stack.push((
                     Node::from_bare_yaml(Yaml::Hash(Hash::new())).with_span(span),
                     aid,
+                    span.start,
                 ));
-                self.key_stack.push(Node::from_bare_yaml(Yaml::BadValue));
+                self.key_stack
+                    .push((Node::from_bare_yaml(Yaml::BadValue), Span::default()));
             }
             Event::MappingEnd => {
+                if span.start != span.end {
+                    self.content_end = span.end;
+                }
                 self.key_stack.pop().unwrap();
-                let node = self.doc_stack.pop().unwrap();
-                self.insert_new_node(node);
+                let (node, aid, start) = self.doc_stack.pop().unwrap();
+                self.end_collection(aid);
+                let end = self.content_end;
+                self.insert_new_node((node.with_end_marker(end), aid), Span::new(start, end));
             }
             Event::Scalar(v, style, aid, tag) => {
-                let node = if style != TScalarStyle::Plain {
-                    Yaml::String(v)
-                } else if let Some(Tag {
-                    ref handle,
-                    ref suffix,
-                }) = tag
-                {
-                    if handle == "tag:yaml.org,2002:" {
-                        match suffix.as_ref() {
-                            "bool" => {
-                                // "true" or "false"
-                                match v.parse::<bool>() {
-                                    Err(_) => Yaml::BadValue,
-                                    Ok(v) => Yaml::Boolean(v),
-                                }
-                            }
-                            "int" => match v.parse::<i64>() {
-                                Err(_) => Yaml::BadValue,
-                                Ok(v) => Yaml::Integer(v),
-                            },
-                            "float" => match parse_f64(&v) {
-                                Some(_) => Yaml::Real(v),
-                                None => Yaml::BadValue,
-                            },
-                            "null" => match v.as_ref() {
-                                "~" | "null" => Yaml::Null,
-                                _ => Yaml::BadValue,
-                            },
-                            _ => Yaml::String(v),
-                        }
-                    } else {
-                        Yaml::String(v)
-                    }
-                } else {
-                    // Datatype is not specified, or unrecognized
-                    Yaml::from_str(&v)
-                };
-                self.insert_new_node((Node::from_bare_yaml(node).with_span(span), aid));
+                self.content_end = span.end;
+                self.register_anchor_span(aid, span);
+                self.count_nodes(aid, 1);
+                self.on_scalar(v, style, aid, tag.as_ref(), span);
             }
             Event::Alias(id) => {
-                let n = match self.anchor_map.get(&id) {
-                    Some(v) => v.clone(),
-                    None => Node::from_bare_yaml(Yaml::BadValue),
+                self.content_end = span.end;
+                self.used_anchors.insert(id);
+                let defined =
+                    self.anchor_map.contains_key(&id) && self.anchor_spans.contains_key(&id);
+                let n = if defined && !self.expand_alias(id, span) {
+                    Node::from_bare_yaml(Yaml::BadValue)
+                } else if let (Some(v), Some(anchor_span)) =
+                    (self.anchor_map.get(&id), self.anchor_spans.get(&id))
+                {
+                    v.clone().with_anchor_span(*anchor_span)
+                } else {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(anchor_id = id, ?span, "alias to an unknown anchor");
+                    self.lints
+                        .push(LoaderLint::new(LoaderLintKind::UndefinedAlias, span));
+                    Node::from_bare_yaml(Yaml::BadValue)
                 };
-                self.insert_new_node((n.with_span(span), 0));
+                self.insert_new_node((n.with_span(span), 0), span);
             }
         }
     }
+
+    fn on_anchor(&mut self, id: usize, name: &str, span: Span) {
+        self.anchor_tokens.insert(id, span);
+        if self.options.record_anchors {
+            // The span is that of the anchored node once its event is loaded.
+            self.anchors.insert(
+                id,
+                Anchor {
+                    name: name.to_owned(),
+                    span,
+                },
+            );
+        }
+    }
 }
 
 impl<Node> YamlLoader<Node>
 where
     Node: LoadableYamlNode,
 {
-    fn insert_new_node(&mut self, node: (Node, usize)) {
+    /// Record `span` as the span of the node anchored with `aid`, if any.
+    fn register_anchor_span(&mut self, aid: usize, span: Span) {
+        // valid anchor id starts from 1
+        if aid > 0 {
+            self.anchor_spans.insert(aid, span);
+            if let Some(anchor) = self.anchors.get_mut(&aid) {
+                anchor.span = span;
+            }
+        }
+    }
+
+    /// Start counting the nodes of a collection, if [`Self::max_alias_nodes`] is set.
+    fn start_collection(&mut self) {
+        if self.max_alias_nodes.is_some() {
+            self.collection_sizes.push(1);
+        }
+    }
+
+    /// Stop counting the nodes of the collection anchored with `aid`, if any, and count them in
+    /// its parent.
+    fn end_collection(&mut self, aid: usize) {
+        if let Some(size) = self.collection_sizes.pop() {
+            self.count_nodes(aid, size);
+        }
+    }
+
+    /// Count `size` nodes in the collection being loaded, for a node anchored with `aid`, if any.
+    fn count_nodes(&mut self, aid: usize, size: usize) {
+        if self.max_alias_nodes.is_none() {
+            return;
+        }
+        if let Some(parent) = self.collection_sizes.last_mut() {
+            *parent = parent.saturating_add(size);
+        }
+        if aid > 0 {
+            self.anchor_sizes.insert(aid, size);
+        }
+    }
+
+    /// Count the nodes of the alias to the anchor `id` at `span`, and return whether it may be
+    /// expanded within [`Self::max_alias_nodes`].
+    fn expand_alias(&mut self, id: usize, span: Span) -> bool {
+        let Some(limit) = self.max_alias_nodes else {
+            return true;
+        };
+        let size = self.anchor_sizes.get(&id).copied().unwrap_or(1);
+        let exceeded = self.alias_nodes > limit;
+        self.alias_nodes = self.alias_nodes.saturating_add(size);
+        if self.alias_nodes <= limit {
+            self.count_nodes(0, size);
+            return true;
+        }
+        if !exceeded {
+            self.hook_errors.push(
+                ScanError::new_with_kind(
+                    span.start,
+                    ScanErrorKind::AliasLimitExceeded,
+                    format!("aliases expand to more than the limit of {limit} nodes"),
+                )
+                .with_span(span),
+            );
+        }
+        self.count_nodes(0, 1);
+        false
+    }
+
+    /// Record a lint for each anchor of the document which ended that no alias referred to.
+    fn check_anchors(&mut self) {
+        for (&id, &span) in self.anchor_tokens.range(self.unchecked_anchors..) {
+            if !self.used_anchors.contains(&id) {
+                self.lints
+                    .push(LoaderLint::new(LoaderLintKind::UnusedAnchor, span));
+            }
+            self.unchecked_anchors = id + 1;
+        }
+    }
+
+    /// Load a scalar and insert it into its parent.
+    fn on_scalar(
+        &mut self,
+        mut v: String,
+        style: TScalarStyle,
+        aid: usize,
+        tag: Option<&Tag>,
+        span: Span,
+    ) {
+        let representation = self
+            .options
+            .keep_representations
+            .then(|| ScalarRepresentation {
+                value: v.clone(),
+                style,
+                tag: tag.cloned(),
+            });
+        if let Some(hook) = &mut self.scalar_hook {
+            if let Err(info) = hook(&mut v, style, span) {
+                self.hook_errors.push(
+                    ScanError::new_with_kind(span.start, ScanErrorKind::Other, info)
+                        .with_span(span),
+                );
+            }
+        }
+        #[cfg(feature = "include")]
+        if self.include_handler.is_some()
+            && tag
+                .as_ref()
+                .is_some_and(|tag| tag.shorthand() == "!include")
+        {
+            let node = self.load_include(&v, span);
+            self.insert_new_node((node, aid), span);
+            return;
+        }
+        let mut node = resolve_scalar(v, style, tag, self.options.formats);
+        if self.options.nan_keys != NanKeyPolicy::AsWritten
+            && node.as_f64().is_some_and(f64::is_nan)
+            && self.expects_key()
+        {
+            match self.options.nan_keys {
+                NanKeyPolicy::AsWritten => {}
+                NanKeyPolicy::Equal => node = Yaml::Real(".nan".to_owned()),
+                NanKeyPolicy::Error => self.hook_errors.push(
+                    ScanError::new_with_kind(
+                        span.start,
+                        ScanErrorKind::Other,
+                        "NaN is not allowed as a hash key".to_owned(),
+                    )
+                    .with_span(span),
+                ),
+            }
+        }
+        #[cfg(feature = "tracing")]
+        if matches!(node, Yaml::BadValue) {
+            tracing::debug!(?tag, ?span, "scalar does not match its tag");
+        } else {
+            tracing::trace!(?node, ?style, ?span, "resolved scalar");
+        }
+        let mut node = Node::from_bare_yaml(node).with_span(span);
+        if let Some(representation) = representation {
+            node = node.with_representation(representation);
+        }
+        self.insert_new_node((node, aid), span);
+    }
+
+    /// Load the first document of the file at `path` with a child loader sharing our hooks.
+    ///
+    /// Errors are recorded in `hook_errors`, located at `span`, and yield a `BadValue`.
+    #[cfg(feature = "include")]
+    fn load_include(&mut self, path: &str, span: Span) -> Node {
+        let error = |info: String| {
+            ScanError::new_with_kind(span.start, ScanErrorKind::Other, info).with_span(span)
+        };
+        if self.include_chain.iter().any(|included| included == path) {
+            let chain = self.include_chain.join("` -> `");
+            self.hook_errors
+                .push(error(format!("include cycle: `{chain}` -> `{path}`")));
+            return Node::from_bare_yaml(Yaml::BadValue).with_span(span);
+        }
+        if self.include_chain.len() >= MAX_INCLUDE_DEPTH {
+            self.hook_errors.push(error(format!(
+                "cannot include `{path}`: includes are nested more than {MAX_INCLUDE_DEPTH} levels \
+                 deep"
+            )));
+            return Node::from_bare_yaml(Yaml::BadValue).with_span(span);
+        }
+        let handler = self.include_handler.as_mut().unwrap();
+        let contents = match handler(path) {
+            Ok(contents) => contents,
+            Err(info) => {
+                self.hook_errors
+                    .push(error(format!("cannot include `{path}`: {info}")));
+                return Node::from_bare_yaml(Yaml::BadValue).with_span(span);
+            }
+        };
+
+        let mut include_chain = self.include_chain.clone();
+        include_chain.push(path.to_owned());
+        let mut child = YamlLoader {
+            hash_entry_hook: self.hash_entry_hook.take(),
+            scalar_hook: self.scalar_hook.take(),
+            include_handler: self.include_handler.take(),
+            include_chain,
+            options: self.options,
+            ..YamlLoader::default()
+        };
+        let result = Parser::new_from_str(&contents).load(&mut child, false);
+        self.hash_entry_hook = child.hash_entry_hook.take();
+        self.scalar_hook = child.scalar_hook.take();
+        self.include_handler = child.include_handler.take();
+        self.duplicate_keys.append(&mut child.duplicate_keys);
+        // The IDs of the anchors of the included file are those of its own parser, and would
+        // collide with ours. They are given IDs counting down from `usize::MAX` instead.
+        for anchor in std::mem::take(&mut child.anchors).into_values() {
+            self.anchors.insert(usize::MAX - self.anchors.len(), anchor);
+        }
+
+        // Errors in the included file are reported at the `!include`.
+        for e in child.hook_errors.drain(..).chain(result.clone().err()) {
+            self.hook_errors
+                .push(error(format!("in included file `{path}`: {e}")));
+        }
+        if result.is_err() {
+            return Node::from_bare_yaml(Yaml::BadValue).with_span(span);
+        }
+        child
+            .docs
+            .pop()
+            .unwrap_or_else(|| Node::from_bare_yaml(Yaml::Null).with_span(span))
+    }
+
+    /// Return whether the next node to be inserted is a hash key.
+    fn expects_key(&self) -> bool {
+        self.doc_stack
+            .last()
+            .is_some_and(|parent| parent.0.is_hash())
+            && self.key_stack.last().is_some_and(|key| key.0.is_badvalue())
+    }
+
+    /// Insert a node that was fully loaded into its parent. `span` is the span of the whole node.
+    fn insert_new_node(&mut self, mut node: (Node, usize), span: Span) {
         // valid anchor id starts from 1
         if node.1 > 0 {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(anchor_id = node.1, "registered anchor");
             self.anchor_map.insert(node.1, node.0.clone());
         }
         if let Some(parent) = self.doc_stack.last_mut() {
@@ -149,21 +697,397 @@ where
             if parent_node.is_array() {
                 parent_node.array_mut().push(node.0);
             } else if parent_node.is_hash() {
-                let cur_key = self.key_stack.last_mut().unwrap();
+                let (cur_key, cur_key_span) = self.key_stack.last_mut().unwrap();
                 // current node is a key
                 if cur_key.is_badvalue() {
                     *cur_key = node.0;
+                    *cur_key_span = span;
                 // current node is a value
                 } else {
                     let hash = parent_node.hash_mut();
-                    hash.insert(cur_key.take(), node.0);
+                    let mut key = cur_key.take();
+                    if let Some(hook) = &mut self.hash_entry_hook {
+                        hook(&mut key, *cur_key_span, &mut node.0, span);
+                    }
+                    let pair_span = Span::new(cur_key_span.start, self.content_end);
+                    let key = key.with_pair_span(pair_span);
+                    node.0 = node.0.with_pair_span(pair_span);
+                    if self.options.record_duplicate_keys {
+                        match hash.raw_entry_mut().from_key(&key) {
+                            RawEntryMut::Occupied(mut entry) => {
+                                // Same as `LinkedHashMap::insert`, which keeps the first key.
+                                entry.to_back();
+                                self.duplicate_keys.push(DuplicateKey {
+                                    key,
+                                    value: entry.replace_value(node.0),
+                                });
+                            }
+                            RawEntryMut::Vacant(entry) => {
+                                entry.insert(key, node.0);
+                            }
+                        }
+                    } else {
+                        hash.insert(key, node.0);
+                    }
                 }
             }
         } else {
-            self.doc_stack.push(node);
+            self.doc_stack.push((node.0, node.1, span.start));
         }
     }
 
+    /// Set a hook called on each hash entry, before it is inserted into its hash.
+    ///
+    /// The hook is given the key and the value of the entry, along with their spans, and may
+    /// modify them. Keys and values are fully loaded when the hook is called. This allows
+    /// normalizing entries as they are loaded rather than in a second pass over the tree.
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, YamlLoader};
+    /// # use saphyr_parser::Parser;
+    /// let mut loader = YamlLoader::<Yaml>::default().hash_entry_hook(|key, _, _, _| {
+    ///     if let Yaml::String(key) = key {
+    ///         key.make_ascii_lowercase();
+    ///     }
+    /// });
+    /// Parser::new_from_str("Name: foo").load(&mut loader, true).unwrap();
+    /// assert_eq!(loader.into_documents()[0]["name"].as_str(), Some("foo"));
+    /// ```
+    #[must_use]
+    pub fn hash_entry_hook(
+        mut self,
+        hook: impl FnMut(&mut Node, Span, &mut Node, Span) + 'static,
+    ) -> Self {
+        self.hash_entry_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Set a hook called on the contents of each scalar, before it is resolved.
+    ///
+    /// The hook is given the contents of the scalar, its style and its span, and may modify the
+    /// contents. Plain scalars are resolved afterwards, so that a placeholder may be replaced by
+    /// e.g. an integer. This allows expanding placeholders (`${VAR}`, ...) as the document is
+    /// loaded.
+    ///
+    /// If the hook returns an error, the scalar is loaded with its contents as they are after the
+    /// hook returned and loading carries on. The error, located at the span of the scalar, can be
+    /// retrieved after loading with [`YamlLoader::hook_errors`].
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, YamlLoader};
+    /// # use saphyr_parser::Parser;
+    /// let mut loader = YamlLoader::<Yaml>::default().scalar_hook(|value, _, _| {
+    ///     if value == "${PORT}" {
+    ///         *value = "8080".to_owned();
+    ///     }
+    ///     Ok(())
+    /// });
+    /// Parser::new_from_str("port: ${PORT}").load(&mut loader, true).unwrap();
+    /// assert_eq!(loader.into_documents()[0]["port"].as_i64(), Some(8080));
+    /// ```
+    #[must_use]
+    pub fn scalar_hook(
+        mut self,
+        hook: impl FnMut(&mut String, TScalarStyle, Span) -> Result<(), String> + 'static,
+    ) -> Self {
+        self.scalar_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Set the handler called to fetch the contents of `!include`d files.
+    ///
+    /// Scalars tagged `!include` are replaced by the first document of the file they name. The
+    /// handler is given the contents of the scalar, after [`YamlLoader::scalar_hook`] was applied,
+    /// and returns the contents of the file. How paths are interpreted (relative to what, whether
+    /// they may be URLs, ...) is up to the handler. Included files may include other files; all
+    /// hooks of the loader apply to them as well.
+    ///
+    /// Nodes loaded from an included file have spans within that file. So do the anchors and the
+    /// duplicate keys of included files, which are recorded along with those of the input if
+    /// [`YamlLoader::record_anchors`] and [`YamlLoader::record_duplicate_keys`] are set. Anchors
+    /// of included files are given IDs counting down from `usize::MAX`, as their own IDs would
+    /// collide with those of the input.
+    ///
+    /// If the handler returns an error, if the included file fails to parse, if files include each
+    /// other in a cycle or if includes are nested more than 32 levels deep, the `!include` is
+    /// loaded as a `BadValue` and the error, located at the span of the `!include`, can be
+    /// retrieved with [`YamlLoader::hook_errors`].
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, YamlLoader};
+    /// # use saphyr_parser::Parser;
+    /// let mut loader = YamlLoader::<Yaml>::default().include_handler(|path| match path {
+    ///     "db.yaml" => Ok("host: localhost\nport: 5432".to_owned()),
+    ///     _ => Err("no such file".to_owned()),
+    /// });
+    /// Parser::new_from_str("db: !include db.yaml").load(&mut loader, true).unwrap();
+    /// assert_eq!(loader.into_documents()[0]["db"]["port"].as_i64(), Some(5432));
+    /// ```
+    ///
+    /// This requires the `include` feature.
+    #[cfg(feature = "include")]
+    #[must_use]
+    pub fn include_handler(
+        mut self,
+        handler: impl FnMut(&str) -> Result<String, String> + 'static,
+    ) -> Self {
+        self.include_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Return the errors returned by the hook set with [`YamlLoader::scalar_hook`], in order.
+    ///
+    /// With the `include` feature, this also contains errors that happened while including files
+    /// (see [`YamlLoader::include_handler`]). With [`NanKeyPolicy::Error`], this also contains an
+    /// error for each NaN hash key. With [`YamlLoader::max_documents`], this also contains an
+    /// error if there are more documents than the limit.
+    #[must_use]
+    pub fn hook_errors(&self) -> &[ScanError] {
+        &self.hook_errors
+    }
+
+    /// Set whether the loader records the anchors it encounters.
+    ///
+    /// Anchors are recorded with their ID, which is the one [`Event`]s and [`Yaml::Alias`] refer
+    /// to, and can be retrieved after loading with [`YamlLoader::anchors`]. Anchor names are only
+    /// known to the loader when it is driven by [`Parser::load`](saphyr_parser::Parser::load).
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, YamlLoader};
+    /// # use saphyr_parser::Parser;
+    /// let mut loader = YamlLoader::<Yaml>::default().record_anchors(true);
+    /// Parser::new_from_str("a: &x 1\nb: *x").load(&mut loader, true).unwrap();
+    /// assert_eq!(loader.anchors()[&1].name, "x");
+    /// ```
+    #[must_use]
+    pub fn record_anchors(mut self, value: bool) -> Self {
+        self.options.record_anchors = value;
+        self
+    }
+
+    /// Set whether the loader records the keys which are defined more than once in a hash.
+    ///
+    /// When a key appears more than once in a hash, the loaded tree keeps its first occurrence
+    /// and its last value. The tree is the same with this setting, and each later occurrence of
+    /// the key is recorded, in order, along with the value it superseded. They can be retrieved
+    /// after loading with [`YamlLoader::duplicate_keys`]. Along with the tree, this accounts for
+    /// every occurrence of each key.
+    ///
+    /// ```
+    /// # use saphyr::{MarkedYaml, YamlLoader};
+    /// # use saphyr_parser::Parser;
+    /// let mut loader = YamlLoader::<MarkedYaml>::default().record_duplicate_keys(true);
+    /// Parser::new_from_str("a: 1\nb: 2\na: 3").load(&mut loader, true).unwrap();
+    /// let duplicate = &loader.duplicate_keys()[0];
+    /// assert_eq!(duplicate.key.span.start.line(), 3);
+    /// assert_eq!(duplicate.value.data.as_i64(), Some(1));
+    /// ```
+    #[must_use]
+    pub fn record_duplicate_keys(mut self, value: bool) -> Self {
+        self.options.record_duplicate_keys = value;
+        self
+    }
+
+    /// Set whether scalar nodes are given the [`ScalarRepresentation`] they were loaded from.
+    ///
+    /// Scalars are resolved to typed values as usual, and the loader also passes their contents,
+    /// style and tag to [`LoadableYamlNode::with_representation`], before [`Self::scalar_hook`]
+    /// is applied. With [`MarkedYaml`], they are stored in [`MarkedYaml::representation`]. This
+    /// gives both typed access to the values and what is needed to write them back the way they
+    /// were written, for instance with [`YamlEmitter::scalar_hook`].
+    ///
+    /// ```
+    /// # use saphyr::{MarkedYaml, YamlLoader};
+    /// # use saphyr_parser::{Parser, TScalarStyle};
+    /// let mut loader = YamlLoader::<MarkedYaml>::default().keep_representations(true);
+    /// Parser::new_from_str("a: '0x10'\nb: 0x10").load(&mut loader, true).unwrap();
+    /// let doc = &loader.into_documents()[0];
+    /// assert_eq!(doc.data["a"].data.as_str(), Some("0x10"));
+    /// assert_eq!(doc.data["b"].data.as_i64(), Some(16));
+    /// let repr = doc.data["b"].representation.as_deref().unwrap();
+    /// assert_eq!((repr.value.as_str(), repr.style), ("0x10", TScalarStyle::Plain));
+    /// ```
+    ///
+    /// [`MarkedYaml`]: crate::MarkedYaml
+    /// [`MarkedYaml::representation`]: crate::MarkedYaml::representation
+    /// [`YamlEmitter::scalar_hook`]: crate::YamlEmitter::scalar_hook
+    #[must_use]
+    pub fn keep_representations(mut self, value: bool) -> Self {
+        self.options.keep_representations = value;
+        self
+    }
+
+    /// Set whether plain scalars may be numbers in the formats of YAML 1.1.
+    ///
+    /// In addition to the core schema formats, integers may then be written in binary (`0b1010`)
+    /// and integers and reals may contain underscores as digit separators (`1_000_000`,
+    /// `1_000.5`). Underscores are not allowed right after the sign or the `0b`, `0o` and `0x`
+    /// prefixes. This also applies to scalars tagged `!!int` and `!!float`. Reals are stored
+    /// without their underscores.
+    ///
+    /// Other YAML 1.1 formats (octal integers with a leading `0`, sexagesimal numbers) are not
+    /// supported.
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, YamlLoader};
+    /// # use saphyr_parser::Parser;
+    /// let mut loader = YamlLoader::<Yaml>::default().yaml_1_1_numbers(true);
+    /// Parser::new_from_str("[1_000_000, 0b1010_1010, 1_0.5]").load(&mut loader, true).unwrap();
+    /// let doc = &loader.into_documents()[0];
+    /// assert_eq!(doc[0].as_i64(), Some(1_000_000));
+    /// assert_eq!(doc[1].as_i64(), Some(0b1010_1010));
+    /// assert_eq!(doc[2].as_f64(), Some(10.5));
+    /// ```
+    #[must_use]
+    pub fn yaml_1_1_numbers(mut self, value: bool) -> Self {
+        self.options.formats.yaml_1_1_numbers = value;
+        self
+    }
+
+    /// Set whether plain scalars may be booleans in the formats of YAML 1.1.
+    ///
+    /// In addition to `true` and `false`, the scalars accepted by [`parse_yaml_1_1_bool`] (e.g.
+    /// `yes`, `No`, `ON`, `y`) are then loaded as booleans. This also applies to scalars tagged
+    /// `!!bool`. Quoted scalars are always strings.
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, YamlLoader};
+    /// # use saphyr_parser::Parser;
+    /// let mut loader = YamlLoader::<Yaml>::default().yaml_1_1_booleans(true);
+    /// Parser::new_from_str("[yes, Off, 'on']").load(&mut loader, true).unwrap();
+    /// let doc = &loader.into_documents()[0];
+    /// assert_eq!(doc[0].as_bool(), Some(true));
+    /// assert_eq!(doc[1].as_bool(), Some(false));
+    /// assert_eq!(doc[2].as_str(), Some("on"));
+    /// ```
+    #[must_use]
+    pub fn yaml_1_1_booleans(mut self, value: bool) -> Self {
+        self.options.formats.yaml_1_1_booleans = value;
+        self
+    }
+
+    /// Set how hash keys which are NaN (`.nan`) are loaded. See [`NanKeyPolicy`].
+    ///
+    /// ```
+    /// # use saphyr::{NanKeyPolicy, Yaml, YamlLoader};
+    /// # use saphyr_parser::Parser;
+    /// let mut loader = YamlLoader::<Yaml>::default().nan_keys(NanKeyPolicy::Equal);
+    /// Parser::new_from_str("{.nan: a, .NAN: b}").load(&mut loader, true).unwrap();
+    /// assert_eq!(loader.into_documents()[0].as_hash().unwrap().len(), 1);
+    /// ```
+    #[must_use]
+    pub fn nan_keys(mut self, policy: NanKeyPolicy) -> Self {
+        self.options.nan_keys = policy;
+        self
+    }
+
+    /// Limit the number of documents which are loaded.
+    ///
+    /// The documents past the limit are skipped without building any of their nodes, and an
+    /// error of kind [`ScanErrorKind::DocumentNotAllowed`], located at the start of the first of
+    /// them, can be retrieved after loading with [`YamlLoader::hook_errors`]. The parser still
+    /// reads the whole stream: to stop parsing at the first document past the limit, use
+    /// [`Parser::max_documents`](saphyr_parser::Parser::max_documents) instead. By default, the
+    /// number of documents is not limited.
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, YamlLoader};
+    /// # use saphyr_parser::{Parser, ScanErrorKind};
+    /// let mut loader = YamlLoader::<Yaml>::default().max_documents(2);
+    /// Parser::new_from_str("a\n---\nb\n---\nc\n---\nd").load(&mut loader, true).unwrap();
+    /// assert_eq!(loader.hook_errors()[0].kind(), ScanErrorKind::DocumentNotAllowed);
+    /// assert_eq!(loader.hook_errors()[0].marker().line(), 4);
+    /// assert_eq!(loader.into_documents().len(), 2);
+    /// ```
+    #[must_use]
+    pub fn max_documents(mut self, limit: usize) -> Self {
+        self.max_documents = Some(limit);
+        self
+    }
+
+    /// Limit the number of nodes which are loaded through aliases.
+    ///
+    /// Aliases are expanded into copies of their anchored node, so that a few nested aliases can
+    /// load into a huge tree (as in the "billion laughs" attack). An alias counts for all the
+    /// nodes of its anchored node, including those it got from its own aliases. Once the total of
+    /// all the aliases of the stream exceeds `limit`, aliases are loaded as `BadValue`s instead,
+    /// and an error of kind [`ScanErrorKind::AliasLimitExceeded`], located at the first of them,
+    /// can be retrieved after loading with [`YamlLoader::hook_errors`]. By default, the number of
+    /// nodes loaded through aliases is not limited.
+    ///
+    /// ```
+    /// # use saphyr::{Yaml, YamlLoader};
+    /// # use saphyr_parser::{Parser, ScanErrorKind};
+    /// let input = "a: &a [x, x, x]\nb: &b [*a, *a, *a]\nc: [*b, *b, *b]";
+    /// let mut loader = YamlLoader::<Yaml>::default().max_alias_nodes(30);
+    /// Parser::new_from_str(input).load(&mut loader, true).unwrap();
+    /// assert_eq!(loader.hook_errors()[0].kind(), ScanErrorKind::AliasLimitExceeded);
+    /// assert_eq!(loader.hook_errors()[0].marker().line(), 3);
+    /// let doc = &loader.into_documents()[0];
+    /// assert_eq!(doc["c"][0].as_vec().unwrap().len(), 3);
+    /// assert!(doc["c"][1].is_badvalue());
+    /// ```
+    #[must_use]
+    pub fn max_alias_nodes(mut self, limit: usize) -> Self {
+        self.max_alias_nodes = Some(limit);
+        self
+    }
+
+    /// Return the keys which were defined again in their hash, with the values they superseded.
+    ///
+    /// This is empty unless [`YamlLoader::record_duplicate_keys`] is set.
+    #[must_use]
+    pub fn duplicate_keys(&self) -> &[DuplicateKey<Node>] {
+        &self.duplicate_keys
+    }
+
+    /// Return the lints about anchors and aliases found while loading.
+    ///
+    /// This holds a [`LoaderLintKind::UnusedAnchor`] for each anchor no alias of its document
+    /// refers to, located at its `&name`, and a [`LoaderLintKind::UndefinedAlias`] for each alias
+    /// which was loaded as a bad value because its node was not loaded yet, located at the alias.
+    /// Unused anchors are found at the end of their document, after the lints about the aliases of
+    /// the document.
+    ///
+    /// ```
+    /// # use saphyr::{LoaderLintKind, Yaml, YamlLoader};
+    /// # use saphyr_parser::Parser;
+    /// let mut loader = YamlLoader::<Yaml>::default();
+    /// Parser::new_from_str("a: &x 1\nb: &y [*y]").load(&mut loader, true).unwrap();
+    /// let lints: Vec<_> = loader.lints().iter().map(|lint| lint.kind()).collect();
+    /// assert_eq!(lints, [LoaderLintKind::UndefinedAlias, LoaderLintKind::UnusedAnchor]);
+    /// assert_eq!(loader.lints()[1].span().start.col(), 3);
+    /// ```
+    #[must_use]
+    pub fn lints(&self) -> &[LoaderLint] {
+        &self.lints
+    }
+
+    /// Return the anchors recorded while loading, indexed by their ID.
+    ///
+    /// This is empty unless [`YamlLoader::record_anchors`] is set.
+    #[must_use]
+    pub fn anchors(&self) -> &BTreeMap<usize, Anchor> {
+        &self.anchors
+    }
+
+    /// Remove and return the last document loaded, if any.
+    #[cfg(feature = "serde")]
+    pub(crate) fn pop_document(&mut self) -> Option<Node> {
+        self.docs.pop()
+    }
+
+    /// Remove and return the hash entries recorded as superseded so far.
+    #[cfg(feature = "serde")]
+    pub(crate) fn take_duplicate_keys(&mut self) -> Vec<DuplicateKey<Node>> {
+        std::mem::take(&mut self.duplicate_keys)
+    }
+
+    /// Return the document nodes and the recorded anchors from `self`, consuming it.
+    pub(crate) fn into_parts(self) -> (Vec<Node>, BTreeMap<usize, Anchor>) {
+        (self.docs, self.anchors)
+    }
+
     /// Return the document nodes from `self`, consuming it in the process.
     #[must_use]
     pub fn into_documents(self) -> Vec<Node> {
@@ -208,10 +1132,153 @@ impl std::fmt::Display for LoadError {
     }
 }
 
+#[cfg(feature = "diagnostics")]
+impl miette::Diagnostic for LoadError {
+    fn code<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        match self {
+            LoadError::IO(_) => Some(Box::new("saphyr::io_error")),
+            LoadError::Scan(e) => e.code(),
+            LoadError::Decode(_) => Some(Box::new("saphyr::decode_error")),
+        }
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        match self {
+            LoadError::Scan(e) => e.labels(),
+            LoadError::IO(_) | LoadError::Decode(_) => None,
+        }
+    }
+}
+
 /// A trait providing methods used by the [`YamlLoader`].
 ///
 /// This trait must be implemented on YAML node types (i.e.: [`Yaml`] and annotated YAML nodes). It
 /// provides the necessary methods for [`YamlLoader`] to load data into the node.
+///
+/// # Implementing the trait
+/// Implementing this trait on your own type allows loading documents directly into it, without
+/// going through [`Yaml`] or [`MarkedYaml`] first. The loader builds nodes as follows:
+///
+///   - Each node is created with [`from_bare_yaml`], then given its span with [`with_span`].
+///     Scalars are already resolved (e.g.: `!!int 3` is a [`Yaml::Integer`]). Collections are
+///     given empty.
+///   - Elements of a collection are built first, then pushed into it through [`array_mut`] or
+///     [`hash_mut`]. [`take`] is used to move mapping keys out of the loader.
+///   - Once all its elements are pushed, a collection is given the end of its span with
+///     [`with_end_marker`].
+///   - Aliases are loaded as a clone of the node they refer to, which is given the span of that
+///     node with [`with_anchor_span`], then the span of the alias.
+///
+/// [`Hash`] and [`Eq`] are required since nodes are used as mapping keys. They should only
+/// consider the data of the node and not its metadata, so that the same key at different positions
+/// in the input compares equal.
+///
+/// ```
+/// use saphyr::{LinkedHashMap, LoadableYamlNode, Yaml, YamlLoader};
+/// use saphyr_parser::{Marker, Parser, Span};
+///
+/// /// A node that only remembers the lines it spans.
+/// #[derive(Clone, Debug)]
+/// struct Node {
+///     lines: (usize, usize),
+///     value: Value,
+/// }
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// enum Value {
+///     Scalar(String),
+///     Seq(Vec<Node>),
+///     Map(LinkedHashMap<Node, Node>),
+///     Invalid,
+/// }
+///
+/// impl PartialEq for Node {
+///     fn eq(&self, other: &Self) -> bool {
+///         self.value == other.value
+///     }
+/// }
+///
+/// impl Eq for Node {}
+///
+/// impl std::hash::Hash for Node {
+///     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+///         self.value.hash(state);
+///     }
+/// }
+///
+/// impl LoadableYamlNode for Node {
+///     fn from_bare_yaml(yaml: Yaml) -> Self {
+///         let value = match yaml {
+///             Yaml::Array(_) => Value::Seq(vec![]),
+///             Yaml::Hash(_) => Value::Map(LinkedHashMap::new()),
+///             Yaml::String(s) | Yaml::Real(s) => Value::Scalar(s),
+///             Yaml::Integer(i) => Value::Scalar(i.to_string()),
+///             Yaml::Boolean(b) => Value::Scalar(b.to_string()),
+///             Yaml::Null => Value::Scalar("null".to_owned()),
+///             Yaml::Alias(_) | Yaml::BadValue => Value::Invalid,
+///         };
+///         Node { lines: (0, 0), value }
+///     }
+///
+///     fn is_array(&self) -> bool {
+///         matches!(self.value, Value::Seq(_))
+///     }
+///
+///     fn is_hash(&self) -> bool {
+///         matches!(self.value, Value::Map(_))
+///     }
+///
+///     fn is_badvalue(&self) -> bool {
+///         matches!(self.value, Value::Invalid)
+///     }
+///
+///     fn array_mut(&mut self) -> &mut Vec<Self> {
+///         match &mut self.value {
+///             Value::Seq(seq) => seq,
+///             _ => panic!("not a sequence"),
+///         }
+///     }
+///
+///     fn hash_mut(&mut self) -> &mut LinkedHashMap<Self, Self> {
+///         match &mut self.value {
+///             Value::Map(map) => map,
+///             _ => panic!("not a mapping"),
+///         }
+///     }
+///
+///     fn take(&mut self) -> Self {
+///         let lines = self.lines;
+///         std::mem::replace(self, Node { lines, value: Value::Invalid })
+///     }
+///
+///     fn with_span(mut self, span: Span) -> Self {
+///         self.lines = (span.start.line(), span.end.line());
+///         self
+///     }
+///
+///     fn with_end_marker(mut self, end: Marker) -> Self {
+///         self.lines.1 = end.line();
+///         self
+///     }
+/// }
+///
+/// let mut loader = YamlLoader::<Node>::default();
+/// Parser::new_from_str("a:\n  - b\n  - c\n").load(&mut loader, true).unwrap();
+/// let doc = &loader.into_documents()[0];
+/// let Value::Map(map) = &doc.value else { unreachable!() };
+/// let (key, seq) = map.iter().next().unwrap();
+/// assert_eq!(key.value, Value::Scalar("a".to_owned()));
+/// assert_eq!(seq.lines, (2, 3));
+/// ```
+///
+/// [`MarkedYaml`]: crate::MarkedYaml
+/// [`from_bare_yaml`]: LoadableYamlNode::from_bare_yaml
+/// [`with_span`]: LoadableYamlNode::with_span
+/// [`array_mut`]: LoadableYamlNode::array_mut
+/// [`hash_mut`]: LoadableYamlNode::hash_mut
+/// [`take`]: LoadableYamlNode::take
+/// [`with_end_marker`]: LoadableYamlNode::with_end_marker
+/// [`with_anchor_span`]: LoadableYamlNode::with_anchor_span
 pub trait LoadableYamlNode: Clone + std::hash::Hash + Eq {
     /// Create an instance of `Self` from a [`Yaml`].
     ///
@@ -256,6 +1323,50 @@ pub trait LoadableYamlNode: Clone + std::hash::Hash + Eq {
     fn with_span(self, _: Span) -> Self {
         self
     }
+
+    /// Provide the end of the span of a collection, once all its elements are loaded
+    /// (builder-style).
+    ///
+    /// This is called on arrays and hashes only, after [`Self::with_span`], with the end of their
+    /// closing `]` or `}` for flow collections, and with the end of their last element for block
+    /// collections.
+    #[inline]
+    #[must_use]
+    fn with_end_marker(self, _: Marker) -> Self {
+        self
+    }
+
+    /// Provide the span of the hash entry the node is the key or the value of (builder-style).
+    ///
+    /// This is called on the key and on the value of each hash entry, after the hook set with
+    /// [`YamlLoader::hash_entry_hook`] and right before the entry is inserted. The span runs from
+    /// the start of the key to the end of the value, the `:` included.
+    #[inline]
+    #[must_use]
+    fn with_pair_span(self, _: Span) -> Self {
+        self
+    }
+
+    /// Provide the span of the node an alias refers to (builder-style).
+    ///
+    /// This is called on the clone of the anchored node that an alias is loaded as, before
+    /// [`Self::with_span`] gives it the span of the alias. The span is that of the anchored node,
+    /// as in [`Anchor::span`].
+    #[inline]
+    #[must_use]
+    fn with_anchor_span(self, _: Span) -> Self {
+        self
+    }
+
+    /// Provide how the scalar the node was loaded from was written (builder-style).
+    ///
+    /// This is only called if [`YamlLoader::keep_representations`] is set, on scalar nodes, after
+    /// [`Self::with_span`].
+    #[inline]
+    #[must_use]
+    fn with_representation(self, _: ScalarRepresentation) -> Self {
+        self
+    }
 }
 
 impl LoadableYamlNode for Yaml {
@@ -292,15 +1403,202 @@ impl LoadableYamlNode for Yaml {
     }
 
     fn take(&mut self) -> Self {
-        let mut taken_out = Yaml::BadValue;
-        std::mem::swap(&mut taken_out, self);
-        taken_out
+        Yaml::take(self)
     }
 }
 
-// parse f64 as Core schema
+/// Resolve the contents of a scalar to a [`Yaml`] node, given its style and tag.
+///
+/// `formats` may enable the YAML 1.1 formats of numbers and booleans.
+fn resolve_scalar(
+    v: String,
+    style: TScalarStyle,
+    tag: Option<&Tag>,
+    formats: ScalarFormats,
+) -> Yaml {
+    let yaml_1_1 = formats.yaml_1_1_numbers;
+    let parse_bool = |v: &str| {
+        parse_core_schema_bool(v).or_else(|| {
+            if formats.yaml_1_1_booleans {
+                parse_yaml_1_1_bool(v)
+            } else {
+                None
+            }
+        })
+    };
+    if style != TScalarStyle::Plain {
+        Yaml::String(v)
+    } else if let Some(tag) = tag {
+        if tag.is_core_schema() {
+            match tag.suffix.as_ref() {
+                "bool" => match parse_bool(&v) {
+                    Some(v) => Yaml::Boolean(v),
+                    None => Yaml::BadValue,
+                },
+                "int" => match resolve_number(&v, yaml_1_1) {
+                    Some(int @ Yaml::Integer(_)) => int,
+                    _ => Yaml::BadValue,
+                },
+                "float" => {
+                    let real = if yaml_1_1 {
+                        yaml_1_1_digits(&v).and_then(|(radix, real)| (radix == 10).then_some(real))
+                    } else {
+                        Some(v)
+                    };
+                    match real {
+                        Some(real) if parse_core_schema_fp(&real).is_some() => Yaml::Real(real),
+                        _ => Yaml::BadValue,
+                    }
+                }
+                "null" if parse_core_schema_null(&v) => Yaml::Null,
+                "null" => Yaml::BadValue,
+                _ => Yaml::String(v),
+            }
+        } else {
+            Yaml::String(v)
+        }
+    } else if let Some(b) = parse_bool(&v) {
+        Yaml::Boolean(b)
+    } else if yaml_1_1 {
+        // Datatype is not specified, or unrecognized
+        resolve_number(&v, true).unwrap_or_else(|| Yaml::from_str(&v))
+    } else {
+        // Datatype is not specified, or unrecognized
+        Yaml::from_str(&v)
+    }
+}
+
+/// Resolve `v` as an integer or a real, optionally in the YAML 1.1 formats.
+///
+/// See [`YamlLoader::yaml_1_1_numbers`].
+fn resolve_number(v: &str, yaml_1_1: bool) -> Option<Yaml> {
+    if let Some(i) = parse_core_schema_int(v) {
+        return Some(Yaml::Integer(i));
+    }
+    if !yaml_1_1 {
+        return parse_core_schema_fp(v).map(|_| Yaml::Real(v.to_owned()));
+    }
+    let (radix, number) = yaml_1_1_digits(v)?;
+    if let Ok(i) = i64::from_str_radix(&number, radix) {
+        Some(Yaml::Integer(i))
+    } else {
+        (radix == 10 && parse_core_schema_fp(&number).is_some()).then_some(Yaml::Real(number))
+    }
+}
+
+/// Split a YAML 1.1 number into its radix and its sign and digits, without underscores.
+///
+/// Return `None` if an underscore directly follows the sign or the radix prefix, or is part of
+/// something else than a number (e.g. `in_f`, which would otherwise be read as `inf`).
+fn yaml_1_1_digits(v: &str) -> Option<(u32, String)> {
+    let (sign, unsigned) = match v.strip_prefix(['+', '-']) {
+        Some(unsigned) => (&v[..1], unsigned),
+        None => ("", v),
+    };
+    let (radix, digits) = match unsigned.get(..2) {
+        Some("0b") => (2, &unsigned[2..]),
+        Some("0o") => (8, &unsigned[2..]),
+        Some("0x") => (16, &unsigned[2..]),
+        _ => (10, unsigned),
+    };
+    let is_numeric = |c: char| c.is_ascii_hexdigit() || matches!(c, '_' | '.' | '+' | '-');
+    if digits.starts_with('_') || (digits.contains('_') && !digits.chars().all(is_numeric)) {
+        return None;
+    }
+    Some((radix, format!("{sign}{}", digits.replace('_', ""))))
+}
+
+/// Parse a plain scalar as a boolean of the core schema (`true` or `false`).
+///
+/// ```
+/// # use saphyr::parse_core_schema_bool;
+/// assert_eq!(parse_core_schema_bool("false"), Some(false));
+/// assert_eq!(parse_core_schema_bool("yes"), None);
+/// ```
+#[must_use]
+pub fn parse_core_schema_bool(v: &str) -> Option<bool> {
+    match v {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parse a plain scalar as a boolean in the formats of YAML 1.1.
+///
+/// Besides `true` and `false`, YAML 1.1 accepts `y`, `yes`, `on` and `n`, `no`, `off`, each in
+/// lowercase, capitalized or uppercase. See [`YamlLoader::yaml_1_1_booleans`].
+///
+/// ```
+/// # use saphyr::parse_yaml_1_1_bool;
+/// assert_eq!(parse_yaml_1_1_bool("Yes"), Some(true));
+/// assert_eq!(parse_yaml_1_1_bool("OFF"), Some(false));
+/// assert_eq!(parse_yaml_1_1_bool("oN"), None);
+/// ```
+#[must_use]
+pub fn parse_yaml_1_1_bool(v: &str) -> Option<bool> {
+    match v {
+        "y" | "Y" | "yes" | "Yes" | "YES" | "true" | "True" | "TRUE" | "on" | "On" | "ON" => {
+            Some(true)
+        }
+        "n" | "N" | "no" | "No" | "NO" | "false" | "False" | "FALSE" | "off" | "Off" | "OFF" => {
+            Some(false)
+        }
+        _ => None,
+    }
+}
+
+/// Parse a plain scalar as an integer of the core schema.
+///
+/// Decimal (with an optional sign), hexadecimal (`0x`) and octal (`0o`) integers are accepted.
+/// `None` is returned if the scalar is not an integer or does not fit in an `i64`.
+///
+/// ```
+/// # use saphyr::parse_core_schema_int;
+/// assert_eq!(parse_core_schema_int("+42"), Some(42));
+/// assert_eq!(parse_core_schema_int("0x2A"), Some(42));
+/// assert_eq!(parse_core_schema_int("0o52"), Some(42));
+/// assert_eq!(parse_core_schema_int("4.2"), None);
+/// ```
+#[must_use]
+pub fn parse_core_schema_int(v: &str) -> Option<i64> {
+    if let Some(number) = v.strip_prefix("0x") {
+        i64::from_str_radix(number, 16).ok()
+    } else if let Some(number) = v.strip_prefix("0o") {
+        i64::from_str_radix(number, 8).ok()
+    } else if let Some(number) = v.strip_prefix('+') {
+        number.parse().ok()
+    } else {
+        v.parse().ok()
+    }
+}
+
+/// Return whether a plain scalar is a null of the core schema (`~` or `null`).
+///
+/// ```
+/// # use saphyr::parse_core_schema_null;
+/// assert!(parse_core_schema_null("~"));
+/// assert!(!parse_core_schema_null("none"));
+/// ```
+#[must_use]
+pub fn parse_core_schema_null(v: &str) -> bool {
+    matches!(v, "~" | "null")
+}
+
+/// Parse a plain scalar as a floating point number of the core schema.
+///
+/// In addition to the formats Rust accepts, `.inf`, `-.inf` and `.nan` (in various cases) are
+/// accepted.
+///
+/// ```
+/// # use saphyr::parse_core_schema_fp;
+/// assert_eq!(parse_core_schema_fp("1.5e3"), Some(1500.0));
+/// assert_eq!(parse_core_schema_fp("-.INF"), Some(f64::NEG_INFINITY));
+/// assert_eq!(parse_core_schema_fp("foo"), None);
+/// ```
 // See: https://github.com/chyh1990/yaml-rust/issues/51
-pub(crate) fn parse_f64(v: &str) -> Option<f64> {
+#[must_use]
+pub fn parse_core_schema_fp(v: &str) -> Option<f64> {
     match v {
         ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" => Some(f64::INFINITY),
         "-.inf" | "-.Inf" | "-.INF" => Some(f64::NEG_INFINITY),
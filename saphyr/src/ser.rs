@@ -0,0 +1,607 @@
+//! Serialization of typed values into YAML trees with `serde`.
+
+use std::fmt;
+
+use serde::ser::{self, Serialize};
+
+use crate::{loader::parse_core_schema_fp, Array, Hash, Yaml, YamlEmitter};
+
+/// An error when serializing a value into a [`Yaml`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SerializeError {
+    /// The description of the error.
+    message: String,
+}
+
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for SerializeError {}
+
+impl ser::Error for SerializeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SerializeError {
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// Serialize `value` into a [`Yaml`] node.
+///
+/// The node can then be inserted into an existing tree before it is emitted, without going
+/// through text. Structs and maps become hashes, sequences and tuples become arrays, and unit
+/// values and `None` become `Null`. Enum variants are represented as in [`Yaml::deserialize_into`]:
+/// unit variants as their name, other variants as a hash with a single entry, from the name of the
+/// variant to its contents.
+///
+/// ```
+/// # use saphyr::{Yaml, YamlEmitter};
+/// #[derive(serde::Serialize)]
+/// struct Server {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// let mut doc = Yaml::load_from_str("servers: []").unwrap().remove(0);
+/// let server = Server { host: "example.com".into(), port: 8080 };
+/// doc["servers"].as_mut_vec().unwrap().push(saphyr::to_node(&server).unwrap());
+///
+/// let mut out = String::new();
+/// YamlEmitter::new(&mut out).dump(&doc).unwrap();
+/// assert_eq!(out, "---\nservers:\n  - host: example.com\n    port: 8080");
+/// ```
+///
+/// # Errors
+/// Returns `SerializeError` if the `Serialize` implementation of `T` fails, or if `value` contains
+/// an integer that does not fit in an `i64`.
+pub fn to_node<T: Serialize + ?Sized>(value: &T) -> Result<Yaml, SerializeError> {
+    WriteOptions::default().to_node(value)
+}
+
+/// Serialize `value` into a YAML document.
+///
+/// The value is serialized with [`to_node`], then emitted with a [`YamlEmitter`]. Strings spanning
+/// multiple lines are written in double quotes, with escaped line breaks. See
+/// [`to_string_multiline`] to write them as literal blocks instead.
+///
+/// ```
+/// #[derive(serde::Serialize)]
+/// struct Server {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// let server = Server { host: "example.com".into(), port: 8080 };
+/// let out = saphyr::to_string(&server).unwrap();
+/// assert_eq!(out, "---\nhost: example.com\nport: 8080");
+/// ```
+///
+/// # Errors
+/// Returns `SerializeError` if `value` cannot be serialized with [`to_node`].
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, SerializeError> {
+    WriteOptions::default().to_string(value)
+}
+
+/// Serialize `value` into a YAML document, writing multi-line strings as literal blocks.
+///
+/// This is [`to_string`] with [`WriteOptions::multiline_strings`] enabled. Strings containing
+/// characters which cannot appear in a literal block (e.g. control characters) are still written
+/// in double quotes.
+///
+/// ```
+/// #[derive(serde::Serialize)]
+/// struct Script {
+///     name: String,
+///     run: String,
+/// }
+///
+/// let script = Script { name: "build".into(), run: "make\nmake install".into() };
+/// let out = saphyr::to_string_multiline(&script).unwrap();
+/// assert_eq!(out, "---\nname: build\nrun: |-\n  make\n  make install");
+/// ```
+///
+/// # Errors
+/// Returns `SerializeError` if `value` cannot be serialized with [`to_node`].
+pub fn to_string_multiline<T: Serialize + ?Sized>(value: &T) -> Result<String, SerializeError> {
+    WriteOptions::default()
+        .multiline_strings(true)
+        .to_string(value)
+}
+
+/// Options for writing typed values as YAML.
+///
+/// The functions [`to_node`], [`to_string`] and [`to_string_multiline`] use the default options,
+/// or enable [`WriteOptions::multiline_strings`]. To use other options, call the methods of the
+/// same name on a `WriteOptions`.
+///
+/// ```
+/// # use saphyr::WriteOptions;
+/// # use std::collections::HashMap;
+/// let ports = HashMap::from([("https", 443), ("http", 80), ("ftp", 21)]);
+/// let out = WriteOptions::default().sort_map_keys(true).to_string(&ports).unwrap();
+/// assert_eq!(out, "---\nftp: 21\nhttp: 80\nhttps: 443");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Whether the entries of maps are sorted by key.
+    sort_map_keys: bool,
+    /// Whether multi-line strings are emitted as literal blocks.
+    multiline_strings: bool,
+}
+
+impl WriteOptions {
+    /// Set whether the entries of maps are sorted by key.
+    ///
+    /// By default, entries are kept in the order the map yields them. For maps with no defined
+    /// order, such as `HashMap`, this order may change between runs. Sorting them makes the output
+    /// deterministic. The fields of structs are always kept in the order they are declared in.
+    /// Keys are compared as [`Yaml`] nodes, which orders strings lexicographically.
+    #[must_use]
+    pub fn sort_map_keys(mut self, value: bool) -> Self {
+        self.sort_map_keys = value;
+        self
+    }
+
+    /// Set whether multi-line strings are emitted as literal blocks.
+    ///
+    /// See [`YamlEmitter::multiline_strings`]. This has no effect on [`WriteOptions::to_node`].
+    #[must_use]
+    pub fn multiline_strings(mut self, value: bool) -> Self {
+        self.multiline_strings = value;
+        self
+    }
+
+    /// Serialize `value` into a [`Yaml`] node, with these options.
+    ///
+    /// See [`to_node`].
+    ///
+    /// # Errors
+    /// Returns `SerializeError` if the `Serialize` implementation of `T` fails, or if `value`
+    /// contains an integer that does not fit in an `i64`.
+    pub fn to_node<T: Serialize + ?Sized>(self, value: &T) -> Result<Yaml, SerializeError> {
+        value.serialize(NodeSerializer { options: self })
+    }
+
+    /// Serialize `value` into a YAML document, with these options.
+    ///
+    /// See [`to_string`].
+    ///
+    /// # Errors
+    /// Returns `SerializeError` if `value` cannot be serialized with [`WriteOptions::to_node`].
+    pub fn to_string<T: Serialize + ?Sized>(self, value: &T) -> Result<String, SerializeError> {
+        let node = self.to_node(value)?;
+        let mut out = String::new();
+        let mut emitter = YamlEmitter::new(&mut out);
+        emitter.multiline_strings(self.multiline_strings);
+        emitter.dump(&node).map_err(ser::Error::custom)?;
+        Ok(out)
+    }
+}
+
+impl Serialize for Yaml {
+    /// Serialize the node with any serializer, e.g. as part of a larger typed value.
+    ///
+    /// Reals are serialized as `f64`s, which may not preserve how they are written. Aliases and
+    /// `BadValue`s cannot be serialized.
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Yaml::Null => serializer.serialize_unit(),
+            Yaml::Boolean(b) => serializer.serialize_bool(*b),
+            Yaml::Integer(i) => serializer.serialize_i64(*i),
+            Yaml::Real(s) => match parse_core_schema_fp(s) {
+                Some(f) => serializer.serialize_f64(f),
+                None => Err(ser::Error::custom(format!("real `{s}` is not a number"))),
+            },
+            Yaml::String(s) => serializer.serialize_str(s),
+            Yaml::Array(array) => serializer.collect_seq(array),
+            Yaml::Hash(hash) => serializer.collect_map(hash),
+            Yaml::Alias(_) => Err(ser::Error::custom("an alias cannot be serialized")),
+            Yaml::BadValue => Err(ser::Error::custom("a bad value cannot be serialized")),
+        }
+    }
+}
+
+/// Return the node for the real `v`, as written by its `Debug` implementation.
+///
+/// `Debug` always writes a fractional part or an exponent, which keeps the node a real if it is
+/// emitted and loaded again.
+pub(crate) fn real<F: fmt::Debug>(
+    v: F,
+    is_nan: bool,
+    is_infinite: bool,
+    is_negative: bool,
+) -> Yaml {
+    Yaml::Real(if is_nan {
+        ".nan".into()
+    } else if is_infinite && is_negative {
+        "-.inf".into()
+    } else if is_infinite {
+        ".inf".into()
+    } else {
+        format!("{v:?}")
+    })
+}
+
+/// Return the node for the integer `v`, which must fit in an `i64`.
+fn integer<I: TryInto<i64> + fmt::Display + Copy>(v: I) -> Result<Yaml, SerializeError> {
+    v.try_into()
+        .map(Yaml::Integer)
+        .map_err(|_| ser::Error::custom(format!("integer `{v}` does not fit in an i64")))
+}
+
+/// Return a hash with a single entry, from the name of an enum variant to its contents.
+fn variant(name: &'static str, value: Yaml) -> Yaml {
+    let mut hash = Hash::new();
+    hash.insert(Yaml::String(name.into()), value);
+    Yaml::Hash(hash)
+}
+
+/// A serializer producing a [`Yaml`] node.
+struct NodeSerializer {
+    /// The options to serialize with.
+    options: WriteOptions,
+}
+
+impl ser::Serializer for NodeSerializer {
+    type Ok = Yaml;
+    type Error = SerializeError;
+
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeArray;
+    type SerializeMap = SerializeHash;
+    type SerializeStruct = SerializeHash;
+    type SerializeStructVariant = SerializeHash;
+
+    fn serialize_bool(self, v: bool) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Boolean(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Integer(v.into()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Integer(v.into()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Integer(v.into()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Integer(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Yaml, SerializeError> {
+        integer(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Integer(v.into()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Integer(v.into()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Integer(v.into()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Yaml, SerializeError> {
+        integer(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Yaml, SerializeError> {
+        integer(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Yaml, SerializeError> {
+        Ok(real(v, v.is_nan(), v.is_infinite(), v.is_sign_negative()))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Yaml, SerializeError> {
+        Ok(real(v, v.is_nan(), v.is_infinite(), v.is_sign_negative()))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::String(v.into()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::String(v.into()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Array(
+            v.iter().map(|&b| Yaml::Integer(b.into())).collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Yaml, SerializeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Yaml, SerializeError> {
+        Ok(Yaml::String(variant.into()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Yaml, SerializeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Yaml, SerializeError> {
+        Ok(variant(name, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeArray, SerializeError> {
+        Ok(SerializeArray {
+            options: self.options,
+            variant: None,
+            array: Array::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeArray, SerializeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray, SerializeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray, SerializeError> {
+        Ok(SerializeArray {
+            options: self.options,
+            variant: Some(variant),
+            array: Array::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeHash, SerializeError> {
+        Ok(SerializeHash {
+            options: self.options,
+            variant: None,
+            hash: Hash::new(),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeHash, SerializeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeHash, SerializeError> {
+        Ok(SerializeHash {
+            options: self.options,
+            variant: Some(variant),
+            hash: Hash::new(),
+            next_key: None,
+        })
+    }
+}
+
+/// A sequence, tuple or tuple variant being serialized.
+struct SerializeArray {
+    /// The options to serialize the items with.
+    options: WriteOptions,
+    /// The name of the variant, if serializing a tuple variant.
+    variant: Option<&'static str>,
+    /// The items serialized so far.
+    array: Array,
+}
+
+impl SerializeArray {
+    /// Serialize an item and append it to the array.
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerializeError> {
+        self.array.push(self.options.to_node(value)?);
+        Ok(())
+    }
+
+    /// Return the serialized node.
+    fn finish(self) -> Yaml {
+        match self.variant {
+            Some(name) => variant(name, Yaml::Array(self.array)),
+            None => Yaml::Array(self.array),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = Yaml;
+    type Error = SerializeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Yaml, SerializeError> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = Yaml;
+    type Error = SerializeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Yaml, SerializeError> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = Yaml;
+    type Error = SerializeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Yaml, SerializeError> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeArray {
+    type Ok = Yaml;
+    type Error = SerializeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Yaml, SerializeError> {
+        Ok(self.finish())
+    }
+}
+
+/// A map, struct or struct variant being serialized.
+struct SerializeHash {
+    /// The options to serialize the entries with.
+    options: WriteOptions,
+    /// The name of the variant, if serializing a struct variant.
+    variant: Option<&'static str>,
+    /// The entries serialized so far.
+    hash: Hash,
+    /// The key of the entry being serialized, between `serialize_key` and `serialize_value`.
+    next_key: Option<Yaml>,
+}
+
+impl SerializeHash {
+    /// Serialize a struct field and insert it into the hash.
+    fn insert<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        self.hash
+            .insert(Yaml::String(key.into()), self.options.to_node(value)?);
+        Ok(())
+    }
+
+    /// Return the serialized node.
+    fn finish(self) -> Yaml {
+        match self.variant {
+            Some(name) => variant(name, Yaml::Hash(self.hash)),
+            None => Yaml::Hash(self.hash),
+        }
+    }
+}
+
+impl ser::SerializeMap for SerializeHash {
+    type Ok = Yaml;
+    type Error = SerializeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        self.next_key = Some(self.options.to_node(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let key = self
+            .next_key
+            .take()
+            .expect("`serialize_value` called before `serialize_key`");
+        self.hash.insert(key, self.options.to_node(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Yaml, SerializeError> {
+        let sort = self.options.sort_map_keys;
+        let mut node = self.finish();
+        if sort {
+            node.sort_keys();
+        }
+        Ok(node)
+    }
+}
+
+impl ser::SerializeStruct for SerializeHash {
+    type Ok = Yaml;
+    type Error = SerializeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Yaml, SerializeError> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeHash {
+    type Ok = Yaml;
+    type Error = SerializeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Yaml, SerializeError> {
+        Ok(self.finish())
+    }
+}
@@ -0,0 +1,244 @@
+//! Helpers to compare YAML trees in tests.
+//!
+//! This requires the `testing` feature.
+
+use std::fmt::{self, Display};
+
+use crate::{loader::parse_core_schema_fp, Hash, Yaml, YamlPath};
+
+/// Assert that two YAML trees are semantically equal.
+///
+/// Each side is either a [`Yaml`] or a string, which is loaded as a single YAML document (see
+/// [`YamlSource`]). The trees are compared with [`differences`]: the order of the entries of
+/// hashes does not matter and reals are compared by value.
+///
+/// On failure, the panic message lists each difference along with the path to it. As with
+/// [`assert_eq!`], a custom message can be given after the two trees.
+///
+/// ```
+/// # use saphyr::{assert_yaml_eq, Yaml};
+/// let doc = &Yaml::load_from_str("{name: foo, ratio: 0.50}").unwrap()[0];
+/// assert_yaml_eq!(doc, "ratio: 0.5\nname: foo");
+/// assert_yaml_eq!("[1, 2]", "- 1\n- 2", "lists should match");
+/// ```
+///
+/// This requires the `testing` feature.
+#[macro_export]
+macro_rules! assert_yaml_eq {
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::testing::assert_yaml_eq_impl(&$actual, &$expected, None)
+    };
+    ($actual:expr, $expected:expr, $($arg:tt)+) => {
+        $crate::testing::assert_yaml_eq_impl(
+            &$actual,
+            &$expected,
+            Some(format_args!($($arg)+)),
+        )
+    };
+}
+
+/// A value which can be compared with [`assert_yaml_eq!`].
+pub trait YamlSource {
+    /// Return the YAML tree to compare.
+    ///
+    /// # Panics
+    /// Strings panic if they fail to load or if they hold more than one document. A string with no
+    /// document is loaded as [`Yaml::Null`].
+    fn to_yaml(&self) -> Yaml;
+}
+
+impl YamlSource for Yaml {
+    fn to_yaml(&self) -> Yaml {
+        self.clone()
+    }
+}
+
+impl YamlSource for str {
+    fn to_yaml(&self) -> Yaml {
+        let mut docs = Yaml::load_from_str(self)
+            .unwrap_or_else(|err| panic!("failed to load {self:?} as YAML: {err}"));
+        assert!(
+            docs.len() <= 1,
+            "expected at most one YAML document, found {} in {self:?}",
+            docs.len()
+        );
+        docs.pop().unwrap_or(Yaml::Null)
+    }
+}
+
+impl YamlSource for String {
+    fn to_yaml(&self) -> Yaml {
+        self.as_str().to_yaml()
+    }
+}
+
+impl<T: YamlSource + ?Sized> YamlSource for &T {
+    fn to_yaml(&self) -> Yaml {
+        (**self).to_yaml()
+    }
+}
+
+/// A difference between two YAML trees, as found by [`differences`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Difference {
+    /// The path to the differing nodes.
+    pub path: YamlPath,
+    /// What differs between the nodes.
+    pub message: String,
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_root() {
+            write!(f, "at the root: {}", self.message)
+        } else {
+            write!(f, "at `{}`: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Return the differences between `actual` and `expected`.
+///
+/// Trees are compared semantically, like [`Yaml::canonical_hash`] hashes them: the order of the
+/// entries of hashes does not matter and reals are compared by value (`1.0` and `1.00` are the
+/// same, as are `0.0` and `-0.0`, and all NaNs). Scalars are compared as they were resolved, so `1`
+/// and `"1"` differ, as do `1` and `1.0`.
+///
+/// Differences are reported at the deepest node they can be: a hash missing a key is one
+/// difference at the path of the key, and an array with extra items is one difference per item.
+///
+/// ```
+/// # use saphyr::{testing::differences, Yaml};
+/// let actual = &Yaml::load_from_str("{a: [1, 2], b: x}").unwrap()[0];
+/// let expected = &Yaml::load_from_str("{b: y, a: [1, 3], c: 4}").unwrap()[0];
+/// let found: Vec<_> = differences(actual, expected).iter().map(ToString::to_string).collect();
+/// assert_eq!(
+///     found,
+///     [
+///         "at `b`: expected y, found x",
+///         "at `a[1]`: expected 3, found 2",
+///         "at `c`: expected 4, found nothing",
+///     ]
+/// );
+/// ```
+#[must_use]
+pub fn differences(actual: &Yaml, expected: &Yaml) -> Vec<Difference> {
+    let mut found = vec![];
+    diff(actual, expected, &mut YamlPath::new(), &mut found);
+    found
+}
+
+/// Panic if `actual` and `expected` differ. See [`assert_yaml_eq!`].
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_yaml_eq_impl<A: YamlSource + ?Sized, E: YamlSource + ?Sized>(
+    actual: &A,
+    expected: &E,
+    message: Option<fmt::Arguments>,
+) {
+    let found = differences(&actual.to_yaml(), &expected.to_yaml());
+    if found.is_empty() {
+        return;
+    }
+    let header = match message {
+        Some(message) => format!("assertion `actual == expected` failed: {message}"),
+        None => "assertion `actual == expected` failed".to_owned(),
+    };
+    let lines: Vec<_> = found
+        .iter()
+        .map(|difference| format!("  {difference}"))
+        .collect();
+    panic!("{header}\n{}", lines.join("\n"));
+}
+
+/// Push the differences between `actual` and `expected`, found at `path`, to `found`.
+fn diff(actual: &Yaml, expected: &Yaml, path: &mut YamlPath, found: &mut Vec<Difference>) {
+    match (actual, expected) {
+        (Yaml::Array(actual), Yaml::Array(expected)) => {
+            for (i, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+                path.push_index(i);
+                diff(actual, expected, path, found);
+                path.pop();
+            }
+            for (i, item) in actual.iter().enumerate().skip(expected.len()) {
+                path.push_index(i);
+                push(found, path, "nothing", &describe(item));
+                path.pop();
+            }
+            for (i, item) in expected.iter().enumerate().skip(actual.len()) {
+                path.push_index(i);
+                push(found, path, &describe(item), "nothing");
+                path.pop();
+            }
+        }
+        (Yaml::Hash(actual), Yaml::Hash(expected)) => {
+            for (key, expected_value) in expected {
+                path.push_key(describe(key));
+                match find_key(actual, key) {
+                    Some(actual_value) => diff(actual_value, expected_value, path, found),
+                    None => push(found, path, &describe(expected_value), "nothing"),
+                }
+                path.pop();
+            }
+            for (key, actual_value) in actual {
+                if find_key(expected, key).is_none() {
+                    path.push_key(describe(key));
+                    push(found, path, "nothing", &describe(actual_value));
+                    path.pop();
+                }
+            }
+        }
+        _ => {
+            if !scalar_eq(actual, expected) {
+                push(found, path, &describe(expected), &describe(actual));
+            }
+        }
+    }
+}
+
+/// Push a difference at `path` to `found`.
+fn push(found: &mut Vec<Difference>, path: &YamlPath, expected: &str, actual: &str) {
+    found.push(Difference {
+        path: path.clone(),
+        message: format!("expected {expected}, found {actual}"),
+    });
+}
+
+/// Return the value of the entry of `hash` whose key is semantically equal to `key`.
+fn find_key<'a>(hash: &'a Hash, key: &Yaml) -> Option<&'a Yaml> {
+    hash.get(key).or_else(|| {
+        hash.iter()
+            .find(|(other, _)| differences(other, key).is_empty())
+            .map(|(_, value)| value)
+    })
+}
+
+/// Return whether two nodes, which are not both arrays nor both hashes, are semantically equal.
+fn scalar_eq(actual: &Yaml, expected: &Yaml) -> bool {
+    match (actual, expected) {
+        (Yaml::Real(a), Yaml::Real(b)) => {
+            match (parse_core_schema_fp(a), parse_core_schema_fp(b)) {
+                (Some(a), Some(b)) => a == b || (a.is_nan() && b.is_nan()),
+                _ => a == b,
+            }
+        }
+        _ => actual == expected,
+    }
+}
+
+/// Return a short description of `node` for messages.
+fn describe(node: &Yaml) -> String {
+    match node {
+        Yaml::Array(array) => match array.len() {
+            1 => "an array of 1 item".to_owned(),
+            n => format!("an array of {n} items"),
+        },
+        Yaml::Hash(hash) => match hash.len() {
+            1 => "a hash of 1 entry".to_owned(),
+            n => format!("a hash of {n} entries"),
+        },
+        Yaml::Alias(id) => format!("an alias to anchor {id}"),
+        Yaml::BadValue => "a bad value".to_owned(),
+        _ => node.to_yaml_string().unwrap_or_default(),
+    }
+}
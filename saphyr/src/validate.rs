@@ -0,0 +1,524 @@
+//! Validation of YAML documents against JSON Schemas.
+
+use std::fmt;
+
+use saphyr_parser::{ScanError, Span};
+
+use crate::{loader::parse_core_schema_fp, MarkedYaml, Yaml, YamlData, YamlPath};
+
+/// A [JSON Schema](https://json-schema.org) against which [`MarkedYaml`] documents can be
+/// validated.
+///
+/// The schema is itself a YAML document; since JSON documents are valid YAML, schemas written in
+/// JSON can be loaded as well. The following keywords are supported:
+///   * `type`, `enum`, `const`
+///   * `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum` (numeric form)
+///   * `minLength`, `maxLength`
+///   * `items` (single schema or array of schemas), `minItems`, `maxItems`, `uniqueItems`
+///   * `properties`, `required`, `additionalProperties`, `minProperties`, `maxProperties`
+///   * `allOf`, `anyOf`, `oneOf`, `not`
+///   * `$ref`, to a JSON pointer within the schema (e.g. `#/$defs/port`)
+///
+/// Other keywords (e.g. `pattern` or `format`) and keywords whose value is not of the expected
+/// type are ignored.
+///
+/// ```
+/// # use saphyr::{MarkedYaml, Schema};
+/// let schema = Schema::load_from_str(
+///     "type: object
+/// properties:
+///   port: { type: integer, maximum: 65535 }
+/// required: [host, port]",
+/// )
+/// .unwrap();
+/// let doc = &MarkedYaml::load_from_str("port: 80000").unwrap()[0];
+/// let violations = schema.validate(doc);
+/// assert_eq!(violations.len(), 2);
+/// assert_eq!(violations[0].path.to_string(), "port");
+/// assert_eq!(violations[0].span.start.col(), 6);
+/// assert_eq!(violations[1].message, "missing required property `host`");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schema {
+    /// The root of the schema, against which `$ref`s are resolved.
+    root: Yaml,
+}
+
+/// A node of a document that does not satisfy a [`Schema`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// The location of the offending node in the document (e.g. `servers[0].port`).
+    ///
+    /// [`YamlPath::to_json_pointer`] returns it as a JSON pointer (e.g. `/servers/0/port`).
+    pub path: YamlPath,
+    /// The span of the offending node.
+    ///
+    /// For `required`, `minProperties` and `maxProperties`, this is the span of the hash. For
+    /// properties disallowed by `additionalProperties`, this is the span of the key.
+    pub span: Span,
+    /// The schema keyword which the node violates (e.g. `type` or `required`).
+    pub keyword: &'static str,
+    /// A human-readable description of the violation.
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at `{}` line {} column {}",
+            self.message,
+            self.path,
+            self.span.start.line(),
+            self.span.start.col() + 1
+        )
+    }
+}
+
+impl Schema {
+    /// Create a schema from its YAML representation.
+    #[must_use]
+    pub fn new(root: Yaml) -> Self {
+        Self { root }
+    }
+
+    /// Load a schema from the first document of the given string.
+    ///
+    /// If the string contains no document, the schema accepts any value.
+    ///
+    /// # Errors
+    /// Returns `ScanError` when loading fails.
+    pub fn load_from_str(source: &str) -> Result<Self, ScanError> {
+        let root = Yaml::load_from_str(source)?
+            .into_iter()
+            .next()
+            .unwrap_or(Yaml::Boolean(true));
+        Ok(Self::new(root))
+    }
+
+    /// Return the YAML representation of the schema.
+    #[must_use]
+    pub fn as_yaml(&self) -> &Yaml {
+        &self.root
+    }
+
+    /// Validate `doc` against the schema, returning all the violations found, in document order.
+    #[must_use]
+    pub fn validate(&self, doc: &MarkedYaml) -> Vec<Violation> {
+        let mut validator = Validator {
+            root: &self.root,
+            violations: vec![],
+            refs: vec![],
+        };
+        validator.validate(&self.root, doc, &mut YamlPath::new());
+        validator.violations
+    }
+
+    /// Return whether `doc` satisfies the schema.
+    #[must_use]
+    pub fn is_valid(&self, doc: &MarkedYaml) -> bool {
+        self.validate(doc).is_empty()
+    }
+}
+
+/// A numeric bound keyword, whether a number satisfies it, and how violating numbers compare to it.
+type Bound = (&'static str, fn(f64, f64) -> bool, &'static str);
+
+/// The state of an ongoing validation.
+struct Validator<'s, 'd> {
+    /// The root of the schema.
+    root: &'s Yaml,
+    /// The violations found so far.
+    violations: Vec<Violation>,
+    /// The `$ref`s being followed, along with the node they apply to, to detect cycles.
+    refs: Vec<(&'s str, &'d MarkedYaml)>,
+}
+
+impl<'s, 'd> Validator<'s, 'd> {
+    /// Validate `node`, found at `path` in the document, against `schema`.
+    fn validate(&mut self, schema: &'s Yaml, node: &'d MarkedYaml, path: &mut YamlPath) {
+        match schema {
+            Yaml::Hash(_) => {}
+            Yaml::Boolean(false) => {
+                self.report(node.span, path, "false", "no value is allowed here".into());
+                return;
+            }
+            _ => return,
+        }
+        if let Some(reference) = schema["$ref"].as_str() {
+            self.validate_ref(reference, node, path);
+        }
+        self.validate_value(schema, node, path);
+        match &node.data {
+            YamlData::String(s) => self.validate_string(schema, node, s, path),
+            YamlData::Array(array) => self.validate_array(schema, node, array, path),
+            YamlData::Hash(_) => self.validate_hash(schema, node, path),
+            _ => {
+                if let Some(n) = node_number(node) {
+                    self.validate_number(schema, node, n, path);
+                }
+            }
+        }
+        self.validate_combinators(schema, node, path);
+    }
+
+    /// Return whether `node` satisfies `schema`, without recording violations.
+    fn is_valid(&mut self, schema: &'s Yaml, node: &'d MarkedYaml, path: &mut YamlPath) -> bool {
+        let len = self.violations.len();
+        self.validate(schema, node, path);
+        let valid = self.violations.len() == len;
+        self.violations.truncate(len);
+        valid
+    }
+
+    /// Validate `node` against the schema `reference` points to.
+    fn validate_ref(&mut self, reference: &'s str, node: &'d MarkedYaml, path: &mut YamlPath) {
+        let Some(target) = resolve_ref(self.root, reference) else {
+            let message = format!("cannot resolve `$ref` `{reference}`");
+            self.report(node.span, path, "$ref", message);
+            return;
+        };
+        // A cycle of references not going deeper into the document adds no constraint.
+        if self
+            .refs
+            .iter()
+            .any(|&(r, n)| r == reference && std::ptr::eq(n, node))
+        {
+            return;
+        }
+        self.refs.push((reference, node));
+        self.validate(target, node, path);
+        self.refs.pop();
+    }
+
+    /// Check the `type`, `enum` and `const` keywords.
+    fn validate_value(&mut self, schema: &'s Yaml, node: &'d MarkedYaml, path: &YamlPath) {
+        let types: Vec<&str> = match &schema["type"] {
+            Yaml::String(ty) => vec![ty],
+            Yaml::Array(types) => types.iter().filter_map(Yaml::as_str).collect(),
+            _ => vec![],
+        };
+        if !types.is_empty() && !types.iter().any(|ty| type_matches(ty, node)) {
+            let message = format!("expected {}, found {}", types.join(" or "), type_name(node));
+            self.report(node.span, path, "type", message);
+        }
+        if let Some(values) = schema["enum"].as_vec() {
+            if !values.iter().any(|value| node_equals(node, value)) {
+                let message = "value is not one of the values allowed by `enum`".into();
+                self.report(node.span, path, "enum", message);
+            }
+        }
+        if let Yaml::Hash(hash) = schema {
+            if let Some(value) = hash.get(&Yaml::String("const".into())) {
+                if !node_equals(node, value) {
+                    let message = "value is not equal to `const`".into();
+                    self.report(node.span, path, "const", message);
+                }
+            }
+        }
+    }
+
+    /// Check the keywords applying to numbers.
+    fn validate_number(&mut self, schema: &Yaml, node: &MarkedYaml, n: f64, path: &YamlPath) {
+        let bounds: [Bound; 4] = [
+            ("minimum", |n, bound| n >= bound, "less than"),
+            ("maximum", |n, bound| n <= bound, "greater than"),
+            (
+                "exclusiveMinimum",
+                |n, bound| n > bound,
+                "less than or equal to",
+            ),
+            (
+                "exclusiveMaximum",
+                |n, bound| n < bound,
+                "greater than or equal to",
+            ),
+        ];
+        for (keyword, satisfies, relation) in bounds {
+            if let Some(bound) = yaml_number(&schema[keyword]) {
+                if !satisfies(n, bound) {
+                    let message = format!("{} is {relation} {bound}", number_text(node));
+                    self.report(node.span, path, keyword, message);
+                }
+            }
+        }
+    }
+
+    /// Check the keywords applying to strings.
+    fn validate_string(&mut self, schema: &Yaml, node: &MarkedYaml, s: &str, path: &YamlPath) {
+        let len = s.chars().count();
+        if let Some(min) = yaml_usize(&schema["minLength"]) {
+            if len < min {
+                let message = format!("string is shorter than {min} characters");
+                self.report(node.span, path, "minLength", message);
+            }
+        }
+        if let Some(max) = yaml_usize(&schema["maxLength"]) {
+            if len > max {
+                let message = format!("string is longer than {max} characters");
+                self.report(node.span, path, "maxLength", message);
+            }
+        }
+    }
+
+    /// Check the keywords applying to arrays and validate their elements.
+    fn validate_array(
+        &mut self,
+        schema: &'s Yaml,
+        node: &'d MarkedYaml,
+        array: &'d [MarkedYaml],
+        path: &mut YamlPath,
+    ) {
+        if let Some(min) = yaml_usize(&schema["minItems"]) {
+            if array.len() < min {
+                let message = format!("array has fewer than {min} items");
+                self.report(node.span, path, "minItems", message);
+            }
+        }
+        if let Some(max) = yaml_usize(&schema["maxItems"]) {
+            if array.len() > max {
+                let message = format!("array has more than {max} items");
+                self.report(node.span, path, "maxItems", message);
+            }
+        }
+        if schema["uniqueItems"].as_bool() == Some(true) {
+            for (i, item) in array.iter().enumerate() {
+                if let Some(first) = array[..i].iter().position(|other| other == item) {
+                    path.push_index(i);
+                    let message = format!("item is a duplicate of item {first}");
+                    self.report(item.span, path, "uniqueItems", message);
+                    path.pop();
+                }
+            }
+        }
+        for (i, item) in array.iter().enumerate() {
+            let item_schema = match &schema["items"] {
+                Yaml::Array(schemas) => match schemas.get(i) {
+                    Some(item_schema) => item_schema,
+                    None => break,
+                },
+                item_schema => item_schema,
+            };
+            path.push_index(i);
+            self.validate(item_schema, item, path);
+            path.pop();
+        }
+    }
+
+    /// Check the keywords applying to hashes and validate their values.
+    fn validate_hash(&mut self, schema: &'s Yaml, node: &'d MarkedYaml, path: &mut YamlPath) {
+        let YamlData::Hash(hash) = &node.data else {
+            return;
+        };
+        if let Some(min) = yaml_usize(&schema["minProperties"]) {
+            if hash.len() < min {
+                let message = format!("hash has fewer than {min} properties");
+                self.report(node.span, path, "minProperties", message);
+            }
+        }
+        if let Some(max) = yaml_usize(&schema["maxProperties"]) {
+            if hash.len() > max {
+                let message = format!("hash has more than {max} properties");
+                self.report(node.span, path, "maxProperties", message);
+            }
+        }
+        let properties = schema["properties"].as_hash();
+        for (key, value) in hash {
+            let name = key_text(key);
+            path.push_key(name.clone());
+            let property_schema = key
+                .data
+                .as_str()
+                .and_then(|name| properties?.get(&Yaml::String(name.into())));
+            match (property_schema, &schema["additionalProperties"]) {
+                (Some(property_schema), _) => self.validate(property_schema, value, path),
+                (None, Yaml::Boolean(false)) => {
+                    let message = format!("property `{name}` is not allowed");
+                    self.report(key.span, path, "additionalProperties", message);
+                }
+                (None, additional) => self.validate(additional, value, path),
+            }
+            path.pop();
+        }
+        if let Some(required) = schema["required"].as_vec() {
+            for name in required.iter().filter_map(Yaml::as_str) {
+                if !hash.keys().any(|key| key.data.as_str() == Some(name)) {
+                    let message = format!("missing required property `{name}`");
+                    self.report(node.span, path, "required", message);
+                }
+            }
+        }
+    }
+
+    /// Check the `allOf`, `anyOf`, `oneOf` and `not` keywords.
+    fn validate_combinators(
+        &mut self,
+        schema: &'s Yaml,
+        node: &'d MarkedYaml,
+        path: &mut YamlPath,
+    ) {
+        if let Some(schemas) = schema["allOf"].as_vec() {
+            for sub_schema in schemas {
+                self.validate(sub_schema, node, path);
+            }
+        }
+        if let Some(schemas) = schema["anyOf"].as_vec() {
+            if !schemas.iter().any(|sub| self.is_valid(sub, node, path)) {
+                let message = "value does not match any of the schemas in `anyOf`".into();
+                self.report(node.span, path, "anyOf", message);
+            }
+        }
+        if let Some(schemas) = schema["oneOf"].as_vec() {
+            let matches = schemas
+                .iter()
+                .filter(|sub| self.is_valid(sub, node, path))
+                .count();
+            if matches != 1 {
+                let message =
+                    format!("value matches {matches} of the schemas in `oneOf` instead of one");
+                self.report(node.span, path, "oneOf", message);
+            }
+        }
+        if let Yaml::Hash(hash) = schema {
+            if let Some(not) = hash.get(&Yaml::String("not".into())) {
+                if self.is_valid(not, node, path) {
+                    let message = "value matches the schema in `not`".into();
+                    self.report(node.span, path, "not", message);
+                }
+            }
+        }
+    }
+
+    /// Record a violation.
+    fn report(&mut self, span: Span, path: &YamlPath, keyword: &'static str, message: String) {
+        self.violations.push(Violation {
+            path: path.clone(),
+            span,
+            keyword,
+            message,
+        });
+    }
+}
+
+/// Resolve a `$ref` of the form `#/json/pointer` within `root`.
+fn resolve_ref<'s>(root: &'s Yaml, reference: &str) -> Option<&'s Yaml> {
+    let pointer = reference.strip_prefix('#')?;
+    if pointer.is_empty() {
+        return Some(root);
+    }
+    pointer
+        .strip_prefix('/')?
+        .split('/')
+        .try_fold(root, |schema, segment| {
+            let segment = segment.replace("~1", "/").replace("~0", "~");
+            match schema {
+                Yaml::Hash(hash) => hash.get(&Yaml::String(segment)),
+                Yaml::Array(array) => array.get(segment.parse::<usize>().ok()?),
+                _ => None,
+            }
+        })
+}
+
+/// Return whether `node` is of the JSON Schema type `ty`.
+fn type_matches(ty: &str, node: &MarkedYaml) -> bool {
+    match ty {
+        "null" => node.data.is_null(),
+        "boolean" => node.data.is_boolean(),
+        "integer" => {
+            node.data.is_integer()
+                || node_number(node).is_some_and(|n| n.is_finite() && n.fract() == 0.0)
+        }
+        "number" => node_number(node).is_some(),
+        "string" => node.data.is_string(),
+        "array" => node.data.is_array(),
+        "object" => node.data.is_hash(),
+        _ => false,
+    }
+}
+
+/// Return the JSON Schema type of `node`, for error messages.
+fn type_name(node: &MarkedYaml) -> &'static str {
+    match node.data {
+        YamlData::Null => "null",
+        YamlData::Boolean(_) => "boolean",
+        YamlData::Integer(_) => "integer",
+        YamlData::Real(_) => "number",
+        YamlData::String(_) => "string",
+        YamlData::Array(_) => "array",
+        YamlData::Hash(_) => "object",
+        YamlData::Alias(_) | YamlData::BadValue => "invalid value",
+    }
+}
+
+/// Return the numeric value of `node`, if it is a number.
+#[allow(clippy::cast_precision_loss)]
+fn node_number(node: &MarkedYaml) -> Option<f64> {
+    match &node.data {
+        YamlData::Integer(i) => Some(*i as f64),
+        YamlData::Real(s) => parse_core_schema_fp(s),
+        _ => None,
+    }
+}
+
+/// Return the numeric value of `value`, if it is a number.
+#[allow(clippy::cast_precision_loss)]
+fn yaml_number(value: &Yaml) -> Option<f64> {
+    match value {
+        Yaml::Integer(i) => Some(*i as f64),
+        Yaml::Real(s) => parse_core_schema_fp(s),
+        _ => None,
+    }
+}
+
+/// Return the value of `value`, if it is a non-negative integer.
+fn yaml_usize(value: &Yaml) -> Option<usize> {
+    value.as_i64().and_then(|i| usize::try_from(i).ok())
+}
+
+/// Return the number in `node` as it was written, for error messages.
+fn number_text(node: &MarkedYaml) -> String {
+    match &node.data {
+        YamlData::Integer(i) => i.to_string(),
+        YamlData::Real(s) => s.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Return the text of a hash key, for use in a JSON pointer.
+///
+/// Keys which are not scalars are represented as `?`.
+fn key_text(key: &MarkedYaml) -> String {
+    match &key.data {
+        YamlData::String(s) | YamlData::Real(s) => s.clone(),
+        YamlData::Integer(i) => i.to_string(),
+        YamlData::Boolean(b) => b.to_string(),
+        YamlData::Null => "null".into(),
+        _ => "?".into(),
+    }
+}
+
+/// Return whether `node` is equal to `value`, as JSON values.
+///
+/// Numbers are compared by value, regardless of whether they are integers or reals.
+#[allow(clippy::float_cmp)]
+fn node_equals(node: &MarkedYaml, value: &Yaml) -> bool {
+    match (&node.data, value) {
+        (YamlData::String(a), Yaml::String(b)) => a == b,
+        (YamlData::Boolean(a), Yaml::Boolean(b)) => a == b,
+        (YamlData::Null, Yaml::Null) => true,
+        (YamlData::Array(a), Yaml::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| node_equals(a, b))
+        }
+        (YamlData::Hash(a), Yaml::Hash(b)) => {
+            a.len() == b.len()
+                && a.iter().all(|(key, a)| {
+                    b.iter()
+                        .any(|(b_key, b)| node_equals(key, b_key) && node_equals(a, b))
+                })
+        }
+        _ => match (node_number(node), yaml_number(value)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        },
+    }
+}
@@ -0,0 +1,529 @@
+//! Depth-first traversal of YAML trees.
+
+use std::fmt;
+
+use crate::{MarkedYaml, Yaml, YamlData};
+
+/// A step in the path from the root of a tree to one of its nodes.
+///
+/// `Node` is the type of the nodes of the tree (e.g.: [`Yaml`] or [`MarkedYaml`]).
+///
+/// [`MarkedYaml`]: crate::MarkedYaml
+#[derive(Debug, PartialEq, Eq)]
+pub enum PathSegment<'a, Node = Yaml> {
+    /// The node is the element at this index of an array.
+    Index(usize),
+    /// The node is the value associated with this key in a hash.
+    Key(&'a Node),
+}
+
+// Not derived, as deriving would require `Node: Clone`.
+impl<Node> Clone for PathSegment<'_, Node> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Node> Copy for PathSegment<'_, Node> {}
+
+/// What to do after visiting a node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Visit {
+    /// Keep going, visiting the children of the node, if any.
+    Continue,
+    /// Do not visit the children of the node, but keep going with its siblings.
+    SkipChildren,
+    /// Stop the traversal.
+    Stop,
+}
+
+/// A visitor for [`Yaml::walk`].
+///
+/// This is implemented for closures taking the same arguments as [`Visitor::visit`].
+pub trait Visitor {
+    /// Visit `node`, found at `path` from the root of the tree.
+    fn visit(&mut self, path: &[PathSegment<'_>], node: &Yaml) -> Visit;
+}
+
+impl<F: FnMut(&[PathSegment<'_>], &Yaml) -> Visit> Visitor for F {
+    fn visit(&mut self, path: &[PathSegment<'_>], node: &Yaml) -> Visit {
+        self(path, node)
+    }
+}
+
+/// A visitor for [`Yaml::walk_mut`].
+///
+/// This is implemented for closures taking the same arguments as [`VisitorMut::visit_mut`].
+pub trait VisitorMut {
+    /// Visit `node`, found at `path` from the root of the tree.
+    ///
+    /// The node may be modified. Its children are visited after it is modified.
+    fn visit_mut(&mut self, path: &[PathSegment<'_>], node: &mut Yaml) -> Visit;
+}
+
+impl<F: FnMut(&[PathSegment<'_>], &mut Yaml) -> Visit> VisitorMut for F {
+    fn visit_mut(&mut self, path: &[PathSegment<'_>], node: &mut Yaml) -> Visit {
+        self(path, node)
+    }
+}
+
+/// A pattern matching paths from the root of a tree to its nodes.
+///
+/// Patterns are made of segments separated by dots (`.`). A segment matches:
+///   * `*`: any single key or index.
+///   * `**`: any number (including none) of keys and indices.
+///   * Anything else: a hash key which is the string of the segment or, if the segment is a
+///     number, the element at that index of an array.
+///
+/// Keys containing dots cannot be matched other than with `*` or `**`. The empty pattern only
+/// matches the root of the tree.
+///
+/// ```
+/// # use saphyr::{PathPattern, PathSegment, Yaml};
+/// let password = Yaml::String("password".into());
+/// let db = Yaml::String("db".into());
+/// let path = [PathSegment::Key(&db), PathSegment::Index(0), PathSegment::Key(&password)];
+/// assert!(PathPattern::new("**.password").matches(&path));
+/// assert!(PathPattern::new("db.0.*").matches(&path));
+/// assert!(!PathPattern::new("*.password").matches(&path));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathPattern {
+    /// The segments of the pattern.
+    segments: Vec<PatternSegment>,
+}
+
+/// A segment of a [`PathPattern`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PatternSegment {
+    /// `*`: any single key or index.
+    Any,
+    /// `**`: any number of keys and indices.
+    AnyDepth,
+    /// A key or index, as written in the pattern.
+    Literal(String),
+}
+
+impl PathPattern {
+    /// Create a pattern from its textual form. See [`PathPattern`] for the syntax.
+    #[must_use]
+    pub fn new(pattern: &str) -> Self {
+        let segments = if pattern.is_empty() {
+            vec![]
+        } else {
+            pattern
+                .split('.')
+                .map(|segment| match segment {
+                    "*" => PatternSegment::Any,
+                    "**" => PatternSegment::AnyDepth,
+                    _ => PatternSegment::Literal(segment.to_owned()),
+                })
+                .collect()
+        };
+        Self { segments }
+    }
+
+    /// Return whether the pattern matches the given path.
+    #[must_use]
+    pub fn matches(&self, path: &[PathSegment<'_>]) -> bool {
+        self.matches_with(path, Yaml::as_str)
+    }
+
+    /// Return whether the pattern matches the given path, using `key_str` to get the string hash
+    /// keys are made of.
+    fn matches_with<Node>(
+        &self,
+        path: &[PathSegment<'_, Node>],
+        key_str: impl Fn(&Node) -> Option<&str> + Copy,
+    ) -> bool {
+        matches_impl(&self.segments, path, key_str)
+    }
+}
+
+/// A path from the root of a tree to one of its nodes, which owns its segments.
+///
+/// Unlike a slice of [`PathSegment`]s, which borrows the keys from the tree, a [`YamlPath`]
+/// outlives the tree. It is how the violations of a [`Schema`], the incompatibilities of
+/// [`Yaml::to_json_lossy`] and the differences of `testing::differences` locate nodes.
+///
+/// It is displayed with keys separated by dots and indices in brackets (e.g. `servers[0].port`).
+/// Keys which are empty or contain any of `.[]"\` are quoted and put in brackets (e.g.
+/// `a["b.c"]`). The root is displayed as the empty string.
+///
+/// ```
+/// # use saphyr::YamlPath;
+/// let mut path = YamlPath::new();
+/// path.push_key("servers");
+/// path.push_index(0);
+/// path.push_key("a/b.c");
+/// assert_eq!(path.to_string(), r#"servers[0]["a/b.c"]"#);
+/// assert_eq!(path.to_json_pointer(), "/servers/0/a~1b.c");
+/// ```
+///
+/// [`Schema`]: crate::Schema
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct YamlPath {
+    /// The segments of the path, from the root.
+    segments: Vec<YamlPathSegment>,
+}
+
+/// A segment of a [`YamlPath`].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum YamlPathSegment {
+    /// The node is the element at this index of an array.
+    Index(usize),
+    /// The node is the value associated with this key in a hash.
+    ///
+    /// Keys which are not strings are written as YAML (e.g. `1` or `true`).
+    Key(String),
+}
+
+impl YamlPath {
+    /// Create the path to the root of a tree.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the segments of the path, from the root.
+    #[must_use]
+    pub fn segments(&self) -> &[YamlPathSegment] {
+        &self.segments
+    }
+
+    /// Return whether the path is that of the root.
+    #[must_use]
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Append a hash key to the path.
+    pub fn push_key(&mut self, key: impl Into<String>) {
+        self.segments.push(YamlPathSegment::Key(key.into()));
+    }
+
+    /// Append an array index to the path.
+    pub fn push_index(&mut self, index: usize) {
+        self.segments.push(YamlPathSegment::Index(index));
+    }
+
+    /// Remove the last segment of the path and return it, if any.
+    pub fn pop(&mut self) -> Option<YamlPathSegment> {
+        self.segments.pop()
+    }
+
+    /// Return the path as a [JSON pointer](https://www.rfc-editor.org/rfc/rfc6901) (e.g.
+    /// `/servers/0/port`). The pointer to the root is the empty string.
+    #[must_use]
+    pub fn to_json_pointer(&self) -> String {
+        self.segments
+            .iter()
+            .fold(String::new(), |mut pointer, segment| {
+                pointer.push('/');
+                match segment {
+                    YamlPathSegment::Index(index) => pointer.push_str(&index.to_string()),
+                    YamlPathSegment::Key(key) => {
+                        pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                    }
+                }
+                pointer
+            })
+    }
+}
+
+impl fmt::Display for YamlPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                YamlPathSegment::Index(index) => write!(f, "[{index}]")?,
+                YamlPathSegment::Key(key)
+                    if key.is_empty() || key.contains(['.', '[', ']', '"', '\\']) =>
+                {
+                    write!(f, "[{key:?}]")?;
+                }
+                YamlPathSegment::Key(key) if i == 0 => f.write_str(key)?,
+                YamlPathSegment::Key(key) => write!(f, ".{key}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Return whether `segments` match `path`.
+fn matches_impl<Node>(
+    segments: &[PatternSegment],
+    path: &[PathSegment<'_, Node>],
+    key_str: impl Fn(&Node) -> Option<&str> + Copy,
+) -> bool {
+    let Some((segment, segments)) = segments.split_first() else {
+        return path.is_empty();
+    };
+    if *segment == PatternSegment::AnyDepth {
+        return (0..=path.len()).any(|skip| matches_impl(segments, &path[skip..], key_str));
+    }
+    let Some((step, path)) = path.split_first() else {
+        return false;
+    };
+    let step_matches = match (segment, step) {
+        (PatternSegment::Literal(literal), PathSegment::Key(key)) => {
+            key_str(key) == Some(literal.as_str())
+        }
+        (PatternSegment::Literal(literal), PathSegment::Index(index)) => {
+            literal.parse() == Ok(*index)
+        }
+        _ => true,
+    };
+    step_matches && matches_impl(segments, path, key_str)
+}
+
+impl Yaml {
+    /// Traverse the tree depth-first, calling `visitor` on each node.
+    ///
+    /// Nodes are visited before their children. Hash keys are not visited; they are part of the
+    /// path to the values. Aliases are not followed.
+    ///
+    /// ```
+    /// # use saphyr::{PathSegment, Visit, Yaml};
+    /// let doc = &Yaml::load_from_str("a: [1, 2]\nb: 3").unwrap()[0];
+    /// let mut integers = 0;
+    /// doc.walk(&mut |path: &[PathSegment], node: &Yaml| {
+    ///     if path.first() == Some(&PathSegment::Key(&Yaml::String("b".into()))) {
+    ///         return Visit::SkipChildren;
+    ///     }
+    ///     integers += usize::from(node.as_i64().is_some());
+    ///     Visit::Continue
+    /// });
+    /// assert_eq!(integers, 2);
+    /// ```
+    pub fn walk(&self, visitor: &mut impl Visitor) {
+        walk_impl(self, &mut vec![], visitor);
+    }
+
+    /// Traverse the tree depth-first, calling `visitor` on each node, allowing it to modify nodes.
+    ///
+    /// See [`Yaml::walk`] for the order in which nodes are visited.
+    ///
+    /// ```
+    /// # use saphyr::{PathSegment, Visit, Yaml};
+    /// let mut doc = Yaml::load_from_str("[1, [2, 3]]").unwrap().remove(0);
+    /// doc.walk_mut(&mut |_: &[PathSegment], node: &mut Yaml| {
+    ///     if let Yaml::Integer(i) = node {
+    ///         *i *= 10;
+    ///     }
+    ///     Visit::Continue
+    /// });
+    /// assert_eq!(doc[1][1].as_i64(), Some(30));
+    /// ```
+    pub fn walk_mut(&mut self, visitor: &mut impl VisitorMut) {
+        walk_mut_impl(self, &mut vec![], visitor);
+    }
+
+    /// Call `f` on each scalar whose path matches `pattern`, allowing it to modify the scalar.
+    ///
+    /// Collections are not given to `f`, even if their path matches, but their children are
+    /// checked in turn. This is typically used to redact secrets before logging a document.
+    /// Return the number of scalars `f` was called on.
+    ///
+    /// ```
+    /// # use saphyr::{PathPattern, Yaml};
+    /// let mut doc = Yaml::load_from_str("db:\n  user: me\n  password: hunter2\npassword: x")
+    ///     .unwrap()
+    ///     .remove(0);
+    /// let redacted = doc.transform_scalars(&PathPattern::new("**.password"), |_, node| {
+    ///     *node = Yaml::String("<redacted>".into());
+    /// });
+    /// assert_eq!(redacted, 2);
+    /// assert_eq!(doc["db"]["user"].as_str(), Some("me"));
+    /// assert_eq!(doc["db"]["password"].as_str(), Some("<redacted>"));
+    /// assert_eq!(doc["password"].as_str(), Some("<redacted>"));
+    /// ```
+    pub fn transform_scalars(
+        &mut self,
+        pattern: &PathPattern,
+        mut f: impl FnMut(&[PathSegment<'_>], &mut Yaml),
+    ) -> usize {
+        let mut count = 0;
+        self.walk_mut(&mut |path: &[PathSegment<'_>], node: &mut Yaml| {
+            if !node.is_array() && !node.is_hash() && pattern.matches(path) {
+                f(path, node);
+                count += 1;
+            }
+            Visit::Continue
+        });
+        count
+    }
+
+    /// Return a copy of the tree containing only the nodes whose path matches one of `patterns`.
+    ///
+    /// Matching nodes are copied along with their children. Other nodes are only kept if they
+    /// have a matching descendant, and then only with the children leading to one. Elements of
+    /// arrays keep their order, but their indices may change. Return [`Yaml::BadValue`] if no node
+    /// matches.
+    ///
+    /// ```
+    /// # use saphyr::{PathPattern, Yaml};
+    /// let doc = &Yaml::load_from_str("name: app\nspec: {replicas: 3, image: app:1}\nstatus: {}")
+    ///     .unwrap()[0];
+    /// let view = doc.project(&[PathPattern::new("name"), PathPattern::new("spec.image")]);
+    /// assert_eq!(view, Yaml::load_from_str("name: app\nspec: {image: app:1}").unwrap()[0]);
+    /// ```
+    #[must_use]
+    pub fn project(&self, patterns: &[PathPattern]) -> Yaml {
+        project_impl(self, &mut vec![], patterns).unwrap_or(Yaml::BadValue)
+    }
+}
+
+/// Copy the parts of `node` matching one of `patterns`, if any.
+fn project_impl<'a>(
+    node: &'a Yaml,
+    path: &mut Vec<PathSegment<'a>>,
+    patterns: &[PathPattern],
+) -> Option<Yaml> {
+    if patterns.iter().any(|pattern| pattern.matches(path)) {
+        return Some(node.clone());
+    }
+    match node {
+        Yaml::Array(array) => {
+            let mut projected = vec![];
+            for (i, child) in array.iter().enumerate() {
+                path.push(PathSegment::Index(i));
+                projected.extend(project_impl(child, path, patterns));
+                path.pop();
+            }
+            (!projected.is_empty()).then_some(Yaml::Array(projected))
+        }
+        Yaml::Hash(hash) => {
+            let mut projected = crate::Hash::new();
+            for (key, child) in hash {
+                path.push(PathSegment::Key(key));
+                if let Some(child) = project_impl(child, path, patterns) {
+                    projected.insert(key.clone(), child);
+                }
+                path.pop();
+            }
+            (!projected.is_empty()).then_some(Yaml::Hash(projected))
+        }
+        _ => None,
+    }
+}
+
+impl MarkedYaml {
+    /// Call `f` on the data of each scalar whose path matches `pattern`, allowing it to modify the
+    /// scalar.
+    ///
+    /// Spans are left untouched. See [`Yaml::transform_scalars`].
+    pub fn transform_scalars(
+        &mut self,
+        pattern: &PathPattern,
+        mut f: impl FnMut(&[PathSegment<'_, MarkedYaml>], &mut YamlData<MarkedYaml>),
+    ) -> usize {
+        transform_marked_impl(self, &mut vec![], pattern, &mut f)
+    }
+}
+
+/// Recursively call `f` on the matching scalars of `node`. Return the number of such scalars.
+fn transform_marked_impl<'a>(
+    node: &'a mut MarkedYaml,
+    path: &mut Vec<PathSegment<'a, MarkedYaml>>,
+    pattern: &PathPattern,
+    f: &mut impl FnMut(&[PathSegment<'_, MarkedYaml>], &mut YamlData<MarkedYaml>),
+) -> usize {
+    let mut count = 0;
+    match &mut node.data {
+        YamlData::Array(array) => {
+            for (i, child) in array.iter_mut().enumerate() {
+                path.push(PathSegment::Index(i));
+                count += transform_marked_impl(child, path, pattern, f);
+                path.pop();
+            }
+        }
+        YamlData::Hash(hash) => {
+            for (key, child) in hash.iter_mut() {
+                path.push(PathSegment::Key(key));
+                count += transform_marked_impl(child, path, pattern, f);
+                path.pop();
+            }
+        }
+        data => {
+            if pattern.matches_with(path, |key: &MarkedYaml| key.data.as_str()) {
+                f(path, data);
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+/// Recursively visit `node` and its children. Return `false` if the traversal must stop.
+fn walk_impl<'a>(
+    node: &'a Yaml,
+    path: &mut Vec<PathSegment<'a>>,
+    visitor: &mut impl Visitor,
+) -> bool {
+    match visitor.visit(path, node) {
+        Visit::Continue => {}
+        Visit::SkipChildren => return true,
+        Visit::Stop => return false,
+    }
+    match node {
+        Yaml::Array(array) => {
+            for (i, child) in array.iter().enumerate() {
+                path.push(PathSegment::Index(i));
+                let keep_going = walk_impl(child, path, visitor);
+                path.pop();
+                if !keep_going {
+                    return false;
+                }
+            }
+        }
+        Yaml::Hash(hash) => {
+            for (key, child) in hash {
+                path.push(PathSegment::Key(key));
+                let keep_going = walk_impl(child, path, visitor);
+                path.pop();
+                if !keep_going {
+                    return false;
+                }
+            }
+        }
+        _ => {}
+    }
+    true
+}
+
+/// Recursively visit `node` and its children. Return `false` if the traversal must stop.
+fn walk_mut_impl<'a>(
+    node: &'a mut Yaml,
+    path: &mut Vec<PathSegment<'a>>,
+    visitor: &mut impl VisitorMut,
+) -> bool {
+    match visitor.visit_mut(path, node) {
+        Visit::Continue => {}
+        Visit::SkipChildren => return true,
+        Visit::Stop => return false,
+    }
+    match node {
+        Yaml::Array(array) => {
+            for (i, child) in array.iter_mut().enumerate() {
+                path.push(PathSegment::Index(i));
+                let keep_going = walk_mut_impl(child, path, visitor);
+                path.pop();
+                if !keep_going {
+                    return false;
+                }
+            }
+        }
+        Yaml::Hash(hash) => {
+            for (key, child) in hash.iter_mut() {
+                path.push(PathSegment::Key(key));
+                let keep_going = walk_mut_impl(child, path, visitor);
+                path.pop();
+                if !keep_going {
+                    return false;
+                }
+            }
+        }
+        _ => {}
+    }
+    true
+}
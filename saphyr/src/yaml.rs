@@ -2,12 +2,23 @@
 
 #![allow(clippy::module_name_repetitions)]
 
-use std::{convert::TryFrom, ops::Index, ops::IndexMut};
+use std::{
+    collections::hash_map::DefaultHasher,
+    convert::TryFrom,
+    hash::{Hash as _, Hasher},
+    ops::Index,
+    ops::IndexMut,
+};
 
-use hashlink::LinkedHashMap;
+use hashlink::{linked_hash_map, LinkedHashMap};
 use saphyr_parser::{BufferedInput, Input, Parser, ScanError};
 
-use crate::{loader::parse_f64, YamlLoader};
+use crate::{
+    loader::{
+        parse_core_schema_bool, parse_core_schema_fp, parse_core_schema_int, parse_core_schema_null,
+    },
+    YamlLoader,
+};
 
 /// A YAML node is stored as this `Yaml` enumeration, which provides an easy way to
 /// access your YAML document.
@@ -87,7 +98,7 @@ impl Yaml {
     /// # Errors
     /// Returns `ScanError` when loading fails.
     pub fn load_from_str(source: &str) -> Result<Vec<Self>, ScanError> {
-        Self::load_from_iter(source.chars())
+        Self::load_from_iter(source.chars()).map_err(|error| error.with_source(source))
     }
 
     /// Load the contents of the given iterator as an array of YAML documents.
@@ -146,7 +157,7 @@ impl Yaml {
     #[must_use]
     pub fn as_f64(&self) -> Option<f64> {
         if let Yaml::Real(ref v) = self {
-            parse_f64(v)
+            parse_core_schema_fp(v)
         } else {
             None
         }
@@ -188,6 +199,473 @@ impl Yaml {
             this => this,
         }
     }
+
+    /// Take the node out of `self`, leaving a [`Yaml::BadValue`] in its place.
+    ///
+    /// This allows moving a node out of a tree without cloning it.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let mut doc = Yaml::load_from_str("a: [1, 2]").unwrap().remove(0);
+    /// let a = doc["a"].take();
+    /// assert_eq!(a[1].as_i64(), Some(2));
+    /// assert!(doc["a"].is_badvalue());
+    /// ```
+    #[must_use]
+    pub fn take(&mut self) -> Self {
+        std::mem::replace(self, Yaml::BadValue)
+    }
+
+    /// Replace the node with `with`, returning the previous node.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let mut doc = Yaml::load_from_str("a: 1").unwrap().remove(0);
+    /// let old = doc["a"].replace(Yaml::Integer(2));
+    /// assert_eq!(old.as_i64(), Some(1));
+    /// assert_eq!(doc["a"].as_i64(), Some(2));
+    /// ```
+    #[must_use = "use `*node = with` to discard the previous node"]
+    pub fn replace(&mut self, with: Self) -> Self {
+        std::mem::replace(self, with)
+    }
+
+    /// Swap the node with `other`.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let mut doc = Yaml::load_from_str("[a, b]").unwrap().remove(0);
+    /// let array = doc.as_mut_vec().unwrap();
+    /// let (first, rest) = array.split_first_mut().unwrap();
+    /// first.swap(&mut rest[0]);
+    /// assert_eq!(doc[0].as_str(), Some("b"));
+    /// ```
+    pub fn swap(&mut self, other: &mut Self) {
+        std::mem::swap(self, other);
+    }
+
+    /// Convert the elements of `self` to `T` if it is a [`Yaml::Array`].
+    ///
+    /// Return `None` if `self` is not an array or if any of its elements cannot be converted. See
+    /// [`Yaml::try_as_vec_of`] to find out which element could not be converted.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let doc = Yaml::load_from_str("ports: [80, 443]").unwrap().remove(0);
+    /// assert_eq!(doc["ports"].as_vec_of::<u16>(), Some(vec![80, 443]));
+    /// assert_eq!(doc["ports"].as_vec_of::<u8>(), None);
+    /// ```
+    #[must_use]
+    pub fn as_vec_of<'a, T: TryFrom<&'a Yaml>>(&'a self) -> Option<Vec<T>> {
+        self.try_as_vec_of().and_then(Result::ok)
+    }
+
+    /// Convert the elements of `self` to `T` if it is a [`Yaml::Array`].
+    ///
+    /// Return `None` if `self` is not an array. If an element cannot be converted, return its
+    /// index along with the conversion error.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let doc = Yaml::load_from_str("[80, 443, http]").unwrap().remove(0);
+    /// let (index, error) = doc.try_as_vec_of::<u16>().unwrap().unwrap_err();
+    /// assert_eq!(index, 2);
+    /// assert_eq!(error.to_string(), "node is not a valid `u16`");
+    /// ```
+    #[must_use]
+    #[allow(clippy::type_complexity)]
+    pub fn try_as_vec_of<'a, T: TryFrom<&'a Yaml>>(
+        &'a self,
+    ) -> Option<Result<Vec<T>, (usize, T::Error)>> {
+        let array = self.as_vec()?;
+        Some(
+            array
+                .iter()
+                .enumerate()
+                .map(|(i, node)| T::try_from(node).map_err(|e| (i, e)))
+                .collect(),
+        )
+    }
+
+    /// Consume `self` and iterate over its elements if it is a [`Yaml::Array`].
+    ///
+    /// Nothing is yielded if `self` is not an array. This is the same as [`Yaml::into_iter`].
+    ///
+    /// [`Yaml::into_iter`]: IntoIterator::into_iter
+    #[must_use]
+    pub fn into_sequence_iter(self) -> YamlIter {
+        self.into_iter()
+    }
+
+    /// Consume `self` and iterate over its entries, in order, if it is a [`Yaml::Hash`].
+    ///
+    /// Nothing is yielded if `self` is not a hash. Keys and values are moved out rather than
+    /// cloned.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let doc = Yaml::load_from_str("a: 1\nb: 2").unwrap().remove(0);
+    /// let swapped: Vec<_> = doc.into_mapping_iter().map(|(k, v)| (v, k)).collect();
+    /// assert_eq!(swapped[1], (Yaml::Integer(2), Yaml::String("b".into())));
+    /// ```
+    #[must_use]
+    pub fn into_mapping_iter(self) -> linked_hash_map::IntoIter<Yaml, Yaml> {
+        self.into_hash().unwrap_or_default().into_iter()
+    }
+
+    /// Iterate over the entries of `self`, in order, with string keys as `&str`, if it is a
+    /// [`Yaml::Hash`].
+    ///
+    /// Keys which are not a [`Yaml::String`] are yielded as `None`. Nothing is yielded if `self`
+    /// is not a hash.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let doc = Yaml::load_from_str("name: foo\n1: one\nport: 80").unwrap().remove(0);
+    /// let entries: Vec<_> = doc.iter_str().map(|(k, v)| (k, v.as_i64())).collect();
+    /// assert_eq!(entries, [(Some("name"), None), (None, None), (Some("port"), Some(80))]);
+    /// ```
+    pub fn iter_str(&self) -> impl Iterator<Item = (Option<&str>, &Yaml)> {
+        self.as_hash()
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// Iterate over the keys of `self` which are strings, in order, if it is a [`Yaml::Hash`].
+    ///
+    /// Keys which are not a [`Yaml::String`] are skipped. Nothing is yielded if `self` is not a
+    /// hash.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let doc = Yaml::load_from_str("name: foo\n1: one\nport: 80").unwrap().remove(0);
+    /// assert_eq!(doc.keys_str().collect::<Vec<_>>(), ["name", "port"]);
+    /// ```
+    pub fn keys_str(&self) -> impl Iterator<Item = &str> {
+        self.iter_str().filter_map(|(key, _)| key)
+    }
+
+    /// Return the value associated with `key` if `self` is a [`Yaml::Hash`].
+    ///
+    /// Unlike indexing with a `&str`, this allows looking up keys of any type:
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let doc = Yaml::load_from_str("~: a\ntrue: b\n[1, 2]: c").unwrap().remove(0);
+    /// assert_eq!(doc.as_hash_get(&Yaml::Null).and_then(Yaml::as_str), Some("a"));
+    /// assert_eq!(doc.as_hash_get(&Yaml::Boolean(true)).and_then(Yaml::as_str), Some("b"));
+    /// let key = Yaml::Array(vec![Yaml::Integer(1), Yaml::Integer(2)]);
+    /// assert_eq!(doc[&key].as_str(), Some("c"));
+    /// ```
+    #[must_use]
+    pub fn as_hash_get(&self, key: &Yaml) -> Option<&Yaml> {
+        self.as_hash().and_then(|hash| hash.get(key))
+    }
+
+    /// Return the value associated with `key` if `self` is a [`Yaml::Hash`].
+    ///
+    /// See [`Yaml::as_hash_get`].
+    #[must_use]
+    pub fn as_mut_hash_get(&mut self, key: &Yaml) -> Option<&mut Yaml> {
+        self.as_mut_hash().and_then(|hash| hash.get_mut(key))
+    }
+
+    /// Return the string associated with `key`, or `default` if there is none.
+    ///
+    /// `default` is returned if `self` is not a [`Yaml::Hash`], if it has no `key` or if its
+    /// value is not a [`Yaml::String`].
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let doc = Yaml::load_from_str("host: example.com\nport: 8080").unwrap().remove(0);
+    /// assert_eq!(doc.get_str_or("host", "localhost"), "example.com");
+    /// assert_eq!(doc.get_str_or("user", "root"), "root");
+    /// assert_eq!(doc.get_i64_or("port", 80), 8080);
+    /// assert!(!doc.get_bool_or("tls", false));
+    /// ```
+    #[must_use]
+    pub fn get_str_or<'a>(&'a self, key: &str, default: &'a str) -> &'a str {
+        self[key].as_str().unwrap_or(default)
+    }
+
+    /// Return the integer associated with `key`, or `default` if there is none.
+    ///
+    /// See [`Yaml::get_str_or`].
+    #[must_use]
+    pub fn get_i64_or(&self, key: &str, default: i64) -> i64 {
+        self[key].as_i64().unwrap_or(default)
+    }
+
+    /// Return the real associated with `key`, or `default` if there is none.
+    ///
+    /// See [`Yaml::get_str_or`].
+    #[must_use]
+    pub fn get_f64_or(&self, key: &str, default: f64) -> f64 {
+        self[key].as_f64().unwrap_or(default)
+    }
+
+    /// Return the boolean associated with `key`, or `default` if there is none.
+    ///
+    /// See [`Yaml::get_str_or`].
+    #[must_use]
+    pub fn get_bool_or(&self, key: &str, default: bool) -> bool {
+        self[key].as_bool().unwrap_or(default)
+    }
+
+    /// Parse the scalar associated with `key` with [`FromStr`].
+    ///
+    /// Strings are parsed from their contents and other scalars from their YAML text (see
+    /// [`Yaml::to_yaml_string`]), so that e.g. `port: 8080` can be parsed as a `u16`. Return
+    /// `None` if `self` is not a [`Yaml::Hash`], if it has no `key`, if its value is not a scalar
+    /// or if it fails to parse.
+    ///
+    /// [`FromStr`]: std::str::FromStr
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// # use std::net::Ipv4Addr;
+    /// let doc = Yaml::load_from_str("ip: 10.0.0.1\nport: 8080").unwrap().remove(0);
+    /// assert_eq!(doc.get_parsed("ip"), Some(Ipv4Addr::new(10, 0, 0, 1)));
+    /// assert_eq!(doc.get_parsed::<u16>("port"), Some(8080));
+    /// assert_eq!(doc.get_parsed::<u8>("port"), None);
+    /// ```
+    #[must_use]
+    pub fn get_parsed<T: std::str::FromStr>(&self, key: &str) -> Option<T> {
+        match &self[key] {
+            Yaml::String(s) => s.parse().ok(),
+            Yaml::BadValue => None,
+            value => value.to_yaml_string()?.parse().ok(),
+        }
+    }
+
+    /// Sort the entries of the hash by key.
+    ///
+    /// This does nothing if `self` is not a [`Yaml::Hash`]. Only the entries of `self` are sorted,
+    /// not those of nested hashes. Elements of arrays and entries of hashes can be filtered in
+    /// place with [`Vec::retain`] and [`LinkedHashMap::retain`] respectively.
+    ///
+    /// [`LinkedHashMap::retain`]: hashlink::LinkedHashMap::retain
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let mut doc = Yaml::load_from_str("b: 1\na: 2\nc: 3").unwrap().remove(0);
+    /// doc.sort_keys();
+    /// let keys: Vec<_> = doc.as_hash().unwrap().keys().filter_map(Yaml::as_str).collect();
+    /// assert_eq!(keys, ["a", "b", "c"]);
+    /// ```
+    pub fn sort_keys(&mut self) {
+        if let Yaml::Hash(hash) = self {
+            let mut entries: Vec<_> = std::mem::take(hash).into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            hash.extend(entries);
+        }
+    }
+
+    /// Rename the key `from` of the hash to `to`, keeping the entry at its position.
+    ///
+    /// If `to` is already a key of the hash, its entry is removed. Return `false`, leaving `self`
+    /// untouched, if `self` is not a [`Yaml::Hash`] or if `from` is not one of its keys.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let mut doc = Yaml::load_from_str("a: 1\nb: 2").unwrap().remove(0);
+    /// assert!(doc.rename_key(&Yaml::String("a".into()), Yaml::String("c".into())));
+    /// let keys: Vec<_> = doc.as_hash().unwrap().keys().filter_map(Yaml::as_str).collect();
+    /// assert_eq!(keys, ["c", "b"]);
+    /// ```
+    pub fn rename_key(&mut self, from: &Yaml, to: Yaml) -> bool {
+        let Yaml::Hash(hash) = self else {
+            return false;
+        };
+        if !hash.contains_key(from) {
+            return false;
+        }
+        if from == &to {
+            return true;
+        }
+        hash.remove(&to);
+        let mut to = Some(to);
+        self.map_keys(|key| {
+            if &key == from {
+                to.take().unwrap_or(key)
+            } else {
+                key
+            }
+        });
+        true
+    }
+
+    /// Replace each key of the hash with the result of `f`, keeping entries in order.
+    ///
+    /// This does nothing if `self` is not a [`Yaml::Hash`]. Keys of a [`LinkedHashMap`] can't be
+    /// modified in place, so the hash is rebuilt, moving its keys and values without cloning them.
+    /// If `f` maps several keys to the same key, the value of the last of them is kept, at the
+    /// position of the first.
+    ///
+    /// [`LinkedHashMap`]: hashlink::LinkedHashMap
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let mut doc = Yaml::load_from_str("B: 1\na: 2\nC: 3").unwrap().remove(0);
+    /// doc.map_keys(|key| match key {
+    ///     Yaml::String(key) => Yaml::String(key.to_lowercase()),
+    ///     key => key,
+    /// });
+    /// assert_eq!(doc.keys_str().collect::<Vec<_>>(), ["b", "a", "c"]);
+    /// ```
+    pub fn map_keys(&mut self, mut f: impl FnMut(Yaml) -> Yaml) {
+        let result: Result<(), std::convert::Infallible> =
+            self.try_map_entries(|key, value| Ok((f(key), value)));
+        let Ok(()) = result;
+    }
+
+    /// Replace each entry of the hash with the result of `f`, keeping entries in order.
+    ///
+    /// This does nothing if `self` is not a [`Yaml::Hash`]. As with [`Yaml::map_keys`], the hash
+    /// is rebuilt without cloning, and the value of the last of several entries mapped to the same
+    /// key is kept, at the position of the first.
+    ///
+    /// # Errors
+    /// Returns the first error of `f`. The hash then holds the entries mapped before the failing
+    /// one, followed by the entries `f` was not called on. The failing entry is lost.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let mut doc = Yaml::load_from_str("a: 1\nb: 2").unwrap().remove(0);
+    /// doc.try_map_entries(|key, value| match value {
+    ///     Yaml::Integer(i) => Ok((key, Yaml::Integer(i * 10))),
+    ///     _ => Err("not an integer"),
+    /// })
+    /// .unwrap();
+    /// assert_eq!(doc["b"].as_i64(), Some(20));
+    ///
+    /// let mut doc = Yaml::load_from_str("a: 1\nb: x\nc: 3").unwrap().remove(0);
+    /// let result = doc.try_map_entries(|key, value| match value {
+    ///     Yaml::Integer(i) => Ok((key, Yaml::Integer(i * 10))),
+    ///     _ => Err("not an integer"),
+    /// });
+    /// assert_eq!(result, Err("not an integer"));
+    /// assert_eq!(doc["a"].as_i64(), Some(10));
+    /// assert_eq!(doc["c"].as_i64(), Some(3));
+    /// ```
+    pub fn try_map_entries<E>(
+        &mut self,
+        mut f: impl FnMut(Yaml, Yaml) -> Result<(Yaml, Yaml), E>,
+    ) -> Result<(), E> {
+        let Yaml::Hash(hash) = self else {
+            return Ok(());
+        };
+        let mut entries = std::mem::take(hash).into_iter();
+        for (key, value) in entries.by_ref() {
+            match f(key, value) {
+                Ok((key, value)) => {
+                    hash.replace(key, value);
+                }
+                Err(error) => {
+                    for (key, value) in entries {
+                        hash.replace(key, value);
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Deep-merge `other` into `self`, as when overlaying a configuration with another.
+    ///
+    /// If both are hashes, each entry of `other` is merged into the value of the same key in
+    /// `self`, or appended if `self` has no such key. Otherwise, `self` is replaced by `other`:
+    /// arrays are not concatenated, and a null in `other` replaces what it is merged into.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let mut doc = Yaml::load_from_str("{a: {b: 1, c: 2}, d: [1]}").unwrap().remove(0);
+    /// let other = Yaml::load_from_str("{a: {c: 3}, d: [2], e: 4}").unwrap().remove(0);
+    /// doc.merge_from(other);
+    /// let expected = Yaml::load_from_str("{a: {b: 1, c: 3}, d: [2], e: 4}").unwrap().remove(0);
+    /// assert_eq!(doc, expected);
+    /// ```
+    pub fn merge_from(&mut self, other: Yaml) {
+        match (&mut *self, other) {
+            (Yaml::Hash(hash), Yaml::Hash(other)) => {
+                for (key, value) in other {
+                    match hash.get_mut(&key) {
+                        Some(existing) => existing.merge_from(value),
+                        None => {
+                            hash.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (_, other) => *self = other,
+        }
+    }
+
+    /// Hash the semantic contents of the tree.
+    ///
+    /// Two trees which only differ by the way they were written have the same hash: the order of
+    /// the entries of hashes is not taken into account, and reals are hashed by value (`1.0`,
+    /// `1.00` and `1e0` are the same, as are `0.0` and `-0.0`, and all NaNs). Scalars are hashed
+    /// as they were resolved, so `1` and `"1"` differ, as do `1` and `1.0`.
+    ///
+    /// The hash is computed with [`DefaultHasher`]. It is the same for all trees built by a given
+    /// binary, but may change between Rust versions and should not be persisted.
+    ///
+    /// ```
+    /// # use saphyr::Yaml;
+    /// let a = &Yaml::load_from_str("{a: 1.0, b: [x, 'y']}").unwrap()[0];
+    /// let b = &Yaml::load_from_str("b:\n  - \"x\"\n  - y\na: 1.00").unwrap()[0];
+    /// let c = &Yaml::load_from_str("{a: 1, b: [x, y]}").unwrap()[0];
+    /// assert_eq!(a.canonical_hash(), b.canonical_hash());
+    /// assert_ne!(a.canonical_hash(), c.canonical_hash());
+    /// ```
+    #[must_use]
+    pub fn canonical_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash_canonical(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Feed the semantic contents of the tree to `state`. See [`Self::canonical_hash`].
+    fn hash_canonical<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Yaml::Real(v) => match parse_core_schema_fp(v) {
+                Some(f) if f.is_nan() => f64::NAN.to_bits().hash(state),
+                // Adding `0.0` turns `-0.0` into `0.0`.
+                Some(f) => (f + 0.0).to_bits().hash(state),
+                None => v.hash(state),
+            },
+            Yaml::Integer(v) => v.hash(state),
+            Yaml::String(v) => v.hash(state),
+            Yaml::Boolean(v) => v.hash(state),
+            Yaml::Array(array) => {
+                array.len().hash(state);
+                for item in array {
+                    item.hash_canonical(state);
+                }
+            }
+            Yaml::Hash(hash) => {
+                // Hash each entry separately and sort the hashes so that the order of the entries
+                // does not matter.
+                let mut entries: Vec<_> = hash
+                    .iter()
+                    .map(|(key, value)| {
+                        let mut hasher = DefaultHasher::new();
+                        key.hash_canonical(&mut hasher);
+                        value.hash_canonical(&mut hasher);
+                        hasher.finish()
+                    })
+                    .collect();
+                entries.sort_unstable();
+                entries.hash(state);
+            }
+            Yaml::Alias(v) => v.hash(state),
+            Yaml::Null | Yaml::BadValue => {}
+        }
+    }
 }
 
 #[allow(clippy::should_implement_trait)]
@@ -211,32 +689,16 @@ impl Yaml {
     /// ```
     #[must_use]
     pub fn from_str(v: &str) -> Yaml {
-        if let Some(number) = v.strip_prefix("0x") {
-            if let Ok(i) = i64::from_str_radix(number, 16) {
-                return Yaml::Integer(i);
-            }
-        } else if let Some(number) = v.strip_prefix("0o") {
-            if let Ok(i) = i64::from_str_radix(number, 8) {
-                return Yaml::Integer(i);
-            }
-        } else if let Some(number) = v.strip_prefix('+') {
-            if let Ok(i) = number.parse::<i64>() {
-                return Yaml::Integer(i);
-            }
-        }
-        match v {
-            "~" | "null" => Yaml::Null,
-            "true" => Yaml::Boolean(true),
-            "false" => Yaml::Boolean(false),
-            _ => {
-                if let Ok(integer) = v.parse::<i64>() {
-                    Yaml::Integer(integer)
-                } else if parse_f64(v).is_some() {
-                    Yaml::Real(v.to_owned())
-                } else {
-                    Yaml::String(v.to_owned())
-                }
-            }
+        if parse_core_schema_null(v) {
+            Yaml::Null
+        } else if let Some(b) = parse_core_schema_bool(v) {
+            Yaml::Boolean(b)
+        } else if let Some(i) = parse_core_schema_int(v) {
+            Yaml::Integer(i)
+        } else if parse_core_schema_fp(v).is_some() {
+            Yaml::Real(v.to_owned())
+        } else {
+            Yaml::String(v.to_owned())
         }
     }
 }
@@ -270,6 +732,29 @@ impl<'a> IndexMut<&'a str> for Yaml {
     }
 }
 
+impl<'a> Index<&'a Yaml> for Yaml {
+    type Output = Yaml;
+
+    fn index(&self, key: &'a Yaml) -> &Yaml {
+        self.as_hash_get(key).unwrap_or(&BAD_VALUE)
+    }
+}
+
+impl<'a> IndexMut<&'a Yaml> for Yaml {
+    /// Perform indexing if `self` is a mapping.
+    ///
+    /// # Panics
+    /// This function panics if the key given does not exist within `self` (as per [`Index`]).
+    ///
+    /// This function also panics if `self` is not a [`Yaml::Hash`].
+    fn index_mut(&mut self, key: &'a Yaml) -> &mut Yaml {
+        match self.as_mut_hash() {
+            Some(h) => h.get_mut(key).unwrap(),
+            None => panic!("Not a hash type"),
+        }
+    }
+}
+
 impl Index<usize> for Yaml {
     type Output = Yaml;
 
@@ -330,3 +815,77 @@ impl Iterator for YamlIter {
         self.yaml.next()
     }
 }
+
+/// The error returned when converting a [`Yaml`] node to a Rust type fails.
+///
+/// This happens when the node is of another type, or is an integer out of the range of the
+/// target type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TryFromYamlError {
+    /// The name of the type the node was converted to.
+    pub expected: &'static str,
+}
+
+impl std::fmt::Display for TryFromYamlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "node is not a valid `{}`", self.expected)
+    }
+}
+
+impl std::error::Error for TryFromYamlError {}
+
+/// Implement `TryFrom<&Yaml>` for integer types, from [`Yaml::Integer`] nodes in range.
+macro_rules! impl_try_from_yaml_int (
+    ($($t:ident),*) => ($(
+impl TryFrom<&Yaml> for $t {
+    type Error = TryFromYamlError;
+
+    fn try_from(node: &Yaml) -> Result<Self, Self::Error> {
+        node.as_i64()
+            .and_then(|i| $t::try_from(i).ok())
+            .ok_or(TryFromYamlError { expected: stringify!($t) })
+    }
+}
+    )*);
+);
+
+impl_try_from_yaml_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl TryFrom<&Yaml> for bool {
+    type Error = TryFromYamlError;
+
+    fn try_from(node: &Yaml) -> Result<Self, Self::Error> {
+        node.as_bool().ok_or(TryFromYamlError { expected: "bool" })
+    }
+}
+
+impl TryFrom<&Yaml> for f64 {
+    type Error = TryFromYamlError;
+
+    /// Convert a [`Yaml::Real`] or a [`Yaml::Integer`] node to an `f64`.
+    #[allow(clippy::cast_precision_loss)]
+    fn try_from(node: &Yaml) -> Result<Self, Self::Error> {
+        match node {
+            Yaml::Integer(i) => Ok(*i as f64),
+            _ => node.as_f64().ok_or(TryFromYamlError { expected: "f64" }),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a Yaml> for &'a str {
+    type Error = TryFromYamlError;
+
+    fn try_from(node: &'a Yaml) -> Result<Self, Self::Error> {
+        node.as_str().ok_or(TryFromYamlError { expected: "&str" })
+    }
+}
+
+impl TryFrom<&Yaml> for String {
+    type Error = TryFromYamlError;
+
+    fn try_from(node: &Yaml) -> Result<Self, Self::Error> {
+        node.as_str()
+            .map(ToOwned::to_owned)
+            .ok_or(TryFromYamlError { expected: "String" })
+    }
+}
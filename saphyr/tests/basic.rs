@@ -1,7 +1,11 @@
 #![allow(clippy::bool_assert_comparison)]
 #![allow(clippy::float_cmp)]
 
-use saphyr::{Yaml, YamlEmitter};
+use saphyr::{
+    LoaderLintKind, MarkedYaml, NanKeyPolicy, Provenance, SharedYaml, Yaml, YamlData, YamlDocument,
+    YamlEmitter, YamlLoader,
+};
+use saphyr_parser::{BlockScalarHeader, Parser, Span, TScalarStyle};
 
 #[test]
 fn test_api() {
@@ -147,6 +151,21 @@ fn test_plain_datatype() {
     assert!(!doc[25][1].as_bool().unwrap());
 }
 
+#[test]
+fn test_verbatim_core_tags() {
+    let s = "
+- !<tag:yaml.org,2002:int> 100
+- !<tag:yaml.org,2002:str> 100
+- !<tag:yaml.org,2002:bool> string
+";
+    let out = Yaml::load_from_str(s).unwrap();
+    let doc = &out[0];
+
+    assert_eq!(doc[0].as_i64().unwrap(), 100);
+    assert_eq!(doc[1].as_str().unwrap(), "100");
+    assert!(doc[2].is_badvalue());
+}
+
 #[test]
 fn test_plain_datatype_with_into_methods() {
     let s = "
@@ -233,3 +252,630 @@ fn test_integer_key() {
     let first = out.into_iter().next().unwrap();
     assert_eq!(first[0]["important"].as_bool().unwrap(), true);
 }
+
+#[test]
+fn test_record_anchors() {
+    let s = "
+a: &x [1, 2]
+b: &y 3
+c: &x 4
+---
+- &z { d: e }
+";
+    let mut loader = YamlLoader::<Yaml>::default().record_anchors(true);
+    Parser::new_from_str(s).load(&mut loader, true).unwrap();
+    let anchors: Vec<_> = loader
+        .anchors()
+        .iter()
+        .map(|(id, anchor)| (*id, anchor.name.as_str(), anchor.span.start.line()))
+        .collect();
+    assert_eq!(
+        anchors,
+        [(1, "x", 2), (2, "y", 3), (3, "x", 4), (4, "z", 6)]
+    );
+
+    let mut loader = YamlLoader::<Yaml>::default();
+    Parser::new_from_str(s).load(&mut loader, true).unwrap();
+    assert!(loader.anchors().is_empty());
+}
+
+#[test]
+fn test_document_metadata() {
+    let s = "%TAG !e! tag:example.com,2000:
+--- !e!foo
+b: &x 2
+...
+%YAML 1.2
+---
+- c
+--- d
+";
+    let docs = YamlDocument::<Yaml>::load_from_str(s).unwrap();
+    assert_eq!(docs.len(), 3);
+
+    assert!(docs[0].explicit_start && docs[0].explicit_end);
+    assert_eq!(docs[0].version, None);
+    assert_eq!(
+        docs[0].tags,
+        [("!e!".to_owned(), "tag:example.com,2000:".to_owned())]
+    );
+    assert_eq!(docs[0].root["b"].as_i64(), Some(2));
+    assert_eq!(docs[0].anchors[&1].name, "x");
+    assert_eq!(docs[0].span.start.line(), 2);
+    assert_eq!(docs[0].span.end.line(), 4);
+
+    assert!(docs[1].explicit_start && !docs[1].explicit_end);
+    assert_eq!(docs[1].version, Some((1, 2)));
+    assert!(docs[1].tags.is_empty());
+    assert_eq!(docs[1].root[0].as_str(), Some("c"));
+    assert_eq!(docs[1].span.start.line(), 6);
+    assert_eq!(docs[1].span.end.line(), 8);
+
+    assert!(docs[2].explicit_start && !docs[2].explicit_end);
+    assert_eq!(docs[2].version, None);
+    assert!(docs[2].anchors.is_empty());
+
+    let docs = YamlDocument::<Yaml>::load_from_str("a: 1").unwrap();
+    assert!(!docs[0].explicit_start && !docs[0].explicit_end);
+    assert_eq!(docs[0].root["a"].as_i64(), Some(1));
+}
+
+#[test]
+fn test_rename_key() {
+    let key = |s: &str| Yaml::String(s.to_owned());
+    let keys = |doc: &Yaml| -> Vec<String> {
+        doc.as_hash()
+            .unwrap()
+            .keys()
+            .map(|k| k.as_str().unwrap().to_owned())
+            .collect()
+    };
+    let mut doc = Yaml::load_from_str("a: 1\nb: 2\nc: 3").unwrap().remove(0);
+
+    // Renaming onto an existing key drops that entry.
+    assert!(doc.rename_key(&key("c"), key("a")));
+    assert_eq!(keys(&doc), ["b", "a"]);
+    assert_eq!(doc["a"].as_i64(), Some(3));
+
+    assert!(!doc.rename_key(&key("z"), key("y")));
+    assert!(doc.rename_key(&key("b"), key("b")));
+    assert_eq!(keys(&doc), ["b", "a"]);
+    assert!(!Yaml::Integer(1).rename_key(&key("b"), key("c")));
+
+    doc.sort_keys();
+    assert_eq!(keys(&doc), ["a", "b"]);
+}
+
+#[test]
+fn test_map_keys() {
+    let mut doc = Yaml::load_from_str("a: 1\nB: 2\nA: 3\nc: 4")
+        .unwrap()
+        .remove(0);
+
+    // Colliding keys keep the last value at the position of the first.
+    doc.map_keys(|key| Yaml::String(key.as_str().unwrap().to_lowercase()));
+    assert_eq!(doc.keys_str().collect::<Vec<_>>(), ["a", "b", "c"]);
+    assert_eq!(doc["a"].as_i64(), Some(3));
+
+    let mut scalar = Yaml::Integer(1);
+    scalar.map_keys(|_| unreachable!());
+    assert_eq!(scalar, Yaml::Integer(1));
+    assert_eq!(
+        scalar.try_map_entries(|_, _| -> Result<_, ()> { unreachable!() }),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_non_string_keys() {
+    let mut doc = Yaml::load_from_str("1: a\n~: b\nfalse: c\n1.5: d\n{x: y}: e")
+        .unwrap()
+        .remove(0);
+    let complex_key = Yaml::load_from_str("{x: y}").unwrap().remove(0);
+
+    assert_eq!(doc[1].as_str(), Some("a"));
+    assert_eq!(doc[&Yaml::Integer(1)].as_str(), Some("a"));
+    assert_eq!(doc[&Yaml::Null].as_str(), Some("b"));
+    assert_eq!(doc[&Yaml::Boolean(false)].as_str(), Some("c"));
+    assert_eq!(doc[&Yaml::Real("1.5".to_owned())].as_str(), Some("d"));
+    assert_eq!(doc[&complex_key].as_str(), Some("e"));
+    assert!(doc[&Yaml::Boolean(true)].is_badvalue());
+    assert!(Yaml::Integer(1)[&Yaml::Null].is_badvalue());
+    assert_eq!(Yaml::Integer(1).as_hash_get(&Yaml::Null), None);
+
+    doc[&Yaml::Null] = Yaml::Integer(2);
+    *doc.as_mut_hash_get(&Yaml::Boolean(false)).unwrap() = Yaml::Integer(3);
+    assert_eq!(doc[&Yaml::Null].as_i64(), Some(2));
+    assert_eq!(doc[&Yaml::Boolean(false)].as_i64(), Some(3));
+    assert!(doc.as_mut_hash_get(&Yaml::Boolean(true)).is_none());
+}
+
+#[test]
+fn test_record_duplicate_keys() {
+    let s = "a: 1\nb: {c: 2, c: 3}\na: 4\na: 5";
+    let mut loader = YamlLoader::<MarkedYaml>::default().record_duplicate_keys(true);
+    Parser::new_from_str(s).load(&mut loader, true).unwrap();
+    let duplicates: Vec<_> = loader
+        .duplicate_keys()
+        .iter()
+        .map(|dup| {
+            (
+                dup.key.data.as_str().unwrap(),
+                dup.key.span.start.line(),
+                dup.value.data.as_i64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(duplicates, [("c", 2, 2), ("a", 3, 1), ("a", 4, 4)]);
+
+    // The entry keeps its first key and moves to the back, as without recording.
+    let doc = &loader.into_documents()[0];
+    let (key, value) = doc.data.as_hash().unwrap().back().unwrap();
+    assert_eq!(key.span.start.line(), 1);
+    assert_eq!(value.data.as_i64(), Some(5));
+    assert_eq!(doc.data["b"].data["c"].data.as_i64(), Some(3));
+
+    // The loaded tree is the same without recording, spans included.
+    let plain = &MarkedYaml::load_from_str(s).unwrap()[0];
+    assert_eq!(plain, doc);
+    assert_eq!(plain.to_debug_yaml(), doc.to_debug_yaml());
+}
+
+#[test]
+fn test_hash_entry_hook() {
+    let s = "a: 1\nb:\n  - x\n  - y\nc: {d: 2}\n";
+    let spans = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+    let hook_spans = spans.clone();
+    let mut loader =
+        YamlLoader::<Yaml>::default().hash_entry_hook(move |key, key_span, value, value_span| {
+            hook_spans.borrow_mut().push((
+                key.as_str().unwrap().to_owned(),
+                (key_span.start.line(), key_span.start.col()),
+                (value_span.start.line(), value_span.start.col()),
+                (value_span.end.line(), value_span.end.col()),
+            ));
+            if let Some(i) = value.as_i64() {
+                *value = Yaml::Integer(i * 10);
+            }
+        });
+    Parser::new_from_str(s).load(&mut loader, true).unwrap();
+
+    let doc = &loader.into_documents()[0];
+    assert_eq!(doc["a"].as_i64(), Some(10));
+    assert_eq!(doc["c"]["d"].as_i64(), Some(20));
+    assert_eq!(
+        *spans.borrow(),
+        [
+            ("a".to_owned(), (1, 0), (1, 3), (1, 4)),
+            ("b".to_owned(), (2, 0), (3, 2), (4, 5)),
+            ("d".to_owned(), (5, 4), (5, 7), (5, 8)),
+            ("c".to_owned(), (5, 0), (5, 3), (5, 9)),
+        ]
+    );
+}
+
+#[test]
+fn test_scalar_hook() {
+    let s = "a: ${A}\nb: '${B}'\nc:\n  - ${MISSING}\n  - d\n";
+    let mut loader = YamlLoader::<Yaml>::default().scalar_hook(|value, _, _| {
+        match value.strip_prefix("${").and_then(|v| v.strip_suffix('}')) {
+            Some("A") => *value = "true".to_owned(),
+            Some("B") => *value = "false".to_owned(),
+            Some(var) => return Err(format!("unknown variable `{var}`")),
+            None => {}
+        }
+        Ok(())
+    });
+    Parser::new_from_str(s).load(&mut loader, true).unwrap();
+
+    let errors: Vec<_> = loader
+        .hook_errors()
+        .iter()
+        .map(|e| (e.marker().line(), e.marker().col(), e.info().to_owned()))
+        .collect();
+    assert_eq!(errors, [(4, 4, "unknown variable `MISSING`".to_owned())]);
+
+    let doc = &loader.into_documents()[0];
+    // Plain scalars are resolved after the hook, quoted ones are strings.
+    assert_eq!(doc["a"].as_bool(), Some(true));
+    assert_eq!(doc["b"].as_str(), Some("false"));
+    assert_eq!(doc["c"][0].as_str(), Some("${MISSING}"));
+}
+
+#[test]
+fn test_core_schema_tagged_scalars() {
+    // Tagged scalars are resolved with the same rules as untagged ones.
+    let doc = &Yaml::load_from_str("[!!int 0x2A, !!int +7, !!bool true, !!null ~, !!int 1.5]")
+        .unwrap()[0];
+    assert_eq!(doc[0].as_i64(), Some(42));
+    assert_eq!(doc[1].as_i64(), Some(7));
+    assert_eq!(doc[2].as_bool(), Some(true));
+    assert!(doc[3].is_null());
+    assert!(doc[4].is_badvalue());
+}
+
+#[test]
+fn test_yaml_1_1_numbers() {
+    let s = "[1_000, -0b1_01, 0x_1, 1_0.2_5, !!int 1_0, !!float 2_0, !!int 1_0.5, _1, in_f, 0b10]";
+    let mut loader = YamlLoader::<Yaml>::default().yaml_1_1_numbers(true);
+    Parser::new_from_str(s).load(&mut loader, true).unwrap();
+    let doc = &loader.into_documents()[0];
+    assert_eq!(doc[0].as_i64(), Some(1000));
+    assert_eq!(doc[1].as_i64(), Some(-5));
+    assert_eq!(doc[2].as_str(), Some("0x_1"));
+    assert_eq!(doc[3], Yaml::Real("10.25".to_owned()));
+    assert_eq!(doc[4].as_i64(), Some(10));
+    assert_eq!(doc[5], Yaml::Real("20".to_owned()));
+    assert!(doc[6].is_badvalue());
+    assert_eq!(doc[7].as_str(), Some("_1"));
+    assert_eq!(doc[8].as_str(), Some("in_f"));
+    assert_eq!(doc[9].as_i64(), Some(2));
+
+    // Without the option, these are strings.
+    let doc = &Yaml::load_from_str(s).unwrap()[0];
+    assert_eq!(doc[0].as_str(), Some("1_000"));
+    assert_eq!(doc[9].as_str(), Some("0b10"));
+}
+
+#[test]
+fn test_yaml_1_1_booleans() {
+    let s = "[yes, No, ON, y, off, True, !!bool YES, !!bool maybe, 'yes', yEs]";
+    let mut loader = YamlLoader::<Yaml>::default().yaml_1_1_booleans(true);
+    Parser::new_from_str(s).load(&mut loader, true).unwrap();
+    let doc = &loader.into_documents()[0];
+    assert_eq!(doc[0].as_bool(), Some(true));
+    assert_eq!(doc[1].as_bool(), Some(false));
+    assert_eq!(doc[2].as_bool(), Some(true));
+    assert_eq!(doc[3].as_bool(), Some(true));
+    assert_eq!(doc[4].as_bool(), Some(false));
+    assert_eq!(doc[5].as_bool(), Some(true));
+    assert_eq!(doc[6].as_bool(), Some(true));
+    assert!(doc[7].is_badvalue());
+    assert_eq!(doc[8].as_str(), Some("yes"));
+    assert_eq!(doc[9].as_str(), Some("yEs"));
+
+    // Without the option, these are strings.
+    let doc = &Yaml::load_from_str(s).unwrap()[0];
+    assert_eq!(doc[0].as_str(), Some("yes"));
+    assert_eq!(doc[5].as_str(), Some("True"));
+    assert!(doc[6].is_badvalue());
+}
+
+#[test]
+fn test_nan_keys() {
+    let s = "{.nan: a, .NAN: b, c: .NAN, .nan: d}";
+    let load = |policy| {
+        let mut loader = YamlLoader::<Yaml>::default().nan_keys(policy);
+        Parser::new_from_str(s).load(&mut loader, true).unwrap();
+        let errors: Vec<_> = loader
+            .hook_errors()
+            .iter()
+            .map(|e| e.marker().col())
+            .collect();
+        (loader.into_documents().remove(0), errors)
+    };
+
+    let (doc, errors) = load(NanKeyPolicy::AsWritten);
+    assert_eq!(doc.as_hash().unwrap().len(), 3);
+    assert!(errors.is_empty());
+
+    let (doc, errors) = load(NanKeyPolicy::Equal);
+    assert_eq!(doc.as_hash().unwrap().len(), 2);
+    assert_eq!(doc[&Yaml::Real(".nan".to_owned())].as_str(), Some("d"));
+    // Values are not affected.
+    assert_eq!(doc["c"], Yaml::Real(".NAN".to_owned()));
+    assert!(errors.is_empty());
+
+    let (doc, errors) = load(NanKeyPolicy::Error);
+    assert_eq!(doc.as_hash().unwrap().len(), 3);
+    assert_eq!(errors, [1, 10, 28]);
+}
+
+#[test]
+fn test_shared_yaml() {
+    let s = "a: &x [1, {b: 2}]\nc: *x\nd: e";
+    let doc = SharedYaml::load_from_str(s).unwrap().remove(0);
+    assert_eq!(
+        SharedYaml::from(Yaml::load_from_str(s).unwrap().remove(0)),
+        doc
+    );
+    // Aliases share their data with the anchored node.
+    assert!(doc["a"].ptr_eq(&doc["c"]));
+
+    let mut copy = doc.clone();
+    assert!(copy.ptr_eq(&doc));
+    copy.data_mut()["a"].data_mut()[1].data_mut()["b"] = YamlData::Integer(3).into();
+    assert_eq!(doc["a"][1]["b"].as_i64(), Some(2));
+    assert_eq!(copy["a"][1]["b"].as_i64(), Some(3));
+    assert_eq!(copy["c"][1]["b"].as_i64(), Some(2));
+    assert!(!copy.ptr_eq(&doc));
+    assert!(!copy["a"].ptr_eq(&doc["a"]));
+    assert!(copy["a"][0].ptr_eq(&doc["a"][0]));
+    assert!(copy["d"].ptr_eq(&doc["d"]));
+}
+
+#[test]
+fn test_canonical_hash() {
+    let hash = |s: &str| Yaml::load_from_str(s).unwrap()[0].canonical_hash();
+
+    // Formatting, key order and the way reals are written do not matter.
+    let base = hash("a: 1\nb: [x, {c: 0.5}]\nd: ~");
+    assert_eq!(base, hash("{d: null, b: ['x', {c: 5e-1}], a: 0x1}"));
+    assert_eq!(base, hash("---\nb:\n  - \"x\"\n  - c: .50\na: +1\nd:\n..."));
+    assert_eq!(hash("[-0.0, .nan]"), hash("[0.0, .NAN]"));
+
+    // Values, types and the order of array items do.
+    assert_ne!(base, hash("a: 2\nb: [x, {c: 0.5}]\nd: ~"));
+    assert_ne!(base, hash("a: '1'\nb: [x, {c: 0.5}]\nd: ~"));
+    assert_ne!(base, hash("a: 1\nb: [{c: 0.5}, x]\nd: ~"));
+    assert_ne!(hash("[1]"), hash("[1.0]"));
+    assert_ne!(hash("{a: b}"), hash("{b: a}"));
+    assert_ne!(hash("[[], []]"), hash("[[[]]]"));
+}
+
+#[test]
+fn test_marked_pair_spans() {
+    let s = "a: |\n  x\n\nb:\n  - 1\n  - 2\n# comment\nc: {d: , e: f}  # comment\n? g\n: h\n";
+    let doc = &MarkedYaml::load_from_str(s).unwrap()[0];
+    let pairs: Vec<String> = doc
+        .data
+        .as_hash()
+        .unwrap()
+        .iter()
+        .map(|(key, value)| {
+            assert_eq!(key.pair_span, value.pair_span);
+            let span = value.pair_span.unwrap();
+            s[span.start.index()..span.end.index()].to_owned()
+        })
+        .collect();
+    assert_eq!(
+        pairs,
+        ["a: |\n  x", "b:\n  - 1\n  - 2", "c: {d: , e: f}", "g\n: h"]
+    );
+
+    let d = doc.data["c"]
+        .data
+        .as_hash()
+        .unwrap()
+        .get_key_value(&MarkedYaml::from(YamlData::String("d".to_owned())));
+    let span = d.unwrap().1.pair_span.unwrap();
+    assert_eq!(&s[span.start.index()..span.end.index()], "d:");
+    assert_eq!(doc.data["b"].data[0].pair_span, None);
+    assert_eq!(doc.pair_span, None);
+}
+
+#[test]
+fn test_marked_source_slice() {
+    let s = "é: \"ü\"  # comment\nb:\n  - &a [1, {c: 'd]'}]\n  - e # comment\n\nf: *a\n";
+    let doc = &MarkedYaml::load_from_str(s).unwrap()[0];
+    let slices: Vec<_> = doc
+        .iter_depth_first()
+        .map(|(_, node)| node.source_slice(s).unwrap())
+        .collect();
+    assert_eq!(
+        slices,
+        [
+            "é: \"ü\"  # comment\nb:\n  - &a [1, {c: 'd]'}]\n  - e # comment\n\nf: *a",
+            "\"ü\"",
+            "- &a [1, {c: 'd]'}]\n  - e",
+            "[1, {c: 'd]'}]",
+            "1",
+            "{c: 'd]'}",
+            "'d]'",
+            "e",
+            "*a",
+            // The children of an alias are those of its anchor.
+            "1",
+            "{c: 'd]'}",
+            "'d]'",
+        ]
+    );
+
+    // The input is checked against the spans where it can be.
+    assert_eq!(doc.data["b"].source_slice("é"), None);
+    assert_eq!(
+        doc.data["b"].data[0].source_slice(&s.replace('[', "(")),
+        None
+    );
+}
+
+#[test]
+fn test_marked_anchor_spans() {
+    let s = "a: &x 1\nb: &y\n  c: *x\nd: &x [2]\ne: [*x, *y]\n";
+    let doc = &MarkedYaml::load_from_str(s).unwrap()[0];
+    let target = |node: &MarkedYaml| {
+        node.anchor_span
+            .map(|span| (span.start.line(), span.start.col()))
+    };
+    assert_eq!(target(&doc.data["a"]), None);
+    assert_eq!(target(&doc.data["b"].data["c"]), Some((1, 6)));
+    // Aliases refer to the last definition of their anchor.
+    assert_eq!(target(&doc.data["e"].data[0]), Some((4, 6)));
+    assert_eq!(target(&doc.data["e"].data[1]), Some((3, 2)));
+    // The anchored node keeps its own spans.
+    assert_eq!(doc.data["e"].data[1].data["c"].span.start.line(), 3);
+    assert_eq!(target(&doc.data["e"].data[1].data["c"]), Some((1, 6)));
+}
+
+#[test]
+fn test_keep_representations() {
+    let s = "a: !!str 1\nb: |\n  x\nc: &y \"${Y}\"\nd: *y\ne: [1]\n";
+    let load = |keep: bool| {
+        let mut loader = YamlLoader::<MarkedYaml>::default()
+            .keep_representations(keep)
+            .scalar_hook(|value, _, _| {
+                if value == "${Y}" {
+                    *value = "2".to_owned();
+                }
+                Ok(())
+            });
+        Parser::new_from_str(s).load(&mut loader, true).unwrap();
+        loader.into_documents().remove(0)
+    };
+
+    let doc = load(true);
+    let repr = |node: &MarkedYaml| {
+        let repr = node.representation.as_deref().unwrap();
+        (
+            repr.value.clone(),
+            repr.style,
+            repr.tag.as_ref().map(|tag| tag.to_string()),
+        )
+    };
+    assert_eq!(doc.data["a"].data.as_str(), Some("1"));
+    assert_eq!(
+        repr(&doc.data["a"]),
+        (
+            "1".to_owned(),
+            TScalarStyle::Plain,
+            Some("!!str".to_owned())
+        )
+    );
+    assert_eq!(
+        repr(&doc.data["b"]),
+        (
+            "x\n".to_owned(),
+            TScalarStyle::Literal(BlockScalarHeader::default()),
+            None
+        )
+    );
+    // The representation is taken before the scalar hook, and aliases share it.
+    assert_eq!(doc.data["c"].data.as_str(), Some("2"));
+    assert_eq!(repr(&doc.data["c"]).0, "${Y}");
+    assert_eq!(repr(&doc.data["d"]), repr(&doc.data["c"]));
+    assert!(doc.data["e"].representation.is_none());
+    assert!(doc.data["e"].data[0].representation.is_some());
+
+    let doc = load(false);
+    assert!(doc.data["a"].representation.is_none());
+}
+
+#[test]
+fn test_anchor_lints() {
+    let s = "a: &x 1\nb: &x 2\nc: *x\nd: &z [*z]\n---\n- &x 3\n- &w 4\n- *w\n";
+    let mut loader = YamlLoader::<Yaml>::default();
+    Parser::new_from_str(s).load(&mut loader, true).unwrap();
+    let lints: Vec<_> = loader
+        .lints()
+        .iter()
+        .map(|lint| {
+            (
+                lint.kind(),
+                lint.span().start.line(),
+                lint.span().start.col(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        lints,
+        [
+            (LoaderLintKind::UndefinedAlias, 4, 7),
+            // The first `&x` is redefined before it is used.
+            (LoaderLintKind::UnusedAnchor, 1, 3),
+            (LoaderLintKind::UnusedAnchor, 6, 2),
+        ]
+    );
+}
+
+#[test]
+fn test_merge_provenance() {
+    let docs = [
+        "a: {b: [1, 2], c: {d: 3}}\ne: 4",
+        "a: {b: [5], c: x}",
+        "a: {f: 6}\ne: ~",
+    ];
+    let mut merged = MarkedYaml::from(YamlData::Null);
+    let mut provenance = Provenance::new();
+    for (source, doc) in docs.iter().enumerate() {
+        let doc = MarkedYaml::load_from_str(doc).unwrap().remove(0);
+        merged.merge_from_tracked(doc, source, &mut provenance);
+    }
+
+    let origins: Vec<_> = provenance
+        .iter()
+        .map(|(path, origin)| (path.to_string(), origin.source, origin.span.start.col()))
+        .collect();
+    assert_eq!(
+        origins,
+        [
+            (String::new(), 0, 0),
+            ("a".to_owned(), 0, 3),
+            ("a.b".to_owned(), 1, 7),
+            ("a.b[0]".to_owned(), 1, 8),
+            ("a.c".to_owned(), 1, 15),
+            ("a.f".to_owned(), 2, 7),
+            ("e".to_owned(), 2, 3),
+        ]
+    );
+    assert_eq!(provenance.len(), 7);
+
+    // Untracked merges give the same tree.
+    let mut untracked = MarkedYaml::from(YamlData::Null);
+    for doc in docs {
+        untracked.merge_from(MarkedYaml::load_from_str(doc).unwrap().remove(0));
+    }
+    assert_eq!(untracked, merged);
+
+    // Nodes under keys which are not strings are merged but not recorded.
+    let mut merged = MarkedYaml::from(YamlData::Null);
+    let mut provenance = Provenance::new();
+    for (source, doc) in ["{'1': a, 1: {b: c}}", "{1: {b: d}, [e]: f}"]
+        .iter()
+        .enumerate()
+    {
+        let doc = MarkedYaml::load_from_str(doc).unwrap().remove(0);
+        merged.merge_from_tracked(doc, source, &mut provenance);
+    }
+    let paths: Vec<_> = provenance
+        .iter()
+        .map(|(path, _)| path.to_string())
+        .collect();
+    assert_eq!(paths, ["", "1"]);
+    let key = MarkedYaml::from(YamlData::Integer(1));
+    let hash = merged.data.as_hash().unwrap();
+    assert_eq!(hash[&key].data["b"].data.as_str(), Some("d"));
+    assert_eq!(hash.len(), 3);
+}
+
+#[test]
+fn test_node_conversions() {
+    let s = "a: &x [1, 2.5, b]\n? {c: ~}\n: true\nd: *x\n";
+    let yaml = Yaml::load_from_str(s).unwrap().remove(0);
+    let marked = MarkedYaml::load_from_str(s).unwrap().remove(0);
+    let shared = SharedYaml::load_from_str(s).unwrap().remove(0);
+
+    assert_eq!(Yaml::from(marked.clone()), yaml);
+    assert_eq!(Yaml::from(marked.data.clone()), yaml);
+    assert_eq!(Yaml::from(shared.clone()), yaml);
+    // The shared node is moved out when it is not shared anymore.
+    assert_eq!(Yaml::from(SharedYaml::from(yaml.clone())), yaml);
+
+    let converted = MarkedYaml::from(yaml.clone());
+    assert_eq!(converted, marked);
+    assert_eq!(converted.data["a"].span, Span::default());
+}
+
+#[cfg(feature = "diagnostics")]
+#[test]
+fn test_diagnostics_are_located_in_bytes() {
+    use miette::Diagnostic;
+
+    let source = "é: [1, 2";
+    let error = Yaml::load_from_str(source).unwrap_err();
+    let offsets: Vec<_> = error
+        .labels()
+        .unwrap()
+        .map(|label| label.offset())
+        .collect();
+    assert_eq!(offsets, [source.len(), 4]);
+
+    let source = "é: &x 1";
+    let mut loader = YamlLoader::<Yaml>::default();
+    Parser::new_from_str(source)
+        .load(&mut loader, true)
+        .unwrap();
+    let lint = loader.lints()[0].with_source(source);
+    assert_eq!(lint.severity(), Some(miette::Severity::Warning));
+    let label = lint.labels().unwrap().next().unwrap();
+    assert_eq!(&source[label.offset()..label.offset() + label.len()], "&x");
+}
@@ -1,4 +1,4 @@
-use saphyr::{Yaml, YamlEmitter};
+use saphyr::{MarkedYaml, Yaml, YamlDocument, YamlEmitter};
 
 #[allow(clippy::similar_names)]
 #[test]
@@ -292,3 +292,332 @@ a:
 
     assert_eq!(s, writer);
 }
+
+#[test]
+fn test_scalar_to_yaml_string() {
+    let docs = Yaml::load_from_str(
+        "[~, true, -3, 1.5e3, -.Inf, .nan, plain, '', 'yes', '1.5', 'a: b', \"tab\\there\"]",
+    )
+    .unwrap();
+    let strings: Vec<_> = docs[0]
+        .as_vec()
+        .unwrap()
+        .iter()
+        .map(|node| node.to_yaml_string().unwrap())
+        .collect();
+    assert_eq!(
+        strings,
+        [
+            "~",
+            "true",
+            "-3",
+            "1.5e3",
+            "-.inf",
+            ".nan",
+            "plain",
+            r#""""#,
+            r#""yes""#,
+            r#""1.5""#,
+            r#""a: b""#,
+            r#""tab\there""#,
+        ]
+    );
+    // Each string loads back to the same value.
+    for (node, s) in docs[0].as_vec().unwrap().iter().zip(&strings) {
+        let reloaded = &Yaml::load_from_str(s).unwrap()[0];
+        if node.as_f64().is_some_and(f64::is_nan) {
+            assert!(reloaded.as_f64().unwrap().is_nan());
+        } else {
+            assert_eq!(node.as_f64(), reloaded.as_f64(), "{s}");
+            assert_eq!(node.as_str(), reloaded.as_str(), "{s}");
+        }
+    }
+    assert_eq!(docs[0].to_yaml_string(), None);
+}
+
+#[test]
+fn test_dump_document_markers() {
+    let dump = |s: &str| {
+        let docs = YamlDocument::<Yaml>::load_from_str(s).unwrap();
+        let mut out = String::new();
+        YamlEmitter::new(&mut out).dump_document(&docs[0]).unwrap();
+        out
+    };
+    assert_eq!(dump("a: 1"), "a: 1");
+    assert_eq!(dump("---\na: 1"), "---\na: 1");
+    assert_eq!(dump("a: 1\n..."), "a: 1\n...");
+    assert_eq!(dump("--- [1]\n...\n"), "---\n- 1\n...");
+    assert_eq!(
+        dump("%TAG !e! tag:example.com,2000:\n--- x"),
+        "%TAG !e! tag:example.com,2000:\n---\nx"
+    );
+
+    // The output loads back with the same markers.
+    for s in ["a: 1", "---\na: 1", "a: 1\n...", "%YAML 1.2\n---\n[1]\n..."] {
+        let doc = &YamlDocument::<Yaml>::load_from_str(s).unwrap()[0];
+        let again = &YamlDocument::<Yaml>::load_from_str(&dump(s)).unwrap()[0];
+        assert_eq!(again.root, doc.root);
+        assert_eq!(again.version, doc.version);
+        assert_eq!(again.explicit_start, doc.explicit_start);
+        assert_eq!(again.explicit_end, doc.explicit_end);
+    }
+}
+
+#[test]
+fn test_dump_marked_document_anchors() {
+    let dump = |s: &str| {
+        let docs = YamlDocument::<MarkedYaml>::load_from_str(s).unwrap();
+        let mut out = String::new();
+        YamlEmitter::new(&mut out)
+            .dump_marked_document(&docs[0])
+            .unwrap();
+        out
+    };
+    for s in [
+        "a: &x 1\nb: *x",
+        "base: &base\n  port: 80\nprod: *base",
+        "- &a\n  - 1\n  - 2\n- *a",
+        "&root\nk: v",
+        "- &k a: 1\n  b: *k",
+        "a: &x []\nb: *x",
+        "&x a: *x",
+        "- &x a\n- {*x : 1}",
+        "a: &x 1\nb: &x 2\nc: *x",
+        "a: &x [&y 1, *y]\nb: *x",
+        "a: &x {b: &y c}\nd: *y",
+    ] {
+        let out = dump(s);
+        // The output loads back as the same tree, with the same anchors.
+        let doc = &YamlDocument::<Yaml>::load_from_str(s).unwrap()[0];
+        let again = &YamlDocument::<Yaml>::load_from_str(&out).unwrap()[0];
+        assert_eq!(again.root, doc.root, "{s:?} -> {out:?}");
+        let names = |doc: &YamlDocument<Yaml>| {
+            doc.anchors
+                .values()
+                .map(|anchor| anchor.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(again), names(doc), "{s:?} -> {out:?}");
+        assert_eq!(out.matches('*').count(), s.matches('*').count(), "{out:?}");
+    }
+    assert_eq!(dump("- &a\n  - 1\n- *a"), "- &a\n  - 1\n- *a");
+    assert_eq!(dump("a: &x [&y 1, *y]"), "a: &x\n  - &y 1\n  - *y");
+}
+
+#[test]
+fn test_dump_marked_document_tags() {
+    let dump = |s: &str| {
+        let docs = YamlDocument::<MarkedYaml>::load_from_str(s).unwrap();
+        let mut out = String::new();
+        YamlEmitter::new(&mut out)
+            .dump_marked_document(&docs[0])
+            .unwrap();
+        out
+    };
+    assert_eq!(
+        dump("- !<tag:example.com,2000:foo> a\n- !local b\n- !!str c"),
+        "- !<tag:example.com,2000:foo> a\n- !local b\n- !!str c"
+    );
+    assert_eq!(
+        dump("!!str a: &x !!int 1\nb: *x"),
+        "!!str a: &x !!int 1\nb: *x"
+    );
+    assert_eq!(
+        dump("%TAG !e! tag:example.com,2000:\n---\n[!e!foo a]"),
+        "%TAG !e! tag:example.com,2000:\n---\n- !e!foo a"
+    );
+    // Tagged scalars are not written in flow style, so that the tags stay on their own items.
+    let docs = YamlDocument::<MarkedYaml>::load_from_str("[!<tag:x> a, b]").unwrap();
+    let mut out = String::new();
+    let mut emitter = YamlEmitter::new(&mut out);
+    emitter.inline_leaf_collections(2, 40);
+    emitter.dump_marked_document(&docs[0]).unwrap();
+    assert_eq!(out, "- !<tag:x> a\n- b");
+}
+
+#[test]
+fn test_need_quotes_matches_emitter() {
+    use saphyr::emitter::util::need_quotes;
+
+    for s in [
+        "foo", "foo bar", "y", "true", "12", "1.5", "a: b", " x", "-x", "", "~", "0x1f",
+    ] {
+        let mut out = String::new();
+        YamlEmitter::new(&mut out)
+            .dump(&Yaml::String(s.to_owned()))
+            .unwrap();
+        let quoted = out.strip_prefix("---\n").unwrap().starts_with('"');
+        assert_eq!(need_quotes(s), quoted, "{s:?}");
+        // Either way, the string loads back as is.
+        assert_eq!(Yaml::load_from_str(&out).unwrap()[0].as_str(), Some(s));
+    }
+}
+
+#[test]
+fn test_is_valid_literal_block_scalar() {
+    use saphyr::emitter::util::is_valid_literal_block_scalar;
+
+    for s in [
+        "tab\there\n",
+        "caf\u{e9}\n",
+        "\u{d7ff}\u{e000}\u{fffd}",
+        "emoji \u{1f600}",
+    ] {
+        assert!(is_valid_literal_block_scalar(s), "{s:?}");
+    }
+    for s in ["cr\r\n", "bell\x07", "del\x7f", "\u{fffe}", "c1\u{9f}"] {
+        assert!(!is_valid_literal_block_scalar(s), "{s:?}");
+    }
+}
+
+#[test]
+fn test_scalar_hook() {
+    use saphyr::emitter::{ScalarRendering, ScalarStyle};
+    use saphyr::PathSegment;
+
+    let s = "
+a: [1, true, 'x y']
+b: {c: 'it''s', d: null}
+e: 'multi\n\n  line'
+f: '  lead'
+g: 2
+";
+    let doc = &Yaml::load_from_str(s).unwrap()[0];
+    // The hook may borrow local state.
+    let mut paths = vec![];
+    let mut out = String::new();
+    let mut emitter = YamlEmitter::new(&mut out);
+    emitter.scalar_hook(|path, node| {
+        paths.push(
+            path.iter()
+                .map(|segment| match segment {
+                    PathSegment::Index(i) => i.to_string(),
+                    PathSegment::Key(key) => key.as_str().unwrap().to_owned(),
+                })
+                .collect::<Vec<_>>()
+                .join("."),
+        );
+        match (path, node) {
+            ([PathSegment::Key(_), PathSegment::Index(0)], _) => {
+                Some(ScalarRendering::Style(ScalarStyle::DoubleQuoted))
+            }
+            ([PathSegment::Key(_), PathSegment::Index(2)], _) => {
+                Some(ScalarRendering::Text("!!str x y".to_owned()))
+            }
+            (_, Yaml::Null) => Some(ScalarRendering::Style(ScalarStyle::Plain)),
+            (_, Yaml::String(_)) => Some(ScalarRendering::Style(ScalarStyle::SingleQuoted)),
+            (_, Yaml::Integer(_)) => Some(ScalarRendering::Style(ScalarStyle::Literal)),
+            _ => None,
+        }
+    });
+    emitter.dump(doc).unwrap();
+    drop(emitter);
+
+    assert_eq!(paths, ["a.0", "a.1", "a.2", "b.c", "b.d", "e", "f", "g"]);
+    assert_eq!(
+        out,
+        r#"---
+a:
+  - "1"
+  - true
+  - !!str x y
+b:
+  c: 'it''s'
+  d: ~
+e: "multi\nline"
+f: '  lead'
+g: |-
+  2"#
+    );
+
+    let again = &Yaml::load_from_str(&out).unwrap()[0];
+    assert_eq!(again["a"][0].as_str(), Some("1"));
+    assert_eq!(again["a"][2].as_str(), Some("x y"));
+    assert_eq!(again["b"], doc["b"]);
+    assert_eq!(again["e"], doc["e"]);
+    assert_eq!(again["f"], doc["f"]);
+    assert_eq!(again["g"].as_str(), Some("2"));
+}
+
+#[test]
+fn test_scalar_hook_literal_fallback() {
+    use saphyr::emitter::{ScalarRendering, ScalarStyle};
+
+    let emit = |s: &str| {
+        let mut doc = saphyr::Hash::new();
+        doc.insert(Yaml::String("k".to_owned()), Yaml::String(s.to_owned()));
+        let mut out = String::new();
+        let mut emitter = YamlEmitter::new(&mut out);
+        emitter.scalar_hook(|_, _| Some(ScalarRendering::Style(ScalarStyle::Literal)));
+        emitter.dump(&Yaml::Hash(doc)).unwrap();
+        out
+    };
+    assert_eq!(emit("a\nb\n"), "---\nk: |\n  a\n  b");
+    assert_eq!(emit(" a\nb"), "---\nk: \" a\\nb\"");
+    assert_eq!(emit("a\n\n"), "---\nk: \"a\\n\\n\"");
+    assert_eq!(emit("a\rb"), "---\nk: \"a\\rb\"");
+}
+
+#[test]
+fn test_inline_leaf_collections() {
+    use saphyr::emitter::{ScalarRendering, ScalarStyle};
+    use saphyr::PathSegment;
+
+    let s = "
+servers:
+  - name: web
+    ports: [80, 443]
+    env: {A: 'a, b', B: ''}
+  - [x, [y]]
+big: [1, 2, 3, 4]
+wide: [aaaaaaaaaa, bbbbbbbbbb]
+text: [\"a\\nb\"]
+? [k]
+: v
+";
+    let doc = &Yaml::load_from_str(s).unwrap()[0];
+    let emit = |multiline: bool, hook: bool| {
+        let mut out = String::new();
+        let mut emitter = YamlEmitter::new(&mut out);
+        emitter.inline_leaf_collections(3, 20);
+        assert_eq!(emitter.inline_leaf_collections_limits(), (3, 20));
+        emitter.multiline_strings(multiline);
+        if hook {
+            emitter.scalar_hook(|path, _| match path.last() {
+                Some(PathSegment::Index(1)) => {
+                    Some(ScalarRendering::Style(ScalarStyle::SingleQuoted))
+                }
+                _ => None,
+            });
+        }
+        emitter.dump(doc).unwrap();
+        if !hook {
+            assert_eq!(&Yaml::load_from_str(&out).unwrap()[0], doc, "{out}");
+        }
+        out
+    };
+
+    assert_eq!(
+        emit(false, false),
+        "---
+servers:
+  - name: web
+    ports: [80, 443]
+    env: {A: \"a, b\", B: \"\"}
+  - - x
+    - [y]
+big:
+  - 1
+  - 2
+  - 3
+  - 4
+wide:
+  - aaaaaaaaaa
+  - bbbbbbbbbb
+text: [\"a\\nb\"]
+? [k]
+: v"
+    );
+    assert!(emit(true, false).contains("text:\n  - |-\n"));
+    assert!(emit(false, true).contains("ports:\n      - 80\n      - '443'\n"));
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "include")]
+
+use saphyr::{Yaml, YamlLoader};
+use saphyr_parser::Parser;
+
+/// Load `input` with an include handler serving `files`.
+fn load_with_files(input: &str, files: &'static [(&str, &str)]) -> YamlLoader<Yaml> {
+    let mut loader = YamlLoader::default().include_handler(|path| {
+        files
+            .iter()
+            .find(|(name, _)| *name == path)
+            .map(|(_, contents)| (*contents).to_owned())
+            .ok_or_else(|| "no such file".to_owned())
+    });
+    Parser::new_from_str(input).load(&mut loader, true).unwrap();
+    loader
+}
+
+#[test]
+fn test_include_nested() {
+    let loader = load_with_files(
+        "a: !include a.yaml\nb: [!include b.yaml, !include empty.yaml]",
+        &[
+            ("a.yaml", "x: !include b.yaml\ny: 2"),
+            ("b.yaml", "[1, 2]"),
+            ("empty.yaml", ""),
+        ],
+    );
+    assert!(loader.hook_errors().is_empty());
+    let doc = &loader.into_documents()[0];
+    assert_eq!(doc["a"]["x"][1].as_i64(), Some(2));
+    assert_eq!(doc["a"]["y"].as_i64(), Some(2));
+    assert_eq!(doc["b"][0][0].as_i64(), Some(1));
+    assert!(doc["b"][1].is_null());
+}
+
+#[test]
+fn test_include_errors() {
+    let loader = load_with_files(
+        "- !include missing.yaml\n- !include a.yaml\n- !include bad.yaml\n- ok",
+        &[
+            ("a.yaml", "!include b.yaml"),
+            ("b.yaml", "[!include a.yaml]"),
+            ("bad.yaml", "[unclosed"),
+        ],
+    );
+    let errors: Vec<_> = loader
+        .hook_errors()
+        .iter()
+        .map(|e| (e.marker().line(), e.info().to_owned()))
+        .collect();
+    assert_eq!(errors.len(), 3);
+    assert_eq!(
+        errors[0],
+        (1, "cannot include `missing.yaml`: no such file".to_owned())
+    );
+    assert_eq!(errors[1].0, 2);
+    assert!(errors[1]
+        .1
+        .contains("include cycle: `a.yaml` -> `b.yaml` -> `a.yaml`"));
+    assert_eq!(errors[2].0, 3);
+    assert!(errors[2].1.starts_with("in included file `bad.yaml`: "));
+
+    let doc = &loader.into_documents()[0];
+    assert!(doc[0].is_badvalue());
+    assert!(doc[1][0].is_badvalue());
+    assert!(doc[2].is_badvalue());
+    assert_eq!(doc[3].as_str(), Some("ok"));
+}
+
+#[test]
+fn test_include_without_handler() {
+    let docs = Yaml::load_from_str("!include a.yaml").unwrap();
+    assert_eq!(docs[0].as_str(), Some("a.yaml"));
+}
+
+#[test]
+fn test_include_depth() {
+    // Paths which differ each time escape the cycle check, but not the depth limit.
+    let mut loader =
+        YamlLoader::<Yaml>::default().include_handler(|path| Ok(format!("!include {path}/again")));
+    Parser::new_from_str("!include a")
+        .load(&mut loader, true)
+        .unwrap();
+    assert_eq!(loader.hook_errors().len(), 1);
+    assert!(loader.hook_errors()[0]
+        .info()
+        .contains("includes are nested more than 32 levels deep"));
+    assert!(loader.into_documents()[0].is_badvalue());
+}
+
+#[test]
+fn test_include_records() {
+    let mut loader = YamlLoader::<Yaml>::default()
+        .record_anchors(true)
+        .record_duplicate_keys(true)
+        .include_handler(|_| Ok("{a: &x 1, a: *x}".to_owned()));
+    Parser::new_from_str("&y [!include a.yaml]")
+        .load(&mut loader, true)
+        .unwrap();
+    let names: Vec<_> = loader
+        .anchors()
+        .iter()
+        .map(|(&id, anchor)| (id, anchor.name.as_str()))
+        .collect();
+    assert_eq!(names, [(1, "y"), (usize::MAX - 1, "x")]);
+    let duplicate = &loader.duplicate_keys()[0];
+    assert_eq!(duplicate.key.as_str(), Some("a"));
+    assert_eq!(duplicate.value.as_i64(), Some(1));
+}
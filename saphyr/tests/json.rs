@@ -0,0 +1,65 @@
+use saphyr::{JsonIncompatibilityKind, MarkedYaml, Yaml};
+
+#[test]
+fn test_json_compatible() {
+    let doc = &Yaml::load_from_str("{a: [1, 2.5, \"x\\ty\"], b: {c: null, d: true}}").unwrap()[0];
+    assert!(doc.is_json_compatible());
+    let (json, incompatibilities) = doc.to_json_lossy();
+    assert_eq!(json, r#"{"a":[1,2.5,"x\ty"],"b":{"c":null,"d":true}}"#);
+    assert!(incompatibilities.is_empty());
+
+    for source in ["{1: a}", "[.inf]", "{a: {~: b}}", "{? [a]: b}"] {
+        let doc = &Yaml::load_from_str(source).unwrap()[0];
+        assert!(!doc.is_json_compatible(), "{source}");
+    }
+}
+
+#[test]
+fn test_json_lossy_invalid_keys() {
+    for key in [Yaml::Alias(1), Yaml::BadValue] {
+        let mut hash = saphyr::Hash::new();
+        hash.insert(key.clone(), Yaml::Integer(1));
+        let (json, incompatibilities) = Yaml::Hash(hash).to_json_lossy();
+        assert_eq!(json, r#"{"null":1}"#, "{key:?}");
+        assert_eq!(incompatibilities.len(), 1, "{key:?}");
+        assert_eq!(incompatibilities[0].path.to_string(), "null");
+        assert_eq!(
+            incompatibilities[0].kind,
+            JsonIncompatibilityKind::InvalidValue
+        );
+    }
+}
+
+#[test]
+fn test_json_lossy_marked() {
+    let doc = &MarkedYaml::load_from_str("a/b: -.inf\n? [k]\n: v\ntrue: [x]").unwrap()[0];
+    assert!(!doc.is_json_compatible());
+    let (json, incompatibilities) = doc.to_json_lossy();
+    assert_eq!(json, r#"{"a/b":null,"true":["x"]}"#);
+    let found: Vec<_> = incompatibilities
+        .iter()
+        .map(|i| {
+            let span = i.span.unwrap();
+            let path = i.path.to_string();
+            (path, i.kind, span.start.line(), span.start.col())
+        })
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (
+                "a/b".to_owned(),
+                JsonIncompatibilityKind::NonFiniteReal,
+                1,
+                5
+            ),
+            (String::new(), JsonIncompatibilityKind::CollectionKey, 2, 2),
+            (
+                "true".to_owned(),
+                JsonIncompatibilityKind::NonStringKey,
+                4,
+                0
+            ),
+        ]
+    );
+}
@@ -0,0 +1,670 @@
+#![cfg(feature = "serde")]
+
+use std::collections::{BTreeMap, HashMap};
+
+use saphyr::{
+    load_config, to_node, DeserializeError, DeserializeErrorKind, DeserializeLimits, MarkedYaml,
+    ReadError, Yaml, YamlEmitter,
+};
+use serde::{Deserialize, Serialize};
+
+fn load(s: &str) -> Yaml {
+    Yaml::load_from_str(s).unwrap().remove(0)
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Config {
+    name: String,
+    version: u32,
+    ratio: f64,
+    enabled: bool,
+    tags: Vec<String>,
+    owner: Option<String>,
+    #[serde(default)]
+    limits: BTreeMap<String, i64>,
+    mode: Mode,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Mode {
+    Fast,
+    Retries(u8),
+    Window(u32, u32),
+    Custom { level: i8 },
+}
+
+#[test]
+fn test_deserialize_struct() {
+    let doc = load(
+        "
+config:
+  name: app
+  version: 3
+  ratio: 1
+  enabled: true
+  tags: [a, b]
+  owner: ~
+  mode: fast
+",
+    );
+    let config: Config = doc["config"].deserialize_into().unwrap();
+    assert_eq!(
+        config,
+        Config {
+            name: "app".into(),
+            version: 3,
+            ratio: 1.0,
+            enabled: true,
+            tags: vec!["a".into(), "b".into()],
+            owner: None,
+            limits: BTreeMap::new(),
+            mode: Mode::Fast,
+        }
+    );
+}
+
+#[test]
+fn test_deserialize_enums() {
+    let modes: Vec<Mode> = load(
+        "
+- fast
+- retries: 3
+- window: [1, 2]
+- custom: {level: -1}
+",
+    )
+    .deserialize_into()
+    .unwrap();
+    assert_eq!(
+        modes,
+        [
+            Mode::Fast,
+            Mode::Retries(3),
+            Mode::Window(1, 2),
+            Mode::Custom { level: -1 }
+        ]
+    );
+
+    assert!(load("slow").deserialize_into::<Mode>().is_err());
+    assert!(load("{fast: ~, retries: 1}")
+        .deserialize_into::<Mode>()
+        .is_err());
+}
+
+#[test]
+fn test_deserialize_borrowed() {
+    let doc = load("a: foo\nb: bar");
+    let map: HashMap<&str, &str> = doc.deserialize_into().unwrap();
+    assert_eq!(map["a"], "foo");
+    assert_eq!(map["b"], "bar");
+}
+
+#[test]
+fn test_deserialize_scalars() {
+    assert_eq!(load("42").deserialize_into::<u8>(), Ok(42));
+    assert_eq!(load("0x2a").deserialize_into::<i64>(), Ok(42));
+    assert_eq!(load("1.5").deserialize_into::<f32>(), Ok(1.5));
+    assert_eq!(
+        load("-.inf").deserialize_into::<f64>(),
+        Ok(f64::NEG_INFINITY)
+    );
+    assert_eq!(load("x").deserialize_into::<char>(), Ok('x'));
+    assert_eq!(load("~").deserialize_into::<()>(), Ok(()));
+    assert_eq!(load("7").deserialize_into::<Option<i32>>(), Ok(Some(7)));
+}
+
+#[test]
+fn test_deserialize_errors() {
+    let error: DeserializeError = load("300").deserialize_into::<u8>().unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid value: integer `300`, expected u8"
+    );
+
+    let error = load("[1, a]").deserialize_into::<Vec<i32>>().unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid type: string \"a\", expected i32"
+    );
+
+    let error = load("name: app").deserialize_into::<Config>().unwrap_err();
+    assert_eq!(error.to_string(), "missing field `version`");
+
+    assert!(Yaml::BadValue.deserialize_into::<i32>().is_err());
+}
+
+#[test]
+fn test_serialize_round_trip() {
+    let config = Config {
+        name: "app".into(),
+        version: 3,
+        ratio: 0.1,
+        enabled: false,
+        tags: vec!["a".into()],
+        owner: Some("me".into()),
+        limits: [("cpu".to_string(), 2)].into(),
+        mode: Mode::Custom { level: 4 },
+    };
+    let node = to_node(&config).unwrap();
+    assert_eq!(node["ratio"], Yaml::Real("0.1".into()));
+    assert_eq!(node["mode"]["custom"]["level"].as_i64(), Some(4));
+    assert_eq!(node.deserialize_into::<Config>().unwrap(), config);
+
+    for mode in [
+        Mode::Fast,
+        Mode::Retries(1),
+        Mode::Window(2, 3),
+        Mode::Custom { level: 0 },
+    ] {
+        let node = to_node(&mode).unwrap();
+        assert_eq!(node.deserialize_into::<Mode>().unwrap(), mode);
+    }
+}
+
+#[test]
+fn test_serialize_emitted() {
+    let mut map = BTreeMap::new();
+    map.insert((1, 2), vec![1.0, f64::INFINITY, f64::NAN]);
+    let node = to_node(&map).unwrap();
+
+    let mut out = String::new();
+    YamlEmitter::new(&mut out).dump(&node).unwrap();
+    assert_eq!(out, "---\n? - 1\n  - 2\n: - 1.0\n  - .inf\n  - .nan");
+
+    // Reals stay reals when loaded again.
+    let loaded = Yaml::load_from_str(&out).unwrap().remove(0);
+    assert_eq!(loaded, node);
+}
+
+#[test]
+fn test_serialize_errors() {
+    let error = to_node(&u64::MAX).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "integer `18446744073709551615` does not fit in an i64"
+    );
+    assert_eq!(to_node(&i128::from(i64::MIN)), Ok(Yaml::Integer(i64::MIN)));
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[allow(dead_code)]
+struct Strict {
+    name: String,
+    port: u16,
+}
+
+#[test]
+fn test_deserialize_error_spans() {
+    let error_at = |s: &str| {
+        let doc = MarkedYaml::load_from_str(s).unwrap().remove(0);
+        let error = doc.deserialize_into::<Vec<Strict>>().unwrap_err();
+        let span = error.span().unwrap();
+        (error.to_string(), span.start.line(), span.start.col())
+    };
+
+    // A missing field is reported at the hash it is missing from.
+    assert_eq!(
+        error_at("- name: a\n  port: 1\n- name: b"),
+        ("missing field `port` at line 3 column 3".to_string(), 3, 2)
+    );
+    // An unknown field is reported at its key.
+    assert_eq!(error_at("- name: a\n  port: 1\n  host: x").1, 3);
+    // An invalid value is reported at the value.
+    assert_eq!(
+        error_at("- name: a\n  port: 70000"),
+        (
+            "invalid value: integer `70000`, expected u16 at line 2 column 9".to_string(),
+            2,
+            8
+        )
+    );
+
+    // Errors from a `Yaml` have no span.
+    let doc = load("- name: a");
+    let error = doc.deserialize_into::<Vec<Strict>>().unwrap_err();
+    assert_eq!(error.span(), None);
+    assert_eq!(error.to_string(), "missing field `port`");
+}
+
+#[test]
+fn test_deserialize_error_paths() {
+    let path_at = |s: &str| {
+        let doc = load(s);
+        let error = doc.deserialize_into::<Vec<Strict>>().unwrap_err();
+        let marked = MarkedYaml::load_from_str(s).unwrap().remove(0);
+        assert_eq!(
+            marked.deserialize_into::<Vec<Strict>>().unwrap_err().path(),
+            error.path()
+        );
+        error.path().unwrap().to_string()
+    };
+
+    // Paths lead to the same node as spans.
+    assert_eq!(path_at("- name: a\n  port: 1\n- name: b"), "[1]");
+    assert_eq!(path_at("- name: a\n  port: 1\n  host: x"), "[0].host");
+    assert_eq!(path_at("- name: a\n  port: 70000"), "[0].port");
+    assert_eq!(path_at("{}"), "");
+
+    let doc = load("{1: {true: [x]}}");
+    let error = doc
+        .deserialize_into::<HashMap<i32, HashMap<bool, Vec<i32>>>>()
+        .unwrap_err();
+    assert_eq!(error.path().unwrap().to_string(), "1.true[0]");
+}
+
+/// Any value of the documents used to test limits.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+#[allow(dead_code)]
+enum Value {
+    Integer(i64),
+    Array(Vec<Value>),
+    Hash(HashMap<String, Value>),
+}
+
+#[test]
+fn test_max_alias_nodes() {
+    use saphyr::{LoadError, ReadOptions};
+    use saphyr_parser::ScanErrorKind;
+
+    // Each level expands to 10 times the nodes of the previous one: 10^9 strings in total.
+    let mut laughs = "a0: &a0 lol\n".to_owned();
+    for i in 1..10 {
+        let aliases = vec![format!("*a{}", i - 1); 10].join(", ");
+        laughs.push_str(&format!("a{i}: &a{i} [{aliases}]\n"));
+    }
+    let options = ReadOptions::default().max_alias_nodes(100_000);
+    let Err(ReadError::Load(LoadError::Scan(error))) = options.from_str::<Yaml>(&laughs) else {
+        panic!()
+    };
+    assert_eq!(error.kind(), ScanErrorKind::AliasLimitExceeded);
+    // The first alias past the limit is in the sixth level.
+    assert_eq!(error.marker().line(), 6);
+
+    // An alias counts for all the nodes of its anchored node.
+    let input = "a: &a [1, [2]]\nb: [*a, *a]";
+    assert!(ReadOptions::default()
+        .max_alias_nodes(8)
+        .from_str::<Yaml>(input)
+        .is_ok());
+    assert!(ReadOptions::default()
+        .max_alias_nodes(7)
+        .from_str::<Yaml>(input)
+        .is_err());
+
+    // The limit applies to the whole sequence when iterating over its items.
+    let mut items = ReadOptions::default()
+        .max_alias_nodes(2)
+        .iter_seq::<Vec<u8>>("- &a [1]\n- *a\n- *a");
+    assert_eq!(items.next().unwrap().unwrap(), [1]);
+    assert_eq!(items.next().unwrap().unwrap(), [1]);
+    assert!(matches!(items.next(), Some(Err(ReadError::Load(_)))));
+    assert!(items.next().is_none());
+}
+
+#[test]
+fn test_deserialize_limits() {
+    let doc = load("a: [1, [2, 3]]\nb: {c: 4}");
+    let limits = DeserializeLimits::default();
+    assert!(doc.deserialize_with_limits::<Value>(limits).is_ok());
+
+    // The root hash, the nested array and the inner array.
+    let error = doc
+        .deserialize_with_limits::<Value>(limits.max_depth(2))
+        .unwrap_err();
+    assert_eq!(error.kind(), DeserializeErrorKind::DepthLimitExceeded);
+    assert!(doc
+        .deserialize_with_limits::<Value>(limits.max_depth(3))
+        .is_ok());
+
+    // 2 hashes, 2 arrays, 3 keys and 4 integers.
+    let error = doc
+        .deserialize_with_limits::<Value>(limits.max_nodes(10))
+        .unwrap_err();
+    assert_eq!(error.kind(), DeserializeErrorKind::NodeLimitExceeded);
+    assert!(doc
+        .deserialize_with_limits::<Value>(limits.max_nodes(11))
+        .is_ok());
+
+    // Nodes of expanded aliases are counted each time.
+    let doc = load("a: &a [1, 2, 3]\nb: [*a, *a, *a]");
+    let error = doc
+        .deserialize_with_limits::<Value>(limits.max_nodes(19))
+        .unwrap_err();
+    assert_eq!(error.kind(), DeserializeErrorKind::NodeLimitExceeded);
+    assert_eq!(
+        error.to_string(),
+        "more than the maximum of 19 nodes visited"
+    );
+
+    // Limit errors carry the span of the node at which they happened.
+    let doc = MarkedYaml::load_from_str("- [[1]]\n- [[[2]]]")
+        .unwrap()
+        .remove(0);
+    let error = doc
+        .deserialize_with_limits::<Value>(limits.max_depth(3))
+        .unwrap_err();
+    assert_eq!(error.kind(), DeserializeErrorKind::DepthLimitExceeded);
+    let span = error.span().unwrap();
+    assert_eq!((span.start.line(), span.start.col()), (2, 4));
+
+    // Type errors are of the `Other` kind.
+    let error = load("a").deserialize_into::<i32>().unwrap_err();
+    assert_eq!(error.kind(), DeserializeErrorKind::Other);
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn test_from_reader() {
+    use saphyr::{from_reader, ReadError};
+
+    let text = "- name: a\n  port: 1\n";
+    let ports = |bytes: Vec<u8>| {
+        from_reader::<_, Vec<Strict>>(bytes.as_slice())
+            .map(|v| v.into_iter().map(|s| s.port).collect::<Vec<_>>())
+    };
+
+    // UTF-8, with and without a BOM.
+    assert_eq!(ports(text.into()).unwrap(), [1]);
+    assert_eq!(ports(format!("\u{feff}{text}").into()).unwrap(), [1]);
+    // UTF-16, with a BOM or detected from the first character.
+    let utf16le = |s: &str| s.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    let utf16be = |s: &str| s.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    assert_eq!(ports(utf16le(&format!("\u{feff}{text}"))).unwrap(), [1]);
+    assert_eq!(ports(utf16le(text)).unwrap(), [1]);
+    assert_eq!(ports(utf16be(&format!("\u{feff}{text}"))).unwrap(), [1]);
+
+    // An empty input is null.
+    assert_eq!(from_reader::<_, Option<i32>>(&b""[..]).unwrap(), None);
+
+    // Malformed input and invalid YAML fail to load.
+    assert!(matches!(
+        from_reader::<_, i32>(&b"a: \xff"[..]),
+        Err(ReadError::Load(_))
+    ));
+    assert!(matches!(
+        from_reader::<_, i32>(&b"[1"[..]),
+        Err(ReadError::Load(_))
+    ));
+
+    // Deserialization errors have a span.
+    let Err(ReadError::Deserialize(error)) = ports("- name: a\n  port: x".into()) else {
+        panic!("expected a deserialization error");
+    };
+    assert_eq!(error.span().unwrap().start.line(), 2);
+    let Err(ReadError::Deserialize(error)) = from_reader::<_, i32>(&b"1\n---\n2"[..]) else {
+        panic!("expected a deserialization error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "expected a single document, found more at line 3 column 1"
+    );
+}
+
+#[test]
+fn test_iter_seq() {
+    use saphyr::{iter_seq, ReadError};
+
+    let items: Vec<_> = iter_seq::<Mode>("- fast\n- &r {retries: 2}\n- slow\n- *r")
+        .map(|item| item.map_err(|e| e.to_string()))
+        .collect();
+    assert_eq!(
+        items,
+        [
+            Ok(Mode::Fast),
+            Ok(Mode::Retries(2)),
+            Err("unknown variant `slow`, expected one of `fast`, `retries`, `window`, `custom` at line 3 column 3".to_string()),
+            // Aliases to earlier items are expanded.
+            Ok(Mode::Retries(2)),
+        ]
+    );
+
+    // Nested collections are a single item.
+    let items: Vec<Vec<i32>> = iter_seq("[[1, 2], [], [3]]")
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(items, [vec![1, 2], vec![], vec![3]]);
+
+    assert_eq!(iter_seq::<i32>("").count(), 0);
+    assert_eq!(iter_seq::<i32>("[]").count(), 0);
+
+    // A root which is not a sequence is an error.
+    let mut items = iter_seq::<i32>("a: 1");
+    assert!(matches!(items.next(), Some(Err(ReadError::Deserialize(_)))));
+    assert!(items.next().is_none());
+
+    // The iteration stops at the first error in the input.
+    let mut items = iter_seq::<i32>("[1, 2");
+    assert_eq!(items.next().unwrap().unwrap(), 1);
+    assert_eq!(items.next().unwrap().unwrap(), 2);
+    assert!(matches!(items.next(), Some(Err(ReadError::Load(_)))));
+    assert!(items.next().is_none());
+}
+
+#[test]
+fn test_serialize_to_string() {
+    #[derive(Serialize)]
+    struct Script<'a> {
+        name: &'a str,
+        run: &'a str,
+        steps: Vec<&'a str>,
+    }
+
+    let script = Script {
+        name: "build",
+        run: "make\nmake install\n",
+        steps: vec!["a\nb", "bell\n\x07"],
+    };
+    assert_eq!(
+        saphyr::to_string(&script).unwrap(),
+        "---\nname: build\nrun: \"make\\nmake install\\n\"\nsteps:\n  - \"a\\nb\"\n  - \"bell\\n\\u0007\""
+    );
+    // Strings which cannot be literal blocks stay quoted.
+    let out = saphyr::to_string_multiline(&script).unwrap();
+    assert_eq!(
+        out,
+        "---\nname: build\nrun: |\n  make\n  make install\nsteps:\n  - |-\n    a\n    b\n  - \"bell\\n\\u0007\""
+    );
+    let loaded = load(&out);
+    assert_eq!(loaded["run"].as_str(), Some(script.run));
+    assert_eq!(loaded["steps"][0].as_str(), Some("a\nb"));
+}
+
+#[test]
+fn test_yaml_in_typed_values() {
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Plugin {
+        name: String,
+        settings: Yaml,
+    }
+
+    let doc = load("name: lint\nsettings: {level: 2, rules: [a, b], ratio: 0.5, owner: ~}");
+    let plugin: Plugin = doc.deserialize_into().unwrap();
+    assert_eq!(plugin.name, "lint");
+    assert_eq!(plugin.settings, doc["settings"]);
+
+    // The tree is serialized back as it was.
+    assert_eq!(to_node(&plugin).unwrap(), doc);
+    assert_eq!(
+        saphyr::to_string(&plugin).unwrap(),
+        "---\nname: lint\nsettings:\n  level: 2\n  rules:\n    - a\n    - b\n  ratio: 0.5\n  owner: ~"
+    );
+
+    // Reals are serialized as numbers.
+    assert_eq!(
+        to_node(&Yaml::Real("1.50".into())),
+        Ok(Yaml::Real("1.5".into()))
+    );
+    assert!(to_node(&Yaml::BadValue).is_err());
+    assert!(to_node(&Yaml::Real("nope".into())).is_err());
+}
+
+#[test]
+fn test_deny_duplicate_keys() {
+    use saphyr::{from_str, ReadError, ReadOptions};
+
+    let input = "a: {x: 1}\nb: {x: 2, y: 3, x: 4}\nb: {}";
+    let map: HashMap<String, HashMap<String, i32>> = from_str(input).unwrap();
+    assert!(map["b"].is_empty());
+
+    let options = ReadOptions::default().deny_duplicate_keys(true);
+    let error = |result| match result {
+        Err(ReadError::Deserialize(error)) => {
+            assert_eq!(error.kind(), DeserializeErrorKind::DuplicateKey);
+            error.to_string()
+        }
+        _ => panic!("expected a duplicate key error"),
+    };
+    // The first duplicate in the document is reported, where it is defined again.
+    assert_eq!(
+        error(options.from_str::<Yaml>(input)),
+        "key `x` is defined more than once at line 2 column 17"
+    );
+    assert_eq!(
+        error(options.from_str::<Yaml>("- [1]\n- {[1]: a, [1]: b}")),
+        "a key is defined more than once at line 2 column 12"
+    );
+    assert!(options.from_str::<Vec<Yaml>>("- {a: 1}\n- {a: 2}").is_ok());
+
+    // Only the items with duplicate keys are errors.
+    let items: Vec<_> = options
+        .iter_seq::<HashMap<String, i32>>("- {a: 1}\n- {a: 2, a: 3}\n- {a: 4}")
+        .map(|item| item.map(|map| map["a"]).map_err(|_| ()))
+        .collect();
+    assert_eq!(items, [Ok(1), Err(()), Ok(4)]);
+}
+
+#[test]
+fn test_read_yaml_1_1() {
+    use saphyr::ReadOptions;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Feature {
+        enabled: bool,
+        limit: u32,
+    }
+
+    let input = "enabled: on\nlimit: 10_000";
+    assert!(saphyr::from_str::<Feature>(input).is_err());
+    let options = ReadOptions::default()
+        .yaml_1_1_booleans(true)
+        .yaml_1_1_numbers(true);
+    assert_eq!(
+        options.from_str::<Feature>(input).unwrap(),
+        Feature {
+            enabled: true,
+            limit: 10_000
+        }
+    );
+}
+
+#[test]
+fn test_error_byte_range() {
+    use saphyr::ReadError;
+
+    let input = "- größe: 1\n- größe: zwölf\n";
+    let Err(ReadError::Deserialize(error)) = saphyr::from_str::<Vec<HashMap<String, u8>>>(input)
+    else {
+        panic!("expected a deserialization error");
+    };
+    let range = error.byte_range(input).unwrap();
+    assert_eq!(&input[range.clone()], "zwölf");
+    assert_eq!(range.start, input.find("zwölf").unwrap());
+
+    // Spans outside of the input, and errors without a span, have no range.
+    assert_eq!(error.byte_range("- a"), None);
+    let error = load("a").deserialize_into::<u8>().unwrap_err();
+    assert_eq!(error.byte_range("a"), None);
+}
+
+#[test]
+fn test_sort_map_keys() {
+    use saphyr::WriteOptions;
+
+    #[derive(Serialize)]
+    struct Inventory {
+        zone: &'static str,
+        counts: HashMap<&'static str, u32>,
+        nested: Vec<HashMap<i32, bool>>,
+    }
+
+    let inventory = Inventory {
+        zone: "b",
+        counts: HashMap::from([("pears", 3), ("apples", 1), ("figs", 2)]),
+        nested: vec![HashMap::from([(3, true), (-1, false), (20, true)])],
+    };
+    let options = WriteOptions::default().sort_map_keys(true);
+    let node = options.to_node(&inventory).unwrap();
+    // Struct fields keep their order, the entries of maps are sorted at any depth.
+    let keys = |node: &Yaml| -> Vec<Yaml> { node.as_hash().unwrap().keys().cloned().collect() };
+    assert_eq!(keys(&node), [load("zone"), load("counts"), load("nested")]);
+    assert_eq!(
+        keys(&node["counts"]),
+        [load("apples"), load("figs"), load("pears")]
+    );
+    assert_eq!(
+        keys(&node["nested"][0]),
+        [Yaml::Integer(-1), Yaml::Integer(3), Yaml::Integer(20)]
+    );
+    assert_eq!(
+        options.multiline_strings(true).to_string(&inventory).unwrap(),
+        "---\nzone: b\ncounts:\n  apples: 1\n  figs: 2\n  pears: 3\nnested:\n  - -1: false\n    3: true\n    20: true"
+    );
+}
+
+#[test]
+fn test_load_config() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Server {
+        host: String,
+        port: u16,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+
+    let defaults = "host: localhost\nport: 8080\ntags: [a, b]\n";
+    let server: Server = load_config(&[defaults, "", "tags: [c]\nport: 80"]).unwrap();
+    assert_eq!(
+        server,
+        Server {
+            host: "localhost".into(),
+            port: 80,
+            tags: vec!["c".into()],
+        }
+    );
+
+    // The failing value is located in the layer which provided it.
+    let error = load_config::<Server>(&[defaults, "port: 1", "tags: [x, {y: z}]"]).unwrap_err();
+    assert_eq!(error.layer(), Some(2));
+    assert_eq!(error.path().unwrap().to_string(), "tags[1]");
+    let ReadError::Deserialize(inner) = error.error() else {
+        panic!("{error}")
+    };
+    assert_eq!(inner.span().unwrap().start.index(), 10);
+    assert!(error.to_string().starts_with("in layer 2: "), "{error}");
+
+    // A missing field is blamed on the layer of the hash.
+    let error = load_config::<Server>(&["{host: a}", "host: b"]).unwrap_err();
+    assert_eq!(error.layer(), Some(0));
+    assert_eq!(error.path().unwrap().to_string(), "");
+
+    // Values under keys which are not strings are blamed on their nearest string-keyed ancestor.
+    let layers = ["a: {1: 2}", "a: {1: x}"];
+    let error = load_config::<BTreeMap<String, BTreeMap<u8, u8>>>(&layers).unwrap_err();
+    assert_eq!(error.layer(), Some(0));
+    let ReadError::Deserialize(inner) = error.error() else {
+        panic!("{error}")
+    };
+    assert_eq!(&layers[0][inner.byte_range(layers[0]).unwrap()], "{1: 2}");
+
+    // Load errors are located in their layer.
+    let error = load_config::<Server>(&[defaults, "a: [", "port: 1"]).unwrap_err();
+    assert_eq!(error.layer(), Some(1));
+    assert!(error.path().is_none());
+    assert!(matches!(error.into_error(), ReadError::Load(_)));
+
+    let error = load_config::<Server>(&[]).unwrap_err();
+    assert_eq!(error.layer(), None);
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "testing")]
+
+use saphyr::{assert_yaml_eq, testing::differences, Yaml};
+
+fn load(s: &str) -> Yaml {
+    Yaml::load_from_str(s).unwrap().remove(0)
+}
+
+fn found(actual: &str, expected: &str) -> Vec<String> {
+    differences(&load(actual), &load(expected))
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[test]
+fn test_semantically_equal() {
+    assert_yaml_eq!(load("{a: 1, b: [x, 'y']}"), "b:\n  - \"x\"\n  - y\na: 1");
+    assert_yaml_eq!("[1.0, -0.0, .nan]", "[1.00, 0.0, .NAN]");
+    assert_yaml_eq!("{1.0: a}", "{1e0: a}");
+    assert_yaml_eq!(String::from("# only a comment"), "~");
+    assert_yaml_eq!(&&load("x"), "x",);
+}
+
+#[test]
+fn test_differences() {
+    assert_eq!(found("1", "'1'"), ["at the root: expected \"1\", found 1"]);
+    assert_eq!(found("1", "1.0"), ["at the root: expected 1.0, found 1"]);
+    assert_eq!(
+        found("[1, [2, 3]]", "[1, [2], 4]"),
+        [
+            "at `[1][1]`: expected nothing, found 3",
+            "at `[2]`: expected 4, found nothing",
+        ]
+    );
+    assert_eq!(
+        found("{a: {b: [1]}, x: 1}", "{a: {b: {c: 1}}, y: 1}"),
+        [
+            "at `a.b`: expected a hash of 1 entry, found an array of 1 item",
+            "at `y`: expected 1, found nothing",
+            "at `x`: expected nothing, found 1",
+        ]
+    );
+}
+
+#[test]
+fn test_failure_message() {
+    let err = std::panic::catch_unwind(|| {
+        assert_yaml_eq!("{a: 1, b: [2]}", "{a: 2, b: []}", "case {}", 3);
+    })
+    .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<String>().unwrap(),
+        "assertion `actual == expected` failed: case 3\n  \
+         at `a`: expected 2, found 1\n  \
+         at `b[0]`: expected nothing, found 2"
+    );
+}
+
+#[test]
+#[should_panic(expected = "expected at most one YAML document")]
+fn test_several_documents() {
+    assert_yaml_eq!("a\n---\nb", "a");
+}
@@ -0,0 +1,126 @@
+use saphyr::{MarkedYaml, Schema, Violation};
+
+/// Validate `doc` against `schema`, returning `(path, keyword, line, col)` for each violation.
+fn violations(schema: &str, doc: &str) -> Vec<(String, &'static str, usize, usize)> {
+    let schema = Schema::load_from_str(schema).unwrap();
+    let doc = &MarkedYaml::load_from_str(doc).unwrap()[0];
+    schema
+        .validate(doc)
+        .into_iter()
+        .map(
+            |Violation {
+                 path,
+                 span,
+                 keyword,
+                 ..
+             }| {
+                (
+                    path.to_json_pointer(),
+                    keyword,
+                    span.start.line(),
+                    span.start.col(),
+                )
+            },
+        )
+        .collect()
+}
+
+const SERVERS_SCHEMA: &str = r##"
+type: object
+properties:
+  name: { type: string, minLength: 1 }
+  servers:
+    type: array
+    minItems: 1
+    items: { $ref: "#/$defs/server" }
+required: [name, servers]
+additionalProperties: false
+$defs:
+  server:
+    type: object
+    properties:
+      host: { type: string }
+      port: { type: integer, minimum: 1, maximum: 65535 }
+      tls: { enum: [on, off, auto] }
+    required: [host]
+"##;
+
+#[test]
+fn test_validate_valid_document() {
+    let doc = "name: prod\nservers:\n  - host: a\n    port: 443\n    tls: auto\n  - host: b";
+    assert_eq!(violations(SERVERS_SCHEMA, doc), []);
+}
+
+#[test]
+fn test_validate_reports_spans() {
+    let doc =
+        "name: ''\nservers:\n  - host: a\n    port: 70000\n  - port: x\n    tls: yes\nextra: 1";
+    assert_eq!(
+        violations(SERVERS_SCHEMA, doc),
+        [
+            ("/name".to_owned(), "minLength", 1, 6),
+            ("/servers/0/port".to_owned(), "maximum", 4, 10),
+            ("/servers/1/port".to_owned(), "type", 5, 10),
+            ("/servers/1/tls".to_owned(), "enum", 6, 9),
+            ("/servers/1".to_owned(), "required", 5, 4),
+            ("/extra".to_owned(), "additionalProperties", 7, 0),
+        ]
+    );
+}
+
+#[test]
+fn test_validate_combinators() {
+    let schema =
+        r#"{"items": {"oneOf": [{"type": "integer"}, {"minimum": 0}], "not": {"const": 3}}}"#;
+    assert_eq!(
+        violations(schema, "[-1, 1, 0.5, -0.5, 3]"),
+        [
+            ("/1".to_owned(), "oneOf", 1, 5),
+            ("/3".to_owned(), "oneOf", 1, 13),
+            ("/4".to_owned(), "oneOf", 1, 19),
+            ("/4".to_owned(), "not", 1, 19),
+        ]
+    );
+
+    let schema = "anyOf: [{type: string}, {type: array, uniqueItems: true}]";
+    assert_eq!(violations(schema, "[a, b]"), []);
+    assert_eq!(violations(schema, "foo"), []);
+    assert_eq!(violations(schema, "3"), [(String::new(), "anyOf", 1, 0)]);
+    assert_eq!(
+        violations(schema, "[a, a]"),
+        [(String::new(), "anyOf", 1, 0)]
+    );
+}
+
+#[test]
+fn test_validate_refs() {
+    // Recursive schemas validate nested structures; a reference cycle adds no constraint.
+    let schema = r##"
+$defs:
+  tree: { type: [array, integer], items: { $ref: "#/$defs/tree" } }
+  loop: { $ref: "#/$defs/loop" }
+allOf: [{ $ref: "#/$defs/tree" }, { $ref: "#/$defs/loop" }]
+"##;
+    assert_eq!(violations(schema, "[1, [2, [3]], []]"), []);
+    assert_eq!(
+        violations(schema, "[1, [2, [x]]]"),
+        [("/1/1/0".to_owned(), "type", 1, 9)]
+    );
+    assert_eq!(
+        violations("$ref: '#/nope'", "a"),
+        [(String::new(), "$ref", 1, 0)]
+    );
+}
+
+#[test]
+fn test_violation_display() {
+    let schema = Schema::load_from_str("properties: {a/b: false}").unwrap();
+    let doc = &MarkedYaml::load_from_str("x: 1\na/b: 2").unwrap()[0];
+    let violations = schema.validate(doc);
+    assert_eq!(violations[0].path.to_json_pointer(), "/a~1b");
+    assert_eq!(
+        violations[0].to_string(),
+        "no value is allowed here at `a/b` line 2 column 6"
+    );
+    assert!(Schema::load_from_str("").unwrap().is_valid(doc));
+}
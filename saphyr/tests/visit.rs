@@ -0,0 +1,214 @@
+use saphyr::{
+    MarkedYaml, PathPattern, PathSegment, Visit, Yaml, YamlData, YamlPath, YamlPathSegment,
+};
+
+/// Format a path as `.key[index]...`, for comparisons.
+fn format_path(path: &[PathSegment]) -> String {
+    path.iter()
+        .map(|segment| match segment {
+            PathSegment::Index(i) => format!("[{i}]"),
+            PathSegment::Key(key) => format!(".{}", key.as_str().unwrap()),
+        })
+        .collect()
+}
+
+/// Format a path to a [`MarkedYaml`] node as `.key[index]...`, for comparisons.
+fn format_marked_path(path: &[PathSegment<MarkedYaml>]) -> String {
+    path.iter()
+        .map(|segment| match segment {
+            PathSegment::Index(i) => format!("[{i}]"),
+            PathSegment::Key(key) => format!(".{}", key.data.as_str().unwrap()),
+        })
+        .collect()
+}
+
+#[test]
+fn test_walk_order_and_paths() {
+    let doc = &Yaml::load_from_str("a: [x, {b: y}]\nc: z").unwrap()[0];
+    let mut paths = vec![];
+    doc.walk(&mut |path: &[PathSegment], _: &Yaml| {
+        paths.push(format_path(path));
+        Visit::Continue
+    });
+    assert_eq!(paths, ["", ".a", ".a[0]", ".a[1]", ".a[1].b", ".c"]);
+}
+
+#[test]
+fn test_walk_control_flow() {
+    let doc = &Yaml::load_from_str("a: [x, {b: y}]\nc: z\nd: w").unwrap()[0];
+
+    let mut paths = vec![];
+    doc.walk(&mut |path: &[PathSegment], node: &Yaml| {
+        paths.push(format_path(path));
+        if node.is_array() {
+            Visit::SkipChildren
+        } else {
+            Visit::Continue
+        }
+    });
+    assert_eq!(paths, ["", ".a", ".c", ".d"]);
+
+    let mut paths = vec![];
+    doc.walk(&mut |path: &[PathSegment], node: &Yaml| {
+        paths.push(format_path(path));
+        if node.as_str() == Some("y") {
+            Visit::Stop
+        } else {
+            Visit::Continue
+        }
+    });
+    assert_eq!(paths, ["", ".a", ".a[0]", ".a[1]", ".a[1].b"]);
+}
+
+#[test]
+fn test_walk_mut() {
+    let mut doc = Yaml::load_from_str("a: [x, {b: y}]\nc: z")
+        .unwrap()
+        .remove(0);
+    doc.walk_mut(&mut |path: &[PathSegment], node: &mut Yaml| {
+        if let Yaml::String(s) = node {
+            *s = format_path(path);
+        }
+        // Children of a replaced node are visited.
+        if node.is_hash() && !path.is_empty() {
+            *node = Yaml::Array(vec![Yaml::String(String::new())]);
+        }
+        Visit::Continue
+    });
+    assert_eq!(doc["a"][0].as_str(), Some(".a[0]"));
+    assert_eq!(doc["a"][1][0].as_str(), Some(".a[1][0]"));
+    assert_eq!(doc["c"].as_str(), Some(".c"));
+}
+
+#[test]
+fn test_marked_yaml_iterators() {
+    let doc = &MarkedYaml::load_from_str("a: [x, {b: y}]\nc: z").unwrap()[0];
+
+    let nodes: Vec<_> = doc
+        .iter_depth_first()
+        .map(|(path, node)| (format_marked_path(&path), node.span.start.line()))
+        .collect();
+    assert_eq!(
+        nodes,
+        [
+            (String::new(), 1),
+            (".a".to_owned(), 1),
+            (".a[0]".to_owned(), 1),
+            (".a[1]".to_owned(), 1),
+            (".a[1].b".to_owned(), 1),
+            (".c".to_owned(), 2),
+        ]
+    );
+
+    let paths: Vec<_> = doc
+        .iter_breadth_first()
+        .map(|(path, _)| format_marked_path(&path))
+        .collect();
+    assert_eq!(paths, ["", ".a", ".c", ".a[0]", ".a[1]", ".a[1].b"]);
+}
+
+#[test]
+fn test_path_pattern() {
+    let doc = &Yaml::load_from_str("a: [x, {b: y}]\nc: {d: {b: z}}").unwrap()[0];
+    let matching = |pattern: &str| {
+        let pattern = PathPattern::new(pattern);
+        let mut paths = vec![];
+        doc.walk(&mut |path: &[PathSegment], _: &Yaml| {
+            if pattern.matches(path) {
+                paths.push(format_path(path));
+            }
+            Visit::Continue
+        });
+        paths
+    };
+    assert_eq!(matching(""), [""]);
+    assert_eq!(
+        matching("**"),
+        ["", ".a", ".a[0]", ".a[1]", ".a[1].b", ".c", ".c.d", ".c.d.b"]
+    );
+    assert_eq!(matching("**.b"), [".a[1].b", ".c.d.b"]);
+    assert_eq!(matching("*.*.b"), [".a[1].b", ".c.d.b"]);
+    assert_eq!(matching("a.1.**"), [".a[1]", ".a[1].b"]);
+    assert_eq!(matching("*"), [".a", ".c"]);
+    assert_eq!(matching("c.**.b"), [".c.d.b"]);
+    assert!(matching("a.b").is_empty());
+}
+
+#[test]
+fn test_transform_scalars() {
+    let s = "user: me\npassword: a\nservices:\n  - name: db\n    password: b\n    extra: {password: [c, d]}\n";
+    let pattern = PathPattern::new("**.password");
+
+    let mut doc = Yaml::load_from_str(s).unwrap().remove(0);
+    let mut paths = vec![];
+    let count = doc.transform_scalars(&pattern, |path, node| {
+        paths.push(format_path(path));
+        *node = Yaml::String("***".into());
+    });
+    assert_eq!(count, 2);
+    assert_eq!(paths, [".password", ".services[0].password"]);
+    assert_eq!(doc["user"].as_str(), Some("me"));
+    assert_eq!(doc["services"][0]["password"].as_str(), Some("***"));
+    // Collections are not transformed, nor are their items unless they match.
+    assert_eq!(
+        doc["services"][0]["extra"]["password"][0].as_str(),
+        Some("c")
+    );
+
+    let mut doc = MarkedYaml::load_from_str(s).unwrap().remove(0);
+    let count = doc.transform_scalars(&PathPattern::new("**.password.*"), |_, data| {
+        *data = YamlData::String("***".into());
+    });
+    assert_eq!(count, 2);
+    let redacted = &doc.data["services"].data[0].data["extra"].data["password"].data[1];
+    assert_eq!(redacted.data.as_str(), Some("***"));
+    assert_eq!(
+        (redacted.span.start.line(), redacted.span.start.col()),
+        (6, 26)
+    );
+}
+
+#[test]
+fn test_extract_and_project() {
+    let s = "a: [x, {b: y, c: z}]\nd: {e: 1, f: {g: 2}}\nh: 3\n";
+
+    let doc = &MarkedYaml::load_from_str(s).unwrap()[0];
+    let (node, span) = doc.extract("d.f.g").unwrap();
+    assert_eq!(node.data.as_i64(), Some(2));
+    assert_eq!((span.start.line(), span.start.col()), (2, 17));
+    assert_eq!(doc.extract("").unwrap().0, doc);
+    assert_eq!(doc.extract("a.1.c").unwrap().0.data.as_str(), Some("z"));
+    assert!(doc.extract("a.x").is_none());
+    assert!(doc.extract("h.i").is_none());
+    assert!(doc.extract("a.1.b.c").is_none());
+
+    let doc = &Yaml::load_from_str(s).unwrap()[0];
+    let project = |patterns: &[&str]| {
+        let patterns: Vec<_> = patterns.iter().map(|p| PathPattern::new(p)).collect();
+        doc.project(&patterns)
+    };
+    let load = |s: &str| Yaml::load_from_str(s).unwrap().remove(0);
+    assert_eq!(project(&["h", "d.f"]), load("d: {f: {g: 2}}\nh: 3"));
+    assert_eq!(project(&["**.c"]), load("a: [{c: z}]"));
+    assert_eq!(project(&["a.1"]), load("a: [{b: y, c: z}]"));
+    assert_eq!(project(&[""]), *doc);
+    assert_eq!(project(&["nope"]), Yaml::BadValue);
+}
+
+#[test]
+fn test_yaml_path() {
+    let mut path = YamlPath::new();
+    assert!(path.is_root());
+    assert_eq!(path.to_string(), "");
+    assert_eq!(path.to_json_pointer(), "");
+
+    path.push_index(1);
+    path.push_key("a");
+    path.push_key("");
+    path.push_key("b[\"c\"]");
+    path.push_key("~d");
+    assert_eq!(path.to_string(), r#"[1].a[""]["b[\"c\"]"].~d"#);
+    assert_eq!(path.to_json_pointer(), "/1/a//b[\"c\"]/~0d");
+    assert_eq!(path.pop(), Some(YamlPathSegment::Key("~d".to_owned())));
+    assert_eq!(path.segments().len(), 4);
+}
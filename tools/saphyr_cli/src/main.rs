@@ -0,0 +1,131 @@
+//! A small command-line front-end to `saphyr`.
+//!
+//! See the README file in this directory for usage.
+
+use std::io::Read;
+use std::process::ExitCode;
+
+use saphyr::{JsonIncompatibilityKind, Yaml, YamlEmitter};
+
+const USAGE: &str = "Usage: saphyr <command> [file...]
+
+Commands:
+  check      Check that the files are valid YAML, reporting errors with their location
+  fmt        Reformat the files and print them on the standard output
+  to-json    Convert the YAML files to JSON
+  from-json  Convert the JSON files to YAML
+
+If no file is given, or if a file is `-`, the standard input is read instead.";
+
+fn main() -> ExitCode {
+    let args: Vec<_> = std::env::args().skip(1).collect();
+    let Some((command, files)) = args.split_first() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    let files = if files.is_empty() {
+        vec!["-".to_owned()]
+    } else {
+        files.to_vec()
+    };
+
+    let run: fn(&str, &str) -> Result<(), String> = match command.as_str() {
+        "check" => check,
+        "fmt" | "from-json" => fmt,
+        "to-json" => to_json,
+        "-h" | "--help" | "help" => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        _ => {
+            eprintln!("Unknown command `{command}`.\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Process every file, even if one fails, so that all errors are reported at once.
+    let mut status = ExitCode::SUCCESS;
+    for file in &files {
+        let result = read_input(file).and_then(|contents| run(file, &contents));
+        if let Err(e) = result {
+            eprintln!("{}: {e}", display_name(file));
+            status = ExitCode::FAILURE;
+        }
+    }
+    status
+}
+
+/// Check that `contents` is valid YAML.
+fn check(file: &str, contents: &str) -> Result<(), String> {
+    load(contents)?;
+    println!("{}: ok", display_name(file));
+    Ok(())
+}
+
+/// Reformat `contents` as YAML on the standard output.
+///
+/// Since JSON is a subset of YAML, this is also used to convert JSON to YAML.
+fn fmt(_: &str, contents: &str) -> Result<(), String> {
+    let mut out = String::new();
+    for doc in load(contents)? {
+        let mut emitter = YamlEmitter::new(&mut out);
+        emitter.multiline_strings(true);
+        emitter.dump(&doc).map_err(|e| e.to_string())?;
+        out.push('\n');
+    }
+    print!("{out}");
+    Ok(())
+}
+
+/// Convert `contents` to JSON on the standard output, one line per document.
+///
+/// Non-string keys and non-finite reals are converted lossily. Collection keys and invalid values
+/// are errors.
+fn to_json(_: &str, contents: &str) -> Result<(), String> {
+    let mut out = String::new();
+    for doc in load(contents)? {
+        let (json, incompatibilities) = doc.to_json_lossy();
+        if let Some(error) = incompatibilities.iter().find(|incompatibility| {
+            matches!(
+                incompatibility.kind,
+                JsonIncompatibilityKind::CollectionKey | JsonIncompatibilityKind::InvalidValue
+            )
+        }) {
+            return Err(format!("`{}`: {}", error.path, error.kind));
+        }
+        out.push_str(&json);
+        out.push('\n');
+    }
+    print!("{out}");
+    Ok(())
+}
+
+/// Load all documents in `contents`, formatting errors with their location.
+fn load(contents: &str) -> Result<Vec<Yaml>, String> {
+    Yaml::load_from_str(contents).map_err(|e| {
+        let mark = e.marker();
+        format!("{}:{}: {}", mark.line(), mark.col() + 1, e.info())
+    })
+}
+
+/// Read the contents of `file`, or of the standard input if `file` is `-`.
+fn read_input(file: &str) -> Result<String, String> {
+    if file == "-" {
+        let mut s = String::new();
+        std::io::stdin()
+            .read_to_string(&mut s)
+            .map_err(|e| e.to_string())?;
+        Ok(s)
+    } else {
+        std::fs::read_to_string(file).map_err(|e| e.to_string())
+    }
+}
+
+/// The name with which to refer to `file` in messages.
+fn display_name(file: &str) -> &str {
+    if file == "-" {
+        "<stdin>"
+    } else {
+        file
+    }
+}
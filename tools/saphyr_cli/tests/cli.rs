@@ -0,0 +1,82 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Run the `saphyr` binary with `args`, feeding `stdin` to its standard input.
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_saphyr"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+fn stderr(output: &Output) -> &str {
+    std::str::from_utf8(&output.stderr).unwrap()
+}
+
+#[test]
+fn test_check() {
+    let output = run(&["check"], "a: [1, 2]\n");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "<stdin>: ok\n");
+
+    let output = run(&["check", "-"], "a: b: c\n");
+    assert!(!output.status.success());
+    assert_eq!(
+        stderr(&output),
+        "<stdin>: 1:5: mapping values are not allowed in this context\n"
+    );
+}
+
+#[test]
+fn test_check_reports_every_file() {
+    let output = run(&["check", "does-not-exist.yaml", "-"], "a: 1\n");
+    assert!(!output.status.success());
+    assert_eq!(stdout(&output), "<stdin>: ok\n");
+    assert!(stderr(&output).starts_with("does-not-exist.yaml: "));
+}
+
+#[test]
+fn test_fmt() {
+    let output = run(&["fmt"], "a:   [1,  2]\n---\nb: {c: d}\n");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "---\na:\n  - 1\n  - 2\n---\nb:\n  c: d\n");
+}
+
+#[test]
+fn test_to_json() {
+    let output = run(&["to-json"], "a: [1, .inf]\n2: \"x\\ny\"\n---\n~\n");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "{\"a\":[1,null],\"2\":\"x\\ny\"}\nnull\n");
+
+    let output = run(&["to-json"], "a: {[1]: 2}\n");
+    assert!(!output.status.success());
+    assert_eq!(stderr(&output), "<stdin>: `a`: hash key is a collection\n");
+}
+
+#[test]
+fn test_from_json() {
+    let output = run(&["from-json"], r#"{"a": [1, "b"], "c": null}"#);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "---\na:\n  - 1\n  - b\nc: ~\n");
+}
+
+#[test]
+fn test_unknown_command() {
+    let output = run(&["lint"], "");
+    assert!(!output.status.success());
+    assert!(stderr(&output).starts_with("Unknown command `lint`."));
+}
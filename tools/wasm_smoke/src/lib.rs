@@ -0,0 +1,115 @@
+//! Uses of the API of `saphyr-parser` without `std`.
+//!
+//! This crate only exists to be built for targets without `std` (see the README). Each function
+//! exercises a part of the parser the way a browser-based editor would: parsing a buffer it holds,
+//! reporting errors with their location and splitting or replaying documents.
+
+#![cfg_attr(not(test), no_std)]
+#![warn(missing_docs, clippy::pedantic)]
+
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+
+use saphyr_parser::{
+    split_documents, BufferedInput, Event, EventReceiver, Marker, Parser, ParserStats,
+    RecordedEvents, ScanError, Span, SpannedEventReceiver, TScalarStyle,
+};
+
+/// Return the scalars of `input` along with their span, or the first error.
+///
+/// # Errors
+/// Returns the error if `input` is not valid YAML.
+pub fn scalars(input: &str) -> Result<Vec<(String, Span)>, ScanError> {
+    let mut scalars = Vec::new();
+    for event in Parser::new_from_str(input) {
+        if let (Event::Scalar(value, ..), span) = event? {
+            scalars.push((value, span));
+        }
+    }
+    Ok(scalars)
+}
+
+/// Return where `input` fails to parse, if it does.
+#[must_use]
+pub fn first_error(input: &str) -> Option<(Marker, String)> {
+    Parser::new(BufferedInput::new(input.chars()))
+        .find_map(Result::err)
+        .map(|err| (*err.marker(), String::from(err.info())))
+}
+
+/// Return the statistics of the parse of `input`.
+///
+/// # Errors
+/// Returns the error if `input` is not valid YAML.
+pub fn stats(input: &str) -> Result<ParserStats, ScanError> {
+    /// A receiver ignoring events.
+    struct Sink;
+
+    impl EventReceiver for Sink {
+        fn on_event(&mut self, _: Event) {}
+    }
+
+    let mut parser = Parser::new_from_str(input).collect_stats(true);
+    parser.load(&mut Sink, true)?;
+    Ok(parser.stats().cloned().unwrap_or_default())
+}
+
+/// Return the number of block scalars in each document of `input`, parsing documents on their
+/// own. Documents which fail to parse are skipped.
+#[must_use]
+pub fn block_scalars_per_document(input: &str) -> Vec<usize> {
+    /// A receiver counting block scalars.
+    struct Counter(usize);
+
+    impl SpannedEventReceiver for Counter {
+        fn on_event(&mut self, ev: Event, _: Span) {
+            if let Event::Scalar(_, TScalarStyle::Literal(_) | TScalarStyle::Folded(_), ..) = ev {
+                self.0 += 1;
+            }
+        }
+    }
+
+    split_documents(input)
+        .into_iter()
+        .filter_map(|doc| RecordedEvents::record(&mut Parser::new_from_str(doc.text), true).ok())
+        .map(|recorded| {
+            let mut counter = Counter(0);
+            recorded.replay(&mut counter);
+            counter.0
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_scalars() {
+        let scalars = super::scalars("a: [b, 'c']").unwrap();
+        let values: Vec<_> = scalars.iter().map(|(value, _)| value.as_str()).collect();
+        assert_eq!(values, ["a", "b", "c"]);
+        assert_eq!(scalars[2].1.start.index(), 7);
+        assert!(super::scalars("a: [b").is_err());
+    }
+
+    #[test]
+    fn test_first_error() {
+        assert_eq!(super::first_error("a: b"), None);
+        let (marker, _) = super::first_error("a: b\n c: d").unwrap();
+        assert_eq!(marker.line(), 2);
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = super::stats("[a, {b: c}]\n---\nd").unwrap();
+        assert_eq!(stats.documents, 2);
+        assert_eq!(stats.scalars, 4);
+        assert_eq!(stats.max_depth, 2);
+    }
+
+    #[test]
+    fn test_block_scalars_per_document() {
+        let input = "a: |\n  x\n---\n[b\n---\nc: >\n  y\nd: |\n  z\n";
+        assert_eq!(super::block_scalars_per_document(input), [1, 2]);
+    }
+}